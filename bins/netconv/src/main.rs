@@ -0,0 +1,359 @@
+use anyhow::{anyhow, bail, Context};
+use clap::{Parser as ClapParser, ValueEnum};
+use scir::rename::RenameSchema;
+use scir::Library;
+use spectre::Spectre;
+use spice::netlist::NetlistOptions;
+use spice::parser::{Dialect, Parser};
+use spice::Spice;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use substrate::schematic::netlist::ConvertibleNetlister;
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let from = match args.from {
+        Some(from) => from,
+        None => InputFormat::infer(&args.file)?,
+    };
+    eprintln!("input file: {:?} ({:?})", &args.file, from);
+    eprintln!("output format: {:?}", args.to);
+    eprintln!(
+        "output: {}",
+        args.out
+            .as_ref()
+            .map(|out| format!("{out:?}"))
+            .unwrap_or_else(|| "stdout".to_string())
+    );
+    netconv(from, args)?;
+    eprintln!("Netlist conversion complete.");
+    Ok(())
+}
+
+/// Arguments to [`netconv`].
+#[derive(ClapParser)]
+#[command(
+    version,
+    about,
+    long_about = "Convert between SPICE, CDL, Spectre, Verilog and SCIR netlists"
+)]
+pub struct Args {
+    /// The path to the input netlist.
+    file: PathBuf,
+    /// The format of the input netlist.
+    ///
+    /// If unspecified, the format is inferred from the file extension.
+    #[arg(short, long, value_enum)]
+    from: Option<InputFormat>,
+    /// The format of the output netlist.
+    #[arg(short, long, value_enum)]
+    to: OutputFormat,
+    /// The path where the output netlist should be saved.
+    ///
+    /// The file and its parent directories will be created if necessary.
+    /// If the file already exists, it will be overwritten.
+    ///
+    /// If unspecified, the output will be written to stdout.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    /// The names of the cells to treat as blackboxes.
+    #[arg(short, long)]
+    blackbox: Vec<String>,
+    /// SPICE or CDL netlists declaring the port orders of the leaf cells
+    /// (e.g. standard cells) instantiated by a Verilog netlist.
+    #[arg(long, value_name = "FILE")]
+    port_orders: Vec<PathBuf>,
+    /// The name of the top cell.
+    ///
+    /// Required by `--flatten` and `--prune`.
+    #[arg(long)]
+    top: Option<String>,
+    /// Flatten the hierarchy below the top cell.
+    #[arg(long, requires = "top")]
+    flatten: bool,
+    /// Remove cells that are not instantiated by the top cell.
+    #[arg(long, requires = "top")]
+    prune: bool,
+    /// Rename cells, given as `old=new`.
+    #[arg(long, value_name = "OLD=NEW", value_parser = parse_rename)]
+    rename: Vec<(String, String)>,
+    /// Rename cells, signals and instances whose names are not legal in the output format.
+    #[arg(long)]
+    legalize: bool,
+}
+
+/// A netlist format that can be read.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    /// A SPICE netlist.
+    Spice,
+    /// A CDL netlist.
+    Cdl,
+    /// A Spectre netlist.
+    Spectre,
+    /// A structural Verilog netlist.
+    Verilog,
+}
+
+impl InputFormat {
+    /// Infers the input format from the extension of `path`.
+    fn infer(path: &Path) -> anyhow::Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        Ok(match ext.as_deref() {
+            Some("sp" | "spi" | "spice" | "cir" | "lib") => Self::Spice,
+            Some("cdl") => Self::Cdl,
+            Some("scs") => Self::Spectre,
+            Some("v" | "vg") => Self::Verilog,
+            _ => bail!("Cannot infer the format of {path:?}; specify it with --from."),
+        })
+    }
+}
+
+/// A netlist format that can be written.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// A SPICE netlist.
+    Spice,
+    /// A Spectre netlist.
+    Spectre,
+    /// A structural Verilog netlist.
+    Verilog,
+    /// Verilog modules declaring only the ports of each cell.
+    VerilogShells,
+    /// A SCIR library serialized as JSON.
+    Json,
+}
+
+impl OutputFormat {
+    /// Returns a legal version of `name` in this format.
+    fn legalize(&self, name: &str) -> String {
+        match self {
+            // SPICE and Spectre names are whitespace-delimited, and some characters
+            // delimit parameters, expressions or bus indices.
+            OutputFormat::Spice | OutputFormat::Spectre => name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "_!#$%&+-./:<>?@|~".contains(c) {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect(),
+            // Verilog names are escaped when needed, but escaped identifiers
+            // are poorly supported by downstream tools.
+            OutputFormat::Verilog | OutputFormat::VerilogShells => {
+                let mut legal: String = name
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                if !legal.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                    legal.insert(0, '_');
+                }
+                if verilog::is_keyword(&legal) {
+                    legal.push('_');
+                }
+                legal
+            }
+            OutputFormat::Json => name.to_string(),
+        }
+    }
+}
+
+fn parse_rename(s: &str) -> Result<(String, String), String> {
+    let (old, new) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `old=new`, found `{s}`"))?;
+    Ok((old.to_string(), new.to_string()))
+}
+
+/// A netlist read into SCIR.
+enum Netlist {
+    /// A library with SPICE primitives.
+    Spice(Library<Spice>),
+    /// A library with Spectre primitives.
+    Spectre(Library<Spectre>),
+}
+
+/// Convert the given netlist to another format.
+pub fn netconv(from: InputFormat, args: Args) -> anyhow::Result<()> {
+    let mut out: Box<dyn Write> = match &args.out {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("Failed to create output file {:?}.", path)
+            })?))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    convert(from, &args, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Reads, transforms and writes the netlist described by `args` to `out`.
+fn convert(from: InputFormat, args: &Args, out: &mut impl Write) -> anyhow::Result<()> {
+    let netlist = match read(from, args)? {
+        Netlist::Spice(lib) => Netlist::Spice(transform(lib, args)?),
+        Netlist::Spectre(lib) => Netlist::Spectre(transform(lib, args)?),
+    };
+    write(netlist, args.to, out).with_context(|| format!("Failed to write {:?} output.", args.to))
+}
+
+/// Reads the input netlist into SCIR.
+fn read(from: InputFormat, args: &Args) -> anyhow::Result<Netlist> {
+    Ok(match from {
+        InputFormat::Spice | InputFormat::Cdl => {
+            let dialect = if from == InputFormat::Cdl {
+                Dialect::Cdl
+            } else {
+                Dialect::Spice
+            };
+            let parsed = Parser::parse_file(dialect, &args.file)
+                .with_context(|| "Failed to parse input netlist.")?;
+            let mut converter = spice::parser::conv::ScirConverter::new(&parsed.ast);
+            for blackbox in args.blackbox.iter() {
+                converter.blackbox(blackbox.as_str());
+            }
+            Netlist::Spice(
+                converter
+                    .convert()
+                    .with_context(|| "Failed to convert to SCIR.")?,
+            )
+        }
+        InputFormat::Spectre => {
+            let parsed = spectre::parser::Parser::parse_file(&args.file)
+                .with_context(|| "Failed to parse input netlist.")?;
+            let mut converter = spectre::parser::conv::ScirConverter::new(&parsed.ast);
+            for blackbox in args.blackbox.iter() {
+                converter.blackbox(blackbox.as_str());
+            }
+            Netlist::Spectre(
+                converter
+                    .convert()
+                    .with_context(|| "Failed to convert to SCIR.")?,
+            )
+        }
+        InputFormat::Verilog => {
+            let ast = verilog::parser::Parser::parse_file(&args.file)
+                .with_context(|| "Failed to parse input netlist.")?;
+            let mut converter = verilog::parser::conv::ScirConverter::new(&ast);
+            for blackbox in args.blackbox.iter() {
+                converter.blackbox(blackbox.as_str());
+            }
+            for path in args.port_orders.iter() {
+                let dialect = if path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("cdl"))
+                {
+                    Dialect::Cdl
+                } else {
+                    Dialect::Spice
+                };
+                let parsed = Parser::parse_file(dialect, path)
+                    .with_context(|| format!("Failed to parse port orders from {path:?}."))?;
+                converter.port_orders_from_spice(&parsed.ast);
+            }
+            Netlist::Spice(
+                converter
+                    .convert()
+                    .with_context(|| "Failed to convert to SCIR.")?,
+            )
+        }
+    })
+}
+
+/// Applies the top cell selection, flattening, pruning and renaming requested by `args`.
+fn transform<S: RenameSchema<Primitive = impl Clone> + ?Sized>(
+    lib: Library<S>,
+    args: &Args,
+) -> anyhow::Result<Library<S>> {
+    let mut lib = lib.into_builder();
+    if let Some(top) = &args.top {
+        let id = lib
+            .try_cell_id_named(top)
+            .ok_or_else(|| anyhow!("No cell named `{top}` in the input netlist."))?;
+        lib.set_top(id);
+        if args.flatten {
+            lib.flatten_cell(id);
+        }
+        if args.prune {
+            lib.remove_unreferenced_cells();
+        }
+    }
+    for (old, new) in args.rename.iter() {
+        let id = lib
+            .try_cell_id_named(old)
+            .ok_or_else(|| anyhow!("No cell named `{old}` to rename."))?;
+        lib.rename_cell(id, new.as_str());
+    }
+    if args.legalize {
+        let to = args.to;
+        lib.legalize_names(|name| to.legalize(name));
+    }
+
+    let issues = lib.validate();
+    for item in issues.iter() {
+        eprintln!("{item}");
+    }
+    if issues.has_error() {
+        bail!("One or more errors in netlist identified; aborting.")
+    }
+    Ok(lib.build()?)
+}
+
+/// Writes `netlist` to `out` in the given format.
+fn write(netlist: Netlist, to: OutputFormat, out: &mut impl Write) -> anyhow::Result<()> {
+    match (to, netlist) {
+        (OutputFormat::Spice, Netlist::Spice(lib)) => {
+            Spice.write_scir_netlist(&lib, out, NetlistOptions::default())?;
+        }
+        (OutputFormat::Spice, Netlist::Spectre(_)) => {
+            bail!("Spectre netlists cannot be converted to SPICE.")
+        }
+        (OutputFormat::Spectre, Netlist::Spice(lib)) => {
+            let lib = lib
+                .convert_schema::<Spectre>()
+                .map_err(|_| anyhow!("Failed to convert to Spectre schema."))?
+                .build()?;
+            Spectre {}.write_scir_netlist(&lib, out, NetlistOptions::default())?;
+        }
+        (OutputFormat::Spectre, Netlist::Spectre(lib)) => {
+            Spectre {}.write_scir_netlist(&lib, out, NetlistOptions::default())?;
+        }
+        (OutputFormat::Verilog, Netlist::Spice(lib)) => {
+            verilog::netlist::export_verilog_netlist(&lib, out)?;
+        }
+        (OutputFormat::Verilog, Netlist::Spectre(_)) => {
+            bail!("Spectre netlists cannot be converted to structural Verilog.")
+        }
+        (OutputFormat::VerilogShells, Netlist::Spice(lib)) => {
+            verilog::export_all_verilog_shells(&lib, out)?;
+        }
+        (OutputFormat::VerilogShells, Netlist::Spectre(lib)) => {
+            verilog::export_all_verilog_shells(&lib, out)?;
+        }
+        (OutputFormat::Json, Netlist::Spice(lib)) => {
+            writeln!(out, "{}", lib.to_json()?)?;
+        }
+        (OutputFormat::Json, Netlist::Spectre(lib)) => {
+            writeln!(out, "{}", lib.to_json()?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
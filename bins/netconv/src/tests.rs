@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use clap::Parser as ClapParser;
+
+use crate::{convert, Args, InputFormat, OutputFormat};
+
+pub const TEST_DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/data");
+
+fn test_data(file_name: &str) -> PathBuf {
+    PathBuf::from(TEST_DATA_DIR).join(file_name)
+}
+
+/// Runs `netconv` with the given arguments, returning the output netlist.
+fn run(args: &[&str]) -> String {
+    let args = Args::parse_from(std::iter::once("netconv").chain(args.iter().copied()));
+    let from = args
+        .from
+        .unwrap_or_else(|| InputFormat::infer(&args.file).unwrap());
+    let mut out = Vec::new();
+    convert(from, &args, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn convert_spice_to_spectre() {
+    let input = test_data("spice/inverter.spice");
+    let netlist = run(&[input.to_str().unwrap(), "--to", "spectre"]);
+    assert!(netlist.contains("subckt inverter ( din dout vdd vss )"));
+    assert!(netlist.contains("sky130_fd_pr__nfet_01v8"));
+    assert!(netlist.contains("ends inverter"));
+}
+
+#[test]
+fn convert_with_legalized_names() {
+    let input = test_data("spice/current_sense.spice");
+    let netlist = run(&[input.to_str().unwrap(), "--to", "spice", "--legalize"]);
+    assert!(!netlist.contains('['));
+    // Instances that refer to renamed instances by name are updated.
+    assert!(netlist.contains("Vprobe_0_ in mid"));
+    assert!(netlist.contains("Fmirror out 0 Vprobe_0_ 2"));
+    assert!(netlist.contains("Lp_1_ mid 0 0.000000001"));
+    assert!(netlist.contains("Kc Lp_1_ Ls 0.9"));
+}
+
+#[test]
+fn convert_with_flattened_instance_refs() {
+    let input = test_data("spice/transformer.spice");
+    let netlist = run(&[
+        input.to_str().unwrap(),
+        "--to",
+        "spice",
+        "--top",
+        "top",
+        "--flatten",
+    ]);
+    // Instances that refer to inlined instances by name use their flattened names.
+    for inst in ["1", "2"] {
+        assert!(netlist.contains(&format!("K{inst}.ps L{inst}.p L{inst}.s 0.95")));
+        assert!(netlist.contains(&format!("V{inst}.sense 2")));
+    }
+}
+
+#[test]
+fn legalize_verilog_keywords() {
+    assert_eq!(OutputFormat::Verilog.legalize("module"), "module_");
+    assert_eq!(OutputFormat::Verilog.legalize("wire"), "wire_");
+    assert_eq!(OutputFormat::Verilog.legalize("Wire"), "Wire");
+}
@@ -74,11 +74,13 @@
 //! Note these text-based representations will generally be substantially larger than binary GDSII data.
 #![warn(missing_docs)]
 
+pub mod merge;
 #[doc(hidden)]
 mod read;
 mod ser;
 #[cfg(test)]
 mod tests;
+pub mod validation;
 #[doc(hidden)]
 mod write;
 
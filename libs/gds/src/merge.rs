@@ -0,0 +1,251 @@
+//! Merge GDS libraries.
+
+use std::collections::{HashMap, HashSet};
+
+use uniquify::Names;
+
+use super::*;
+
+/// A policy for resolving struct name conflicts when merging GDS libraries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Renames conflicting structs from the incoming library by prepending the given prefix.
+    ///
+    /// If the prefixed name is also in use, a numeric suffix is appended.
+    Rename(ArcStr),
+    /// Reuses existing structs that are geometrically identical to the conflicting incoming structs.
+    ///
+    /// Returns an error if a conflicting incoming struct differs from the existing struct.
+    Deduplicate,
+    /// Returns an error on any conflict.
+    #[default]
+    Error,
+}
+
+/// Keeps track of struct names after a library is merged.
+#[derive(Clone, Debug, Default)]
+pub struct MergedMapping {
+    names: HashMap<ArcStr, ArcStr>,
+}
+
+/// Identifies a struct in either library being merged.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+enum StructKey {
+    /// A struct in the destination library.
+    Dst(usize),
+    /// A struct in the source library.
+    Src(usize),
+}
+
+struct Merger<'a> {
+    /// Source struct name -> destination struct name.
+    mapping: HashMap<ArcStr, ArcStr>,
+    /// Source structs that are replaced by identical destination structs.
+    deduplicated: HashSet<usize>,
+    /// Struct key -> name.
+    names: Names<StructKey>,
+    policy: ConflictPolicy,
+    dst: &'a mut GdsLibrary,
+    src: GdsLibrary,
+}
+
+impl<'a> Merger<'a> {
+    #[inline]
+    fn new(dst: &'a mut GdsLibrary, src: GdsLibrary, policy: ConflictPolicy) -> Self {
+        Self {
+            mapping: HashMap::with_capacity(src.structs.len()),
+            deduplicated: HashSet::new(),
+            names: Names::with_capacity(src.structs.len() + dst.structs.len()),
+            policy,
+            dst,
+            src,
+        }
+    }
+
+    fn merge(mut self) -> GdsResult<MergedMapping> {
+        if self.src.units != self.dst.units {
+            return Err(GdsError::Str(format!(
+                "cannot merge library `{}` into library `{}` due to mismatched units",
+                self.src.name, self.dst.name
+            )));
+        }
+        for (i, strukt) in self.dst.structs.iter().enumerate() {
+            self.names
+                .reserve_name(StructKey::Dst(i), strukt.name.clone());
+        }
+
+        // Assign names to children before their parents,
+        // so that deduplication can compare rewritten references.
+        for i in self.src_postorder()? {
+            self.assign_name(i)?;
+        }
+
+        // Nothing is modified until all names are assigned,
+        // so the destination library is left untouched on error.
+        let structs = std::mem::take(&mut self.src.structs);
+        for (i, mut strukt) in structs.into_iter().enumerate() {
+            if self.deduplicated.contains(&i) {
+                continue;
+            }
+            strukt.name = self.mapping[&strukt.name].clone();
+            rewrite_references(&mut strukt, &self.mapping);
+            self.dst.structs.push(strukt);
+        }
+
+        Ok(MergedMapping {
+            names: self.mapping,
+        })
+    }
+
+    /// Returns the indices of the source structs such that children precede their parents.
+    ///
+    /// Structs that are part of a hierarchy cycle are ordered arbitrarily.
+    fn src_postorder(&self) -> GdsResult<Vec<usize>> {
+        let mut indices = HashMap::with_capacity(self.src.structs.len());
+        for (i, strukt) in self.src.structs.iter().enumerate() {
+            if indices.insert(&strukt.name, i).is_some() {
+                return Err(GdsError::Str(format!(
+                    "cannot merge library `{}`: found two or more structs named `{}`",
+                    self.src.name, strukt.name
+                )));
+            }
+        }
+
+        let mut visited = vec![false; self.src.structs.len()];
+        let mut order = Vec::with_capacity(self.src.structs.len());
+        for root in 0..self.src.structs.len() {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((i, elem)) = stack.pop() {
+                let elems = &self.src.structs[i].elems;
+                match elems[elem..].iter().position(|e| reference(e).is_some()) {
+                    Some(offset) => {
+                        stack.push((i, elem + offset + 1));
+                        let name = reference(&elems[elem + offset]).unwrap();
+                        if let Some(&child) = indices.get(name) {
+                            if !visited[child] {
+                                visited[child] = true;
+                                stack.push((child, 0));
+                            }
+                        }
+                    }
+                    None => order.push(i),
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    fn assign_name(&mut self, i: usize) -> GdsResult<()> {
+        let name = self.src.structs[i].name.clone();
+        if self.names.reserve_name(StructKey::Src(i), name.clone()) {
+            self.mapping.insert(name.clone(), name);
+            return Ok(());
+        }
+
+        let conflict = || {
+            GdsError::Str(format!(
+                "cannot merge library `{}` into library `{}`: struct name `{}` is already in use",
+                self.src.name, self.dst.name, name
+            ))
+        };
+
+        match &self.policy {
+            ConflictPolicy::Rename(prefix) => {
+                let n_name = self
+                    .names
+                    .assign_name(StructKey::Src(i), &format!("{}{}", prefix, name));
+                self.mapping.insert(name, n_name);
+            }
+            ConflictPolicy::Deduplicate => {
+                let existing = self
+                    .dst
+                    .structs
+                    .iter()
+                    .find(|s| s.name == name)
+                    .ok_or_else(conflict)?;
+                let mut candidate = self.src.structs[i].clone();
+                rewrite_references(&mut candidate, &self.mapping);
+                if !geometrically_identical(&existing.elems, &candidate.elems) {
+                    return Err(conflict());
+                }
+                self.deduplicated.insert(i);
+                self.mapping.insert(name.clone(), name);
+            }
+            ConflictPolicy::Error => return Err(conflict()),
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of the struct referenced by `elem`, if any.
+fn reference(elem: &GdsElement) -> Option<&ArcStr> {
+    match elem {
+        GdsElement::GdsStructRef(sref) => Some(&sref.name),
+        GdsElement::GdsArrayRef(aref) => Some(&aref.name),
+        _ => None,
+    }
+}
+
+/// Renames all references in `strukt` according to `mapping`.
+///
+/// References to structs not in `mapping` are left unchanged.
+fn rewrite_references(strukt: &mut GdsStruct, mapping: &HashMap<ArcStr, ArcStr>) {
+    for elem in strukt.elems.iter_mut() {
+        let name = match elem {
+            GdsElement::GdsStructRef(sref) => &mut sref.name,
+            GdsElement::GdsArrayRef(aref) => &mut aref.name,
+            _ => continue,
+        };
+        if let Some(n_name) = mapping.get(name) {
+            *name = n_name.clone();
+        }
+    }
+}
+
+/// Returns `true` if the two element lists contain the same elements, in any order.
+fn geometrically_identical(a: &[GdsElement], b: &[GdsElement]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+    let mut used = vec![false; b.len()];
+    a.iter().all(
+        |elem| match (0..b.len()).find(|&j| !used[j] && b[j] == *elem) {
+            Some(j) => {
+                used[j] = true;
+                true
+            }
+            None => false,
+        },
+    )
+}
+
+impl MergedMapping {
+    /// Get the struct name in the merged library
+    /// corresponding to `old_name` in the original library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `old_name` is not the name of a struct
+    /// in the original library.
+    pub fn new_name(&self, old_name: &str) -> ArcStr {
+        self.names.get(old_name).cloned().unwrap()
+    }
+}
+
+impl GdsLibrary {
+    /// Merges another GDS library into the current library.
+    ///
+    /// Struct name conflicts are resolved according to `policy`,
+    /// and all references in `other` are rewritten to match.
+    /// On error, the current library is left unmodified.
+    pub fn merge(&mut self, other: Self, policy: ConflictPolicy) -> GdsResult<MergedMapping> {
+        Merger::new(self, other, policy).merge()
+    }
+}
@@ -179,6 +179,219 @@ fn record_too_long() -> GdsResult<()> {
     }
 }
 
+#[test]
+fn sample_validates() -> GdsResult<()> {
+    let lib = GdsLibrary::load(resource("sample1.gds"))?;
+    let issues = lib.validate();
+    assert!(!issues.has_error(), "{issues}");
+    Ok(())
+}
+
+#[test]
+fn validate_struct_names() {
+    let mut lib = GdsLibrary::new("mylib");
+    lib.structs.push(GdsStruct::new("has space"));
+    lib.structs.push(GdsStruct::new("a".repeat(40)));
+    lib.structs.push(GdsStruct::new("dup"));
+    lib.structs.push(GdsStruct::new("dup"));
+    let issues = lib.validate();
+    let causes: Vec<_> = issues.iter().map(|i| i.cause().clone()).collect();
+    assert_eq!(issues.num_errors(), 2);
+    assert_eq!(issues.num_warnings(), 1);
+    assert!(causes.contains(&validation::Cause::IllegalStructName {
+        name: "has space".into()
+    }));
+    assert!(causes.contains(&validation::Cause::StructNameTooLong {
+        name: "a".repeat(40).into(),
+        len: 40,
+    }));
+    assert!(causes.contains(&validation::Cause::DuplicateStructNames { name: "dup".into() }));
+}
+
+#[test]
+fn validate_references() {
+    let sref = |name: &str| -> GdsElement {
+        GdsStructRef {
+            name: name.into(),
+            ..Default::default()
+        }
+        .into()
+    };
+    let mut lib = GdsLibrary::new("mylib");
+    let mut a = GdsStruct::new("a");
+    a.elems.push(sref("b"));
+    a.elems.push(sref("missing"));
+    let mut b = GdsStruct::new("b");
+    b.elems.push(sref("c"));
+    let mut c = GdsStruct::new("c");
+    c.elems.push(sref("a"));
+    lib.structs.extend([a, b, c]);
+
+    let issues = lib.validate();
+    let causes: Vec<_> = issues.iter().map(|i| i.cause().clone()).collect();
+    assert_eq!(
+        causes,
+        vec![
+            validation::Cause::UndefinedStruct {
+                name: "missing".into(),
+                parent: "a".into(),
+            },
+            validation::Cause::HierarchyCycle {
+                cycle: vec!["a".into(), "b".into(), "c".into()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn validate_geometry() {
+    let mut lib = GdsLibrary::new("mylib");
+    let mut cell = GdsStruct::new("cell");
+    // Valid boundary.
+    cell.elems.push(
+        GdsBoundary {
+            xy: GdsPoint::vec(&[(0, 0), (1, 0), (1, 1), (0, 0)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    // Too few points.
+    cell.elems.push(
+        GdsBoundary {
+            xy: GdsPoint::vec(&[(0, 0), (1, 0), (0, 0)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    // Unclosed.
+    cell.elems.push(
+        GdsBoundary {
+            xy: GdsPoint::vec(&[(0, 0), (1, 0), (1, 1), (0, 1)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    // Negative layer.
+    cell.elems.push(
+        GdsTextElem {
+            string: "text".into(),
+            layer: -1,
+            ..Default::default()
+        }
+        .into(),
+    );
+    // Path whose width pushes it past `i32::MAX`.
+    cell.elems.push(
+        GdsPath {
+            xy: GdsPoint::vec(&[(0, 0), (i32::MAX - 10, 0)]),
+            width: Some(100),
+            ..Default::default()
+        }
+        .into(),
+    );
+    lib.structs.push(cell);
+
+    let issues = lib.validate();
+    let causes: Vec<_> = issues.iter().map(|i| i.cause().clone()).collect();
+    assert_eq!(issues.num_errors(), 4);
+    assert!(matches!(
+        causes[0],
+        validation::Cause::TooFewPoints { num_points: 3, .. }
+    ));
+    assert!(matches!(
+        causes[1],
+        validation::Cause::UnclosedBoundary { .. }
+    ));
+    assert!(matches!(
+        causes[2],
+        validation::Cause::LayerOutOfRange {
+            layer: -1,
+            xtype: 0,
+            ..
+        }
+    ));
+    assert!(matches!(
+        causes[3],
+        validation::Cause::CoordinateOverflow { .. }
+    ));
+}
+
+/// Creates a library with a `top` struct instantiating a `leaf` struct
+/// containing a single rectangle on layer `layer`.
+fn merge_lib(name: &str, top: &str, layer: i16) -> GdsLibrary {
+    let mut lib = GdsLibrary::new(name);
+    let mut leaf = GdsStruct::new("leaf");
+    leaf.elems.push(
+        GdsBoundary {
+            layer,
+            xy: GdsPoint::vec(&[(0, 0), (1, 0), (1, 1), (0, 1), (0, 0)]),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let mut top = GdsStruct::new(top);
+    top.elems.push(
+        GdsStructRef {
+            name: "leaf".into(),
+            ..Default::default()
+        }
+        .into(),
+    );
+    lib.structs.extend([leaf, top]);
+    lib
+}
+
+#[test]
+fn merge_rename() -> GdsResult<()> {
+    let mut lib = merge_lib("lib", "top1", 1);
+    let mapping = lib.merge(
+        merge_lib("vendor", "top2", 2),
+        merge::ConflictPolicy::Rename("vendor_".into()),
+    )?;
+    assert_eq!(mapping.new_name("leaf"), "vendor_leaf");
+    assert_eq!(mapping.new_name("top2"), "top2");
+
+    let names: Vec<_> = lib.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["leaf", "top1", "vendor_leaf", "top2"]);
+    match &lib.structs[3].elems[0] {
+        GdsElement::GdsStructRef(sref) => assert_eq!(sref.name, "vendor_leaf"),
+        _ => panic!("expected a struct reference"),
+    }
+    assert!(!lib.validate().has_error());
+    Ok(())
+}
+
+#[test]
+fn merge_deduplicate() -> GdsResult<()> {
+    let mut lib = merge_lib("lib", "top1", 1);
+    lib.merge(
+        merge_lib("vendor", "top2", 1),
+        merge::ConflictPolicy::Deduplicate,
+    )?;
+    let names: Vec<_> = lib.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["leaf", "top1", "top2"]);
+
+    // Conflicting structs with different geometry cannot be deduplicated.
+    let before = lib.clone();
+    assert!(lib
+        .merge(
+            merge_lib("vendor", "top3", 2),
+            merge::ConflictPolicy::Deduplicate
+        )
+        .is_err());
+    assert_eq!(lib, before);
+    Ok(())
+}
+
+#[test]
+fn merge_conflict_error() {
+    let mut lib = merge_lib("lib", "top1", 1);
+    assert!(lib
+        .merge(merge_lib("vendor", "top2", 1), merge::ConflictPolicy::Error)
+        .is_err());
+    assert_eq!(lib.structs.len(), 2);
+}
+
 /// Compare `lib` to "golden" data loaded from JSON at path `golden`.
 fn check(lib: &GdsLibrary, fname: impl AsRef<Path>) {
     use crate::ser::SerializationFormat::Json;
@@ -0,0 +1,430 @@
+//! GDS validation utilities.
+//!
+//! This module provides helpers for checking that a [`GdsLibrary`]
+//! can be written out and consumed by downstream tools.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use diagnostics::{Diagnostic, IssueSet, Severity};
+
+use super::*;
+
+/// The maximum structure name length permitted by the GDSII spec.
+pub const MAX_STRUCT_NAME_LEN: usize = 32;
+
+/// The maximum layer (and datatype) number permitted by the GDSII spec.
+pub const MAX_LAYER: i16 = 255;
+
+/// An issue identified during validation of a GDS library.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorIssue {
+    cause: Cause,
+    severity: Severity,
+}
+
+/// The cause of a GDS validation error or warning.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cause {
+    /// A struct name is empty or contains characters that are not printable ASCII.
+    IllegalStructName {
+        /// The offending name.
+        name: ArcStr,
+    },
+    /// A struct name is longer than [`MAX_STRUCT_NAME_LEN`] characters.
+    StructNameTooLong {
+        /// The offending name.
+        name: ArcStr,
+        /// The length of the name.
+        len: usize,
+    },
+    /// Two or more structs have the same name.
+    DuplicateStructNames {
+        /// The conflicting name.
+        name: ArcStr,
+    },
+    /// A struct or array reference names a struct not present in the library.
+    UndefinedStruct {
+        /// The name of the missing struct.
+        name: ArcStr,
+        /// The name of the struct containing the reference.
+        parent: ArcStr,
+    },
+    /// The struct hierarchy contains a cycle.
+    HierarchyCycle {
+        /// The names of the structs forming the cycle, in reference order.
+        ///
+        /// The first struct references the second, and so on;
+        /// the last struct references the first.
+        cycle: Vec<ArcStr>,
+    },
+    /// A boundary has fewer than four points.
+    ///
+    /// A closed polygon with at least three vertices requires four points.
+    TooFewPoints {
+        /// The name of the struct containing the boundary.
+        strukt: ArcStr,
+        /// The number of points in the boundary.
+        num_points: usize,
+    },
+    /// A boundary's first and last points differ.
+    UnclosedBoundary {
+        /// The name of the struct containing the boundary.
+        strukt: ArcStr,
+        /// The first point of the boundary.
+        first: GdsPoint,
+        /// The last point of the boundary.
+        last: GdsPoint,
+    },
+    /// A layer or datatype number is outside of `0..=MAX_LAYER`.
+    LayerOutOfRange {
+        /// The name of the struct containing the element.
+        strukt: ArcStr,
+        /// The offending layer spec.
+        layer: i16,
+        /// The offending datatype (or text type, node type, etc.).
+        xtype: i16,
+    },
+    /// A coordinate implied by an element does not fit in an `i32`.
+    ///
+    /// Examples include the far corner of an array reference
+    /// and the extended endpoints of a wide path.
+    CoordinateOverflow {
+        /// The name of the struct containing the element.
+        strukt: ArcStr,
+        /// The overflowing coordinate.
+        x: i64,
+        /// The overflowing coordinate.
+        y: i64,
+    },
+}
+
+impl Diagnostic for ValidatorIssue {
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+impl ValidatorIssue {
+    /// Creates a new validator issue from the given cause and severity.
+    pub(crate) fn new(cause: Cause, severity: Severity) -> Self {
+        Self { cause, severity }
+    }
+
+    /// Gets the underlying cause of this issue.
+    #[inline]
+    pub fn cause(&self) -> &Cause {
+        &self.cause
+    }
+}
+
+impl Display for ValidatorIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cause)
+    }
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalStructName { name } => write!(
+                f,
+                "illegal struct name: `{}` is empty or contains characters other than printable ASCII",
+                name
+            ),
+            Self::StructNameTooLong { name, len } => write!(
+                f,
+                "struct name too long: `{}` has {} characters, but GDSII permits at most {}",
+                name, len, MAX_STRUCT_NAME_LEN
+            ),
+            Self::DuplicateStructNames { name } => write!(
+                f,
+                "duplicate struct names: found two or more structs named `{}`",
+                name
+            ),
+            Self::UndefinedStruct { name, parent } => write!(
+                f,
+                "undefined struct: struct `{}` references struct `{}`, but no struct with this name was found in the library",
+                parent, name
+            ),
+            Self::HierarchyCycle { cycle } => {
+                write!(f, "hierarchy cycle: ")?;
+                for name in cycle.iter() {
+                    write!(f, "`{}` -> ", name)?;
+                }
+                write!(f, "`{}`", cycle[0])
+            }
+            Self::TooFewPoints { strukt, num_points } => write!(
+                f,
+                "too few points: boundary in struct `{}` has {} points, but at least 4 are required",
+                strukt, num_points
+            ),
+            Self::UnclosedBoundary { strukt, first, last } => write!(
+                f,
+                "unclosed boundary: boundary in struct `{}` starts at {} but ends at {}",
+                strukt, first, last
+            ),
+            Self::LayerOutOfRange { strukt, layer, xtype } => write!(
+                f,
+                "layer out of range: element in struct `{}` is on layer {}/{}, but GDSII permits only 0 through {}",
+                strukt, layer, xtype, MAX_LAYER
+            ),
+            Self::CoordinateOverflow { strukt, x, y } => write!(
+                f,
+                "coordinate overflow: element in struct `{}` implies coordinate ({}, {}), which does not fit in a 32-bit integer",
+                strukt, x, y
+            ),
+        }
+    }
+}
+
+impl GdsLibrary {
+    /// Checks whether this library is valid.
+    ///
+    /// Libraries that produce errors may still be written by [`GdsLibrary::save`],
+    /// but are likely to be rejected by other tools.
+    pub fn validate(&self) -> IssueSet<ValidatorIssue> {
+        let mut issues = IssueSet::new();
+
+        let mut names = HashSet::with_capacity(self.structs.len());
+        for strukt in self.structs.iter() {
+            validate_struct_name(&strukt.name, &mut issues);
+            if !names.insert(&strukt.name) {
+                issues.add(ValidatorIssue::new(
+                    Cause::DuplicateStructNames {
+                        name: strukt.name.clone(),
+                    },
+                    Severity::Error,
+                ));
+            }
+        }
+
+        for strukt in self.structs.iter() {
+            for elem in strukt.elems.iter() {
+                validate_element(strukt, elem, &names, &mut issues);
+            }
+        }
+
+        self.validate_hierarchy(&mut issues);
+        issues
+    }
+
+    /// Reports each cycle in the struct hierarchy once.
+    fn validate_hierarchy(&self, issues: &mut IssueSet<ValidatorIssue>) {
+        let structs: HashMap<&ArcStr, &GdsStruct> =
+            self.structs.iter().map(|s| (&s.name, s)).collect();
+        let mut state = HashMap::with_capacity(structs.len());
+        let mut stack = Vec::new();
+        for strukt in self.structs.iter() {
+            visit_struct(strukt, &structs, &mut state, &mut stack, issues);
+        }
+    }
+}
+
+/// The depth-first search state of a struct.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum VisitState {
+    /// The struct is on the current search path.
+    InProgress,
+    /// The struct and all of its descendants have been visited.
+    Done,
+}
+
+fn visit_struct<'a>(
+    strukt: &'a GdsStruct,
+    structs: &HashMap<&'a ArcStr, &'a GdsStruct>,
+    state: &mut HashMap<&'a ArcStr, VisitState>,
+    stack: &mut Vec<&'a ArcStr>,
+    issues: &mut IssueSet<ValidatorIssue>,
+) {
+    if state.contains_key(&strukt.name) {
+        return;
+    }
+    state.insert(&strukt.name, VisitState::InProgress);
+    stack.push(&strukt.name);
+
+    for elem in strukt.elems.iter() {
+        let name = match elem {
+            GdsElement::GdsStructRef(sref) => &sref.name,
+            GdsElement::GdsArrayRef(aref) => &aref.name,
+            _ => continue,
+        };
+        let child = match structs.get(name) {
+            Some(child) => *child,
+            // Undefined references are reported separately.
+            None => continue,
+        };
+        match state.get(&child.name) {
+            Some(VisitState::InProgress) => {
+                let start = stack.iter().position(|n| *n == &child.name).unwrap();
+                issues.add(ValidatorIssue::new(
+                    Cause::HierarchyCycle {
+                        cycle: stack[start..].iter().map(|n| (*n).clone()).collect(),
+                    },
+                    Severity::Error,
+                ));
+            }
+            Some(VisitState::Done) => {}
+            None => visit_struct(child, structs, state, stack, issues),
+        }
+    }
+
+    stack.pop();
+    state.insert(&strukt.name, VisitState::Done);
+}
+
+fn validate_struct_name(name: &ArcStr, issues: &mut IssueSet<ValidatorIssue>) {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+        issues.add(ValidatorIssue::new(
+            Cause::IllegalStructName { name: name.clone() },
+            Severity::Error,
+        ));
+    } else if name.len() > MAX_STRUCT_NAME_LEN {
+        issues.add(ValidatorIssue::new(
+            Cause::StructNameTooLong {
+                name: name.clone(),
+                len: name.len(),
+            },
+            Severity::Warning,
+        ));
+    }
+}
+
+fn validate_element(
+    strukt: &GdsStruct,
+    elem: &GdsElement,
+    names: &HashSet<&ArcStr>,
+    issues: &mut IssueSet<ValidatorIssue>,
+) {
+    let undefined = |name: &ArcStr| {
+        ValidatorIssue::new(
+            Cause::UndefinedStruct {
+                name: name.clone(),
+                parent: strukt.name.clone(),
+            },
+            Severity::Error,
+        )
+    };
+
+    match elem {
+        GdsElement::GdsBoundary(boundary) => {
+            validate_layer(strukt, boundary, issues);
+            validate_boundary(strukt, boundary, issues);
+        }
+        GdsElement::GdsPath(path) => {
+            validate_layer(strukt, path, issues);
+            validate_path(strukt, path, issues);
+        }
+        GdsElement::GdsStructRef(sref) => {
+            if !names.contains(&sref.name) {
+                issues.add(undefined(&sref.name));
+            }
+        }
+        GdsElement::GdsArrayRef(aref) => {
+            if !names.contains(&aref.name) {
+                issues.add(undefined(&aref.name));
+            }
+            // The corner opposite the origin lies at `xy[1] + xy[2] - xy[0]`.
+            let [p0, p1, p2] = &aref.xy;
+            let x = p1.x as i64 + p2.x as i64 - p0.x as i64;
+            let y = p1.y as i64 + p2.y as i64 - p0.y as i64;
+            validate_coordinate(strukt, x, y, issues);
+        }
+        GdsElement::GdsTextElem(text) => validate_layer(strukt, text, issues),
+        GdsElement::GdsNode(node) => validate_layer(strukt, node, issues),
+        GdsElement::GdsBox(box_) => validate_layer(strukt, box_, issues),
+    }
+}
+
+fn validate_layer(strukt: &GdsStruct, elem: &impl HasLayer, issues: &mut IssueSet<ValidatorIssue>) {
+    let GdsLayerSpec { layer, xtype } = elem.layerspec();
+    let severity = if layer < 0 || xtype < 0 {
+        Severity::Error
+    } else if layer > MAX_LAYER || xtype > MAX_LAYER {
+        Severity::Warning
+    } else {
+        return;
+    };
+    issues.add(ValidatorIssue::new(
+        Cause::LayerOutOfRange {
+            strukt: strukt.name.clone(),
+            layer,
+            xtype,
+        },
+        severity,
+    ));
+}
+
+fn validate_boundary(
+    strukt: &GdsStruct,
+    boundary: &GdsBoundary,
+    issues: &mut IssueSet<ValidatorIssue>,
+) {
+    if boundary.xy.len() < 4 {
+        issues.add(ValidatorIssue::new(
+            Cause::TooFewPoints {
+                strukt: strukt.name.clone(),
+                num_points: boundary.xy.len(),
+            },
+            Severity::Error,
+        ));
+    }
+    if let (Some(first), Some(last)) = (boundary.xy.first(), boundary.xy.last()) {
+        if first != last {
+            issues.add(ValidatorIssue::new(
+                Cause::UnclosedBoundary {
+                    strukt: strukt.name.clone(),
+                    first: first.clone(),
+                    last: last.clone(),
+                },
+                Severity::Error,
+            ));
+        }
+    }
+}
+
+fn validate_path(strukt: &GdsStruct, path: &GdsPath, issues: &mut IssueSet<ValidatorIssue>) {
+    // Paths may extend beyond their points by half their width,
+    // plus any custom extensions.
+    let half_width = path.width.unwrap_or(0).unsigned_abs() as i64 / 2;
+    let extn = path
+        .begin_extn
+        .unwrap_or(0)
+        .unsigned_abs()
+        .max(path.end_extn.unwrap_or(0).unsigned_abs()) as i64;
+    let reach = half_width + extn;
+    if reach == 0 {
+        return;
+    }
+    for pt in path.xy.iter() {
+        for (dx, dy) in [(-reach, -reach), (reach, reach)] {
+            let (x, y) = (pt.x as i64 + dx, pt.y as i64 + dy);
+            if validate_coordinate(strukt, x, y, issues) {
+                return;
+            }
+        }
+    }
+}
+
+/// Adds an issue if `(x, y)` does not fit in an `i32` coordinate.
+///
+/// Returns `true` if an issue was added.
+fn validate_coordinate(
+    strukt: &GdsStruct,
+    x: i64,
+    y: i64,
+    issues: &mut IssueSet<ValidatorIssue>,
+) -> bool {
+    if i32::try_from(x).is_ok() && i32::try_from(y).is_ok() {
+        return false;
+    }
+    issues.add(ValidatorIssue::new(
+        Cause::CoordinateOverflow {
+            strukt: strukt.name.clone(),
+            x,
+            y,
+        },
+        Severity::Error,
+    ));
+    true
+}
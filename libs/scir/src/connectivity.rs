@@ -0,0 +1,303 @@
+//! Indexed connectivity queries on SCIR libraries.
+//!
+//! A [`Connectivity`] index maps every net bit in every cell to the instance
+//! terminals connected to it. The index can be used to trace nets through the
+//! hierarchy, and to find the fan-in, fan-out, or leaf primitives of a net.
+
+use std::collections::HashMap;
+
+use super::*;
+
+/// A single bit of an instance port.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Terminal {
+    instance: InstanceId,
+    port: ArcStr,
+    index: usize,
+    child: ChildId,
+    direction: Option<Direction>,
+}
+
+impl Terminal {
+    /// The ID of the instance.
+    #[inline]
+    pub fn instance(&self) -> InstanceId {
+        self.instance
+    }
+
+    /// The name of the port.
+    #[inline]
+    pub fn port(&self) -> &ArcStr {
+        &self.port
+    }
+
+    /// The index of the bit within the port's connection.
+    ///
+    /// Zero for single-bit ports.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The cell or primitive instantiated by the instance.
+    #[inline]
+    pub fn child(&self) -> ChildId {
+        self.child
+    }
+
+    /// The direction of the port.
+    ///
+    /// Returns [`None`] for ports of primitives, which have no declared direction.
+    #[inline]
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+}
+
+/// The instance terminals connected to each net bit in a single cell.
+#[derive(Clone, Debug, Default)]
+pub struct CellConnectivity {
+    terminals: HashMap<SliceOne, Vec<Terminal>>,
+}
+
+impl CellConnectivity {
+    /// Indexes the connections of `cell`, whose children are defined in `lib`.
+    pub(crate) fn new<S: Schema + ?Sized>(lib: &LibraryBuilder<S>, cell: &Cell) -> Self {
+        let mut terminals: HashMap<SliceOne, Vec<Terminal>> = HashMap::new();
+        for (id, inst) in cell.instances() {
+            let child = match inst.child() {
+                ChildId::Cell(child) => Some(lib.cell(child)),
+                ChildId::Primitive(_) => None,
+            };
+            for (port, conn) in inst.connections() {
+                let direction = child.map(|child| child.port(port).direction());
+                for (index, bit) in conn.parts().flat_map(bits).enumerate() {
+                    terminals.entry(bit).or_default().push(Terminal {
+                        instance: id,
+                        port: port.clone(),
+                        index,
+                        child: inst.child(),
+                        direction,
+                    });
+                }
+            }
+        }
+        Self { terminals }
+    }
+
+    /// The terminals connected to the given net bit.
+    pub fn terminals(&self, net: SliceOne) -> &[Terminal] {
+        self.terminals.get(&net).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Iterates over every net bit with at least one terminal connected to it.
+    pub fn nets(&self) -> impl Iterator<Item = (SliceOne, &[Terminal])> {
+        self.terminals
+            .iter()
+            .map(|(net, terminals)| (*net, terminals.as_slice()))
+    }
+
+    /// The terminals of the given direction connected to the given net bit.
+    pub fn terminals_with_direction(
+        &self,
+        net: SliceOne,
+        direction: Direction,
+    ) -> impl Iterator<Item = &Terminal> {
+        self.terminals(net)
+            .iter()
+            .filter(move |terminal| terminal.direction == Some(direction))
+    }
+
+    /// The input terminals read from the given net bit.
+    pub fn fanout(&self, net: SliceOne) -> impl Iterator<Item = &Terminal> {
+        self.terminals_with_direction(net, Direction::Input)
+    }
+
+    /// The output terminals driving the given net bit.
+    pub fn fanin(&self, net: SliceOne) -> impl Iterator<Item = &Terminal> {
+        self.terminals_with_direction(net, Direction::Output)
+    }
+}
+
+/// A net bit in a cell reached while tracing a net through the hierarchy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NetSegment {
+    /// The path to the cell containing the net.
+    pub path: InstancePath,
+    /// The cell containing the net.
+    pub cell: CellId,
+    /// The net bit.
+    pub net: SliceOne,
+}
+
+impl NetSegment {
+    /// Returns the path to this net bit.
+    pub fn slice_one_path(&self) -> SliceOnePath {
+        self.path.clone().slice_one(self.net)
+    }
+}
+
+/// A terminal reached while tracing a net through the hierarchy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HierarchicalTerminal {
+    /// The path to the cell containing the instance.
+    pub path: InstancePath,
+    /// The cell containing the instance.
+    pub cell: CellId,
+    /// The terminal.
+    pub terminal: Terminal,
+}
+
+/// A net traced through the hierarchy.
+///
+/// Produced by [`Connectivity::trace`].
+#[derive(Clone, Debug, Default)]
+pub struct NetTrace {
+    segments: Vec<NetSegment>,
+    terminals: Vec<HierarchicalTerminal>,
+}
+
+impl NetTrace {
+    /// The net bits that make up the traced net, from the top down.
+    pub fn segments(&self) -> &[NetSegment] {
+        &self.segments
+    }
+
+    /// Every terminal connected to the traced net, at any level of hierarchy.
+    pub fn terminals(&self) -> &[HierarchicalTerminal] {
+        &self.terminals
+    }
+
+    /// Iterates over the primitive instance terminals connected to the traced net.
+    pub fn leaves(&self) -> impl Iterator<Item = &HierarchicalTerminal> {
+        self.terminals
+            .iter()
+            .filter(|t| t.terminal.child.is_primitive())
+    }
+
+    /// Iterates over the input terminals connected to the traced net,
+    /// including input ports of intermediate cells.
+    pub fn fanout(&self) -> impl Iterator<Item = &HierarchicalTerminal> {
+        self.terminals
+            .iter()
+            .filter(|t| t.terminal.direction == Some(Direction::Input))
+    }
+
+    /// Iterates over the output terminals connected to the traced net,
+    /// including output ports of intermediate cells.
+    pub fn fanin(&self) -> impl Iterator<Item = &HierarchicalTerminal> {
+        self.terminals
+            .iter()
+            .filter(|t| t.terminal.direction == Some(Direction::Output))
+    }
+}
+
+/// An index of the connectivity of every cell in a SCIR library.
+pub struct Connectivity<'a, S: Schema + ?Sized> {
+    lib: &'a LibraryBuilder<S>,
+    cells: HashMap<CellId, CellConnectivity>,
+}
+
+impl<'a, S: Schema + ?Sized> Connectivity<'a, S> {
+    /// Indexes the connectivity of every cell in `lib`.
+    pub fn new(lib: &'a LibraryBuilder<S>) -> Self {
+        let cells = lib
+            .cells()
+            .map(|(id, cell)| (id, CellConnectivity::new(lib, cell)))
+            .collect();
+        Self { lib, cells }
+    }
+
+    /// The connectivity of the given cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no cell has the given ID.
+    pub fn cell(&self, id: CellId) -> &CellConnectivity {
+        &self.cells[&id]
+    }
+
+    /// The terminals connected to the given net bit in the given cell.
+    pub fn terminals(&self, cell: CellId, net: SliceOne) -> &[Terminal] {
+        self.cell(cell).terminals(net)
+    }
+
+    /// The net bit in the parent cell connected to the given terminal.
+    pub fn net(&self, cell: CellId, terminal: &Terminal) -> SliceOne {
+        self.lib
+            .cell(cell)
+            .instance(terminal.instance)
+            .connection(&terminal.port)
+            .index(terminal.index)
+    }
+
+    /// Traces the net at `path` through the hierarchy.
+    ///
+    /// The path is first simplified to the highest-level net connected to it
+    /// (see [`LibraryBuilder::simplify_path`]), then every connection to that net
+    /// is followed down through instance ports to the leaf primitives.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided path does not exist within the SCIR library.
+    pub fn trace(&self, path: SliceOnePath) -> NetTrace {
+        let path = self.lib.simplify_path(path);
+        let SignalPath { instances, tail } = path.0;
+        let cell = self
+            .lib
+            .annotate_instance_path(instances.clone())
+            .bot()
+            .expect("path must end in a SCIR cell");
+        let net = match tail {
+            SignalPathTail::Id(net) => net,
+            SignalPathTail::Name(name) => SliceOne::new(
+                self.lib.cell(cell).signal_named(name.signal()).id,
+                name.index(),
+            ),
+        };
+
+        let mut trace = NetTrace::default();
+        self.trace_inner(instances, cell, net, &mut trace);
+        trace
+    }
+
+    fn trace_inner(&self, path: InstancePath, cell: CellId, net: SliceOne, trace: &mut NetTrace) {
+        trace.segments.push(NetSegment {
+            path: path.clone(),
+            cell,
+            net,
+        });
+        for terminal in self.terminals(cell, net) {
+            trace.terminals.push(HierarchicalTerminal {
+                path: path.clone(),
+                cell,
+                terminal: terminal.clone(),
+            });
+            if let ChildId::Cell(child_id) = terminal.child {
+                let child = self.lib.cell(child_id);
+                let info = child.signal(child.port(&terminal.port).signal());
+                let child_net = SliceOne::new(info.id, info.width.map(|_| terminal.index));
+                let mut child_path = path.clone();
+                child_path.push(terminal.instance);
+                self.trace_inner(child_path, child_id, child_net, trace);
+            }
+        }
+    }
+}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S> {
+    /// Indexes the connectivity of every cell in this library.
+    pub fn connectivity(&self) -> Connectivity<'_, S> {
+        Connectivity::new(self)
+    }
+}
+
+/// Iterates over the individual bits of a slice.
+pub(crate) fn bits(slice: &Slice) -> impl Iterator<Item = SliceOne> {
+    let signal = slice.signal();
+    let indices: Box<dyn Iterator<Item = Option<usize>>> = match slice.range() {
+        Some(range) => Box::new(range.indices().map(Some)),
+        None => Box::new(std::iter::once(None)),
+    };
+    indices.map(move |index| SliceOne::new(signal, index))
+}
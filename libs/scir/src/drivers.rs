@@ -8,6 +8,7 @@ use std::fmt::{Display, Formatter};
 use diagnostics::{Diagnostic, IssueSet, Severity};
 
 use super::*;
+use crate::connectivity::CellConnectivity;
 
 /// A single node in a SCIR circuit.
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -20,6 +21,17 @@ pub struct Net {
     idx: Option<usize>,
 }
 
+impl Net {
+    /// Creates a new [`Net`] referring to bit `idx` of `signal_name` in `cell_name`.
+    pub(crate) fn new(cell_name: ArcStr, signal_name: ArcStr, idx: Option<usize>) -> Self {
+        Self {
+            cell_name,
+            signal_name,
+            idx,
+        }
+    }
+}
+
 impl Display for Net {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}/{}", self.cell_name, self.signal_name)?;
@@ -225,8 +237,15 @@ impl<S: Schema + ?Sized> LibraryBuilder<S> {
             }
         }
 
-        for (_, instance) in cell.instances.iter() {
-            analyze_instance(self, &mut net_states, instance);
+        let connectivity = CellConnectivity::new(self, cell);
+        for (net, terminals) in connectivity.nets() {
+            let state = &mut net_states.get_mut(&net.signal()).unwrap()[net.index().unwrap_or(0)];
+            for terminal in terminals {
+                // Primitive ports have no direction, so they are not counted.
+                if let Some(dir) = terminal.direction() {
+                    update_net_state(state, dir);
+                }
+            }
         }
 
         for (sig, list) in net_states.iter() {
@@ -245,30 +264,6 @@ impl<S: Schema + ?Sized> LibraryBuilder<S> {
     }
 }
 
-fn analyze_instance<S: Schema + ?Sized>(
-    lib: &LibraryBuilder<S>,
-    net_states: &mut HashMap<SignalId, Vec<NetState>>,
-    inst: &Instance,
-) {
-    if inst.child().is_primitive() {
-        return;
-    }
-    let cell = lib.cell(inst.child().unwrap_cell());
-    for (port, conn) in inst.connections() {
-        let dir = cell.port(port).direction;
-        for part in conn.parts() {
-            let states = net_states.get_mut(&part.signal()).unwrap();
-            if let Some(range) = part.range() {
-                for idx in range {
-                    update_net_state(&mut states[idx], dir);
-                }
-            } else {
-                update_net_state(&mut states[0], dir);
-            }
-        }
-    }
-}
-
 fn update_net_state(state: &mut NetState, dir: Direction) {
     match dir {
         Direction::Output => state.drivers += 1,
@@ -0,0 +1,466 @@
+//! SCIR electrical rule checks.
+//!
+//! Unlike driver analysis, which only considers port directions,
+//! electrical rule checks use the [terminal roles](TerminalRole) of primitives
+//! to find issues such as floating bulk terminals and supply-to-ground shorts.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use diagnostics::{Diagnostic, IssueSet, Severity};
+use rust_decimal_macros::dec;
+
+use super::*;
+use crate::connectivity::CellConnectivity;
+use crate::drivers::Net;
+use crate::reduce::ReduceSchema;
+use crate::schema::StringSchema;
+
+/// The role of a primitive terminal.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TerminalRole {
+    /// A MOSFET gate.
+    Gate,
+    /// A MOSFET drain.
+    Drain,
+    /// A MOSFET source.
+    Source,
+    /// A MOSFET bulk (body) terminal.
+    Bulk,
+    /// A terminal with a DC path to the device's other conductive terminals,
+    /// such as a resistor or inductor terminal.
+    Conductive,
+    /// A terminal with no DC path to the device's other terminals,
+    /// such as a capacitor terminal.
+    Blocking,
+    /// A terminal with no role known to the checks.
+    #[default]
+    Other,
+}
+
+/// The polarity of a MOSFET.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Polarity {
+    /// An n-channel MOSFET, whose bulk is expected to be tied to ground.
+    Nmos,
+    /// A p-channel MOSFET, whose bulk is expected to be tied to a supply.
+    Pmos,
+}
+
+impl Display for Polarity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nmos => write!(f, "NMOS"),
+            Self::Pmos => write!(f, "PMOS"),
+        }
+    }
+}
+
+/// A schema that describes the terminal roles of its primitives.
+///
+/// The resistance of [resistors](ReduceSchema::resistor) is used to tell
+/// supply-to-ground shorts apart from resistive paths such as bleeder resistors.
+pub trait ErcSchema: ReduceSchema {
+    /// Returns the role of port `port` of `primitive`.
+    fn terminal_role(_primitive: &Self::Primitive, _port: &str) -> TerminalRole {
+        TerminalRole::Other
+    }
+
+    /// Returns the polarity of `primitive`, if it is a MOSFET.
+    fn polarity(_primitive: &Self::Primitive) -> Option<Polarity> {
+        None
+    }
+}
+
+impl ErcSchema for NoSchema {}
+
+impl ErcSchema for StringSchema {}
+
+/// The default maximum resistance of a resistor that shorts a supply to ground.
+pub const DEFAULT_SHORT_RESISTANCE: Decimal = dec!(1);
+
+/// Options for electrical rule checks.
+#[derive(Clone, Debug)]
+pub struct ErcOptions {
+    supplies: HashSet<ArcStr>,
+    grounds: HashSet<ArcStr>,
+    short_resistance: Decimal,
+}
+
+impl Default for ErcOptions {
+    fn default() -> Self {
+        Self {
+            supplies: HashSet::new(),
+            grounds: HashSet::new(),
+            short_resistance: DEFAULT_SHORT_RESISTANCE,
+        }
+    }
+}
+
+impl ErcOptions {
+    /// Creates a new set of options with no supply or ground rails.
+    ///
+    /// Checks involving rails are skipped unless rails are specified.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treats signals named `name` as a positive supply rail.
+    pub fn with_supply(mut self, name: impl Into<ArcStr>) -> Self {
+        self.supplies.insert(name.into());
+        self
+    }
+
+    /// Treats signals named `name` as a ground rail.
+    pub fn with_ground(mut self, name: impl Into<ArcStr>) -> Self {
+        self.grounds.insert(name.into());
+        self
+    }
+
+    /// Sets the maximum resistance (ohms) of a resistor between a supply and ground
+    /// that is reported as a short.
+    ///
+    /// Defaults to [`DEFAULT_SHORT_RESISTANCE`].
+    pub fn with_short_resistance(mut self, resistance: Decimal) -> Self {
+        self.short_resistance = resistance;
+        self
+    }
+}
+
+/// An issue identified by electrical rule checks.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ErcIssue {
+    cause: Cause,
+    severity: Severity,
+}
+
+/// The cause of an electrical rule check error or warning.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Cause {
+    /// A net connected only to MOSFET gates.
+    ///
+    /// Nothing drives the gates, so their voltage is undefined.
+    GateOnlyNet(Net),
+    /// A net connected only to MOSFET bulk terminals.
+    FloatingBulk(Net),
+    /// A MOSFET bulk terminal is tied to the wrong rail,
+    /// such as an NMOS bulk tied to a supply.
+    BulkOnWrongRail {
+        /// The name of the MOSFET instance.
+        instance: ArcStr,
+        /// The polarity of the MOSFET.
+        polarity: Polarity,
+        /// The rail the bulk is tied to.
+        net: Net,
+    },
+    /// The drain and source of a MOSFET are connected to the same net.
+    DrainSourceShorted {
+        /// The name of the MOSFET instance.
+        instance: ArcStr,
+        /// The net connected to the drain and source.
+        net: Net,
+    },
+    /// A single device connects a supply rail to a ground rail.
+    ///
+    /// Resistors are only reported if their resistance is at most the
+    /// [short resistance](ErcOptions::with_short_resistance). Other conductive devices,
+    /// whose resistance is unknown, are reported as warnings.
+    SupplyShort {
+        /// The name of the device instance.
+        instance: ArcStr,
+        /// The supply rail.
+        supply: Net,
+        /// The ground rail.
+        ground: Net,
+    },
+    /// A net whose connections have incompatible directions.
+    ///
+    /// This is the case if the net is only read, by instance inputs, MOSFET gates
+    /// or an output port of the cell containing the net, with nothing that can drive it;
+    /// or if an instance output drives an input port of the cell containing the net.
+    ///
+    /// Nets with multiple drivers are reported by
+    /// [driver analysis](LibraryBuilder::validate_drivers) instead.
+    MismatchedDirections(Net),
+}
+
+impl Diagnostic for ErcIssue {
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+impl ErcIssue {
+    /// Gets the underlying cause of this issue.
+    #[inline]
+    pub fn cause(&self) -> &Cause {
+        &self.cause
+    }
+
+    /// Creates a new ERC issue and logs it immediately.
+    ///
+    /// The log level will be selected according to the given severity.
+    pub(crate) fn new_and_log(cause: Cause, severity: Severity) -> Self {
+        let result = Self { cause, severity };
+        match severity {
+            Severity::Info => tracing::event!(Level::INFO, issue = ?result.cause, "{}", result),
+            Severity::Warning => tracing::event!(Level::WARN, issue = ?result.cause, "{}", result),
+            Severity::Error => tracing::event!(Level::ERROR, issue = ?result.cause, "{}", result),
+        }
+        result
+    }
+}
+
+impl Display for ErcIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cause)
+    }
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GateOnlyNet(net) => write!(f, "net is connected only to MOSFET gates: {net}"),
+            Self::FloatingBulk(net) => {
+                write!(f, "net is connected only to MOSFET bulk terminals: {net}")
+            }
+            Self::BulkOnWrongRail {
+                instance,
+                polarity,
+                net,
+            } => write!(
+                f,
+                "bulk of {polarity} instance `{instance}` is tied to the wrong rail: {net}"
+            ),
+            Self::DrainSourceShorted { instance, net } => write!(
+                f,
+                "drain and source of instance `{instance}` are shorted together: {net}"
+            ),
+            Self::SupplyShort {
+                instance,
+                supply,
+                ground,
+            } => write!(
+                f,
+                "instance `{instance}` connects supply {supply} directly to ground {ground}"
+            ),
+            Self::MismatchedDirections(net) => {
+                write!(f, "net connects ports of mismatched directions: {net}")
+            }
+        }
+    }
+}
+
+/// The kind of rail a net belongs to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Rail {
+    Supply,
+    Ground,
+}
+
+impl<S: ErcSchema + ?Sized> LibraryBuilder<S> {
+    /// Performs electrical rule checks on this library.
+    ///
+    /// Each cell is checked independently. Rails are identified by signal name,
+    /// so rail-related checks apply in every cell that names its rails consistently.
+    pub fn check_erc(&self, options: &ErcOptions) -> IssueSet<ErcIssue> {
+        let _guard = span!(
+            Level::INFO,
+            "performing electrical rule checks on SCIR Library"
+        )
+        .entered();
+        let mut issues = IssueSet::new();
+        for (_, cell) in self.cells() {
+            self.check_cell_erc(cell, options, &mut issues);
+        }
+        issues
+    }
+
+    fn check_cell_erc(&self, cell: &Cell, options: &ErcOptions, issues: &mut IssueSet<ErcIssue>) {
+        let _guard =
+            span!(Level::INFO, "checking SCIR cell electrical rules", cell.name = %cell.name)
+                .entered();
+        let connectivity = CellConnectivity::new(self, cell);
+        let net = |bit: SliceOne| {
+            Net::new(
+                cell.name.clone(),
+                cell.signal(bit.signal()).name.clone(),
+                bit.index(),
+            )
+        };
+        let rail = |bit: SliceOne| {
+            let name = &cell.signal(bit.signal()).name;
+            if options.supplies.contains(name) {
+                Some(Rail::Supply)
+            } else if options.grounds.contains(name) {
+                Some(Rail::Ground)
+            } else {
+                None
+            }
+        };
+        let role = |inst: &Instance, port: &str| match inst.child() {
+            ChildId::Primitive(id) => S::terminal_role(self.primitive(id), port),
+            ChildId::Cell(_) => TerminalRole::Other,
+        };
+
+        let mut signals: Vec<_> = cell.signals().map(|(_, info)| info).collect();
+        signals.sort_by_key(|info| info.id);
+        for info in signals {
+            let bits: Vec<_> = match info.width {
+                Some(width) => (0..width).map(|i| info.slice().index(i)).collect(),
+                None => vec![SliceOne::new(info.id, None)],
+            };
+            let port = info
+                .port
+                .is_some()
+                .then(|| cell.port(&info.name).direction());
+            for bit in bits {
+                let terminals = connectivity.terminals(bit);
+                let roles: Vec<_> = terminals
+                    .iter()
+                    .map(|t| role(cell.instance(t.instance()), t.port()))
+                    .collect();
+                if port.is_none() && !roles.is_empty() {
+                    if roles.iter().all(|role| *role == TerminalRole::Gate) {
+                        issues.add(ErcIssue::new_and_log(
+                            Cause::GateOnlyNet(net(bit)),
+                            Severity::Warning,
+                        ));
+                    } else if roles.iter().all(|role| *role == TerminalRole::Bulk) {
+                        issues.add(ErcIssue::new_and_log(
+                            Cause::FloatingBulk(net(bit)),
+                            Severity::Error,
+                        ));
+                    }
+                }
+
+                // Primitive terminals other than gates may drive the net.
+                let (mut inputs, mut gates, mut outputs, mut others) = (0, 0, 0, 0);
+                for (terminal, role) in terminals.iter().zip(roles.iter()) {
+                    match (terminal.direction(), role) {
+                        (Some(Direction::Input), _) => inputs += 1,
+                        (Some(Direction::Output), _) => outputs += 1,
+                        (None, TerminalRole::Gate) => gates += 1,
+                        _ => others += 1,
+                    }
+                }
+                let undriven = outputs == 0 && others == 0;
+                let mismatched = match port {
+                    // An input port is driven from outside the cell.
+                    Some(Direction::Input) => outputs > 0,
+                    Some(Direction::Output) => undriven && inputs + gates > 0,
+                    Some(Direction::InOut) => false,
+                    // Nets connected only to gates are reported as gate-only nets.
+                    None => undriven && inputs > 0,
+                };
+                if mismatched {
+                    issues.add(ErcIssue::new_and_log(
+                        Cause::MismatchedDirections(net(bit)),
+                        Severity::Warning,
+                    ));
+                }
+            }
+        }
+
+        for (_, inst) in cell.instances() {
+            let ChildId::Primitive(id) = inst.child() else {
+                continue;
+            };
+            let prim = self.primitive(id);
+            // Single-bit connections of each terminal role.
+            let mut terminals: Vec<(TerminalRole, SliceOne)> = inst
+                .connections()
+                .iter()
+                .filter(|(_, conn)| conn.width() == 1)
+                .map(|(port, conn)| (S::terminal_role(prim, port), conn.index(0)))
+                .collect();
+            terminals.sort_by_key(|(_, bit)| *bit);
+            let with_role = |role: TerminalRole| {
+                terminals
+                    .iter()
+                    .filter(move |(r, _)| *r == role)
+                    .map(|(_, bit)| *bit)
+            };
+
+            if let (Some(d), Some(s)) = (
+                with_role(TerminalRole::Drain).next(),
+                with_role(TerminalRole::Source).next(),
+            ) {
+                if d == s {
+                    issues.add(ErcIssue::new_and_log(
+                        Cause::DrainSourceShorted {
+                            instance: inst.name().clone(),
+                            net: net(d),
+                        },
+                        Severity::Warning,
+                    ));
+                }
+                if let Some((supply, ground)) = rail_pair(&rail, [d, s].into_iter()) {
+                    issues.add(ErcIssue::new_and_log(
+                        Cause::SupplyShort {
+                            instance: inst.name().clone(),
+                            supply: net(supply),
+                            ground: net(ground),
+                        },
+                        Severity::Warning,
+                    ));
+                }
+            }
+
+            if let (Some(polarity), Some(bulk)) =
+                (S::polarity(prim), with_role(TerminalRole::Bulk).next())
+            {
+                let wrong = match polarity {
+                    Polarity::Nmos => Rail::Supply,
+                    Polarity::Pmos => Rail::Ground,
+                };
+                if rail(bulk) == Some(wrong) {
+                    issues.add(ErcIssue::new_and_log(
+                        Cause::BulkOnWrongRail {
+                            instance: inst.name().clone(),
+                            polarity,
+                            net: net(bulk),
+                        },
+                        Severity::Warning,
+                    ));
+                }
+            }
+
+            if let Some((supply, ground)) = rail_pair(&rail, with_role(TerminalRole::Conductive)) {
+                let severity = match S::resistor(prim) {
+                    Some(r) if r.value <= options.short_resistance => Some(Severity::Error),
+                    // Resistive paths such as bleeder resistors are intentional.
+                    Some(_) => None,
+                    None => Some(Severity::Warning),
+                };
+                if let Some(severity) = severity {
+                    issues.add(ErcIssue::new_and_log(
+                        Cause::SupplyShort {
+                            instance: inst.name().clone(),
+                            supply: net(supply),
+                            ground: net(ground),
+                        },
+                        severity,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Returns a supply bit and a ground bit from `bits`, if both are present.
+fn rail_pair(
+    rail: &impl Fn(SliceOne) -> Option<Rail>,
+    bits: impl Iterator<Item = SliceOne>,
+) -> Option<(SliceOne, SliceOne)> {
+    let (mut supply, mut ground) = (None, None);
+    for bit in bits {
+        match rail(bit) {
+            Some(Rail::Supply) => supply = supply.or(Some(bit)),
+            Some(Rail::Ground) => ground = ground.or(Some(bit)),
+            None => {}
+        }
+    }
+    supply.zip(ground)
+}
@@ -0,0 +1,444 @@
+//! Flatten SCIR cell hierarchies.
+
+use std::collections::HashSet;
+use uniquify::Names;
+
+use super::*;
+use crate::param::Expr;
+use crate::rename::RenameSchema;
+
+/// Options for flattening a SCIR cell.
+#[derive(Clone, Debug)]
+pub struct FlattenOptions {
+    depth: Option<usize>,
+    separator: ArcStr,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            depth: None,
+            separator: arcstr::literal!("."),
+        }
+    }
+}
+
+impl FlattenOptions {
+    /// Creates a new set of options that flattens the entire hierarchy
+    /// and joins hierarchical names with `.`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only inlines instances that are at most `depth` levels below the flattened cell.
+    ///
+    /// Cell instances below this depth are kept as instances in the flattened cell.
+    /// A depth of 1 inlines only the instances directly contained in the flattened cell.
+    #[inline]
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Sets the separator used to join hierarchical instance and signal names.
+    #[inline]
+    pub fn with_separator(mut self, separator: impl Into<ArcStr>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+}
+
+/// Keeps track of signals and instances after a cell is flattened.
+///
+/// Paths are relative to the hierarchy of the cell before flattening.
+#[derive(Clone, Debug)]
+pub struct FlattenedMapping {
+    cell: CellId,
+    cell_name: ArcStr,
+    /// Instance path and bit in the original hierarchy -> bit in the flattened cell.
+    signals: HashMap<(Vec<InstanceId>, SliceOne), SliceOne>,
+    /// Instance path in the original hierarchy -> instance in the flattened cell.
+    ///
+    /// Only contains instances that were not inlined.
+    instances: HashMap<Vec<InstanceId>, InstanceId>,
+    /// Parent instance path and instance name -> instance ID in the original hierarchy.
+    instance_names: HashMap<(Vec<InstanceId>, ArcStr), InstanceId>,
+    /// Instance path and signal name -> signal ID in the original hierarchy.
+    signal_names: HashMap<(Vec<InstanceId>, ArcStr), SignalId>,
+}
+
+struct Flattener<'a, S: RenameSchema + ?Sized> {
+    lib: &'a LibraryBuilder<S>,
+    options: FlattenOptions,
+    flat: Cell,
+    mapping: FlattenedMapping,
+    signal_names: Names<(Vec<InstanceId>, SignalId)>,
+    instance_names: Names<Vec<InstanceId>>,
+    /// Primitives of flattened instances that refer to renamed instances.
+    renamed_primitives: Vec<(InstanceId, S::Primitive)>,
+}
+
+impl<'a, S: RenameSchema + ?Sized> Flattener<'a, S> {
+    fn new(lib: &'a LibraryBuilder<S>, id: CellId, options: FlattenOptions) -> Self {
+        let cell = lib.cell(id);
+        Self {
+            lib,
+            options,
+            flat: Cell::new(cell.name.clone()),
+            mapping: FlattenedMapping {
+                cell: id,
+                cell_name: cell.name.clone(),
+                signals: HashMap::new(),
+                instances: HashMap::new(),
+                instance_names: HashMap::new(),
+                signal_names: HashMap::new(),
+            },
+            signal_names: Names::new(),
+            instance_names: Names::new(),
+            renamed_primitives: Vec::new(),
+        }
+    }
+
+    fn flatten(mut self) -> (Cell, FlattenedMapping, Vec<(InstanceId, S::Primitive)>) {
+        let cell = self.lib.cell(self.mapping.cell);
+
+        // Reserve the names of the top-level signals and instances first
+        // so that they are preserved in the flattened cell.
+        let mut signals: Vec<_> = cell.signals().map(|(_, info)| info).collect();
+        signals.sort_by_key(|info| info.id);
+        for info in signals.iter() {
+            self.signal_names
+                .reserve_name((Vec::new(), info.id), info.name.clone());
+        }
+        for (id, inst) in cell.instances() {
+            self.instance_names
+                .reserve_name(vec![id], inst.name.clone());
+        }
+
+        let mut bits = HashMap::with_capacity(signals.len());
+        for info in signals {
+            self.add_flat_signal(&[], info, &mut bits);
+        }
+        for port in cell.ports() {
+            self.flat
+                .expose_port(bits[&port.signal][0].signal(), port.direction());
+        }
+        self.flat.params = cell.params.clone();
+        self.flatten_instances(&mut Vec::new(), cell, &bits, &HashMap::new());
+
+        (self.flat, self.mapping, self.renamed_primitives)
+    }
+
+    /// Creates a new signal in the flattened cell corresponding to `info`,
+    /// which lives in the cell at instance path `path`.
+    fn add_flat_signal(
+        &mut self,
+        path: &[InstanceId],
+        info: &SignalInfo,
+        bits: &mut HashMap<SignalId, Vec<SliceOne>>,
+    ) {
+        let key = (path.to_vec(), info.id);
+        let name = match self.signal_names.name(&key) {
+            Some(name) => name,
+            None => {
+                let name = self.hierarchical_name(path, &info.name);
+                self.signal_names.assign_name(key, &name)
+            }
+        };
+        let flat_bits = match info.width {
+            Some(width) => {
+                let bus = self.flat.add_bus(name, width);
+                (0..width).map(|i| bus.index(i)).collect()
+            }
+            None => vec![self.flat.add_node(name)],
+        };
+        bits.insert(info.id, flat_bits);
+    }
+
+    /// Inlines the instances of `cell`, which lives at instance path `path`.
+    ///
+    /// `bits` maps each signal of `cell` to its bits in the flattened cell.
+    /// `bindings` maps each parameter of `cell` to its value in the flattened cell.
+    fn flatten_instances(
+        &mut self,
+        path: &mut Vec<InstanceId>,
+        cell: &Cell,
+        bits: &HashMap<SignalId, Vec<SliceOne>>,
+        bindings: &HashMap<ArcStr, Expr>,
+    ) {
+        let lib = self.lib;
+        // Kept instance name in `cell` -> name in the flattened cell.
+        let mut renamed = HashMap::new();
+        let mut primitives = Vec::new();
+        for info in cell.signals().map(|(_, info)| info) {
+            self.mapping
+                .signal_names
+                .insert((path.clone(), info.name.clone()), info.id);
+            for (i, &bit) in bits[&info.id].iter().enumerate() {
+                let orig = SliceOne::new(info.id, info.width.map(|_| i));
+                self.mapping.signals.insert((path.clone(), orig), bit);
+            }
+        }
+
+        for (id, inst) in cell.instances() {
+            self.mapping
+                .instance_names
+                .insert((path.clone(), inst.name.clone()), id);
+            path.push(id);
+            let connections: HashMap<_, _> = inst
+                .connections()
+                .iter()
+                .map(|(port, conn)| {
+                    let conn_bits: Vec<_> = (0..conn.width())
+                        .map(|i| {
+                            let bit = conn.index(i);
+                            bits[&bit.signal()][bit.index().unwrap_or_default()]
+                        })
+                        .collect();
+                    (port.clone(), conn_bits)
+                })
+                .collect();
+
+            match inst.child() {
+                ChildId::Cell(child_id)
+                    if self.options.depth.map_or(true, |depth| path.len() <= depth) =>
+                {
+                    let child = lib.cell(child_id);
+                    let mut child_bits = HashMap::with_capacity(child.signals.len());
+                    let mut signals: Vec<_> = child.signals().map(|(_, info)| info).collect();
+                    signals.sort_by_key(|info| info.id);
+                    for info in signals {
+                        match connections.get(&info.name) {
+                            Some(conn_bits) if info.is_port() => {
+                                child_bits.insert(info.id, conn_bits.clone());
+                            }
+                            _ => self.add_flat_signal(path, info, &mut child_bits),
+                        }
+                    }
+                    let mut child_bindings = HashMap::with_capacity(child.params.len());
+                    for (name, value) in inst.params() {
+                        if let Some(expr) = param_expr(value.substitute(bindings)) {
+                            child_bindings.insert(name.clone(), expr);
+                        }
+                    }
+                    let mut visiting = HashSet::new();
+                    for name in child.params.keys() {
+                        bind_default(child, name, &mut child_bindings, &mut visiting);
+                    }
+                    self.flatten_instances(path, child, &child_bits, &child_bindings);
+                }
+                child => {
+                    let name = match self.instance_names.name(path) {
+                        Some(name) => name,
+                        None => {
+                            let name = self.hierarchical_name(&path[..path.len() - 1], &inst.name);
+                            self.instance_names.assign_name(path.clone(), &name)
+                        }
+                    };
+                    let mut flat_inst = Instance::new(name.clone(), child);
+                    for (port, conn_bits) in connections {
+                        flat_inst.connect(port, concat_bits(conn_bits));
+                    }
+                    for (name, value) in inst.params() {
+                        flat_inst.set_param(name.clone(), value.substitute(bindings));
+                    }
+                    if name != inst.name {
+                        renamed.insert(inst.name.clone(), name);
+                    }
+                    let flat_id = self.flat.add_instance(flat_inst);
+                    self.mapping.instances.insert(path.clone(), flat_id);
+                    if let ChildId::Primitive(prim) = child {
+                        primitives.push((flat_id, prim));
+                    }
+                }
+            }
+            path.pop();
+        }
+
+        // Primitives may refer to other instances of `cell` by name,
+        // so they are updated to use the names in the flattened cell.
+        let rename = |name: &str| renamed.get(name).cloned();
+        for (flat_id, prim) in primitives {
+            if let Some(primitive) = S::rename_instance_refs(lib.primitive(prim), &rename) {
+                self.renamed_primitives.push((flat_id, primitive));
+            }
+        }
+    }
+
+    /// Prefixes `name` with the names of the instances in `path`.
+    fn hierarchical_name(&self, path: &[InstanceId], name: &str) -> String {
+        let mut cell = self.lib.cell(self.mapping.cell);
+        let mut hname = String::new();
+        for &id in path {
+            let inst = cell.instance(id);
+            hname.push_str(&inst.name);
+            hname.push_str(&self.options.separator);
+            if let ChildId::Cell(child) = inst.child() {
+                cell = self.lib.cell(child);
+            }
+        }
+        hname.push_str(name);
+        hname
+    }
+}
+
+/// Binds parameter `name` of `cell` to its default value,
+/// unless `bindings` already contains a value for it.
+///
+/// The parameters that the default refers to are bound first,
+/// so defaults may refer to other parameters of `cell` in any order.
+fn bind_default(
+    cell: &Cell,
+    name: &ArcStr,
+    bindings: &mut HashMap<ArcStr, Expr>,
+    visiting: &mut HashSet<ArcStr>,
+) {
+    if bindings.contains_key(name) || !visiting.insert(name.clone()) {
+        return;
+    }
+    let default = &cell.params[name];
+    for dep in default.referenced_params() {
+        if cell.params.contains_key(dep) {
+            bind_default(cell, dep, bindings, visiting);
+        }
+    }
+    if let Some(expr) = param_expr(default.substitute(bindings)) {
+        bindings.insert(name.clone(), expr);
+    }
+}
+
+/// Converts a parameter value to an expression that can be substituted into other expressions.
+///
+/// Returns [`None`] for string values.
+fn param_expr(value: ParamValue) -> Option<Expr> {
+    match value {
+        ParamValue::Numeric(value) => Some(Expr::Literal(value)),
+        ParamValue::Expr(expr) => Some(expr),
+        ParamValue::String(_) => None,
+    }
+}
+
+/// Combines a list of bits into a [`Concat`],
+/// merging consecutive bits of the same bus into a single [`Slice`].
+pub(crate) fn concat_bits(bits: Vec<SliceOne>) -> Concat {
+    let mut parts: Vec<Slice> = Vec::with_capacity(bits.len());
+    for bit in bits {
+        if let (Some(last), Some(index)) = (parts.last_mut(), bit.index()) {
+            if let Some(range) = last.range() {
+                if last.signal() == bit.signal() && range.end() == index {
+                    *last = Slice::new(
+                        bit.signal(),
+                        Some(SliceRange::new(range.start(), index + 1)),
+                    );
+                    continue;
+                }
+            }
+        }
+        parts.push(bit.into());
+    }
+    Concat::new(parts)
+}
+
+impl FlattenedMapping {
+    /// The ID of the flattened cell.
+    #[inline]
+    pub fn cell(&self) -> CellId {
+        self.cell
+    }
+
+    /// Converts a path to a signal bit in the original hierarchy
+    /// to a bit of a signal in the flattened cell.
+    ///
+    /// The path must start at the flattened cell and address a signal
+    /// within a cell, rather than a primitive port.
+    /// Returns [`None`] if the path does not address such a signal.
+    pub fn flat_signal(&self, path: &SliceOnePath) -> Option<SliceOne> {
+        let instances = self.resolve_instance_path(path.instances())?;
+        let tail = match path.tail() {
+            SignalPathTail::Id(slice) => *slice,
+            SignalPathTail::Name(slice) => {
+                let signal = *self
+                    .signal_names
+                    .get(&(instances.clone(), slice.signal().clone()))?;
+                SliceOne::new(signal, slice.index())
+            }
+        };
+        self.signals.get(&(instances, tail)).copied()
+    }
+
+    /// Converts a path to an instance in the original hierarchy
+    /// to an instance in the flattened cell.
+    ///
+    /// Returns [`None`] if the path does not address an instance
+    /// that was kept in the flattened cell.
+    pub fn flat_instance(&self, path: &InstancePath) -> Option<InstanceId> {
+        let instances = self.resolve_instance_path(path)?;
+        self.instances.get(&instances).copied()
+    }
+
+    /// Converts an instance path to a list of instance IDs,
+    /// checking that it starts at the flattened cell.
+    fn resolve_instance_path(&self, path: &InstancePath) -> Option<Vec<InstanceId>> {
+        match path.top() {
+            InstancePathCell::Id(id) if *id == self.cell => {}
+            InstancePathCell::Name(name) if *name == self.cell_name => {}
+            _ => return None,
+        }
+        let mut instances = Vec::with_capacity(path.len());
+        for elem in path.iter() {
+            let id = match elem {
+                InstancePathElement::Id(id) => *id,
+                InstancePathElement::Name(name) => *self
+                    .instance_names
+                    .get(&(instances.clone(), name.clone()))?,
+            };
+            instances.push(id);
+        }
+        Some(instances)
+    }
+}
+
+impl<S: RenameSchema + ?Sized> LibraryBuilder<S> {
+    /// Flattens the hierarchy below the given cell into a single cell,
+    /// replacing the cell's contents in place.
+    ///
+    /// Signals and instances from inlined cells are named by joining
+    /// the names of their parent instances with the name of the signal or instance.
+    /// The ports of the cell are unchanged, so existing instances of the cell remain valid.
+    /// Parameters of inlined cells are substituted into the parameter values
+    /// of the instances that are kept in the flattened cell,
+    /// and primitives that refer to other instances by name are
+    /// [updated](RenameSchema::rename_instance_refs) to use their flattened names.
+    /// Child cells are left in the library, even if they are no longer instantiated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell does not exist.
+    #[inline]
+    pub fn flatten_cell(&mut self, id: CellId) -> FlattenedMapping {
+        self.flatten_cell_with_options(id, FlattenOptions::default())
+    }
+
+    /// Flattens the hierarchy below the given cell according to `options`.
+    ///
+    /// See [`LibraryBuilder::flatten_cell`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell does not exist.
+    pub fn flatten_cell_with_options(
+        &mut self,
+        id: CellId,
+        options: FlattenOptions,
+    ) -> FlattenedMapping {
+        let (mut cell, mapping, renamed) = Flattener::new(self, id, options).flatten();
+        for (inst, primitive) in renamed {
+            // The primitive may be shared with other instances, so it is copied.
+            let prim = self.add_primitive(primitive);
+            cell.instances.get_mut(&inst).unwrap().child = ChildId::Primitive(prim);
+        }
+        self.overwrite_cell_with_id(id, cell);
+        mapping
+    }
+}
@@ -36,10 +36,18 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tracing::{span, Level};
 
+pub mod connectivity;
+pub mod erc;
+pub mod flatten;
 pub mod merge;
+pub mod param;
+pub mod reduce;
+pub mod rename;
 pub mod schema;
+pub mod serialization;
 mod slice;
 
+use crate::param::Expr;
 use crate::schema::{FromSchema, NoSchema, NoSchemaError, Schema};
 use crate::validation::ValidatorIssue;
 pub use slice::{Concat, IndexOwned, NamedSlice, NamedSliceOne, Slice, SliceOne, SliceRange};
@@ -58,6 +66,8 @@ pub enum ParamValue {
     String(ArcStr),
     /// A numeric parameter value.
     Numeric(Decimal),
+    /// A parameter value given by an expression.
+    Expr(Expr),
 }
 
 impl From<ArcStr> for ParamValue {
@@ -72,11 +82,18 @@ impl From<Decimal> for ParamValue {
     }
 }
 
+impl From<Expr> for ParamValue {
+    fn from(value: Expr) -> Self {
+        Self::Expr(value)
+    }
+}
+
 impl Display for ParamValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ParamValue::String(s) => write!(f, "{}", s),
             ParamValue::Numeric(n) => write!(f, "{}", n),
+            ParamValue::Expr(e) => write!(f, "{}", e),
         }
     }
 }
@@ -630,7 +647,9 @@ impl Issues {
 }
 
 /// Port directions.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default, Serialize, Deserialize)]
+#[derive(
+    Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Default, Serialize, Deserialize,
+)]
 pub enum Direction {
     /// Input.
     Input,
@@ -752,7 +771,14 @@ pub struct Instance {
     ///
     /// The ports are the ports of the **child** cell.
     /// The connected signals are signals of the **parent** cell.
+    #[serde(serialize_with = "serialization::serialize_sorted")]
     connections: HashMap<ArcStr, Concat>,
+    /// A map mapping parameter names to values.
+    ///
+    /// The parameters are the parameters of the **child** cell.
+    /// Values may refer to parameters of the **parent** cell.
+    #[serde(serialize_with = "serialization::serialize_sorted")]
+    params: HashMap<ArcStr, ParamValue>,
 }
 
 /// The ID of an instance's child.
@@ -780,7 +806,7 @@ impl From<PrimitiveId> for ChildId {
 }
 
 /// A cell.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct Cell {
     /// The last signal ID used.
     ///
@@ -803,6 +829,8 @@ pub struct Cell {
     ///
     /// Instance names are only guaranteed to be unique in a validated [`Library`].
     instance_name_map: HashMap<ArcStr, InstanceId>,
+    /// The parameters declared by this cell and their default values.
+    pub(crate) params: IndexMap<ArcStr, ParamValue>,
 }
 
 /// Metadata associated with the conversion from a SCIR library to a netlist.
@@ -1440,6 +1468,7 @@ impl Cell {
             instance_id: 0,
             instances: IndexMap::new(),
             instance_name_map: HashMap::new(),
+            params: IndexMap::new(),
         }
     }
 
@@ -1607,6 +1636,27 @@ impl Cell {
     pub fn instances(&self) -> impl Iterator<Item = (InstanceId, &Instance)> {
         self.instances.iter().map(|x| (*x.0, x.1))
     }
+
+    /// Removes the instance with the given ID, if it exists.
+    pub(crate) fn remove_instance(&mut self, id: InstanceId) {
+        if let Some(inst) = self.instances.shift_remove(&id) {
+            if self.instance_name_map.get(&inst.name) == Some(&id) {
+                self.instance_name_map.remove(&inst.name);
+            }
+        }
+    }
+
+    /// Removes the signal with the given ID, if it exists.
+    ///
+    /// The signal must not be a port or be connected to any instance.
+    pub(crate) fn remove_signal(&mut self, id: SignalId) {
+        if let Some(info) = self.signals.remove(&id) {
+            debug_assert!(info.port.is_none());
+            if self.signal_name_map.get(&info.name) == Some(&id) {
+                self.signal_name_map.remove(&info.name);
+            }
+        }
+    }
 }
 
 impl Instance {
@@ -1616,6 +1666,7 @@ impl Instance {
             child: child.into(),
             name: name.into(),
             connections: HashMap::new(),
+            params: HashMap::new(),
         }
     }
 
@@ -0,0 +1,783 @@
+//! Parameter expressions and parameterized cells.
+//!
+//! Cells may declare parameters with default values, and instances of those cells
+//! may override them. Parameter values may be [expressions](Expr) that refer to the
+//! parameters of the enclosing cell.
+//!
+//! Netlisters that support parameters can emit expressions verbatim,
+//! while other consumers can evaluate them using [`LibraryBuilder::eval_params`].
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use rust_decimal::MathematicalOps;
+
+use super::*;
+
+/// A parameter expression.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Expr {
+    /// A numeric literal.
+    Literal(Decimal),
+    /// A reference to a parameter of the enclosing cell.
+    Param(ArcStr),
+    /// A unary operation.
+    Unary(UnaryOp, Box<Expr>),
+    /// A binary operation.
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// A call to a built-in function.
+    Call(Function, Vec<Expr>),
+}
+
+/// A unary operator.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnaryOp {
+    /// Negation.
+    Neg,
+}
+
+/// A binary operator.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinaryOp {
+    /// Addition.
+    Add,
+    /// Subtraction.
+    Sub,
+    /// Multiplication.
+    Mul,
+    /// Division.
+    Div,
+    /// Exponentiation.
+    Pow,
+}
+
+/// A built-in function.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Function {
+    /// Absolute value.
+    Abs,
+    /// Square root.
+    Sqrt,
+    /// Natural exponential.
+    Exp,
+    /// Natural logarithm.
+    Log,
+    /// Base 10 logarithm.
+    Log10,
+    /// Exponentiation.
+    Pow,
+    /// The smaller of two values.
+    Min,
+    /// The larger of two values.
+    Max,
+    /// Sine.
+    Sin,
+    /// Cosine.
+    Cos,
+    /// Tangent.
+    Tan,
+    /// Rounds down to the nearest integer.
+    Floor,
+    /// Rounds up to the nearest integer.
+    Ceil,
+}
+
+/// An error parsing a parameter expression.
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error("invalid expression: {message} at offset {offset}")]
+pub struct ParseError {
+    offset: usize,
+    message: &'static str,
+}
+
+/// An error evaluating a parameter expression.
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+pub enum EvalError {
+    /// An expression referenced a parameter that is not defined.
+    #[error("undefined parameter `{0}`")]
+    UndefinedParam(ArcStr),
+    /// A parameter default value depends on itself.
+    #[error("parameter `{0}` is defined in terms of itself")]
+    CyclicParam(ArcStr),
+    /// A parameter has a string value, which cannot be evaluated to a number.
+    #[error("parameter value `{0}` is not numeric")]
+    NotNumeric(ArcStr),
+    /// A function was called with the wrong number of arguments.
+    #[error("function `{function}` expects {expected} argument(s), but {found} were given")]
+    WrongArity {
+        /// The function being called.
+        function: Function,
+        /// The expected number of arguments.
+        expected: usize,
+        /// The number of arguments given.
+        found: usize,
+    },
+    /// Division by zero.
+    #[error("division by zero")]
+    DivisionByZero,
+    /// The result of an operation overflowed or was undefined.
+    #[error("arithmetic error evaluating `{0}`")]
+    Arithmetic(ArcStr),
+}
+
+impl Function {
+    const ALL: [Function; 13] = [
+        Function::Abs,
+        Function::Sqrt,
+        Function::Exp,
+        Function::Log,
+        Function::Log10,
+        Function::Pow,
+        Function::Min,
+        Function::Max,
+        Function::Sin,
+        Function::Cos,
+        Function::Tan,
+        Function::Floor,
+        Function::Ceil,
+    ];
+
+    /// The name of the function.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Abs => "abs",
+            Function::Sqrt => "sqrt",
+            Function::Exp => "exp",
+            Function::Log => "log",
+            Function::Log10 => "log10",
+            Function::Pow => "pow",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+        }
+    }
+
+    /// Looks up a function by name, ignoring case.
+    ///
+    /// `ln` is accepted as an alias for [`Function::Log`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("ln") {
+            return Some(Function::Log);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The number of arguments taken by the function.
+    pub fn arity(&self) -> usize {
+        match self {
+            Function::Pow | Function::Min | Function::Max => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl BinaryOp {
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Add | BinaryOp::Sub => 1,
+            BinaryOp::Mul | BinaryOp::Div => 2,
+            BinaryOp::Pow => 4,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "**",
+        }
+    }
+}
+
+/// The precedence of unary operators.
+const UNARY_PRECEDENCE: u8 = 3;
+/// The precedence of literals, parameter references and function calls.
+const ATOM_PRECEDENCE: u8 = 5;
+
+impl Expr {
+    /// Parses an expression.
+    ///
+    /// Expressions use SPICE-like syntax: numbers with optional SI suffixes (e.g. `1.5u`),
+    /// parameter names, the operators `+`, `-`, `*`, `/` and `**` (or `^`),
+    /// parentheses and calls to [built-in functions](Function).
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        let mut parser = ExprParser {
+            src: s.as_bytes(),
+            pos: 0,
+        };
+        let expr = parser.parse_sum()?;
+        parser.skip_ws();
+        if parser.pos < parser.src.len() {
+            return Err(parser.err("unexpected trailing characters"));
+        }
+        Ok(expr)
+    }
+
+    /// Creates a reference to the given parameter.
+    #[inline]
+    pub fn param(name: impl Into<ArcStr>) -> Self {
+        Self::Param(name.into())
+    }
+
+    /// Returns the names of the parameters referenced by this expression.
+    pub fn referenced_params(&self) -> HashSet<&ArcStr> {
+        let mut params = HashSet::new();
+        let mut stack = vec![self];
+        while let Some(expr) = stack.pop() {
+            match expr {
+                Expr::Literal(_) => {}
+                Expr::Param(name) => {
+                    params.insert(name);
+                }
+                Expr::Unary(_, arg) => stack.push(arg),
+                Expr::Binary(_, lhs, rhs) => {
+                    stack.push(lhs);
+                    stack.push(rhs);
+                }
+                Expr::Call(_, args) => stack.extend(args),
+            }
+        }
+        params
+    }
+
+    /// Replaces references to parameters in `bindings` with the bound expressions.
+    ///
+    /// References to parameters not in `bindings` are left unchanged.
+    pub fn substitute(&self, bindings: &HashMap<ArcStr, Expr>) -> Expr {
+        match self {
+            Expr::Literal(_) => self.clone(),
+            Expr::Param(name) => bindings.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Expr::Unary(op, arg) => Expr::Unary(*op, Box::new(arg.substitute(bindings))),
+            Expr::Binary(op, lhs, rhs) => Expr::Binary(
+                *op,
+                Box::new(lhs.substitute(bindings)),
+                Box::new(rhs.substitute(bindings)),
+            ),
+            Expr::Call(f, args) => Expr::Call(
+                *f,
+                args.iter().map(|arg| arg.substitute(bindings)).collect(),
+            ),
+        }
+    }
+
+    /// Evaluates this expression, looking up parameter references in `params`.
+    pub fn eval(&self, params: &HashMap<ArcStr, Decimal>) -> Result<Decimal, EvalError> {
+        let arithmetic = || EvalError::Arithmetic(self.to_string().into());
+        match self {
+            Expr::Literal(value) => Ok(*value),
+            Expr::Param(name) => params
+                .get(name)
+                .copied()
+                .ok_or_else(|| EvalError::UndefinedParam(name.clone())),
+            Expr::Unary(UnaryOp::Neg, arg) => Ok(-arg.eval(params)?),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(params)?;
+                let rhs = rhs.eval(params)?;
+                match op {
+                    BinaryOp::Add => lhs.checked_add(rhs),
+                    BinaryOp::Sub => lhs.checked_sub(rhs),
+                    BinaryOp::Mul => lhs.checked_mul(rhs),
+                    BinaryOp::Div => {
+                        if rhs.is_zero() {
+                            return Err(EvalError::DivisionByZero);
+                        }
+                        lhs.checked_div(rhs)
+                    }
+                    BinaryOp::Pow => pow(lhs, rhs),
+                }
+                .ok_or_else(arithmetic)
+            }
+            Expr::Call(f, args) => {
+                if args.len() != f.arity() {
+                    return Err(EvalError::WrongArity {
+                        function: *f,
+                        expected: f.arity(),
+                        found: args.len(),
+                    });
+                }
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(params))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let x = args[0];
+                match f {
+                    Function::Abs => Some(x.abs()),
+                    Function::Sqrt => x.sqrt(),
+                    Function::Exp => x.checked_exp(),
+                    Function::Log => x.checked_ln(),
+                    Function::Log10 => x.checked_log10(),
+                    Function::Pow => pow(x, args[1]),
+                    Function::Min => Some(x.min(args[1])),
+                    Function::Max => Some(x.max(args[1])),
+                    Function::Sin => x.checked_sin(),
+                    Function::Cos => x.checked_cos(),
+                    Function::Tan => x.checked_tan(),
+                    Function::Floor => Some(x.floor()),
+                    Function::Ceil => Some(x.ceil()),
+                }
+                .ok_or_else(arithmetic)
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Literal(value) if value.is_sign_negative() => UNARY_PRECEDENCE,
+            Expr::Literal(_) | Expr::Param(_) | Expr::Call(..) => ATOM_PRECEDENCE,
+            Expr::Unary(..) => UNARY_PRECEDENCE,
+            Expr::Binary(op, ..) => op.precedence(),
+        }
+    }
+
+    /// Writes this expression, parenthesized if its precedence is less than `min_precedence`.
+    fn fmt_with_precedence(&self, f: &mut Formatter<'_>, min_precedence: u8) -> std::fmt::Result {
+        let parens = self.precedence() < min_precedence;
+        if parens {
+            write!(f, "(")?;
+        }
+        match self {
+            Expr::Literal(value) => write!(f, "{}", value.normalize())?,
+            Expr::Param(name) => write!(f, "{}", name)?,
+            Expr::Unary(UnaryOp::Neg, arg) => {
+                write!(f, "-")?;
+                arg.fmt_with_precedence(f, UNARY_PRECEDENCE + 1)?;
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs_precedence, rhs_precedence) = match op {
+                    // Exponentiation is right-associative.
+                    BinaryOp::Pow => (op.precedence() + 1, UNARY_PRECEDENCE),
+                    _ => (op.precedence(), op.precedence() + 1),
+                };
+                lhs.fmt_with_precedence(f, lhs_precedence)?;
+                write!(f, "{}", op.symbol())?;
+                rhs.fmt_with_precedence(f, rhs_precedence)?;
+            }
+            Expr::Call(func, args) => {
+                write!(f, "{}(", func)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    arg.fmt_with_precedence(f, 0)?;
+                }
+                write!(f, ")")?;
+            }
+        }
+        if parens {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+fn pow(base: Decimal, exp: Decimal) -> Option<Decimal> {
+    if exp.fract().is_zero() {
+        base.checked_powi(i64::try_from(exp).ok()?)
+    } else {
+        base.checked_powd(exp)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with_precedence(f, 0)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<Decimal> for Expr {
+    fn from(value: Decimal) -> Self {
+        Self::Literal(value)
+    }
+}
+
+struct ExprParser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn err(&self, message: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.pos < self.src.len() && self.src[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.src.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.src[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.parse_product()?;
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_product()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = if self.src[self.pos..].starts_with(b"**") {
+                return Ok(lhs);
+            } else if self.eat("*") {
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat("-") {
+            let arg = self.parse_unary()?;
+            Ok(match arg {
+                Expr::Literal(value) if value.is_sign_positive() => Expr::Literal(-value),
+                arg => Expr::Unary(UnaryOp::Neg, Box::new(arg)),
+            })
+        } else if self.eat("+") {
+            self.parse_unary()
+        } else {
+            self.parse_power()
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.parse_atom()?;
+        if self.eat("**") || self.eat("^") {
+            let exp = self.parse_unary()?;
+            Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(exp)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let expr = self.parse_sum()?;
+                if !self.eat(")") {
+                    return Err(self.err("expected `)`"));
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => self.parse_number(),
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = self.pos;
+                while self.pos < self.src.len()
+                    && (self.src[self.pos].is_ascii_alphanumeric() || self.src[self.pos] == b'_')
+                {
+                    self.pos += 1;
+                }
+                let name = std::str::from_utf8(&self.src[start..self.pos]).unwrap();
+                if self.peek() != Some(b'(') {
+                    return Ok(Expr::Param(name.into()));
+                }
+                let func = Function::from_name(name).ok_or(ParseError {
+                    offset: start,
+                    message: "unknown function",
+                })?;
+                self.pos += 1;
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.parse_sum()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return Err(self.err("expected `,` or `)`"));
+                        }
+                    }
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(_) => Err(self.err("unexpected character")),
+            None => Err(self.err("unexpected end of expression")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Expr, ParseError> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            while p.pos < p.src.len() && p.src[p.pos].is_ascii_digit() {
+                p.pos += 1;
+            }
+        };
+        digits(self);
+        if self.src.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        let mantissa = std::str::from_utf8(&self.src[start..self.pos]).unwrap();
+        let mut value = Decimal::from_str(mantissa).map_err(|_| ParseError {
+            offset: start,
+            message: "invalid number",
+        })?;
+
+        // Parse an exponent, if the `e` is followed by an integer.
+        let rest = &self.src[self.pos..];
+        if let Some(b'e' | b'E') = rest.first() {
+            let sign = matches!(rest.get(1), Some(b'+' | b'-')) as usize;
+            if rest.get(1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                let exp_start = self.pos + 1;
+                self.pos += 1 + sign;
+                digits(self);
+                let exp = std::str::from_utf8(&self.src[exp_start..self.pos]).unwrap();
+                let exp: i64 = exp.parse().map_err(|_| ParseError {
+                    offset: exp_start,
+                    message: "invalid exponent",
+                })?;
+                value = scale(value, exp).ok_or(ParseError {
+                    offset: start,
+                    message: "number out of range",
+                })?;
+            }
+        }
+
+        // Parse an SI suffix. Any trailing letters (e.g. units) are ignored.
+        let suffix_start = self.pos;
+        while self.pos < self.src.len() && self.src[self.pos].is_ascii_alphabetic() {
+            self.pos += 1;
+        }
+        let suffix = std::str::from_utf8(&self.src[suffix_start..self.pos])
+            .unwrap()
+            .to_ascii_lowercase();
+        let exp = if suffix.starts_with("meg") {
+            6
+        } else {
+            match suffix.chars().next() {
+                Some('t') => 12,
+                Some('g') => 9,
+                Some('x') => 6,
+                Some('k') => 3,
+                Some('m') => -3,
+                Some('u') => -6,
+                Some('n') => -9,
+                Some('p') => -12,
+                Some('f') => -15,
+                Some('a') => -18,
+                _ => 0,
+            }
+        };
+        value = scale(value, exp).ok_or(ParseError {
+            offset: start,
+            message: "number out of range",
+        })?;
+        Ok(Expr::Literal(value.normalize()))
+    }
+}
+
+/// Multiplies `value` by `10^exp`.
+fn scale(value: Decimal, exp: i64) -> Option<Decimal> {
+    Decimal::TEN
+        .checked_powi(exp)
+        .and_then(|s| value.checked_mul(s))
+}
+
+impl ParamValue {
+    /// Evaluates this value to a number, looking up parameter references in `params`.
+    ///
+    /// String values are parsed as expressions.
+    pub fn eval(&self, params: &HashMap<ArcStr, Decimal>) -> Result<Decimal, EvalError> {
+        match self {
+            ParamValue::Numeric(value) => Ok(*value),
+            ParamValue::Expr(expr) => expr.eval(params),
+            ParamValue::String(s) => Expr::parse(s)
+                .map_err(|_| EvalError::NotNumeric(s.clone()))?
+                .eval(params),
+        }
+    }
+
+    /// Replaces references to parameters in `bindings` with the bound expressions.
+    ///
+    /// If the resulting expression no longer references any parameters,
+    /// it is evaluated to a numeric value.
+    /// String values are left unchanged.
+    pub fn substitute(&self, bindings: &HashMap<ArcStr, Expr>) -> ParamValue {
+        match self {
+            ParamValue::Expr(expr) => {
+                let expr = expr.substitute(bindings);
+                if expr.referenced_params().is_empty() {
+                    if let Ok(value) = expr.eval(&HashMap::new()) {
+                        return ParamValue::Numeric(value);
+                    }
+                }
+                ParamValue::Expr(expr)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns the names of the parameters referenced by this value.
+    pub fn referenced_params(&self) -> HashSet<&ArcStr> {
+        match self {
+            ParamValue::Expr(expr) => expr.referenced_params(),
+            _ => HashSet::new(),
+        }
+    }
+}
+
+impl Cell {
+    /// Declares a parameter with the given default value.
+    ///
+    /// The default value may refer to other parameters of this cell.
+    /// If the parameter was already declared, its default value is replaced.
+    pub fn add_param(&mut self, name: impl Into<ArcStr>, default: impl Into<ParamValue>) {
+        self.params.insert(name.into(), default.into());
+    }
+
+    /// Iterates over the parameters declared by this cell and their default values.
+    pub fn params(&self) -> impl Iterator<Item = (&ArcStr, &ParamValue)> {
+        self.params.iter()
+    }
+
+    /// The default value of the given parameter,
+    /// or [`None`] if this cell does not declare the parameter.
+    pub fn param(&self, name: &str) -> Option<&ParamValue> {
+        self.params.get(name)
+    }
+
+    /// Evaluates the parameters of this cell,
+    /// using the values in `overrides` in place of the defaults.
+    ///
+    /// Default values may refer to other parameters of this cell, in any order.
+    pub fn eval_params(
+        &self,
+        overrides: &HashMap<ArcStr, Decimal>,
+    ) -> Result<HashMap<ArcStr, Decimal>, EvalError> {
+        let mut values = HashMap::with_capacity(self.params.len());
+        for name in self.params.keys() {
+            if let Some(value) = overrides.get(name) {
+                values.insert(name.clone(), *value);
+            }
+        }
+        let mut visiting = HashSet::new();
+        for name in self.params.keys() {
+            self.eval_param(name, &mut values, &mut visiting)?;
+        }
+        Ok(values)
+    }
+
+    fn eval_param(
+        &self,
+        name: &ArcStr,
+        values: &mut HashMap<ArcStr, Decimal>,
+        visiting: &mut HashSet<ArcStr>,
+    ) -> Result<(), EvalError> {
+        if values.contains_key(name) {
+            return Ok(());
+        }
+        if !visiting.insert(name.clone()) {
+            return Err(EvalError::CyclicParam(name.clone()));
+        }
+        let default = &self.params[name];
+        for dep in default.referenced_params() {
+            if self.params.contains_key(dep) {
+                self.eval_param(dep, values, visiting)?;
+            }
+        }
+        let value = default.eval(values)?;
+        visiting.remove(name);
+        values.insert(name.clone(), value);
+        Ok(())
+    }
+}
+
+impl Instance {
+    /// Sets the value of a parameter of the child cell.
+    ///
+    /// The value may refer to parameters of the parent cell.
+    pub fn set_param(&mut self, name: impl Into<ArcStr>, value: impl Into<ParamValue>) {
+        self.params.insert(name.into(), value.into());
+    }
+
+    /// The parameter values set on this instance.
+    #[inline]
+    pub fn params(&self) -> &HashMap<ArcStr, ParamValue> {
+        &self.params
+    }
+
+    /// The value of the given parameter set on this instance, if any.
+    pub fn param(&self, name: &str) -> Option<&ParamValue> {
+        self.params.get(name)
+    }
+}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S> {
+    /// Evaluates the parameters of the cell instantiated at the end of `path`.
+    ///
+    /// Parameters of the top cell of the path take their default values.
+    /// Parameters of each instance along the path are evaluated
+    /// in the context of the parameters of its parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path does not exist, or if it contains instances of primitives.
+    pub fn eval_params(&self, path: &InstancePath) -> Result<HashMap<ArcStr, Decimal>, EvalError> {
+        let (_, mut cell) = self.convert_instance_path_cell(path.top()).unwrap();
+        let mut values = cell.eval_params(&HashMap::new())?;
+        for elem in path.iter() {
+            let inst = cell.instance_from_path_element(elem);
+            let overrides = inst
+                .params
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), value.eval(&values)?)))
+                .collect::<Result<HashMap<_, _>, EvalError>>()?;
+            cell = self.cell(inst.child.unwrap_cell());
+            values = cell.eval_params(&overrides)?;
+        }
+        Ok(values)
+    }
+}
@@ -0,0 +1,362 @@
+//! Netlist reduction passes for SCIR libraries.
+//!
+//! Each pass is optional and simplifies the library in place, without changing
+//! its electrical behavior as seen from the ports of its cells.
+//! Passes that need to inspect primitives require the schema to implement [`ReduceSchema`].
+
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+use crate::connectivity::{bits, CellConnectivity, Terminal};
+use crate::flatten::concat_bits;
+use crate::schema::StringSchema;
+
+/// A two-terminal resistor primitive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Resistor {
+    /// The names of the two ports of the resistor.
+    pub ports: [ArcStr; 2],
+    /// The resistance, in ohms.
+    pub value: Decimal,
+}
+
+/// A schema whose primitives can be simplified by netlist reduction passes.
+///
+/// Every method has a default implementation that disables the corresponding reduction.
+pub trait ReduceSchema: Schema {
+    /// Returns a primitive equivalent to `a` and `b` connected in parallel,
+    /// or [`None`] if they cannot be merged.
+    ///
+    /// Only called for instances with identical connections,
+    /// up to swapping [interchangeable ports](ReduceSchema::interchangeable_ports).
+    fn merge_parallel(_a: &Self::Primitive, _b: &Self::Primitive) -> Option<Self::Primitive> {
+        None
+    }
+
+    /// Pairs of ports of `primitive` that can be swapped without changing its behavior,
+    /// such as the drain and source of a MOSFET.
+    fn interchangeable_ports(_primitive: &Self::Primitive) -> Vec<(ArcStr, ArcStr)> {
+        Vec::new()
+    }
+
+    /// Returns the ports and resistance of `primitive`
+    /// if it is a two-terminal linear resistor.
+    fn resistor(_primitive: &Self::Primitive) -> Option<Resistor> {
+        None
+    }
+
+    /// Returns a resistor with the same ports as the resistor `template`
+    /// but with resistance `value`.
+    fn with_resistance(_template: &Self::Primitive, _value: Decimal) -> Option<Self::Primitive> {
+        None
+    }
+}
+
+impl ReduceSchema for NoSchema {}
+
+impl ReduceSchema for StringSchema {}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S> {
+    /// Removes every cell that is not the top cell and is not instantiated,
+    /// directly or indirectly, by the top cell.
+    ///
+    /// Primitives that are no longer instantiated are also removed.
+    /// Does nothing if the library has no top cell.
+    pub fn remove_unreferenced_cells(&mut self) {
+        let Some(top) = self.top else {
+            return;
+        };
+        let mut reachable = HashSet::from([top]);
+        let mut stack = vec![top];
+        while let Some(id) = stack.pop() {
+            for (_, inst) in self.cell(id).instances() {
+                if let ChildId::Cell(child) = inst.child() {
+                    if reachable.insert(child) {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        self.cells.retain(|id, _| reachable.contains(id));
+        self.name_map.retain(|_, id| reachable.contains(id));
+
+        let primitives: HashSet<_> = self
+            .cells
+            .values()
+            .flat_map(|cell| cell.instances.values())
+            .filter_map(|inst| inst.child().into_primitive())
+            .collect();
+        self.primitives.retain(|id, _| primitives.contains(id));
+    }
+
+    /// Removes dangling instances and nets from every cell.
+    ///
+    /// An instance is dangling if every bit connected to it is an internal net
+    /// that is not connected to any other instance. Removing instances may
+    /// leave other instances dangling, so instances are removed until none remain.
+    /// Internal signals with no connections are then removed.
+    pub fn remove_dangling(&mut self) {
+        let ids: Vec<_> = self.cells.keys().copied().collect();
+        for id in ids {
+            loop {
+                let cell = self.cell(id);
+                let connectivity = CellConnectivity::new(self, cell);
+                let dangling: Vec<_> = cell
+                    .instances()
+                    .filter(|(inst_id, inst)| {
+                        !inst.connections().is_empty()
+                            && inst.connections().values().all(|conn| {
+                                conn.parts().flat_map(bits).all(|bit| {
+                                    !cell.signal(bit.signal()).is_port()
+                                        && connectivity
+                                            .terminals(bit)
+                                            .iter()
+                                            .all(|t| t.instance() == *inst_id)
+                                })
+                            })
+                    })
+                    .map(|(inst_id, _)| inst_id)
+                    .collect();
+                if dangling.is_empty() {
+                    break;
+                }
+                let cell = self.cells.get_mut(&id).unwrap();
+                for inst in dangling {
+                    cell.remove_instance(inst);
+                }
+            }
+
+            let cell = self.cell(id);
+            let connected: HashSet<_> = cell
+                .instances()
+                .flat_map(|(_, inst)| inst.connections().values())
+                .flat_map(|conn| conn.parts().map(|part| part.signal()))
+                .collect();
+            let unused: Vec<_> = cell
+                .signals()
+                .filter(|(signal, info)| !info.is_port() && !connected.contains(signal))
+                .map(|(signal, _)| signal)
+                .collect();
+            let cell = self.cells.get_mut(&id).unwrap();
+            for signal in unused {
+                cell.remove_signal(signal);
+            }
+        }
+    }
+}
+
+impl<S: ReduceSchema + ?Sized> LibraryBuilder<S> {
+    /// Merges primitive instances connected in parallel.
+    ///
+    /// Instances with identical connections (up to swapping interchangeable ports)
+    /// are replaced by a single instance of the primitive returned by
+    /// [`ReduceSchema::merge_parallel`], which usually sets a multiplier.
+    /// The merged instance keeps the name of the first instance.
+    pub fn merge_parallel_devices(&mut self) {
+        let ids: Vec<_> = self.cells.keys().copied().collect();
+        for id in ids {
+            // Connection key -> kept instances with those connections.
+            let mut groups: HashMap<Vec<(ArcStr, Vec<SliceOne>)>, Vec<InstanceId>> = HashMap::new();
+            // Kept instance -> merged primitive.
+            let mut merged: IndexMap<InstanceId, S::Primitive> = IndexMap::new();
+            let mut removed = Vec::new();
+
+            let cell = self.cell(id);
+            for (inst_id, inst) in cell.instances() {
+                let ChildId::Primitive(prim_id) = inst.child() else {
+                    continue;
+                };
+                let prim = self.primitive(prim_id);
+                let group = groups.entry(parallel_key::<S>(inst, prim)).or_default();
+                let target = group.iter().find_map(|&other| {
+                    let other_prim = merged.get(&other).unwrap_or_else(|| {
+                        self.primitive(cell.instance(other).child().unwrap_primitive())
+                    });
+                    S::merge_parallel(other_prim, prim).map(|prim| (other, prim))
+                });
+                match target {
+                    Some((other, prim)) => {
+                        merged.insert(other, prim);
+                        removed.push(inst_id);
+                    }
+                    None => group.push(inst_id),
+                }
+            }
+
+            for (inst_id, prim) in merged {
+                let prim_id = self.add_primitive(prim);
+                let cell = self.cells.get_mut(&id).unwrap();
+                cell.instances.get_mut(&inst_id).unwrap().child = prim_id.into();
+            }
+            let cell = self.cells.get_mut(&id).unwrap();
+            for inst_id in removed {
+                cell.remove_instance(inst_id);
+            }
+        }
+    }
+
+    /// Replaces chains of resistors connected in series through internal nets
+    /// with single resistors.
+    ///
+    /// A net is only eliminated if it is not a port and connects exactly two resistors.
+    pub fn reduce_series_resistors(&mut self) {
+        let ids: Vec<_> = self.cells.keys().copied().collect();
+        for id in ids {
+            loop {
+                let cell = self.cell(id);
+                let connectivity = CellConnectivity::new(self, cell);
+                // Instances already modified in this iteration.
+                let mut touched = HashSet::new();
+                // (kept instance, port to reconnect, new net, new primitive, removed instance)
+                let mut merges = Vec::new();
+                let mut nets: Vec<_> = connectivity.nets().collect();
+                nets.sort_by_key(|(net, _)| *net);
+                for (net, terminals) in nets {
+                    if cell.signal(net.signal()).is_port() {
+                        continue;
+                    }
+                    let [a, b] = terminals else {
+                        continue;
+                    };
+                    if a.instance() == b.instance()
+                        || touched.contains(&a.instance())
+                        || touched.contains(&b.instance())
+                    {
+                        continue;
+                    }
+                    let resistor = |t: &Terminal| {
+                        let prim = self.primitive(t.child().into_primitive()?);
+                        let res = S::resistor(prim)?;
+                        let inst = cell.instance(t.instance());
+                        let other = res.ports.iter().find(|port| **port != *t.port())?;
+                        let other_net = inst.connections().get(other)?;
+                        (other_net.width() == 1 && inst.connections().len() == 2)
+                            .then(|| (prim, res.value, other_net.index(0)))
+                    };
+                    let (Some((prim_a, value_a, _)), Some((_, value_b, end_b))) =
+                        (resistor(a), resistor(b))
+                    else {
+                        continue;
+                    };
+                    if end_b == net {
+                        continue;
+                    }
+                    let Some(prim) = S::with_resistance(prim_a, value_a + value_b) else {
+                        continue;
+                    };
+                    touched.insert(a.instance());
+                    touched.insert(b.instance());
+                    merges.push((a.instance(), a.port().clone(), end_b, prim, b.instance()));
+                }
+                if merges.is_empty() {
+                    break;
+                }
+                for (inst_id, port, net, prim, removed) in merges {
+                    let prim_id = self.add_primitive(prim);
+                    let cell = self.cells.get_mut(&id).unwrap();
+                    let inst = cell.instances.get_mut(&inst_id).unwrap();
+                    inst.child = prim_id.into();
+                    inst.connect(port, net);
+                    cell.remove_instance(removed);
+                }
+            }
+        }
+    }
+
+    /// Removes zero-ohm resistors, merging the nets they connect.
+    ///
+    /// Merged nets are renamed to a port if one of them is a port.
+    /// Resistors that connect two different ports are kept,
+    /// since ports cannot be merged.
+    pub fn collapse_zero_ohm_resistors(&mut self) {
+        let ids: Vec<_> = self.cells.keys().copied().collect();
+        for id in ids {
+            let cell = self.cell(id);
+            let mut parents: HashMap<SliceOne, SliceOne> = HashMap::new();
+            let find = |parents: &HashMap<SliceOne, SliceOne>, mut bit: SliceOne| {
+                while let Some(&parent) = parents.get(&bit) {
+                    bit = parent;
+                }
+                bit
+            };
+            let mut removed = Vec::new();
+            for (inst_id, inst) in cell.instances() {
+                let Some(res) = inst
+                    .child()
+                    .into_primitive()
+                    .and_then(|prim| S::resistor(self.primitive(prim)))
+                else {
+                    continue;
+                };
+                if !res.value.is_zero() {
+                    continue;
+                }
+                let (Some(a), Some(b)) = (
+                    inst.connections().get(&res.ports[0]),
+                    inst.connections().get(&res.ports[1]),
+                ) else {
+                    continue;
+                };
+                if a.width() != 1 || b.width() != 1 {
+                    continue;
+                }
+                let (a, b) = (find(&parents, a.index(0)), find(&parents, b.index(0)));
+                let (a_port, b_port) = (
+                    cell.signal(a.signal()).is_port(),
+                    cell.signal(b.signal()).is_port(),
+                );
+                if a != b {
+                    match (a_port, b_port) {
+                        (true, true) => continue,
+                        (false, true) => parents.insert(a, b),
+                        _ => parents.insert(b, a),
+                    };
+                }
+                removed.push(inst_id);
+            }
+            if removed.is_empty() {
+                continue;
+            }
+
+            let cell = self.cells.get_mut(&id).unwrap();
+            for inst_id in removed {
+                cell.remove_instance(inst_id);
+            }
+            for inst in cell.instances.values_mut() {
+                for conn in inst.connections.values_mut() {
+                    *conn = concat_bits(
+                        conn.parts()
+                            .flat_map(bits)
+                            .map(|bit| find(&parents, bit))
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A key identifying the connections of a primitive instance,
+/// with interchangeable ports in a canonical order.
+fn parallel_key<S: ReduceSchema + ?Sized>(
+    inst: &Instance,
+    prim: &S::Primitive,
+) -> Vec<(ArcStr, Vec<SliceOne>)> {
+    let mut conns: HashMap<ArcStr, Vec<SliceOne>> = inst
+        .connections()
+        .iter()
+        .map(|(port, conn)| (port.clone(), conn.parts().flat_map(bits).collect()))
+        .collect();
+    for (a, b) in S::interchangeable_ports(prim) {
+        if let (Some(conn_a), Some(conn_b)) = (conns.get(&a), conns.get(&b)) {
+            if conn_a > conn_b {
+                let conn_a = conns.remove(&a).unwrap();
+                let conn_b = conns.insert(b, conn_a).unwrap();
+                conns.insert(a, conn_b);
+            }
+        }
+    }
+    let mut key: Vec<_> = conns.into_iter().collect();
+    key.sort();
+    key
+}
@@ -0,0 +1,230 @@
+//! Renaming of cells, signals and instances.
+//!
+//! Netlist formats differ in which characters they allow in identifiers.
+//! [`LibraryBuilder::legalize_names`] rewrites every name in a library
+//! using a target-specific legalization function, keeping names unique.
+
+use std::collections::HashSet;
+
+use super::*;
+use crate::schema::StringSchema;
+
+/// A schema whose primitives may refer to other instances in the same cell by name,
+/// such as a current-controlled source that refers to its controlling voltage source.
+pub trait RenameSchema: Schema {
+    /// Returns a copy of `primitive` that refers to instances by their new names,
+    /// or [`None`] if `primitive` does not refer to any renamed instance.
+    ///
+    /// `rename` returns the new name of the instance with the given name,
+    /// or [`None`] if the instance was not renamed.
+    fn rename_instance_refs(
+        _primitive: &Self::Primitive,
+        _rename: &dyn Fn(&str) -> Option<ArcStr>,
+    ) -> Option<Self::Primitive> {
+        None
+    }
+}
+
+impl RenameSchema for NoSchema {}
+
+impl RenameSchema for StringSchema {}
+
+impl Cell {
+    /// Renames the given instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no instance with the given ID exists.
+    pub fn rename_instance(&mut self, id: InstanceId, name: impl Into<ArcStr>) {
+        let name = name.into();
+        let inst = self.instances.get_mut(&id).unwrap();
+        if self.instance_name_map.get(&inst.name) == Some(&id) {
+            self.instance_name_map.remove(&inst.name);
+        }
+        inst.name = name.clone();
+        self.instance_name_map.insert(name, id);
+    }
+
+    /// Renames the given signal, renaming the corresponding port if the signal is a port.
+    ///
+    /// Returns the previous name of the signal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no signal with the given ID exists.
+    fn rename_signal_inner(&mut self, id: SignalId, name: ArcStr) -> ArcStr {
+        let info = self.signals.get_mut(&id).unwrap();
+        let old = std::mem::replace(&mut info.name, name.clone());
+        if self.signal_name_map.get(&old) == Some(&id) {
+            self.signal_name_map.remove(&old);
+        }
+        self.signal_name_map.insert(name.clone(), id);
+        if info.port.is_some() {
+            // Rebuild the port map to preserve port order.
+            self.ports = std::mem::take(&mut self.ports)
+                .into_iter()
+                .map(|(k, port)| {
+                    if k == old {
+                        (name.clone(), port)
+                    } else {
+                        (k, port)
+                    }
+                })
+                .collect();
+        }
+        old
+    }
+}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S> {
+    /// Renames the given cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no cell with the given ID exists.
+    pub fn rename_cell(&mut self, id: CellId, name: impl Into<ArcStr>) {
+        let name = name.into();
+        let cell = self.cells.get_mut(&id).unwrap();
+        if self.name_map.get(&cell.name) == Some(&id) {
+            self.name_map.remove(&cell.name);
+        }
+        cell.name = name.clone();
+        self.name_map.insert(name, id);
+    }
+
+    /// Renames a signal of the given cell.
+    ///
+    /// If the signal is a port, the connections of every instance of the cell
+    /// are updated to use the new port name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell or signal does not exist.
+    pub fn rename_signal(&mut self, cell: CellId, signal: SignalId, name: impl Into<ArcStr>) {
+        let name = name.into();
+        let c = self.cells.get_mut(&cell).unwrap();
+        let is_port = c.signal(signal).port.is_some();
+        let old = c.rename_signal_inner(signal, name.clone());
+        if !is_port {
+            return;
+        }
+        for parent in self.cells.values_mut() {
+            for inst in parent.instances.values_mut() {
+                if inst.child == ChildId::Cell(cell) {
+                    if let Some(conn) = inst.connections.remove(&old) {
+                        inst.connections.insert(name.clone(), conn);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S: RenameSchema + ?Sized> LibraryBuilder<S> {
+    /// Renames every cell, signal and instance whose name is changed by `legalize`.
+    ///
+    /// Names that `legalize` leaves unchanged are kept.
+    /// If a legalized name collides with another name in the same scope
+    /// (cells in the library, or signals or instances in a cell),
+    /// a numeric suffix is appended to make it unique.
+    ///
+    /// Primitives that refer to renamed instances are
+    /// [updated](RenameSchema::rename_instance_refs) to use the new names.
+    pub fn legalize_names(&mut self, legalize: impl Fn(&str) -> String) {
+        let cells: Vec<_> = self.cells.keys().copied().collect();
+        let renames = unique_renames(
+            cells.iter().map(|id| (*id, self.cells[id].name.clone())),
+            &legalize,
+        );
+        for (id, name) in renames {
+            self.rename_cell(id, name);
+        }
+
+        for id in cells {
+            let cell = &self.cells[&id];
+            let signals = unique_renames(
+                cell.signals
+                    .values()
+                    .map(|info| (info.id, info.name.clone())),
+                &legalize,
+            );
+            for (signal, name) in signals {
+                self.rename_signal(id, signal, name);
+            }
+
+            let cell = self.cells.get_mut(&id).unwrap();
+            let instances = unique_renames(
+                cell.instances
+                    .iter()
+                    .map(|(inst_id, inst)| (*inst_id, inst.name.clone())),
+                &legalize,
+            );
+            let renamed: HashMap<ArcStr, ArcStr> = instances
+                .iter()
+                .map(|(inst, name)| (cell.instance(*inst).name.clone(), name.clone()))
+                .collect();
+            for (inst, name) in instances {
+                cell.rename_instance(inst, name);
+            }
+            if !renamed.is_empty() {
+                self.rename_instance_refs(id, &renamed);
+            }
+        }
+    }
+
+    /// Updates the primitive instances of the given cell to refer to instances
+    /// by their new names, given a map from old to new instance names.
+    fn rename_instance_refs(&mut self, id: CellId, renamed: &HashMap<ArcStr, ArcStr>) {
+        let rename = |name: &str| renamed.get(name).cloned();
+        let updated: Vec<_> = self.cells[&id]
+            .instances
+            .iter()
+            .filter_map(|(inst, instance)| {
+                let ChildId::Primitive(prim) = instance.child else {
+                    return None;
+                };
+                Some((
+                    *inst,
+                    S::rename_instance_refs(&self.primitives[&prim], &rename)?,
+                ))
+            })
+            .collect();
+        for (inst, primitive) in updated {
+            // The primitive may be shared with other instances, so it is copied.
+            let prim = self.add_primitive(primitive);
+            let cell = self.cells.get_mut(&id).unwrap();
+            cell.instances.get_mut(&inst).unwrap().child = ChildId::Primitive(prim);
+        }
+    }
+}
+
+/// Computes new names for the items whose names are changed by `legalize`,
+/// avoiding collisions with each other and with the names that are kept.
+fn unique_renames<K: Copy + Ord>(
+    items: impl Iterator<Item = (K, ArcStr)>,
+    legalize: impl Fn(&str) -> String,
+) -> Vec<(K, ArcStr)> {
+    let mut items: Vec<_> = items.collect();
+    items.sort_by_key(|(k, _)| *k);
+    let mut used: HashSet<ArcStr> = items
+        .iter()
+        .filter(|(_, name)| legalize(name) == name.as_str())
+        .map(|(_, name)| name.clone())
+        .collect();
+    let mut renames = Vec::new();
+    for (k, name) in items {
+        let legal = legalize(&name);
+        if legal == name.as_str() {
+            continue;
+        }
+        let mut candidate = ArcStr::from(legal.as_str());
+        let mut i = 1;
+        while used.contains(&candidate) {
+            candidate = arcstr::format!("{}_{}", legal, i);
+            i += 1;
+        }
+        used.insert(candidate.clone());
+        renames.push((k, candidate));
+    }
+    renames
+}
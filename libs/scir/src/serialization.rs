@@ -0,0 +1,310 @@
+//! Serialization of SCIR libraries.
+//!
+//! Libraries can be saved as JSON, which is human-readable and stable under diffing,
+//! or in a compact binary format based on [FlexBuffers](https://flatbuffers.dev/flexbuffers.html).
+//! Both formats record the [`FORMAT_VERSION`] they were written with,
+//! and libraries written with a different version are rejected when loaded.
+//!
+//! Primitives are serialized using their [`Serialize`] implementation,
+//! so only schemas whose primitives implement [`Serialize`] and [`Deserialize`]
+//! support serialization.
+
+use std::hash::Hash;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserializer, Serializer};
+
+use super::*;
+
+/// The version of the SCIR serialization format.
+///
+/// Incremented whenever the serialized representation of a library changes.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The magic bytes at the start of a binary SCIR library.
+const BINARY_MAGIC: &[u8; 4] = b"SCIR";
+
+/// The error type for SCIR serialization.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// An error in JSON serialization or deserialization.
+    #[error("error serializing or deserializing JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// An error in binary serialization.
+    #[error("error serializing binary library: {0}")]
+    BinarySerialization(#[from] flexbuffers::SerializationError),
+    /// An error in binary deserialization.
+    #[error("error deserializing binary library: {0}")]
+    BinaryDeserialization(#[from] flexbuffers::DeserializationError),
+    /// The data does not contain a SCIR library.
+    #[error("data does not contain a SCIR library")]
+    InvalidHeader,
+    /// The library was written with an unsupported format version.
+    #[error("unsupported SCIR format version {found} (expected version {FORMAT_VERSION})")]
+    UnsupportedVersion {
+        /// The format version of the library.
+        found: u32,
+    },
+    /// The deserialized library failed validation.
+    #[error("deserialized SCIR library is invalid")]
+    Invalid(#[from] Issues),
+}
+
+/// The result type for SCIR serialization.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The JSON representation of a serialized library.
+#[derive(Serialize)]
+struct JsonLibraryRef<'a, L> {
+    version: u32,
+    library: &'a L,
+}
+
+#[derive(Deserialize)]
+struct JsonHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonLibrary<L> {
+    library: L,
+}
+
+#[derive(Serialize)]
+struct LibraryRef<'a, P> {
+    cell_id: u64,
+    primitive_id: u64,
+    cells: Vec<(CellId, &'a Cell)>,
+    primitives: Vec<(PrimitiveId, &'a P)>,
+    top: Option<CellId>,
+}
+
+#[derive(Deserialize)]
+struct LibraryRepr<P> {
+    cell_id: u64,
+    primitive_id: u64,
+    cells: Vec<(CellId, Cell)>,
+    primitives: Vec<(PrimitiveId, P)>,
+    top: Option<CellId>,
+}
+
+#[derive(Serialize)]
+struct CellRef<'a> {
+    name: &'a ArcStr,
+    signal_id: u64,
+    port_idx: usize,
+    signals: Vec<&'a SignalInfo>,
+    ports: &'a IndexMap<ArcStr, Port>,
+    instance_id: u64,
+    instances: Vec<(InstanceId, &'a Instance)>,
+    params: &'a IndexMap<ArcStr, ParamValue>,
+}
+
+#[derive(Deserialize)]
+struct CellRepr {
+    name: ArcStr,
+    signal_id: u64,
+    port_idx: usize,
+    signals: Vec<SignalInfo>,
+    ports: IndexMap<ArcStr, Port>,
+    instance_id: u64,
+    instances: Vec<(InstanceId, Instance)>,
+    params: IndexMap<ArcStr, ParamValue>,
+}
+
+impl<S: Schema + ?Sized> Serialize for LibraryBuilder<S>
+where
+    S::Primitive: Serialize,
+{
+    fn serialize<Ser: Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        LibraryRef {
+            cell_id: self.cell_id,
+            primitive_id: self.primitive_id,
+            cells: self.cells.iter().map(|(id, cell)| (*id, cell)).collect(),
+            primitives: self.primitives.iter().map(|(id, p)| (*id, p)).collect(),
+            top: self.top,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, S: Schema + ?Sized> Deserialize<'de> for LibraryBuilder<S>
+where
+    S::Primitive: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = LibraryRepr::<S::Primitive>::deserialize(deserializer)?;
+        let mut lib = LibraryBuilder {
+            cell_id: repr.cell_id,
+            primitive_id: repr.primitive_id,
+            top: repr.top,
+            ..Default::default()
+        };
+        for (id, cell) in repr.cells {
+            lib.name_map.insert(cell.name.clone(), id);
+            lib.cells.insert(id, cell);
+        }
+        lib.primitives.extend(repr.primitives);
+        Ok(lib)
+    }
+}
+
+impl<S: Schema + ?Sized> Serialize for Library<S>
+where
+    S::Primitive: Serialize,
+{
+    fn serialize<Ser: Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, S: Schema + ?Sized> Deserialize<'de> for Library<S>
+where
+    S::Primitive: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        LibraryBuilder::<S>::deserialize(deserializer)?
+            .build()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<Ser: Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        let mut signals: Vec<_> = self.signals.values().collect();
+        signals.sort_by_key(|info| info.id);
+        CellRef {
+            name: &self.name,
+            signal_id: self.signal_id,
+            port_idx: self.port_idx,
+            signals,
+            ports: &self.ports,
+            instance_id: self.instance_id,
+            instances: self
+                .instances
+                .iter()
+                .map(|(id, inst)| (*id, inst))
+                .collect(),
+            params: &self.params,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = CellRepr::deserialize(deserializer)?;
+        let mut cell = Cell::new(repr.name);
+        cell.signal_id = repr.signal_id;
+        cell.port_idx = repr.port_idx;
+        cell.ports = repr.ports;
+        cell.instance_id = repr.instance_id;
+        cell.params = repr.params;
+        for info in repr.signals {
+            cell.signal_name_map.insert(info.name.clone(), info.id);
+            cell.signals.insert(info.id, info);
+        }
+        for (id, inst) in repr.instances {
+            cell.instance_name_map.insert(inst.name.clone(), id);
+            cell.instances.insert(id, inst);
+        }
+        Ok(cell)
+    }
+}
+
+/// Serializes a [`HashMap`] with its entries sorted by key,
+/// so that the serialized output is deterministic.
+///
+/// Can be used with `#[serde(serialize_with = "...")]` on map fields of schema primitives.
+pub fn serialize_sorted<K: Ord + Hash + Serialize, V: Serialize, Ser: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: Ser,
+) -> std::result::Result<Ser::Ok, Ser::Error> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(k, _)| *k);
+    serializer.collect_map(entries)
+}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S>
+where
+    S::Primitive: Serialize,
+{
+    /// Serializes the library to pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&JsonLibraryRef {
+            version: FORMAT_VERSION,
+            library: self,
+        })?)
+    }
+
+    /// Serializes the library to the compact binary format.
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::from(*BINARY_MAGIC);
+        data.extend(FORMAT_VERSION.to_le_bytes());
+        data.extend(flexbuffers::to_vec(self)?);
+        Ok(data)
+    }
+}
+
+impl<S: Schema + ?Sized> LibraryBuilder<S>
+where
+    S::Primitive: DeserializeOwned,
+{
+    /// Deserializes a library from JSON produced by [`LibraryBuilder::to_json`].
+    ///
+    /// The library is not validated.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let header: JsonHeader = serde_json::from_str(json)?;
+        check_version(header.version)?;
+        let lib: JsonLibrary<Self> = serde_json::from_str(json)?;
+        Ok(lib.library)
+    }
+
+    /// Deserializes a library from binary data produced by [`LibraryBuilder::to_binary`].
+    ///
+    /// The library is not validated.
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        let data = data
+            .strip_prefix(BINARY_MAGIC)
+            .ok_or(Error::InvalidHeader)?;
+        if data.len() < 4 {
+            return Err(Error::InvalidHeader);
+        }
+        let (version, data) = data.split_at(4);
+        check_version(u32::from_le_bytes(version.try_into().unwrap()))?;
+        Ok(flexbuffers::from_slice(data)?)
+    }
+}
+
+impl<S: Schema + ?Sized> Library<S>
+where
+    S::Primitive: DeserializeOwned,
+{
+    /// Deserializes and validates a library from JSON produced by [`LibraryBuilder::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(LibraryBuilder::from_json(json)?.build()?)
+    }
+
+    /// Deserializes and validates a library from binary data
+    /// produced by [`LibraryBuilder::to_binary`].
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        Ok(LibraryBuilder::from_binary(data)?.build()?)
+    }
+}
+
+fn check_version(found: u32) -> Result<()> {
+    if found == FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedVersion { found })
+    }
+}
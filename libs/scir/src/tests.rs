@@ -1,6 +1,9 @@
+use rust_decimal_macros::dec;
 use test_log::test;
 
+use crate::param::Expr;
 use crate::schema::{FromSchema, StringSchema};
+use crate::validation::Cause;
 use crate::*;
 
 #[test]
@@ -316,3 +319,593 @@ fn name_path_conversion() {
         );
     }
 }
+
+/// Returns a SCIR library with a three-level hierarchy of resistors
+/// connected through buses and concatenations.
+fn hierarchical_lib() -> LibraryBuilder<StringSchema> {
+    let mut lib = LibraryBuilder::<StringSchema>::new();
+    let res = lib.add_primitive("res".into());
+
+    let mut inner = Cell::new("inner");
+    let a = inner.add_bus("a", 2);
+    let b = inner.add_node("b");
+    let x = inner.add_node("x");
+    for (name, p1, p2) in [("r0", a.index(0), x), ("r1", a.index(1), x), ("r2", x, b)] {
+        let mut r = Instance::new(name, res);
+        r.connect("1", p1);
+        r.connect("2", p2);
+        inner.add_instance(r);
+    }
+    inner.expose_port(a, Direction::InOut);
+    inner.expose_port(b, Direction::InOut);
+    let inner = lib.add_cell(inner);
+
+    let mut mid = Cell::new("mid");
+    let d = mid.add_bus("d", 3);
+    let y = mid.add_node("y");
+    let mut i0 = Instance::new("i0", inner);
+    i0.connect("a", d.index(0..2));
+    i0.connect("b", y);
+    mid.add_instance(i0);
+    let mut i1 = Instance::new("i1", inner);
+    i1.connect("a", Concat::new(vec![y.into(), d.index(2..3)]));
+    i1.connect("b", d.index(0));
+    mid.add_instance(i1);
+    mid.expose_port(d, Direction::InOut);
+    let mid = lib.add_cell(mid);
+
+    let mut top = Cell::new("top");
+    let bus = top.add_bus("bus", 3);
+    let mut m = Instance::new("m", mid);
+    m.connect("d", bus);
+    top.add_instance(m);
+    top.expose_port(bus, Direction::InOut);
+    let top = lib.add_cell(top);
+    lib.set_top(top);
+
+    lib
+}
+
+#[test]
+fn flatten_cell() {
+    let mut lib = hierarchical_lib();
+    let top = lib.cell_id_named("top");
+    let mapping = lib.flatten_cell(top);
+    let lib = lib.build().unwrap();
+
+    let top = lib.cell(top);
+    let bus = top.signal_named("bus").slice();
+    let y = top.signal_named("m.y").slice().slice_one().unwrap();
+    let x0 = top.signal_named("m.i0.x").slice().slice_one().unwrap();
+    let x1 = top.signal_named("m.i1.x").slice().slice_one().unwrap();
+    assert_eq!(top.signals().count(), 4);
+    assert_eq!(top.ports().count(), 1);
+    assert_eq!(top.instances().count(), 6);
+
+    let r = top.instance_named("m.i0.r1");
+    assert_eq!(r.connection("1").index(0), bus.index(1));
+    assert_eq!(r.connection("2").index(0), x0);
+    let r = top.instance_named("m.i0.r2");
+    assert_eq!(r.connection("2").index(0), y);
+    let r = top.instance_named("m.i1.r0");
+    assert_eq!(r.connection("1").index(0), y);
+    let r = top.instance_named("m.i1.r1");
+    assert_eq!(r.connection("1").index(0), bus.index(2));
+    let r = top.instance_named("m.i1.r2");
+    assert_eq!(r.connection("1").index(0), x1);
+    assert_eq!(r.connection("2").index(0), bus.index(0));
+
+    let mut path = InstancePath::new("top");
+    path.push_iter(["m", "i1"]);
+    assert_eq!(
+        mapping.flat_signal(
+            &path
+                .clone()
+                .slice_one(NamedSliceOne::with_index("a".into(), 0))
+        ),
+        Some(y)
+    );
+    assert_eq!(
+        mapping.flat_signal(
+            &path
+                .clone()
+                .slice_one(NamedSliceOne::with_index("a".into(), 1))
+        ),
+        Some(bus.index(2))
+    );
+    assert_eq!(
+        mapping.flat_signal(&path.clone().slice_one(NamedSliceOne::new("x"))),
+        Some(x1)
+    );
+    assert_eq!(
+        mapping.flat_signal(&path.slice_one(NamedSliceOne::new("nonexistent"))),
+        None
+    );
+    let mut path = InstancePath::new("top");
+    path.push_iter(["m", "i1", "r2"]);
+    let r = mapping.flat_instance(&path).unwrap();
+    assert_eq!(top.instance(r).name(), "m.i1.r2");
+}
+
+#[test]
+fn flatten_cell_to_depth() {
+    let mut lib = hierarchical_lib();
+    let top = lib.cell_id_named("top");
+    let mapping = lib.flatten_cell_with_options(
+        top,
+        flatten::FlattenOptions::new()
+            .with_depth(1)
+            .with_separator("/"),
+    );
+    let lib = lib.build().unwrap();
+
+    let inner = lib.cell_id_named("inner");
+    let top = lib.cell(top);
+    let bus = top.signal_named("bus").slice();
+    let y = top.signal_named("m/y").slice().slice_one().unwrap();
+    assert_eq!(top.signals().count(), 2);
+    assert_eq!(top.instances().count(), 2);
+
+    let i1 = top.instance_named("m/i1");
+    assert_eq!(i1.child(), ChildId::Cell(inner));
+    assert_eq!(i1.connection("a").width(), 2);
+    assert_eq!(i1.connection("a").index(0), y);
+    assert_eq!(i1.connection("a").index(1), bus.index(2));
+    assert_eq!(i1.connection("b").index(0), bus.index(0));
+    let i0 = top.instance_named("m/i0");
+    assert_eq!(i0.connection("a").parts().count(), 1);
+
+    let mut path = InstancePath::new("top");
+    path.push_iter(["m", "i0"]);
+    assert!(mapping.flat_instance(&path).is_some());
+    path.push("r0");
+    assert_eq!(mapping.flat_instance(&path), None);
+}
+
+#[test]
+fn serialization_round_trip() {
+    let lib = hierarchical_lib().build().unwrap();
+    let json = lib.to_json().unwrap();
+    let binary = lib.to_binary().unwrap();
+
+    let from_json = Library::<StringSchema>::from_json(&json).unwrap();
+    let from_binary = Library::<StringSchema>::from_binary(&binary).unwrap();
+    assert!(binary.len() < json.len());
+
+    for lib in [from_json, from_binary] {
+        assert_eq!(lib.to_json().unwrap(), json);
+        let top = lib.cell(lib.top_cell().unwrap());
+        assert_eq!(top.name(), "top");
+        assert_eq!(top.signal_named("bus").width, Some(3));
+        let mid = lib.cell_named("mid");
+        let i1 = mid.instance_named("i1");
+        assert_eq!(lib.cell(i1.child().unwrap_cell()).name(), "inner");
+        assert_eq!(
+            i1.connection("a").index(0),
+            mid.signal_named("y").slice().slice_one().unwrap()
+        );
+        let inner = lib.cell_named("inner");
+        let r0 = inner.instance_named("r0");
+        assert_eq!(lib.primitive(r0.child().unwrap_primitive()), "res");
+    }
+}
+
+#[test]
+fn serialization_format_version() {
+    let lib = hierarchical_lib();
+
+    let json = lib.to_json().unwrap().replacen(
+        &format!("\"version\": {}", serialization::FORMAT_VERSION),
+        "\"version\": 0",
+        1,
+    );
+    assert!(matches!(
+        LibraryBuilder::<StringSchema>::from_json(&json),
+        Err(serialization::Error::UnsupportedVersion { found: 0 })
+    ));
+
+    let mut binary = lib.to_binary().unwrap();
+    binary[4..8].copy_from_slice(&0u32.to_le_bytes());
+    assert!(matches!(
+        LibraryBuilder::<StringSchema>::from_binary(&binary),
+        Err(serialization::Error::UnsupportedVersion { found: 0 })
+    ));
+    assert!(matches!(
+        LibraryBuilder::<StringSchema>::from_binary(&binary[8..]),
+        Err(serialization::Error::InvalidHeader)
+    ));
+}
+
+#[test]
+fn param_expr_parse_and_display() {
+    use crate::param::{BinaryOp, EvalError};
+
+    let expr = Expr::parse("2*w").unwrap();
+    assert_eq!(
+        expr,
+        Expr::Binary(
+            BinaryOp::Mul,
+            Box::new(Expr::Literal(dec!(2))),
+            Box::new(Expr::param("w"))
+        )
+    );
+    assert_eq!(Expr::parse("1.5u").unwrap(), Expr::Literal(dec!(0.0000015)));
+    assert_eq!(Expr::parse("2.2MEG").unwrap(), Expr::Literal(dec!(2200000)));
+    assert_eq!(Expr::parse("1e-3").unwrap(), Expr::Literal(dec!(0.001)));
+
+    for (src, display) in [
+        ("(a + b) * c", "(a+b)*c"),
+        ("a - (b - c)", "a-(b-c)"),
+        ("a - b - c", "a-b-c"),
+        ("-a^2", "-a**2"),
+        ("(-a)**2", "(-a)**2"),
+        ("2**3**2", "2**3**2"),
+        ("(2**3)**2", "(2**3)**2"),
+        ("MAX(w, 2*l) / ln(x)", "max(w,2*l)/log(x)"),
+    ] {
+        let expr = Expr::parse(src).unwrap();
+        assert_eq!(expr.to_string(), display);
+        assert_eq!(Expr::parse(display).unwrap(), expr);
+    }
+
+    let params = HashMap::from_iter([(arcstr::literal!("w"), dec!(2))]);
+    for (src, value) in [
+        ("2**3**2", dec!(512)),
+        ("max(1k, 2meg) + -w", dec!(1999998)),
+        ("sqrt(w * 8)", dec!(4)),
+        ("floor(7 / w) - ceil(-1.5)", dec!(4)),
+    ] {
+        assert_eq!(Expr::parse(src).unwrap().eval(&params).unwrap(), value);
+    }
+    assert_eq!(
+        Expr::parse("w / (w - 2)").unwrap().eval(&params),
+        Err(EvalError::DivisionByZero)
+    );
+    assert_eq!(
+        Expr::parse("2 * l").unwrap().eval(&params),
+        Err(EvalError::UndefinedParam(arcstr::literal!("l")))
+    );
+    assert!(matches!(
+        Expr::parse("min(w)").unwrap().eval(&params),
+        Err(EvalError::WrongArity {
+            expected: 2,
+            found: 1,
+            ..
+        })
+    ));
+
+    assert!(Expr::parse("2 *").is_err());
+    assert!(Expr::parse("(w").is_err());
+    assert!(Expr::parse("foo(1)").is_err());
+}
+
+fn parameterized_lib() -> LibraryBuilder<StringSchema> {
+    let mut lib = LibraryBuilder::<StringSchema>::new();
+    let res = lib.add_primitive("res".into());
+
+    let mut inner = Cell::new("inner");
+    let a = inner.add_node("a");
+    let b = inner.add_node("b");
+    inner.add_param("l", Expr::parse("2*w").unwrap());
+    inner.add_param("w", dec!(0.000001));
+    let mut r = Instance::new("r", res);
+    r.connect("1", a);
+    r.connect("2", b);
+    r.set_param("value", Expr::parse("l/w").unwrap());
+    inner.add_instance(r);
+    inner.expose_port(a, Direction::InOut);
+    inner.expose_port(b, Direction::InOut);
+    let inner = lib.add_cell(inner);
+
+    let mut top = Cell::new("top");
+    let a = top.add_node("a");
+    top.add_param("scale", dec!(3));
+    let mut x = Instance::new("x", inner);
+    x.connect("a", a);
+    x.connect("b", a);
+    x.set_param("w", Expr::parse("scale*2u").unwrap());
+    top.add_instance(x);
+    top.expose_port(a, Direction::InOut);
+    let top = lib.add_cell(top);
+    lib.set_top(top);
+
+    lib
+}
+
+#[test]
+fn eval_params() {
+    let lib = parameterized_lib();
+    let issues = lib.validate();
+    assert_eq!(issues.num_errors(), 0);
+    assert_eq!(issues.num_warnings(), 0);
+
+    let params = lib.eval_params(&InstancePath::new("top")).unwrap();
+    assert_eq!(params.get("scale"), Some(&dec!(3)));
+
+    let mut path = InstancePath::new("top");
+    path.push("x");
+    let params = lib.eval_params(&path).unwrap();
+    assert_eq!(params.len(), 2);
+    assert_eq!(params.get("w"), Some(&dec!(0.000006)));
+    assert_eq!(params.get("l"), Some(&dec!(0.000012)));
+
+    let mut inner = lib.cell_named("inner").clone();
+    inner.add_param("w", Expr::parse("l/2").unwrap());
+    assert_eq!(
+        inner.eval_params(&HashMap::new()),
+        Err(param::EvalError::CyclicParam(arcstr::literal!("l")))
+    );
+    let overrides = HashMap::from_iter([(arcstr::literal!("w"), dec!(1))]);
+    assert_eq!(
+        inner.eval_params(&overrides).unwrap().get("l"),
+        Some(&dec!(2))
+    );
+}
+
+#[test]
+fn param_validation() {
+    let mut lib = parameterized_lib();
+    let mut top = Cell::new("top2");
+    let a = top.add_node("a");
+    let mut y = Instance::new("y", lib.cell_id_named("inner"));
+    y.connect("a", a);
+    y.connect("b", a);
+    y.set_param("nf", dec!(2));
+    y.set_param("w", Expr::parse("width").unwrap());
+    top.add_instance(y);
+    lib.add_cell(top);
+
+    let issues = lib.validate();
+    assert_eq!(issues.num_errors(), 1);
+    assert_eq!(issues.num_warnings(), 1);
+    for issue in issues.iter() {
+        match issue.cause() {
+            Cause::ExtraParam {
+                param,
+                instance_name,
+                ..
+            } => {
+                assert_eq!(param, "nf");
+                assert_eq!(instance_name, "y");
+            }
+            Cause::UndeclaredParam { param, .. } => assert_eq!(param, "width"),
+            cause => panic!("unexpected cause: {cause:?}"),
+        }
+    }
+}
+
+#[test]
+fn flatten_cell_substitutes_params() {
+    let mut lib = parameterized_lib();
+    let top = lib.cell_id_named("top");
+    lib.flatten_cell(top);
+    let lib = lib.build().unwrap();
+
+    let top = lib.cell(top);
+    assert_eq!(top.param("scale"), Some(&ParamValue::Numeric(dec!(3))));
+    let r = top.instance_named("x.r");
+    let value = r.param("value").unwrap();
+    let params = HashMap::from_iter([(arcstr::literal!("scale"), dec!(5))]);
+    assert_eq!(value.eval(&params).unwrap(), dec!(2));
+    assert!(value
+        .referenced_params()
+        .contains(&arcstr::literal!("scale")));
+}
+
+#[test]
+fn flatten_cell_binds_defaults_in_dependency_order() {
+    let mut lib = parameterized_lib();
+    let inner = lib.cell_id_named("inner");
+    let mut top = Cell::new("top2");
+    let a = top.add_node("a");
+    // A parameter of the parent with the same name as a parameter of the child.
+    top.add_param("w", dec!(5));
+    let mut y = Instance::new("y", inner);
+    y.connect("a", a);
+    y.connect("b", a);
+    top.add_instance(y);
+    let top = lib.add_cell(top);
+    lib.flatten_cell(top);
+    let lib = lib.build().unwrap();
+
+    // `l` defaults to `2*w`, where `w` is declared after `l`.
+    let r = lib.cell(top).instance_named("y.r");
+    assert_eq!(r.param("value"), Some(&ParamValue::Numeric(dec!(2))));
+}
+
+#[test]
+fn connectivity_trace() {
+    let lib = hierarchical_lib();
+    let conn = lib.connectivity();
+    let top = lib.cell_id_named("top");
+    let mid = lib.cell_id_named("mid");
+    let inner = lib.cell_id_named("inner");
+
+    // Net-to-terminal maps.
+    let d = lib.cell(mid).signal_named("d").slice();
+    let terminals = conn.terminals(mid, d.index(0));
+    assert_eq!(terminals.len(), 2);
+    for terminal in terminals {
+        assert_eq!(conn.net(mid, terminal), d.index(0));
+        assert_eq!(terminal.direction(), Some(Direction::InOut));
+    }
+
+    // `bus[0]` in `top` reaches `a[0]` of `m/i0` and `b` of `m/i1`.
+    let bus = lib.cell(top).signal_named("bus").slice();
+    let trace = conn.trace(InstancePath::new(top).slice_one(bus.index(0)));
+    assert_eq!(trace.segments().len(), 4);
+    assert_eq!(trace.segments()[0].cell, top);
+    assert_eq!(trace.segments()[1].net, d.index(0));
+    let leaves: Vec<_> = trace
+        .leaves()
+        .map(|t| {
+            assert_eq!(t.cell, inner);
+            let inst = lib.cell(inner).instance(t.terminal.instance());
+            (inst.name().clone(), t.terminal.port().clone())
+        })
+        .collect();
+    assert_eq!(
+        leaves,
+        vec![
+            (ArcStr::from("r0"), ArcStr::from("1")),
+            (ArcStr::from("r2"), ArcStr::from("2"))
+        ]
+    );
+
+    // Tracing from inside the hierarchy bubbles up through ports first.
+    let mut path = InstancePath::new(top);
+    path.push_iter(["m", "i1"]);
+    let trace2 = conn.trace(path.slice_one(NamedSliceOne::new("b")));
+    assert_eq!(trace2.segments(), trace.segments());
+
+    // Internal nets are not traced upwards.
+    let mut path = InstancePath::new(top);
+    path.push_iter(["m", "i0"]);
+    let trace = conn.trace(path.slice_one(NamedSliceOne::new("x")));
+    assert_eq!(trace.segments().len(), 1);
+    assert_eq!(trace.leaves().count(), 3);
+}
+
+#[test]
+fn connectivity_fanout() {
+    let mut lib = LibraryBuilder::<StringSchema>::new();
+    let inv = lib.add_primitive("inv".into());
+    let mut buf = Cell::new("buf");
+    let a = buf.add_node("a");
+    let y = buf.add_node("y");
+    let mut i = Instance::new("i", inv);
+    i.connect("a", a);
+    i.connect("y", y);
+    buf.add_instance(i);
+    buf.expose_port(a, Direction::Input);
+    buf.expose_port(y, Direction::Output);
+    let buf = lib.add_cell(buf);
+
+    let mut top = Cell::new("top");
+    let din = top.add_node("din");
+    let n = top.add_node("n");
+    let dout = top.add_bus("dout", 2);
+    for (name, a, y) in [
+        ("b0", din, n),
+        ("b1", n, dout.index(0)),
+        ("b2", n, dout.index(1)),
+    ] {
+        let mut b = Instance::new(name, buf);
+        b.connect("a", a);
+        b.connect("y", y);
+        top.add_instance(b);
+    }
+    top.expose_port(din, Direction::Input);
+    top.expose_port(dout, Direction::Output);
+    let top = lib.add_cell(top);
+
+    let conn = lib.connectivity();
+    let cell = conn.cell(top);
+    assert_eq!(cell.fanout(n).count(), 2);
+    assert_eq!(cell.fanin(n).count(), 1);
+    assert_eq!(cell.fanin(dout.index(1)).count(), 1);
+    assert_eq!(cell.fanout(dout.index(1)).count(), 0);
+
+    let trace = conn.trace(InstancePath::new(top).slice_one(n));
+    assert_eq!(trace.fanout().count(), 2);
+    assert_eq!(trace.fanin().count(), 1);
+    assert_eq!(trace.leaves().count(), 3);
+}
+
+#[test]
+fn erc_mismatched_directions() {
+    let mut lib = LibraryBuilder::<StringSchema>::new();
+    let inv = lib.add_primitive("inv".into());
+    let mut buf = Cell::new("buf");
+    let a = buf.add_node("a");
+    let y = buf.add_node("y");
+    let mut i = Instance::new("i", inv);
+    i.connect("a", a);
+    i.connect("y", y);
+    buf.add_instance(i);
+    buf.expose_port(a, Direction::Input);
+    buf.expose_port(y, Direction::Output);
+    let buf = lib.add_cell(buf);
+
+    let mut top = Cell::new("top");
+    let din = top.add_node("din");
+    let n = top.add_node("n");
+    let m = top.add_node("m");
+    let dout = top.add_node("dout");
+    let q = top.add_node("q");
+    for (name, a, y) in [
+        ("b0", din, n),
+        // An output drives an input port.
+        ("b1", n, din),
+        // `m` is read, but never driven.
+        ("b2", m, dout),
+        // Multiple drivers on `n` are left to driver analysis.
+        ("b3", q, n),
+    ] {
+        let mut b = Instance::new(name, buf);
+        b.connect("a", a);
+        b.connect("y", y);
+        top.add_instance(b);
+    }
+    top.expose_port(din, Direction::Input);
+    top.expose_port(dout, Direction::Output);
+    // An output port that is only read.
+    top.expose_port(q, Direction::Output);
+    lib.add_cell(top);
+
+    let issues = lib.check_erc(&erc::ErcOptions::new());
+    let mut nets: Vec<_> = issues
+        .iter()
+        .map(|issue| match issue.cause() {
+            erc::Cause::MismatchedDirections(net) => net.to_string(),
+            cause => panic!("unexpected issue: {cause}"),
+        })
+        .collect();
+    nets.sort();
+    assert_eq!(nets, vec!["top/din", "top/m", "top/q"]);
+    assert_eq!(issues.num_warnings(), 3);
+}
+
+#[test]
+fn legalize_names() {
+    let mut lib = <LibraryBuilder>::new();
+
+    let mut leaf = Cell::new("leaf cell");
+    let a = leaf.add_node("a<0>");
+    let b = leaf.add_node("a_0_");
+    leaf.expose_port(a, Direction::Input);
+    leaf.expose_port(b, Direction::Output);
+    let leaf = lib.add_cell(leaf);
+
+    let mut top = Cell::new("top");
+    let x = top.add_node("x");
+    let y = top.add_node("y");
+    let mut inst = Instance::new("inst 0", leaf);
+    inst.connect("a<0>", x);
+    inst.connect("a_0_", y);
+    top.add_instance(inst);
+    let top = lib.add_cell(top);
+
+    let legalize = |name: &str| {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    };
+    lib.legalize_names(legalize);
+    let lib = lib.build().unwrap();
+
+    assert_eq!(lib.cell(leaf).name(), "leaf_cell");
+    assert_eq!(lib.cell_id_named("leaf_cell"), leaf);
+    let leaf = lib.cell(leaf);
+    let ports: Vec<_> = leaf
+        .ports()
+        .map(|port| leaf.signal(port.signal()).name.clone())
+        .collect();
+    // `a_0_` is already legal, so the legalized `a<0>` gets a suffix.
+    assert_eq!(ports, vec!["a_0__1", "a_0_"]);
+
+    let top = lib.cell(top);
+    let inst = top.instance_named("inst_0");
+    assert_eq!(inst.connection("a_0__1").index(0), x);
+    assert_eq!(inst.connection("a_0_").index(0), y);
+}
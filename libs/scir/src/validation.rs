@@ -160,6 +160,33 @@ pub enum Cause {
         /// The name of the child cell.
         child_cell_name: ArcStr,
     },
+    /// An instance sets a parameter that is not declared by the child cell.
+    ExtraParam {
+        /// The ID of the child cell.
+        child_cell_id: CellId,
+        /// The name of the child cell.
+        child_cell_name: ArcStr,
+        /// The name of the parameter the instance is trying to set.
+        param: ArcStr,
+        /// The ID of the cell containing the offending instance.
+        parent_cell_id: CellId,
+        /// The name of the cell containing the offending instance.
+        parent_cell_name: ArcStr,
+        /// The name of the offending instance in the parent cell.
+        instance_name: ArcStr,
+    },
+    /// A parameter expression refers to a parameter not declared by the enclosing cell.
+    ///
+    /// The parameter may still be defined when the cell is netlisted,
+    /// for example by a global parameter definition.
+    UndeclaredParam {
+        /// The name of the referenced parameter.
+        param: ArcStr,
+        /// The ID of the offending cell.
+        cell_id: CellId,
+        /// The name of the offending cell.
+        cell_name: ArcStr,
+    },
 }
 
 impl Diagnostic for ValidatorIssue {
@@ -302,6 +329,24 @@ impl Display for Cause {
                     expected_width
                 ),
 
+            Self::ExtraParam { child_cell_name, param, parent_cell_name, instance_name, .. } =>
+                write!(
+                    f,
+                    "extra parameter: instance `{}` in cell `{}` sets parameter `{}` of cell `{}`, but this cell has no such parameter",
+                    instance_name,
+                    parent_cell_name,
+                    param,
+                    child_cell_name
+                ),
+
+            Self::UndeclaredParam { param, cell_name, .. } =>
+                write!(
+                    f,
+                    "undeclared parameter: an expression in cell `{}` refers to parameter `{}`, but this cell declares no such parameter",
+                    cell_name,
+                    param
+                ),
+
         }
     }
 }
@@ -482,6 +527,35 @@ impl<S: Schema + ?Sized> LibraryBuilder<S> {
             span!(Level::INFO, "validating SCIR cell (pass 2)", cell.id = %id, cell.name = %cell.name)
                 .entered();
 
+        // Check for references to undeclared parameters
+        let mut referenced = HashSet::new();
+        for value in cell.params.values() {
+            referenced.extend(value.referenced_params());
+        }
+        for instance in cell.instances.values() {
+            if instance.child.is_cell() {
+                for value in instance.params.values() {
+                    referenced.extend(value.referenced_params());
+                }
+            }
+        }
+        let mut undeclared: Vec<_> = referenced
+            .into_iter()
+            .filter(|param| !cell.params.contains_key(*param))
+            .collect();
+        undeclared.sort();
+        for param in undeclared {
+            let issue = ValidatorIssue::new_and_log(
+                Cause::UndeclaredParam {
+                    param: param.clone(),
+                    cell_id: id,
+                    cell_name: cell.name.clone(),
+                },
+                Severity::Warning,
+            );
+            issues.add(issue);
+        }
+
         for (_id, instance) in cell.instances.iter() {
             match instance.child {
                 ChildId::Cell(c) => {
@@ -561,6 +635,26 @@ impl<S: Schema + ?Sized> LibraryBuilder<S> {
                             issues.add(issue);
                         }
                     }
+
+                    // Check for extra parameters
+                    let mut params: Vec<_> = instance.params.keys().collect();
+                    params.sort();
+                    for param in params {
+                        if !child.params.contains_key(param) {
+                            let issue = ValidatorIssue::new_and_log(
+                                Cause::ExtraParam {
+                                    child_cell_id: instance.child.unwrap_cell(),
+                                    child_cell_name: child.name.clone(),
+                                    param: param.clone(),
+                                    parent_cell_name: cell.name.clone(),
+                                    parent_cell_id: id,
+                                    instance_name: instance.name.clone(),
+                                },
+                                Severity::Error,
+                            );
+                            issues.add(issue);
+                        }
+                    }
                 }
                 ChildId::Primitive(p) => {
                     if self.try_primitive(p).is_none() {
@@ -7,8 +7,12 @@ use crate::parser::{Dialect, ParsedSpice, Parser};
 use arcstr::ArcStr;
 use itertools::Itertools;
 use rust_decimal::Decimal;
+use scir::erc::{ErcSchema, Polarity, TerminalRole};
+use scir::reduce::ReduceSchema;
+use scir::rename::RenameSchema;
 use scir::schema::{FromSchema, NoSchema, NoSchemaError, Schema};
 use scir::{Instance, Library, NetlistLibConversion, ParamValue, SliceOnePath};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::path::Path;
@@ -121,6 +125,173 @@ impl Schema for Spice {
     type Primitive = Primitive;
 }
 
+impl ReduceSchema for Spice {
+    /// Merges identical resistors, diodes and MOSFETs by summing their multipliers (`m`),
+    /// and capacitors by summing their values.
+    fn merge_parallel(a: &Primitive, b: &Primitive) -> Option<Primitive> {
+        match (a, b) {
+            (
+                Primitive::Res2 { value, params },
+                Primitive::Res2 {
+                    value: value_b,
+                    params: params_b,
+                },
+            ) if value == value_b => Some(Primitive::Res2 {
+                value: value.clone(),
+                params: merge_multipliers(params, params_b)?,
+            }),
+            (Primitive::Cap2 { value }, Primitive::Cap2 { value: value_b }) => {
+                Some(Primitive::Cap2 {
+                    value: value + value_b,
+                })
+            }
+            (
+                Primitive::Diode2 { model, params },
+                Primitive::Diode2 {
+                    model: model_b,
+                    params: params_b,
+                },
+            ) if model == model_b => Some(Primitive::Diode2 {
+                model: model.clone(),
+                params: merge_multipliers(params, params_b)?,
+            }),
+            (
+                Primitive::Mos { model, params },
+                Primitive::Mos {
+                    model: model_b,
+                    params: params_b,
+                },
+            ) if model == model_b => Some(Primitive::Mos {
+                model: model.clone(),
+                params: merge_multipliers(params, params_b)?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn interchangeable_ports(primitive: &Primitive) -> Vec<(ArcStr, ArcStr)> {
+        match primitive {
+            Primitive::Res2 { .. } | Primitive::Cap2 { .. } => {
+                vec![(arcstr::literal!("1"), arcstr::literal!("2"))]
+            }
+            Primitive::Mos { .. } => vec![(arcstr::literal!("D"), arcstr::literal!("S"))],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Resistors with a fixed value and no parameters are linear resistors.
+    fn resistor(primitive: &Primitive) -> Option<scir::reduce::Resistor> {
+        match primitive {
+            Primitive::Res2 {
+                value: ComponentValue::Fixed(value),
+                params,
+            } if params.is_empty() => Some(scir::reduce::Resistor {
+                ports: [arcstr::literal!("1"), arcstr::literal!("2")],
+                value: *value,
+            }),
+            _ => None,
+        }
+    }
+
+    fn with_resistance(template: &Primitive, value: Decimal) -> Option<Primitive> {
+        match template {
+            Primitive::Res2 { params, .. } => Some(Primitive::Res2 {
+                value: ComponentValue::Fixed(value),
+                params: params.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the parameters `a` with the multiplier `m` set to the sum of the multipliers
+/// of `a` and `b`, if `a` and `b` are otherwise identical.
+///
+/// Returns [`None`] if the parameters differ or a multiplier is not numeric.
+fn merge_multipliers(
+    a: &HashMap<UniCase<ArcStr>, ParamValue>,
+    b: &HashMap<UniCase<ArcStr>, ParamValue>,
+) -> Option<HashMap<UniCase<ArcStr>, ParamValue>> {
+    let m = UniCase::new(arcstr::literal!("m"));
+    let multiplier = |params: &HashMap<UniCase<ArcStr>, ParamValue>| match params.get(&m) {
+        Some(value) => value.get_numeric().copied(),
+        None => Some(Decimal::ONE),
+    };
+    let others_equal = a.len() - a.contains_key(&m) as usize
+        == b.len() - b.contains_key(&m) as usize
+        && a.iter().all(|(k, v)| *k == m || b.get(k) == Some(v));
+    if !others_equal {
+        return None;
+    }
+    let total = multiplier(a)? + multiplier(b)?;
+    let mut params = a.clone();
+    params.insert(m, ParamValue::Numeric(total));
+    Some(params)
+}
+
+impl RenameSchema for Spice {
+    /// Updates the inductors coupled by mutual inductances
+    /// and the voltage sources sensed by current-controlled sources.
+    fn rename_instance_refs(
+        primitive: &Primitive,
+        rename: &dyn Fn(&str) -> Option<ArcStr>,
+    ) -> Option<Primitive> {
+        match primitive {
+            Primitive::MutualInd {
+                ind1,
+                ind2,
+                coupling,
+            } => {
+                let (new1, new2) = (rename(ind1), rename(ind2));
+                (new1.is_some() || new2.is_some()).then(|| Primitive::MutualInd {
+                    ind1: new1.unwrap_or_else(|| ind1.clone()),
+                    ind2: new2.unwrap_or_else(|| ind2.clone()),
+                    coupling: coupling.clone(),
+                })
+            }
+            Primitive::Cccs { vsource, gain } => Some(Primitive::Cccs {
+                vsource: rename(vsource)?,
+                gain: gain.clone(),
+            }),
+            Primitive::Ccvs { vsource, gain } => Some(Primitive::Ccvs {
+                vsource: rename(vsource)?,
+                gain: gain.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl ErcSchema for Spice {
+    fn terminal_role(primitive: &Primitive, port: &str) -> TerminalRole {
+        match (primitive, port) {
+            (Primitive::Res2 { .. } | Primitive::Ind2 { .. }, _) => TerminalRole::Conductive,
+            (Primitive::Cap2 { .. }, _) => TerminalRole::Blocking,
+            (Primitive::Mos { .. }, "D") => TerminalRole::Drain,
+            (Primitive::Mos { .. }, "G") => TerminalRole::Gate,
+            (Primitive::Mos { .. }, "S") => TerminalRole::Source,
+            (Primitive::Mos { .. }, "B") => TerminalRole::Bulk,
+            _ => TerminalRole::Other,
+        }
+    }
+
+    /// Infers MOSFET polarity from the model name,
+    /// which must contain `pfet`/`pmos` or `nfet`/`nmos`.
+    fn polarity(primitive: &Primitive) -> Option<Polarity> {
+        let Primitive::Mos { model, .. } = primitive else {
+            return None;
+        };
+        let model = model.to_lowercase();
+        if model.contains("pfet") || model.contains("pmos") {
+            Some(Polarity::Pmos)
+        } else if model.contains("nfet") || model.contains("nmos") {
+            Some(Polarity::Nmos)
+        } else {
+            None
+        }
+    }
+}
+
 impl FromSchema<NoSchema> for Spice {
     type Error = NoSchemaError;
 
@@ -138,8 +309,34 @@ impl FromSchema<NoSchema> for Spice {
     }
 }
 
+/// Serialization of case-insensitive parameter maps.
+///
+/// Parameters are serialized as a map sorted by name so that the output is deterministic.
+mod params_serde {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        params: &HashMap<UniCase<ArcStr>, ParamValue>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<_> = params.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        entries.sort_by_key(|(k, _)| *k);
+        serializer.collect_map(entries)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<UniCase<ArcStr>, ParamValue>, D::Error> {
+        let params = HashMap::<ArcStr, ParamValue>::deserialize(deserializer)?;
+        Ok(params
+            .into_iter()
+            .map(|(k, v)| (UniCase::new(k), v))
+            .collect())
+    }
+}
+
 /// The value of a component.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ComponentValue {
     /// The component has a fixed, known, numeric value.
     Fixed(Decimal),
@@ -156,14 +353,115 @@ impl Display for ComponentValue {
     }
 }
 
+/// The kind of a transient source waveform.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum WaveformKind {
+    /// A pulse train (`PULSE(v1 v2 td tr tf pw per)`).
+    Pulse,
+    /// A piecewise linear waveform (`PWL(t1 v1 t2 v2 ...)`).
+    Pwl,
+    /// A damped sinusoid (`SIN(vo va freq td theta)`).
+    Sin,
+}
+
+impl Display for WaveformKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaveformKind::Pulse => write!(f, "PULSE"),
+            WaveformKind::Pwl => write!(f, "PWL"),
+            WaveformKind::Sin => write!(f, "SIN"),
+        }
+    }
+}
+
+/// A transient source waveform.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Waveform<T> {
+    /// The kind of waveform.
+    pub kind: WaveformKind,
+    /// The positional arguments of the waveform, in the order SPICE expects them.
+    pub args: Vec<T>,
+}
+
+/// The specification of an independent voltage or current source.
+///
+/// `T` is the type of source values:
+/// [`Substr`](crate::parser::Substr) in the parser AST and [`ParamValue`] in SCIR.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SourceSpec<T> {
+    /// The DC value.
+    pub dc: Option<T>,
+    /// The AC magnitude.
+    pub ac_mag: Option<T>,
+    /// The AC phase, in degrees.
+    pub ac_phase: Option<T>,
+    /// The transient waveform.
+    pub waveform: Option<Waveform<T>>,
+}
+
+impl<T> Default for SourceSpec<T> {
+    fn default() -> Self {
+        Self {
+            dc: None,
+            ac_mag: None,
+            ac_phase: None,
+            waveform: None,
+        }
+    }
+}
+
+impl<T> SourceSpec<T> {
+    /// Converts each value of the specification using `f`.
+    pub fn try_map<U, E>(&self, mut f: impl FnMut(&T) -> Result<U, E>) -> Result<SourceSpec<U>, E> {
+        Ok(SourceSpec {
+            dc: self.dc.as_ref().map(&mut f).transpose()?,
+            ac_mag: self.ac_mag.as_ref().map(&mut f).transpose()?,
+            ac_phase: self.ac_phase.as_ref().map(&mut f).transpose()?,
+            waveform: self
+                .waveform
+                .as_ref()
+                .map(|waveform| {
+                    Ok(Waveform {
+                        kind: waveform.kind,
+                        args: waveform.args.iter().map(&mut f).collect::<Result<_, _>>()?,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+
+    /// Iterates over all values of the specification.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.dc
+            .iter()
+            .chain(self.ac_mag.iter())
+            .chain(self.ac_phase.iter())
+            .chain(
+                self.waveform
+                    .iter()
+                    .flat_map(|waveform| waveform.args.iter()),
+            )
+    }
+}
+
+/// The quantity produced by a behavioral source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum BehavioralKind {
+    /// The expression gives the source voltage (`V=...`).
+    Voltage,
+    /// The expression gives the source current (`I=...`).
+    Current,
+}
+
 /// A SPICE primitive.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Primitive {
     /// A resistor primitive with ports "1" and "2" and value `value`.
     Res2 {
         /// The resistor value.
         value: ComponentValue,
         /// Parameters associated with the resistor.
+        #[serde(with = "params_serde")]
         params: HashMap<UniCase<ArcStr>, ParamValue>,
     },
     /// A capacitor primitive with ports "1" and "2" and value `value`.
@@ -176,6 +474,7 @@ pub enum Primitive {
         /// The name of the diode model.
         model: ArcStr,
         /// Parameters associated with the diode.
+        #[serde(with = "params_serde")]
         params: HashMap<UniCase<ArcStr>, ParamValue>,
     },
     /// A MOS primitive with ports "D", "G", "S", and "B".
@@ -183,6 +482,7 @@ pub enum Primitive {
         /// The name of the MOS model.
         model: ArcStr,
         /// Parameters associated with the MOS primitive.
+        #[serde(with = "params_serde")]
         params: HashMap<UniCase<ArcStr>, ParamValue>,
     },
     /// A raw instance with an associated cell.
@@ -192,6 +492,7 @@ pub enum Primitive {
         /// The associated cell.
         cell: ArcStr,
         /// Parameters associated with the raw instance.
+        #[serde(with = "params_serde")]
         params: HashMap<UniCase<ArcStr>, ParamValue>,
     },
     /// A raw instance with an associated cell.
@@ -203,10 +504,72 @@ pub enum Primitive {
         /// The associated cell.
         cell: ArcStr,
         /// Parameters associated with the raw instance.
+        #[serde(with = "params_serde")]
         params: HashMap<UniCase<ArcStr>, ParamValue>,
         /// The body of the associated cell.
         body: ArcStr,
     },
+    /// An independent voltage source with ports "1" (positive) and "2" (negative).
+    Vsource2 {
+        /// The source specification.
+        spec: SourceSpec<ParamValue>,
+    },
+    /// An independent current source with ports "1" and "2".
+    ///
+    /// Positive current flows from "1" through the source to "2".
+    Isource2 {
+        /// The source specification.
+        spec: SourceSpec<ParamValue>,
+    },
+    /// An inductor primitive with ports "1" and "2" and value `value`.
+    Ind2 {
+        /// The inductor value.
+        value: ParamValue,
+    },
+    /// A mutual inductance coupling two inductors in the same cell.
+    ///
+    /// Has no ports.
+    MutualInd {
+        /// The instance name of the first inductor.
+        ind1: ArcStr,
+        /// The instance name of the second inductor.
+        ind2: ArcStr,
+        /// The coupling coefficient.
+        coupling: ParamValue,
+    },
+    /// A voltage-controlled voltage source with output ports "P" and "N"
+    /// and control ports "CP" and "CN".
+    Vcvs {
+        /// The voltage gain.
+        gain: ParamValue,
+    },
+    /// A voltage-controlled current source with output ports "P" and "N"
+    /// and control ports "CP" and "CN".
+    Vccs {
+        /// The transconductance.
+        gain: ParamValue,
+    },
+    /// A current-controlled current source with ports "P" and "N".
+    Cccs {
+        /// The instance name of the voltage source through which the controlling current flows.
+        vsource: ArcStr,
+        /// The current gain.
+        gain: ParamValue,
+    },
+    /// A current-controlled voltage source with ports "P" and "N".
+    Ccvs {
+        /// The instance name of the voltage source through which the controlling current flows.
+        vsource: ArcStr,
+        /// The transresistance.
+        gain: ParamValue,
+    },
+    /// A behavioral source with ports "1" and "2".
+    Behavioral {
+        /// Whether the expression gives a voltage or a current.
+        kind: BehavioralKind,
+        /// The expression, without enclosing quotes or braces.
+        expr: ArcStr,
+    },
     /// An instance with blackboxed contents.
     BlackboxInstance {
         /// The contents of the cell.
@@ -215,7 +578,7 @@ pub enum Primitive {
 }
 
 /// Contents of a blackboxed instance.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlackboxContents {
     /// The elements that make up this blackbox.
     pub elems: Vec<BlackboxElement>,
@@ -229,7 +592,7 @@ impl BlackboxContents {
 }
 
 /// An element of a blackbox instance.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BlackboxElement {
     /// A placeholder for the instance's name.
     InstanceName,
@@ -275,6 +638,15 @@ impl Primitive {
             Primitive::Cap2 { .. } => vec!["1".into(), "2".into()],
             Primitive::Diode2 { .. } => vec!["1".into(), "2".into()],
             Primitive::Mos { .. } => vec!["D".into(), "G".into(), "S".into(), "B".into()],
+            Primitive::Vsource2 { .. }
+            | Primitive::Isource2 { .. }
+            | Primitive::Ind2 { .. }
+            | Primitive::Behavioral { .. } => vec!["1".into(), "2".into()],
+            Primitive::MutualInd { .. } => Vec::new(),
+            Primitive::Vcvs { .. } | Primitive::Vccs { .. } => {
+                vec!["P".into(), "N".into(), "CP".into(), "CN".into()]
+            }
+            Primitive::Cccs { .. } | Primitive::Ccvs { .. } => vec!["P".into(), "N".into()],
             Primitive::RawInstance { ports, .. } => ports.clone(),
             Primitive::RawInstanceWithCell { ports, .. } => ports.clone(),
             Primitive::BlackboxInstance { contents } => contents
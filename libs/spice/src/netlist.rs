@@ -7,10 +7,11 @@ use std::collections::HashMap;
 use std::io::{Result, Write};
 use std::path::PathBuf;
 
-use crate::{BlackboxElement, Primitive, Spice};
+use crate::{BehavioralKind, BlackboxElement, Primitive, SourceSpec, Spice};
 use scir::schema::Schema;
 use scir::{
-    Cell, ChildId, Library, NetlistCellConversion, NetlistLibConversion, SignalInfo, Slice,
+    Cell, ChildId, Library, NetlistCellConversion, NetlistLibConversion, ParamValue, SignalInfo,
+    Slice,
 };
 
 use substrate::schematic::netlist::ConvertibleNetlister;
@@ -68,6 +69,37 @@ pub trait HasSpiceLikeNetlist: Schema {
         name: &ArcStr,
         ports: &[&SignalInfo],
     ) -> Result<()>;
+    /// Writes the parameters declared by a subcircuit and their default values.
+    ///
+    /// Called immediately after [`HasSpiceLikeNetlist::write_start_subckt`]
+    /// if the subcircuit declares at least one parameter.
+    /// Should not include a newline at the end.
+    fn write_subckt_params<W: Write>(
+        &self,
+        out: &mut W,
+        params: &[(&ArcStr, &ParamValue)],
+    ) -> Result<()> {
+        for (name, value) in params {
+            self.write_param(out, name, value)?;
+        }
+        Ok(())
+    }
+    /// Writes the parameters declared by a testbench top cell, which is not
+    /// wrapped in a subcircuit.
+    ///
+    /// Called before any instances are written.
+    /// A newline will be added afterward.
+    fn write_top_params<W: Write>(
+        &self,
+        out: &mut W,
+        params: &[(&ArcStr, &ParamValue)],
+    ) -> Result<()> {
+        write!(out, ".PARAM")?;
+        for (name, value) in params {
+            self.write_param(out, name, value)?;
+        }
+        Ok(())
+    }
     /// Writes an end subcircuit statement.
     ///
     /// A newline will be added afterward.
@@ -82,6 +114,29 @@ pub trait HasSpiceLikeNetlist: Schema {
         connections: Vec<ArcStr>,
         child: &ArcStr,
     ) -> Result<ArcStr>;
+    /// Writes the parameters set on an instance of a SCIR cell.
+    ///
+    /// Called immediately after [`HasSpiceLikeNetlist::write_instance`]
+    /// if the instance sets at least one parameter.
+    /// Should not include a newline at the end.
+    fn write_instance_params<W: Write>(
+        &self,
+        out: &mut W,
+        params: &[(&ArcStr, &ParamValue)],
+    ) -> Result<()> {
+        for (name, value) in params {
+            self.write_param(out, name, value)?;
+        }
+        Ok(())
+    }
+    /// Writes a single parameter assignment, preceded by a space.
+    ///
+    /// By default, parameters are written as `name=value`,
+    /// with expressions written verbatim.
+    /// Should not include a newline at the end.
+    fn write_param<W: Write>(&self, out: &mut W, name: &ArcStr, value: &ParamValue) -> Result<()> {
+        write!(out, " {}={}", name, value)
+    }
     /// Writes a primitive instantiation.
     ///
     /// A newline will be added afterward.
@@ -226,7 +281,17 @@ impl<'a, S: HasSpiceLikeNetlist, W: Write> NetlisterInstance<'a, S, W> {
                 .collect();
             self.schema
                 .write_start_subckt(self.out, cell.name(), &ports)?;
+            let params: Vec<_> = cell.params().collect();
+            if !params.is_empty() {
+                self.schema.write_subckt_params(self.out, &params)?;
+            }
             writeln!(self.out, "\n")?;
+        } else {
+            let params: Vec<_> = cell.params().collect();
+            if !params.is_empty() {
+                self.schema.write_top_params(self.out, &params)?;
+                writeln!(self.out, "\n")?;
+            }
         }
 
         let mut conv = NetlistCellConversion::new();
@@ -254,8 +319,18 @@ impl<'a, S: HasSpiceLikeNetlist, W: Write> NetlisterInstance<'a, S, W> {
                             connections.remove(port_name).unwrap()
                         })
                         .collect::<Vec<_>>();
-                    self.schema
-                        .write_instance(self.out, inst.name(), ports, child.name())?
+                    let name =
+                        self.schema
+                            .write_instance(self.out, inst.name(), ports, child.name())?;
+                    let params: Vec<_> = inst
+                        .params()
+                        .iter()
+                        .sorted_by_key(|(key, _)| *key)
+                        .collect();
+                    if !params.is_empty() {
+                        self.schema.write_instance_params(self.out, &params)?;
+                    }
+                    name
                 }
                 ChildId::Primitive(child_id) => {
                     let child = self.lib.primitive(child_id);
@@ -352,6 +427,19 @@ impl HasSpiceLikeNetlist for Spice {
         write!(out, ".ENDS {}", name)
     }
 
+    fn write_param<W: Write>(
+        &self,
+        out: &mut W,
+        name: &ArcStr,
+        value: &ParamValue,
+    ) -> std::io::Result<()> {
+        match value {
+            // Expressions must be quoted so that they are not split into separate tokens.
+            ParamValue::Expr(expr) => write!(out, " {}='{}'", name, expr),
+            value => write!(out, " {}={}", name, value),
+        }
+    }
+
     fn write_instance<W: Write>(
         &self,
         out: &mut W,
@@ -389,7 +477,7 @@ impl HasSpiceLikeNetlist for Spice {
                 }
                 write!(out, " {value}")?;
                 for (key, value) in params.iter().sorted_by_key(|(key, _)| *key) {
-                    write!(out, " {key}={value}")?;
+                    self.write_param(out, key, value)?;
                 }
                 name
             }
@@ -417,7 +505,7 @@ impl HasSpiceLikeNetlist for Spice {
                 }
                 write!(out, " {}", mname)?;
                 for (key, value) in params.iter().sorted_by_key(|(key, _)| *key) {
-                    write!(out, " {key}={value}")?;
+                    self.write_param(out, key, value)?;
                 }
                 name
             }
@@ -434,10 +522,77 @@ impl HasSpiceLikeNetlist for Spice {
                 }
                 write!(out, " {}", mname)?;
                 for (key, value) in params.iter().sorted_by_key(|(key, _)| *key) {
-                    write!(out, " {key}={value}")?;
+                    self.write_param(out, key, value)?;
                 }
                 name
             }
+            Primitive::Vsource2 { spec } | Primitive::Isource2 { spec } => {
+                let prefix = if matches!(primitive, Primitive::Vsource2 { .. }) {
+                    "V"
+                } else {
+                    "I"
+                };
+                let name = arcstr::format!("{}{}", prefix, name);
+                write!(out, "{}", name)?;
+                write_connections(out, &mut connections, &["1", "2"])?;
+                write_source_spec(out, spec)?;
+                name
+            }
+            Primitive::Ind2 { value } => {
+                let name = arcstr::format!("L{}", name);
+                write!(out, "{}", name)?;
+                write_connections(out, &mut connections, &["1", "2"])?;
+                write!(out, " ")?;
+                write_value(out, value)?;
+                name
+            }
+            Primitive::MutualInd {
+                ind1,
+                ind2,
+                coupling,
+            } => {
+                let name = arcstr::format!("K{}", name);
+                write!(out, "{} L{} L{} ", name, ind1, ind2)?;
+                write_value(out, coupling)?;
+                name
+            }
+            Primitive::Vcvs { gain } | Primitive::Vccs { gain } => {
+                let prefix = if matches!(primitive, Primitive::Vcvs { .. }) {
+                    "E"
+                } else {
+                    "G"
+                };
+                let name = arcstr::format!("{}{}", prefix, name);
+                write!(out, "{}", name)?;
+                write_connections(out, &mut connections, &["P", "N", "CP", "CN"])?;
+                write!(out, " ")?;
+                write_value(out, gain)?;
+                name
+            }
+            Primitive::Cccs { vsource, gain } | Primitive::Ccvs { vsource, gain } => {
+                let prefix = if matches!(primitive, Primitive::Cccs { .. }) {
+                    "F"
+                } else {
+                    "H"
+                };
+                let name = arcstr::format!("{}{}", prefix, name);
+                write!(out, "{}", name)?;
+                write_connections(out, &mut connections, &["P", "N"])?;
+                write!(out, " V{} ", vsource)?;
+                write_value(out, gain)?;
+                name
+            }
+            Primitive::Behavioral { kind, expr } => {
+                let name = arcstr::format!("B{}", name);
+                write!(out, "{}", name)?;
+                write_connections(out, &mut connections, &["1", "2"])?;
+                let quantity = match kind {
+                    BehavioralKind::Voltage => "V",
+                    BehavioralKind::Current => "I",
+                };
+                write!(out, " {}={{{}}}", quantity, expr)?;
+                name
+            }
             Primitive::RawInstance {
                 cell,
                 ports,
@@ -458,7 +613,7 @@ impl HasSpiceLikeNetlist for Spice {
                 }
                 write!(out, " {}", cell)?;
                 for (key, value) in params.iter().sorted_by_key(|(key, _)| *key) {
-                    write!(out, " {key}={value}")?;
+                    self.write_param(out, key, value)?;
                 }
                 name
             }
@@ -485,6 +640,55 @@ impl HasSpiceLikeNetlist for Spice {
     }
 }
 
+/// Writes the nodes connected to each of `ports`, in order.
+fn write_connections<W: Write>(
+    out: &mut W,
+    connections: &mut HashMap<ArcStr, Vec<ArcStr>>,
+    ports: &[&str],
+) -> Result<()> {
+    for port in ports {
+        for part in connections.remove(*port).unwrap() {
+            write!(out, " {}", part)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a source value, quoting expressions so that they are not split into separate tokens.
+fn write_value<W: Write>(out: &mut W, value: &ParamValue) -> Result<()> {
+    match value {
+        ParamValue::Expr(expr) => write!(out, "'{}'", expr),
+        value => write!(out, "{}", value),
+    }
+}
+
+/// Writes the DC, AC and transient specifications of an independent source.
+fn write_source_spec<W: Write>(out: &mut W, spec: &SourceSpec<ParamValue>) -> Result<()> {
+    if let Some(dc) = &spec.dc {
+        write!(out, " DC ")?;
+        write_value(out, dc)?;
+    }
+    if let Some(mag) = &spec.ac_mag {
+        write!(out, " AC ")?;
+        write_value(out, mag)?;
+        if let Some(phase) = &spec.ac_phase {
+            write!(out, " ")?;
+            write_value(out, phase)?;
+        }
+    }
+    if let Some(waveform) = &spec.waveform {
+        write!(out, " {}(", waveform.kind)?;
+        for (i, arg) in waveform.args.iter().enumerate() {
+            if i > 0 {
+                write!(out, " ")?;
+            }
+            write_value(out, arg)?;
+        }
+        write!(out, ")")?;
+    }
+    Ok(())
+}
+
 impl ConvertibleNetlister<Spice> for Spice {
     type Error = std::io::Error;
     type Options<'a> = NetlistOptions<'a>;
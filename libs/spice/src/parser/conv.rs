@@ -2,7 +2,7 @@
 //!
 //! Currently, we only support converting to SCIR.
 //!
-//! TODO: bus ports, expressions, validation, ArcStr deduplication.
+//! TODO: bus ports, validation, ArcStr deduplication.
 
 use std::collections::{HashMap, HashSet};
 
@@ -14,12 +14,13 @@ use num_traits::Pow;
 use regex::Regex;
 use rust_decimal::prelude::One;
 use rust_decimal::Decimal;
+use scir::param::Expr;
 use scir::ParamValue;
 
 use thiserror::Error;
 use unicase::UniCase;
 
-use super::{Ast, Component, DeviceValue, Elem, Node, Subckt, Substr};
+use super::{Ast, Component, DeviceValue, Elem, Node, Params, Subckt, Substr};
 
 /// The type representing subcircuit names.
 pub type SubcktName = Substr;
@@ -52,30 +53,64 @@ pub enum ConvError {
     /// Netlist conversion produced invalid SCIR.
     #[error("netlist conversion produced SCIR containing errors: {0}")]
     InvalidScir(Box<scir::Issues>),
-    /// A non-blackbox cell was instantiated with parameters.
-    ///
-    /// Substrate does not support SPICE-like parameters on non-blackbox cells.
-    #[error("parameters for instance {inst} of cell `{child}` (in cell `{parent}`) are not allowed because `{child}` was not blackboxed")]
-    UnsupportedParams {
+    /// An instance of a non-blackbox cell sets a parameter that the cell does not declare.
+    #[error("instance {inst} of cell `{child}` (in cell `{parent}`) sets parameter `{param}`, but `{child}` does not declare this parameter")]
+    UndeclaredParam {
         /// The name of the instance.
         inst: Substr,
         /// The name of the cell being instantiated.
         child: Substr,
         /// The name of the cell containing the offending instance.
         parent: Substr,
+        /// The name of the parameter.
+        param: Substr,
     },
+    /// A parameter value is not a valid expression.
+    #[error("invalid expression: `{0}`")]
+    InvalidExpression(Substr),
+    /// The name of the cell containing the top-level components is already used by a subcircuit.
+    #[error("cannot convert top-level components to cell `{0}`, since a subcircuit with the same name exists")]
+    DuplicateTopCell(Substr),
+    /// Local parameters of a subcircuit refer to each other in a cycle.
+    #[error("local parameter `{0}` refers to itself")]
+    CyclicParam(Substr),
 }
 
+/// The default name of the cell containing the top-level components of a netlist.
+pub const DEFAULT_TOP_CELL_NAME: &str = "spice_top";
+
 /// Converts a parsed SPICE netlist to [`scir`].
 ///
-/// The converter only converts subcircuits.
-/// Top-level component instantiations are ignored.
+/// Every subcircuit is converted to a SCIR cell. Top-level components, if any, are
+/// converted to a testbench cell named by [`ScirConverter::top_cell_name`], which becomes
+/// the top cell of the library. Its only port is the ground node `0`, if used.
+/// Model cards are ignored.
+///
+/// Parameters declared with `.param` inside a subcircuit are local: their values are
+/// substituted wherever they are used, so instances cannot override them.
+/// Top-level `.param`s referenced by a subcircuit are added to the cell as parameters
+/// whose defaults are the top-level values.
+/// Parameter names are case-insensitive; references to parameters within expressions
+/// are converted to use the spelling from the parameter's declaration.
+///
+/// Nodes declared with `.global` become ports of every subcircuit that uses them,
+/// directly or through its children. Global ports are added after the declared ports.
 pub struct ScirConverter<'a> {
     ast: &'a Ast,
     lib: scir::LibraryBuilder<Spice>,
     blackbox_cells: HashSet<Substr>,
     subckts: HashMap<SubcktName, &'a Subckt>,
     ids: HashMap<SubcktName, scir::CellId>,
+    /// Top-level parameters, keyed by lowercase name.
+    global_params: HashMap<String, &'a Substr>,
+    /// The declared spellings of the top-level parameters.
+    global_scope: ParamScope,
+    /// Global nodes, in the order they were declared.
+    global_nodes: Vec<Node>,
+    /// The global nodes exposed as ports by each converted subcircuit.
+    global_ports: HashMap<SubcktName, Vec<Node>>,
+    /// The name of the cell containing the top-level components.
+    top_cell_name: Substr,
 }
 
 impl<'a> ScirConverter<'a> {
@@ -87,6 +122,11 @@ impl<'a> ScirConverter<'a> {
             blackbox_cells: Default::default(),
             subckts: Default::default(),
             ids: Default::default(),
+            global_params: Default::default(),
+            global_scope: Default::default(),
+            global_nodes: Default::default(),
+            global_ports: Default::default(),
+            top_cell_name: DEFAULT_TOP_CELL_NAME.into(),
         }
     }
 
@@ -95,19 +135,67 @@ impl<'a> ScirConverter<'a> {
         self.blackbox_cells.insert(cell_name.into());
     }
 
+    /// Sets the name of the cell containing the top-level components.
+    ///
+    /// Defaults to [`DEFAULT_TOP_CELL_NAME`].
+    pub fn top_cell_name(&mut self, name: impl Into<Substr>) {
+        self.top_cell_name = name.into();
+    }
+
     /// Consumes the converter, yielding a SCIR [library](scir::Library).
     pub fn convert(mut self) -> ConvResult<scir::Library<Spice>> {
         self.subckts = map_subckts(self.ast);
+        for elem in self.ast.elems.iter() {
+            match elem {
+                Elem::Param(params) => {
+                    for (k, v) in params.iter() {
+                        self.global_params.insert(k.to_lowercase(), v);
+                        self.global_scope.declare(k);
+                    }
+                }
+                Elem::Global(nodes) => {
+                    for node in nodes {
+                        if !self.global_nodes.contains(node) {
+                            self.global_nodes.push(node.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
         let subckts = self.subckts.values().copied().collect::<Vec<_>>();
         let mut shorts = ShortPropagator::analyze(self.ast, &self.blackbox_cells);
         for subckt in subckts {
-            match self.convert_subckt(subckt, &mut shorts) {
+            match self.convert_subckt(subckt, &mut shorts, false) {
                 // Export blackbox errors can be ignored; we just skip
                 // exporting a SCIR cell for blackboxed subcircuits.
                 Ok(_) | Err(ConvError::ExportBlackbox) => (),
                 Err(e) => return Err(e),
             };
         }
+
+        let top = Subckt {
+            name: self.top_cell_name.clone(),
+            components: self
+                .ast
+                .elems
+                .iter()
+                .filter_map(|elem| match elem {
+                    Elem::Component(component) => Some(component.clone()),
+                    _ => None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        if !top.components.is_empty() {
+            if self.subckts.contains_key(&top.name) {
+                return Err(ConvError::DuplicateTopCell(top.name));
+            }
+            shorts.analyze_subckt(&top, &self.subckts, &self.blackbox_cells);
+            let id = self.convert_subckt(&top, &mut shorts, true)?;
+            self.lib.set_top(id);
+        }
+
         let lib = self
             .lib
             .build()
@@ -115,10 +203,14 @@ impl<'a> ScirConverter<'a> {
         Ok(lib)
     }
 
+    /// Converts `subckt` to a SCIR cell.
+    ///
+    /// If `top` is true, `subckt` contains the top-level components of the netlist.
     fn convert_subckt(
         &mut self,
         subckt: &Subckt,
         shorts: &mut ShortPropagator,
+        top: bool,
     ) -> ConvResult<scir::CellId> {
         if let Some(&id) = self.ids.get(&subckt.name) {
             return Ok(id);
@@ -131,6 +223,14 @@ impl<'a> ScirConverter<'a> {
         let parent_name = subckt.name.clone();
 
         let mut cell = scir::Cell::new(ArcStr::from(subckt.name.as_str()));
+        let mut scope = self.global_scope.clone();
+        for (k, _) in subckt.params.iter() {
+            scope.declare(k);
+        }
+        scope.declare_locals(&subckt.local_params)?;
+        for (k, v) in subckt.params.iter() {
+            cell.add_param(k.as_str(), scope.value(v)?);
+        }
         let mut nodes: HashMap<Substr, scir::SliceOne> = HashMap::new();
         // TODO: this is an expensive clone
         let mut local_shorts = shorts.get_cell(&parent_name).clone();
@@ -151,15 +251,7 @@ impl<'a> ScirConverter<'a> {
                     let params = mos
                         .params
                         .iter()
-                        .map(|(k, v)| {
-                            Ok((
-                                UniCase::new(ArcStr::from(k.as_str())),
-                                match substr_as_numeric_lit(v) {
-                                    Ok(v) => ParamValue::Numeric(v),
-                                    Err(_) => ParamValue::String(v.to_string().into()),
-                                },
-                            ))
-                        })
+                        .map(|(k, v)| Ok((UniCase::new(ArcStr::from(k.as_str())), scope.value(v)?)))
                         .collect::<ConvResult<HashMap<_, _>>>()?;
                     // TODO: Deduplicate primitives, though does not affect functionality
                     let id = self.lib.add_primitive(Primitive::Mos { model, params });
@@ -175,15 +267,7 @@ impl<'a> ScirConverter<'a> {
                     let params = diode
                         .params
                         .iter()
-                        .map(|(k, v)| {
-                            Ok((
-                                UniCase::new(ArcStr::from(k.as_str())),
-                                match substr_as_numeric_lit(v) {
-                                    Ok(v) => ParamValue::Numeric(v),
-                                    Err(_) => ParamValue::String(v.to_string().into()),
-                                },
-                            ))
-                        })
+                        .map(|(k, v)| Ok((UniCase::new(ArcStr::from(k.as_str())), scope.value(v)?)))
                         .collect::<ConvResult<HashMap<_, _>>>()?;
                     // TODO: Deduplicate primitives, though does not affect functionality
                     let id = self.lib.add_primitive(Primitive::Diode2 { model, params });
@@ -204,15 +288,7 @@ impl<'a> ScirConverter<'a> {
                     let params = res
                         .params
                         .iter()
-                        .map(|(k, v)| {
-                            Ok((
-                                UniCase::new(ArcStr::from(k.as_str())),
-                                match substr_as_numeric_lit(v) {
-                                    Ok(v) => ParamValue::Numeric(v),
-                                    Err(_) => ParamValue::String(v.to_string().into()),
-                                },
-                            ))
-                        })
+                        .map(|(k, v)| Ok((UniCase::new(ArcStr::from(k.as_str())), scope.value(v)?)))
                         .collect::<ConvResult<HashMap<_, _>>>()?;
                     let id = self.lib.add_primitive(Primitive::Res2 { value, params });
                     let mut sinst = scir::Instance::new(&res.name[1..], id);
@@ -229,19 +305,75 @@ impl<'a> ScirConverter<'a> {
                     sinst.connect("2", node(&cap.neg, &mut cell));
                     cell.add_instance(sinst);
                 }
+                Component::Vsource(source) | Component::Isource(source) => {
+                    let spec = source.spec.try_map(|v| scope.value(v))?;
+                    let id = self.lib.add_primitive(match component {
+                        Component::Vsource(_) => Primitive::Vsource2 { spec },
+                        _ => Primitive::Isource2 { spec },
+                    });
+                    let mut sinst = scir::Instance::new(&source.name[1..], id);
+                    sinst.connect("1", node(&source.pos, &mut cell));
+                    sinst.connect("2", node(&source.neg, &mut cell));
+                    cell.add_instance(sinst);
+                }
+                Component::Ind(ind) => {
+                    let id = self.lib.add_primitive(Primitive::Ind2 {
+                        value: scope.value(&ind.value)?,
+                    });
+                    let mut sinst = scir::Instance::new(&ind.name[1..], id);
+                    sinst.connect("1", node(&ind.pos, &mut cell));
+                    sinst.connect("2", node(&ind.neg, &mut cell));
+                    cell.add_instance(sinst);
+                }
+                Component::MutualInd(k) => {
+                    // Inductor instance names are converted by stripping the leading 'L'.
+                    let id = self.lib.add_primitive(Primitive::MutualInd {
+                        ind1: ArcStr::from(&k.ind1[1..]),
+                        ind2: ArcStr::from(&k.ind2[1..]),
+                        coupling: scope.value(&k.coupling)?,
+                    });
+                    cell.add_instance(scir::Instance::new(&k.name[1..], id));
+                }
+                Component::Vcvs(source) | Component::Vccs(source) => {
+                    let gain = scope.value(&source.gain)?;
+                    let id = self.lib.add_primitive(match component {
+                        Component::Vcvs(_) => Primitive::Vcvs { gain },
+                        _ => Primitive::Vccs { gain },
+                    });
+                    let mut sinst = scir::Instance::new(&source.name[1..], id);
+                    sinst.connect("P", node(&source.pos, &mut cell));
+                    sinst.connect("N", node(&source.neg, &mut cell));
+                    sinst.connect("CP", node(&source.ctrl_pos, &mut cell));
+                    sinst.connect("CN", node(&source.ctrl_neg, &mut cell));
+                    cell.add_instance(sinst);
+                }
+                Component::Cccs(source) | Component::Ccvs(source) => {
+                    // Voltage source instance names are converted by stripping the leading 'V'.
+                    let vsource = ArcStr::from(&source.vsource[1..]);
+                    let gain = scope.value(&source.gain)?;
+                    let id = self.lib.add_primitive(match component {
+                        Component::Cccs(_) => Primitive::Cccs { vsource, gain },
+                        _ => Primitive::Ccvs { vsource, gain },
+                    });
+                    let mut sinst = scir::Instance::new(&source.name[1..], id);
+                    sinst.connect("P", node(&source.pos, &mut cell));
+                    sinst.connect("N", node(&source.neg, &mut cell));
+                    cell.add_instance(sinst);
+                }
+                Component::Behavioral(source) => {
+                    let id = self.lib.add_primitive(Primitive::Behavioral {
+                        kind: source.kind,
+                        expr: ArcStr::from(expr_body(&source.expr).unwrap_or(&source.expr)),
+                    });
+                    let mut sinst = scir::Instance::new(&source.name[1..], id);
+                    sinst.connect("1", node(&source.pos, &mut cell));
+                    sinst.connect("2", node(&source.neg, &mut cell));
+                    cell.add_instance(sinst);
+                }
                 Component::Instance(inst) => {
                     let blackbox = self.blackbox_cells.contains(&inst.child);
                     if let (false, Some(subckt)) = (blackbox, self.subckts.get(&inst.child)) {
-                        // Parameters are not supported for instances of non-blackboxed subcircuit.
-                        if !inst.params.values.is_empty() {
-                            return Err(ConvError::UnsupportedParams {
-                                inst: inst.name.clone(),
-                                child: subckt.name.clone(),
-                                parent: parent_name.clone(),
-                            });
-                        }
-
-                        let id = self.convert_subckt(subckt, shorts)?;
+                        let id = self.convert_subckt(subckt, shorts, false)?;
                         let mut sinst = scir::Instance::new(&inst.name[1..], id);
                         let subckt = self
                             .subckts
@@ -263,13 +395,24 @@ impl<'a> ScirConverter<'a> {
                                 sinst.connect(cport.as_str(), node(iport, &mut cell));
                             }
                         }
+                        for global in self.global_ports[&inst.child].iter() {
+                            sinst.connect(global.as_str(), node(global, &mut cell));
+                        }
 
-                        if !inst.params.values.is_empty() {
-                            return Err(ConvError::IncorrectConnections {
-                                inst: inst.name.clone(),
-                                child: subckt.name.clone(),
-                                parent: parent_name.clone(),
-                            });
+                        for (k, v) in inst.params.iter() {
+                            // SPICE parameter names are case-insensitive.
+                            let param = subckt
+                                .params
+                                .iter()
+                                .map(|(name, _)| name)
+                                .find(|name| name.eq_ignore_ascii_case(k))
+                                .ok_or_else(|| ConvError::UndeclaredParam {
+                                    inst: inst.name.clone(),
+                                    child: subckt.name.clone(),
+                                    parent: parent_name.clone(),
+                                    param: k.clone(),
+                                })?;
+                            sinst.set_param(param.as_str(), scope.value(v)?);
                         }
 
                         cell.add_instance(sinst);
@@ -279,13 +422,7 @@ impl<'a> ScirConverter<'a> {
                             .params
                             .iter()
                             .map(|(k, v)| {
-                                Ok((
-                                    UniCase::new(ArcStr::from(k.as_str())),
-                                    match substr_as_numeric_lit(v) {
-                                        Ok(v) => ParamValue::Numeric(v),
-                                        Err(_) => ParamValue::String(v.to_string().into()),
-                                    },
-                                ))
+                                Ok((UniCase::new(ArcStr::from(k.as_str())), scope.value(v)?))
                             })
                             .collect::<ConvResult<HashMap<_, _>>>()?;
                         let ports: Vec<_> = (0..inst.ports.len())
@@ -313,12 +450,162 @@ impl<'a> ScirConverter<'a> {
             cell.expose_port(port, Default::default());
         }
 
+        if top {
+            if let Some(&ground) = nodes.get(&local_shorts.root(&"0".into())) {
+                cell.expose_port(ground, Default::default());
+            }
+        }
+
+        let mut global_ports = Vec::new();
+        for global in self.global_nodes.iter() {
+            // Global nodes are ordinary nodes of the top cell.
+            if top || subckt.ports.contains(global) {
+                continue;
+            }
+            if let Some(&port) = nodes.get(&local_shorts.root(global)) {
+                cell.expose_port(port, Default::default());
+                global_ports.push(global.clone());
+            }
+        }
+        self.global_ports.insert(subckt.name.clone(), global_ports);
+
+        self.add_global_params(&mut cell)?;
+
         let id = self.lib.add_cell(cell);
         self.ids.insert(subckt.name.clone(), id);
         Ok(id)
     }
 }
 
+impl ScirConverter<'_> {
+    /// Declares the top-level parameters referenced by `cell` as parameters of `cell`,
+    /// using the top-level values as defaults.
+    fn add_global_params(&self, cell: &mut scir::Cell) -> ConvResult<()> {
+        loop {
+            let mut referenced: HashSet<ArcStr> = HashSet::new();
+            let mut add = |value: &ParamValue| {
+                referenced.extend(value.referenced_params().into_iter().cloned());
+            };
+            cell.params().for_each(|(_, v)| add(v));
+            for (_, inst) in cell.instances() {
+                inst.params().values().for_each(&mut add);
+                if let scir::ChildId::Primitive(id) = inst.child() {
+                    primitive_values(self.lib.primitive(id))
+                        .into_iter()
+                        .for_each(&mut add);
+                }
+            }
+            let mut referenced: Vec<_> = referenced
+                .into_iter()
+                .filter(|name| cell.param(name).is_none())
+                .filter_map(|name| Some((self.global_params.get(&name.to_lowercase())?, name)))
+                .collect();
+            if referenced.is_empty() {
+                return Ok(());
+            }
+            referenced.sort_by(|a, b| a.1.cmp(&b.1));
+            for (value, name) in referenced {
+                cell.add_param(name, self.global_scope.value(value)?);
+            }
+        }
+    }
+}
+
+/// The parameters visible in a SPICE scope.
+///
+/// SPICE parameter names are case-insensitive, but SCIR parameter names are not,
+/// so parameter references are rewritten to use the declared spelling.
+/// References to local parameters are replaced with the values of the local parameters.
+#[derive(Clone, Debug, Default)]
+struct ParamScope(HashMap<String, Expr>);
+
+impl ParamScope {
+    /// Declares a parameter, shadowing any parameter whose name differs only in case.
+    fn declare(&mut self, name: &str) {
+        self.0
+            .insert(name.to_lowercase(), Expr::Param(ArcStr::from(name)));
+    }
+
+    /// Declares the local parameters of a subcircuit,
+    /// whose values may refer to each other in any order.
+    fn declare_locals(&mut self, params: &Params) -> ConvResult<()> {
+        for (k, _) in params.iter() {
+            self.declare(k);
+        }
+        let mut values = params
+            .iter()
+            .map(|(k, v)| Ok((k, self.value(v)?)))
+            .collect::<ConvResult<Vec<_>>>()?;
+        let is_local = |name: &ArcStr| params.iter().any(|(k, _)| k.as_str() == name.as_str());
+        // Each pass resolves one more level of references between local parameters.
+        for _ in 0..values.len() {
+            let bindings: HashMap<ArcStr, Expr> = values
+                .iter()
+                .filter_map(|(k, v)| Some((ArcStr::from(k.as_str()), param_expr(v)?)))
+                .collect();
+            for (_, value) in values.iter_mut() {
+                *value = value.substitute(&bindings);
+            }
+        }
+        for (k, value) in values {
+            if value.referenced_params().into_iter().any(is_local) {
+                return Err(ConvError::CyclicParam(k.clone()));
+            }
+            if let Some(expr) = param_expr(&value) {
+                self.0.insert(k.to_lowercase(), expr);
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a SPICE parameter value to a SCIR [`ParamValue`],
+    /// referring to parameters in this scope by their declared names.
+    fn value(&self, s: &Substr) -> ConvResult<ParamValue> {
+        let value = substr_as_param_value(s)?;
+        let bindings: HashMap<ArcStr, Expr> = value
+            .referenced_params()
+            .into_iter()
+            .filter_map(|name| {
+                let expr = self.0.get(&name.to_lowercase())?;
+                (*expr != Expr::Param(name.clone())).then(|| (name.clone(), expr.clone()))
+            })
+            .collect();
+        if bindings.is_empty() {
+            Ok(value)
+        } else {
+            Ok(value.substitute(&bindings))
+        }
+    }
+}
+
+/// Converts a parameter value to an expression, or returns [`None`] for string values.
+fn param_expr(value: &ParamValue) -> Option<Expr> {
+    match value {
+        ParamValue::Numeric(value) => Some(Expr::Literal(*value)),
+        ParamValue::Expr(expr) => Some(expr.clone()),
+        ParamValue::String(_) => None,
+    }
+}
+
+/// The parameter values and source values of a primitive.
+fn primitive_values(primitive: &Primitive) -> Vec<&ParamValue> {
+    match primitive {
+        Primitive::Res2 { params, .. }
+        | Primitive::Diode2 { params, .. }
+        | Primitive::Mos { params, .. }
+        | Primitive::RawInstance { params, .. }
+        | Primitive::RawInstanceWithCell { params, .. } => params.values().collect(),
+        Primitive::Vsource2 { spec } | Primitive::Isource2 { spec } => spec.values().collect(),
+        Primitive::Ind2 { value } => vec![value],
+        Primitive::MutualInd { coupling, .. } => vec![coupling],
+        Primitive::Vcvs { gain }
+        | Primitive::Vccs { gain }
+        | Primitive::Cccs { gain, .. }
+        | Primitive::Ccvs { gain, .. } => vec![gain],
+        _ => Vec::new(),
+    }
+}
+
 lazy_static! {
     static ref NUMERIC_LITERAL_REGEX: Regex =
         Regex::new(r"^(-?[0-9]+\.?[0-9]*)((t|g|x|meg|k|m|u|n|p|f)|(e(-?[0-9]+\.?[0-9]*)))?$")
@@ -362,6 +649,32 @@ fn substr_as_numeric_lit(s: &Substr) -> ConvResult<Decimal> {
     str_as_numeric_lit(s).map_err(|_| ConvError::InvalidLiteral(s.clone()))
 }
 
+/// Converts a SPICE parameter value to a SCIR [`ParamValue`].
+///
+/// Numeric literals become numeric values, and expressions enclosed
+/// in quotes or braces become [`Expr`]s.
+/// All other values (such as model names) are kept as strings.
+fn substr_as_param_value(s: &Substr) -> ConvResult<ParamValue> {
+    if let Ok(v) = substr_as_numeric_lit(s) {
+        return Ok(ParamValue::Numeric(v));
+    }
+    match expr_body(s) {
+        Some(expr) => Ok(ParamValue::Expr(
+            Expr::parse(expr).map_err(|_| ConvError::InvalidExpression(s.clone()))?,
+        )),
+        None => Ok(ParamValue::String(s.to_string().into())),
+    }
+}
+
+/// The body of an expression enclosed in quotes or braces,
+/// or [`None`] if `s` is not such an expression.
+fn expr_body(s: &str) -> Option<&str> {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .or_else(|| s.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+        .or_else(|| s.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+}
+
 pub(crate) fn map_subckts(ast: &Ast) -> HashMap<SubcktName, &Subckt> {
     let mut subckts = HashMap::new();
     for elem in ast.elems.iter() {
@@ -6,14 +6,15 @@ pub mod shorts;
 mod tests;
 
 use std::borrow::Borrow;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
 use crate::parser::conv::convert_str_to_numeric_lit;
-use crate::Spice;
+use crate::{BehavioralKind, SourceSpec, Spice, Waveform, WaveformKind};
 use arcstr::ArcStr;
+use indexmap::IndexMap;
 use nom::bytes::complete::{take_till, take_while};
 use nom::error::ErrorKind;
 use nom::{IResult, InputTakeAtPosition};
@@ -59,7 +60,7 @@ struct ParserState {
 enum ReaderState {
     #[default]
     Top,
-    Subckt(Subckt),
+    Subckt(Box<Subckt>),
 }
 
 impl Parser {
@@ -85,7 +86,7 @@ impl Parser {
             Some(name) => ArcStr::from(name),
             None => arcstr::format!("{:?}", path),
         };
-        parser.parse_inner(s)?;
+        parser.parse_inner(s, None)?;
 
         let parsed = ParsedSpice {
             ast: parser.ast,
@@ -95,7 +96,29 @@ impl Parser {
         Ok(parsed)
     }
 
-    fn parse_file_inner(&mut self, path: impl AsRef<Path>) -> Result<(), ParserError> {
+    /// Parse only the library section named `section` of the given file.
+    ///
+    /// Equivalent to `.lib 'path' section`.
+    pub fn parse_file_section(
+        dialect: Dialect,
+        path: impl AsRef<Path>,
+        section: impl Into<Substr>,
+    ) -> Result<ParsedSpice, ParserError> {
+        let path = path.as_ref();
+        let mut parser = Self::new(dialect);
+        parser.parse_file_inner(path, Some(&section.into()))?;
+        Ok(ParsedSpice {
+            ast: parser.ast,
+            root: Some(path.to_path_buf()),
+            name: arcstr::format!("{:?}", path),
+        })
+    }
+
+    fn parse_file_inner(
+        &mut self,
+        path: impl AsRef<Path>,
+        section: Option<&Substr>,
+    ) -> Result<(), ParserError> {
         let path = path.as_ref();
         let s: ArcStr = std::fs::read_to_string(path)
             .map_err(|err| ParserError::FailedToRead {
@@ -105,7 +128,7 @@ impl Parser {
             .into();
         let s = Substr(arcstr::Substr::full(s));
         self.state.include_stack.push(path.into());
-        let res = self.parse_inner(s);
+        let res = self.parse_inner(s, section);
         self.state.include_stack.pop().unwrap();
         res?;
         Ok(())
@@ -119,7 +142,7 @@ impl Parser {
             Some(name) => ArcStr::from(name),
             None => arcstr::literal!("spice_library"),
         };
-        parser.parse_inner(data)?;
+        parser.parse_inner(data, None)?;
 
         let parsed = ParsedSpice {
             ast: parser.ast,
@@ -129,34 +152,78 @@ impl Parser {
         Ok(parsed)
     }
 
-    fn parse_inner(&mut self, data: Substr) -> Result<(), ParserError> {
+    /// Parses `data`, skipping lines outside of the library section `section`
+    /// if a section is given.
+    fn parse_inner(&mut self, data: Substr, section: Option<&Substr>) -> Result<(), ParserError> {
         let mut tok = Tokenizer::new(self.dialect, data);
+        let mut sections: Vec<Substr> = Vec::new();
+        let mut found = section.is_none();
         while let Some(line) = self.parse_line(&mut tok)? {
+            // Library section markers are processed even outside the selected section.
+            match line {
+                Line::LibSection { name } => {
+                    found |= section.is_some_and(|s| s.eq_ignore_ascii_case(&name));
+                    sections.push(name);
+                    continue;
+                }
+                Line::EndLibSection => {
+                    if sections.pop().is_none() {
+                        return Err(ParserError::UnexpectedLine(Box::new(line)));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some(section) = section {
+                if !sections.iter().any(|s| s.eq_ignore_ascii_case(section)) {
+                    continue;
+                }
+            }
+
             match (&mut self.state.reader_state, line) {
-                (ReaderState::Top, Line::SubcktDecl { name, ports }) => {
-                    self.state.reader_state = ReaderState::Subckt(Subckt {
+                (
+                    ReaderState::Top,
+                    Line::SubcktDecl {
+                        name,
+                        ports,
+                        params,
+                    },
+                ) => {
+                    self.state.reader_state = ReaderState::Subckt(Box::new(Subckt {
                         name,
                         ports,
-                        components: vec![],
-                        connects: vec![],
-                    });
+                        params,
+                        ..Default::default()
+                    }));
                 }
                 (ReaderState::Top, Line::Component(c)) => {
                     self.ast.elems.push(Elem::Component(c));
                 }
                 (ReaderState::Top, Line::Include { path }) => {
-                    let resolved_path = Path::new::<str>(path.0.as_ref());
-                    let resolved_path = if resolved_path.is_relative() {
-                        let root = self
-                            .state
-                            .include_stack
-                            .last()
-                            .ok_or(ParserError::UnexpectedRelativePath(path.clone()))?;
-                        root.parent().unwrap().join(resolved_path)
-                    } else {
-                        resolved_path.into()
-                    };
-                    self.parse_file_inner(resolved_path)?;
+                    let resolved_path = self.resolve_path(&path)?;
+                    self.parse_file_inner(resolved_path, None)?;
+                }
+                (ReaderState::Top, Line::Lib { path, section }) => {
+                    let resolved_path = self.resolve_path(&path)?;
+                    self.parse_file_inner(resolved_path, Some(&section))?;
+                }
+                (ReaderState::Top, Line::Param { params }) => {
+                    self.ast.elems.push(Elem::Param(params));
+                }
+                (ReaderState::Top, Line::Model(model)) => {
+                    self.ast.elems.push(Elem::Model(model));
+                }
+                (ReaderState::Top, Line::Global { nodes }) => {
+                    self.ast.elems.push(Elem::Global(nodes));
+                }
+                (ReaderState::Top, Line::End) => break,
+                (ReaderState::Subckt(ref mut subckt), Line::Param { params }) => {
+                    for (k, v) in params.iter() {
+                        subckt.local_params.insert(k.clone(), v.clone());
+                    }
+                }
+                (ReaderState::Subckt(ref mut subckt), Line::Model(model)) => {
+                    subckt.models.push(model);
                 }
                 (ReaderState::Subckt(ref mut subckt), Line::Component(c)) => {
                     subckt.components.push(c);
@@ -165,16 +232,37 @@ impl Parser {
                     subckt.connects.push((node1, node2));
                 }
                 (ReaderState::Subckt(ref mut subckt), Line::EndSubckt) => {
-                    let subckt = std::mem::take(subckt);
+                    let subckt = std::mem::take(&mut **subckt);
                     self.ast.elems.push(Elem::Subckt(subckt));
                     self.state.reader_state = ReaderState::Top;
                 }
                 (_, line) => return Err(ParserError::UnexpectedLine(Box::new(line))),
             }
         }
+        if !found {
+            return Err(ParserError::MissingSection {
+                path: self.state.include_stack.last().cloned().unwrap_or_default(),
+                section: section.unwrap().clone(),
+            });
+        }
         Ok(())
     }
 
+    /// Resolves `path` relative to the file currently being parsed.
+    fn resolve_path(&self, path: &Substr) -> Result<PathBuf, ParserError> {
+        let resolved_path = Path::new::<str>(path.0.as_ref());
+        Ok(if resolved_path.is_relative() {
+            let root = self
+                .state
+                .include_stack
+                .last()
+                .ok_or(ParserError::UnexpectedRelativePath(path.clone()))?;
+            root.parent().unwrap().join(resolved_path)
+        } else {
+            resolved_path.into()
+        })
+    }
+
     fn parse_line(&mut self, tok: &mut Tokenizer) -> Result<Option<Line>, ParserError> {
         while let Some(token) = tok.get()? {
             if token == Token::LineEnd {
@@ -193,25 +281,104 @@ impl Parser {
         let line = match self.buffer.first().unwrap() {
             Token::Directive(d) => {
                 if d.eq_ignore_ascii_case(".subckt") {
-                    // TODO params
+                    // A subcircuit declaration looks like this:
+                    //
+                    // ```spice
+                    // .subckt name port0 port1 [params:] param1=value1 param2=value2
+                    // ```
+                    //
+                    // The tokens before the first parameter name are ports.
                     let name = self.buffer[1].try_ident()?.clone();
-                    let ports = self.buffer[2..]
+                    let pos = self.buffer.iter().position(|t| matches!(t, Token::Equals));
+                    let mut port_end_idx = pos.map_or(self.buffer.len(), |pos| pos - 1);
+                    let params = self.parse_params(port_end_idx)?;
+                    if port_end_idx > 2
+                        && self.buffer[port_end_idx - 1]
+                            .try_ident()?
+                            .eq_ignore_ascii_case("params:")
+                    {
+                        port_end_idx -= 1;
+                    }
+                    let ports = self.buffer[2..port_end_idx]
                         .iter()
                         .map(|tok| tok.try_ident().cloned())
                         .collect::<Result<_, _>>()?;
-                    Line::SubcktDecl { name, ports }
+                    Line::SubcktDecl {
+                        name,
+                        ports,
+                        params,
+                    }
                 } else if d.eq_ignore_ascii_case(".ends") {
                     Line::EndSubckt
                 } else if d.eq_ignore_ascii_case(".include") {
-                    let mut path = self.buffer[1].try_ident()?.clone();
-                    // remove enclosing quotation marks, if any.
-                    if path.starts_with('"') {
-                        let mut chars = path.chars();
-                        chars.next().unwrap();
-                        chars.next_back().unwrap();
-                        path = Substr(path.substr_from(chars.as_str()));
-                    }
+                    let path = unquote(self.buffer[1].try_ident()?);
                     Line::Include { path }
+                } else if d.eq_ignore_ascii_case(".param") || d.eq_ignore_ascii_case(".params") {
+                    Line::Param {
+                        params: self.parse_params(1)?,
+                    }
+                } else if d.eq_ignore_ascii_case(".model") {
+                    // A model card looks like this:
+                    //
+                    // ```spice
+                    // .model name kind [(] param1=value1 param2=value2 [)]
+                    // ```
+                    //
+                    // The parameters may be enclosed in parentheses.
+                    let name = self.buffer[1].try_ident()?.clone();
+                    let kind = self.buffer[2].try_ident()?.clone();
+                    let mut tokens = self.buffer[3..].to_vec();
+                    let (kind, rest) = match kind.find('(') {
+                        Some(idx) => (Substr(kind.substr(..idx)), Some(Substr(kind.substr(idx..)))),
+                        None => (kind, None),
+                    };
+                    if let Some(rest) = rest {
+                        tokens.insert(0, Token::Ident(rest));
+                    }
+                    if let Some(Token::Ident(first)) = tokens.first_mut() {
+                        if let Some(stripped) = first.strip_prefix('(') {
+                            *first = Substr(first.substr_from(stripped));
+                        }
+                    }
+                    if let Some(Token::Ident(last)) = tokens.last_mut() {
+                        if let Some(stripped) = last.strip_suffix(')') {
+                            *last = Substr(last.substr_from(stripped));
+                        }
+                    }
+                    tokens.retain(|t| !matches!(t, Token::Ident(id) if id.is_empty()));
+                    Line::Model(Model {
+                        name,
+                        kind,
+                        params: parse_params(&tokens, &self.buffer)?,
+                    })
+                } else if d.eq_ignore_ascii_case(".global") {
+                    let nodes = self.buffer[1..]
+                        .iter()
+                        .map(|tok| tok.try_ident().cloned())
+                        .collect::<Result<_, _>>()?;
+                    Line::Global { nodes }
+                } else if d.eq_ignore_ascii_case(".lib") {
+                    // `.lib name` starts a library section,
+                    // while `.lib path name` includes a section of another file.
+                    match self.buffer.len() {
+                        2 => Line::LibSection {
+                            name: self.buffer[1].try_ident()?.clone(),
+                        },
+                        3 => Line::Lib {
+                            path: unquote(self.buffer[1].try_ident()?),
+                            section: self.buffer[2].try_ident()?.clone(),
+                        },
+                        _ => return Err(ParserError::InvalidLine {
+                            line: self.buffer.clone(),
+                            reason:
+                                ".lib statements must specify a section name and an optional path"
+                                    .to_string(),
+                        }),
+                    }
+                } else if d.eq_ignore_ascii_case(".endl") {
+                    Line::EndLibSection
+                } else if d.eq_ignore_ascii_case(".end") {
+                    Line::End
                 } else {
                     return Err(ParserError::UnexpectedDirective(d.clone()));
                 }
@@ -239,13 +406,7 @@ impl Parser {
 
                 match kind {
                     'M' => {
-                        let mut params = Params::default();
-                        for i in (6..self.buffer.len()).step_by(3) {
-                            let k = self.buffer[i].try_ident()?.clone();
-                            assert!(matches!(self.buffer[i + 1], Token::Equals));
-                            let v = self.buffer[i + 2].try_ident()?.clone();
-                            params.insert(k, v);
-                        }
+                        let params = self.parse_params(6)?;
                         Line::Component(Component::Mos(Mos {
                             name: self.buffer[0].try_ident()?.clone(),
                             d: self.buffer[1].try_ident()?.clone(),
@@ -257,13 +418,7 @@ impl Parser {
                         }))
                     }
                     'D' => {
-                        let mut params = Params::default();
-                        for i in (4..self.buffer.len()).step_by(3) {
-                            let k = self.buffer[i].try_ident()?.clone();
-                            assert!(matches!(self.buffer[i + 1], Token::Equals));
-                            let v = self.buffer[i + 2].try_ident()?.clone();
-                            params.insert(k, v);
-                        }
+                        let params = self.parse_params(4)?;
                         Line::Component(Component::Diode(Diode {
                             name: self.buffer[0].try_ident()?.clone(),
                             pos: self.buffer[1].try_ident()?.clone(),
@@ -273,13 +428,7 @@ impl Parser {
                         }))
                     }
                     'R' => {
-                        let mut params = Params::default();
-                        for i in (4..self.buffer.len()).step_by(3) {
-                            let k = self.buffer[i].try_ident()?.clone();
-                            assert!(matches!(self.buffer[i + 1], Token::Equals));
-                            let v = self.buffer[i + 2].try_ident()?.clone();
-                            params.insert(k, v);
-                        }
+                        let params = self.parse_params(4)?;
                         let value = self.buffer[3].try_ident()?.clone();
                         let value = if convert_str_to_numeric_lit(&value).is_some() {
                             DeviceValue::Value(value)
@@ -313,9 +462,8 @@ impl Parser {
                         // The tokens after Xname and before `child_idx` are ports;
                         // the tokens after `child_idx` should come in groups of 3
                         // and represent parameter values.
-                        //
-                        // TODO: this logic needs to change to support expressions
-                        // in parameter values.
+                        // Expressions in parameter values must be enclosed in single quotes
+                        // or braces.
                         let pos = self.buffer.iter().position(|t| matches!(t, Token::Equals));
                         let child_idx = pos.unwrap_or(self.buffer.len() + 1) - 2;
                         let child = self.buffer[child_idx].try_ident()?.clone();
@@ -325,13 +473,7 @@ impl Parser {
                             .map(|x| x.try_ident().cloned())
                             .collect::<Result<_, _>>()?;
 
-                        let mut params = Params::default();
-                        for i in (child_idx + 1..self.buffer.len()).step_by(3) {
-                            let k = self.buffer[i].try_ident()?.clone();
-                            assert!(matches!(self.buffer[i + 1], Token::Equals));
-                            let v = self.buffer[i + 2].try_ident()?.clone();
-                            params.insert(k, v);
-                        }
+                        let params = self.parse_params(child_idx + 1)?;
 
                         Line::Component(Component::Instance(Instance {
                             name: self.buffer[0].try_ident()?.clone(),
@@ -340,6 +482,93 @@ impl Parser {
                             params,
                         }))
                     }
+                    'V' | 'I' => {
+                        let source = Source {
+                            name: self.buffer[0].try_ident()?.clone(),
+                            pos: self.buffer[1].try_ident()?.clone(),
+                            neg: self.buffer[2].try_ident()?.clone(),
+                            spec: parse_source_spec(&self.buffer[3..], &self.buffer)?,
+                        };
+                        if kind == 'V' {
+                            Line::Component(Component::Vsource(source))
+                        } else {
+                            Line::Component(Component::Isource(source))
+                        }
+                    }
+                    'L' => Line::Component(Component::Ind(Ind {
+                        name: self.buffer[0].try_ident()?.clone(),
+                        pos: self.buffer[1].try_ident()?.clone(),
+                        neg: self.buffer[2].try_ident()?.clone(),
+                        value: self.buffer[3].try_ident()?.clone(),
+                    })),
+                    'K' => Line::Component(Component::MutualInd(MutualInd {
+                        name: self.buffer[0].try_ident()?.clone(),
+                        ind1: self.buffer[1].try_ident()?.clone(),
+                        ind2: self.buffer[2].try_ident()?.clone(),
+                        coupling: self.buffer[3].try_ident()?.clone(),
+                    })),
+                    'E' | 'G' => {
+                        let source = VoltageControlled {
+                            name: self.buffer[0].try_ident()?.clone(),
+                            pos: self.buffer[1].try_ident()?.clone(),
+                            neg: self.buffer[2].try_ident()?.clone(),
+                            ctrl_pos: self.buffer[3].try_ident()?.clone(),
+                            ctrl_neg: self.buffer[4].try_ident()?.clone(),
+                            gain: self.buffer[5].try_ident()?.clone(),
+                        };
+                        if kind == 'E' {
+                            Line::Component(Component::Vcvs(source))
+                        } else {
+                            Line::Component(Component::Vccs(source))
+                        }
+                    }
+                    'F' | 'H' => {
+                        let source = CurrentControlled {
+                            name: self.buffer[0].try_ident()?.clone(),
+                            pos: self.buffer[1].try_ident()?.clone(),
+                            neg: self.buffer[2].try_ident()?.clone(),
+                            vsource: self.buffer[3].try_ident()?.clone(),
+                            gain: self.buffer[4].try_ident()?.clone(),
+                        };
+                        if kind == 'F' {
+                            Line::Component(Component::Cccs(source))
+                        } else {
+                            Line::Component(Component::Ccvs(source))
+                        }
+                    }
+                    'B' => {
+                        // A behavioral source line looks like this:
+                        //
+                        // ```spice
+                        // Bname n+ n- V={expr}
+                        // ```
+                        let quantity = self.buffer[3].try_ident()?;
+                        let kind = if quantity.eq_ignore_ascii_case("v") {
+                            BehavioralKind::Voltage
+                        } else if quantity.eq_ignore_ascii_case("i") {
+                            BehavioralKind::Current
+                        } else {
+                            return Err(ParserError::InvalidLine {
+                                line: self.buffer.clone(),
+                                reason: "behavioral sources must specify V={expr} or I={expr}"
+                                    .to_string(),
+                            });
+                        };
+                        if !matches!(self.buffer.get(4), Some(Token::Equals)) {
+                            return Err(ParserError::InvalidLine {
+                                line: self.buffer.clone(),
+                                reason: "behavioral sources must specify V={expr} or I={expr}"
+                                    .to_string(),
+                            });
+                        }
+                        Line::Component(Component::Behavioral(Behavioral {
+                            name: self.buffer[0].try_ident()?.clone(),
+                            pos: self.buffer[1].try_ident()?.clone(),
+                            neg: self.buffer[2].try_ident()?.clone(),
+                            kind,
+                            expr: self.buffer[5].try_ident()?.clone(),
+                        }))
+                    }
                     kind => return Err(ParserError::UnexpectedComponentType(kind)),
                 }
             }
@@ -348,6 +577,136 @@ impl Parser {
         self.buffer.clear();
         Ok(Some(line))
     }
+
+    /// Parses the tokens in the buffer starting at index `start` as `key=value` pairs.
+    fn parse_params(&self, start: usize) -> Result<Params, ParserError> {
+        parse_params(&self.buffer[start..], &self.buffer)
+    }
+}
+
+/// Parses `tokens` as `key=value` pairs.
+///
+/// `line` is the full line containing `tokens`, used for error reporting.
+fn parse_params(tokens: &[Token], line: &[Token]) -> Result<Params, ParserError> {
+    let mut params = Params::default();
+    for i in (0..tokens.len()).step_by(3) {
+        let k = tokens[i].try_ident()?.clone();
+        if !matches!(tokens.get(i + 1), Some(Token::Equals)) || i + 2 >= tokens.len() {
+            return Err(ParserError::InvalidLine {
+                line: line.to_vec(),
+                reason: format!("expected a value for parameter `{k}`"),
+            });
+        }
+        let v = tokens[i + 2].try_ident()?.clone();
+        params.insert(k, v);
+    }
+    Ok(params)
+}
+
+/// Parses the DC, AC and transient specifications of an independent source.
+///
+/// Accepts specifications such as `DC 1.8 AC 1 0 PULSE(0 1.8 1n 1n 1n 5n 10n)`.
+/// A bare value before any keyword is the DC value.
+/// Parentheses around waveform arguments are optional,
+/// and `=` signs between keywords and values are ignored.
+///
+/// `line` is the full line containing `tokens`, used for error reporting.
+fn parse_source_spec(tokens: &[Token], line: &[Token]) -> Result<SourceSpec<Substr>, ParserError> {
+    let err = |reason: &str| ParserError::InvalidLine {
+        line: line.to_vec(),
+        reason: reason.to_string(),
+    };
+
+    // Split waveform parentheses into their own words, leaving quoted expressions intact.
+    let mut words: Vec<Substr> = Vec::new();
+    for tok in tokens {
+        let word = match tok {
+            Token::Equals => continue,
+            tok => tok.try_ident()?,
+        };
+        if word.starts_with(['\'', '"', '{']) {
+            words.push(word.clone());
+            continue;
+        }
+        let mut start = 0;
+        for (i, c) in word.char_indices() {
+            if c == '(' || c == ')' {
+                if start < i {
+                    words.push(Substr(word.substr(start..i)));
+                }
+                words.push(Substr(word.substr(i..i + 1)));
+                start = i + 1;
+            }
+        }
+        if start < word.len() {
+            words.push(Substr(word.substr(start..)));
+        }
+    }
+
+    let waveform_kind = |word: &str| {
+        [
+            ("pulse", WaveformKind::Pulse),
+            ("pwl", WaveformKind::Pwl),
+            ("sin", WaveformKind::Sin),
+        ]
+        .into_iter()
+        .find(|(name, _)| word.eq_ignore_ascii_case(name))
+        .map(|(_, kind)| kind)
+    };
+    let is_keyword = |word: &str| {
+        word.eq_ignore_ascii_case("dc")
+            || word.eq_ignore_ascii_case("ac")
+            || waveform_kind(word).is_some()
+    };
+    let is_value = |word: Option<&Substr>| {
+        word.is_some_and(|word| !is_keyword(word) && word.as_str() != "(" && word.as_str() != ")")
+    };
+
+    let mut spec = SourceSpec::default();
+    let mut words = words.into_iter().peekable();
+    while let Some(word) = words.next() {
+        if word.eq_ignore_ascii_case("dc") {
+            if !is_value(words.peek()) {
+                return Err(err("expected a DC value"));
+            }
+            spec.dc = words.next();
+        } else if word.eq_ignore_ascii_case("ac") {
+            if !is_value(words.peek()) {
+                return Err(err("expected an AC magnitude"));
+            }
+            spec.ac_mag = words.next();
+            if is_value(words.peek()) {
+                spec.ac_phase = words.next();
+            }
+        } else if let Some(kind) = waveform_kind(&word) {
+            let parenthesized = words.next_if(|word| word.as_str() == "(").is_some();
+            let mut args = Vec::new();
+            while is_value(words.peek()) {
+                args.push(words.next().unwrap());
+            }
+            if parenthesized && words.next().map_or(true, |word| word.as_str() != ")") {
+                return Err(err("unterminated waveform arguments"));
+            }
+            spec.waveform = Some(Waveform { kind, args });
+        } else if spec.dc.is_none() && is_value(Some(&word)) {
+            spec.dc = Some(word);
+        } else {
+            return Err(err(&format!("unexpected source specification `{word}`")));
+        }
+    }
+    Ok(spec)
+}
+
+/// Removes enclosing single or double quotation marks, if any.
+fn unquote(s: &Substr) -> Substr {
+    match s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+    {
+        Some(inner) => Substr(s.substr_from(inner)),
+        None => s.clone(),
+    }
 }
 
 /// Data associated with parsing a SPICE file.
@@ -387,6 +746,8 @@ pub enum Line {
         ///
         /// Each port is the name of a node exposed by the subcircuit.
         ports: Vec<Node>,
+        /// Subcircuit parameters and their default values.
+        params: Params,
     },
     /// A component instantiation.
     Component(Component),
@@ -404,6 +765,36 @@ pub enum Line {
         /// The second node.
         node2: Substr,
     },
+    /// A parameter declaration (`.param`).
+    Param {
+        /// The declared parameters and their values.
+        params: Params,
+    },
+    /// A model card (`.model`).
+    Model(Model),
+    /// A declaration of global nodes (`.global`).
+    Global {
+        /// The global nodes.
+        nodes: Vec<Node>,
+    },
+    /// An include directive for a library section (`.lib path section`).
+    Lib {
+        /// The path to include.
+        path: Substr,
+        /// The name of the section to include.
+        section: Substr,
+    },
+    /// The start of a library section (`.lib section`).
+    LibSection {
+        /// The name of the section.
+        name: Substr,
+    },
+    /// The end of a library section (`.endl`).
+    EndLibSection,
+    /// The end of the netlist (`.end`).
+    ///
+    /// Any lines after `.end` are ignored.
+    End,
 }
 
 /// An element of a SPICE netlist AST.
@@ -413,6 +804,12 @@ pub enum Elem {
     Subckt(Subckt),
     /// A top-level component instance.
     Component(Component),
+    /// Top-level parameters declared with `.param`.
+    Param(Params),
+    /// A top-level model card.
+    Model(Model),
+    /// Global nodes declared with `.global`.
+    Global(Vec<Node>),
 }
 
 /// The contents of a subcircuit.
@@ -424,6 +821,8 @@ pub struct Subckt {
     ///
     /// Each port is a node exposed by this subcircuit.
     pub ports: Vec<Node>,
+    /// Subcircuit parameters and their default values.
+    pub params: Params,
     /// List of components in the subcircuit.
     pub components: Vec<Component>,
 
@@ -433,6 +832,24 @@ pub struct Subckt {
     /// and no other `.CONNECT` statements will yield
     /// `connects = vec![("node1", "node2")]`.
     pub connects: Vec<(Node, Node)>,
+
+    /// Parameters declared with `.param` inside the subcircuit.
+    ///
+    /// Unlike [`params`](Subckt::params), these cannot be overridden by instances.
+    pub local_params: Params,
+    /// Model cards declared inside the subcircuit.
+    pub models: Vec<Model>,
+}
+
+/// A model card.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Model {
+    /// The name of the model.
+    pub name: Substr,
+    /// The kind of model (e.g. `nmos` or `d`).
+    pub kind: Substr,
+    /// Model parameters.
+    pub params: Params,
 }
 
 /// A SPICE netlist component.
@@ -448,6 +865,24 @@ pub enum Component {
     Cap(Cap),
     /// An instance of a subcircuit (declared with an 'X').
     Instance(Instance),
+    /// An independent voltage source (declared with a 'V').
+    Vsource(Source),
+    /// An independent current source (declared with an 'I').
+    Isource(Source),
+    /// An inductor (declared with an 'L').
+    Ind(Ind),
+    /// A mutual inductance (declared with a 'K').
+    MutualInd(MutualInd),
+    /// A voltage-controlled voltage source (declared with an 'E').
+    Vcvs(VoltageControlled),
+    /// A voltage-controlled current source (declared with a 'G').
+    Vccs(VoltageControlled),
+    /// A current-controlled current source (declared with an 'F').
+    Cccs(CurrentControlled),
+    /// A current-controlled voltage source (declared with an 'H').
+    Ccvs(CurrentControlled),
+    /// A behavioral source (declared with a 'B').
+    Behavioral(Behavioral),
 }
 
 /// A way of specifying the value of a primitive device.
@@ -502,6 +937,92 @@ pub struct Cap {
     pub value: Substr,
 }
 
+/// An independent voltage or current source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Source {
+    /// The name of the source instance.
+    pub name: Substr,
+    /// The node connected to the positive terminal.
+    pub pos: Node,
+    /// The node connected to the negative terminal.
+    pub neg: Node,
+    /// The DC, AC and transient specifications of the source.
+    pub spec: SourceSpec<Substr>,
+}
+
+/// An inductor.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ind {
+    /// The name of the inductor instance.
+    pub name: Substr,
+    /// The node connected to the positive terminal.
+    pub pos: Node,
+    /// The node connected to the negative terminal.
+    pub neg: Node,
+    /// The value of the inductor.
+    pub value: Substr,
+}
+
+/// A mutual inductance between two inductors.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MutualInd {
+    /// The name of the mutual inductance instance.
+    pub name: Substr,
+    /// The name of the first inductor.
+    pub ind1: Substr,
+    /// The name of the second inductor.
+    pub ind2: Substr,
+    /// The coupling coefficient.
+    pub coupling: Substr,
+}
+
+/// A voltage-controlled source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VoltageControlled {
+    /// The name of the source instance.
+    pub name: Substr,
+    /// The node connected to the positive output terminal.
+    pub pos: Node,
+    /// The node connected to the negative output terminal.
+    pub neg: Node,
+    /// The positive controlling node.
+    pub ctrl_pos: Node,
+    /// The negative controlling node.
+    pub ctrl_neg: Node,
+    /// The gain of the source.
+    pub gain: Substr,
+}
+
+/// A current-controlled source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CurrentControlled {
+    /// The name of the source instance.
+    pub name: Substr,
+    /// The node connected to the positive output terminal.
+    pub pos: Node,
+    /// The node connected to the negative output terminal.
+    pub neg: Node,
+    /// The name of the voltage source through which the controlling current flows.
+    pub vsource: Substr,
+    /// The gain of the source.
+    pub gain: Substr,
+}
+
+/// A behavioral source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Behavioral {
+    /// The name of the source instance.
+    pub name: Substr,
+    /// The node connected to the positive terminal.
+    pub pos: Node,
+    /// The node connected to the negative terminal.
+    pub neg: Node,
+    /// Whether the expression gives a voltage or a current.
+    pub kind: BehavioralKind,
+    /// The expression, including any enclosing quotes or braces.
+    pub expr: Substr,
+}
+
 /// A subcircuit instance.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Instance {
@@ -537,8 +1058,8 @@ pub struct Mos {
 /// Parameter values.
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct Params {
-    /// A map of key-value pairs.
-    values: HashMap<Substr, Substr>,
+    /// A map of key-value pairs, in the order they were declared.
+    values: IndexMap<Substr, Substr>,
 }
 
 #[inline]
@@ -570,7 +1091,10 @@ struct Tokenizer {
     data: Substr,
     rem: Substr,
     state: TokState,
+    /// Characters that start a comment at the beginning of a line.
     comments: HashSet<char>,
+    /// Characters that start a comment in the middle of a line.
+    inline_comments: HashSet<char>,
     /// Characters to treat as equivalent to whitespace.
     ignore_chars: HashSet<char>,
     line_continuation: char,
@@ -644,6 +1168,14 @@ pub enum ParserError {
         /// The reason the line is invalid.
         reason: String,
     },
+    /// An included file does not contain the requested library section.
+    #[error("file at path `{path:?}` has no library section named `{section}`")]
+    MissingSection {
+        /// The path of the included file.
+        path: PathBuf,
+        /// The name of the requested section.
+        section: Substr,
+    },
     /// Error trying to read the given file.
     #[error("failed to read file at path `{path:?}`: {err:?}")]
     FailedToRead {
@@ -688,6 +1220,7 @@ impl Tokenizer {
             rem: Substr(rem),
             state: TokState::Init,
             comments: HashSet::from(['*', '$']),
+            inline_comments: HashSet::from(['$', ';']),
             ignore_chars,
             line_continuation: '+',
             meta_directive_prefix,
@@ -755,11 +1288,14 @@ impl Tokenizer {
                         }
                     } else if c == self.line_continuation || self.ignore_chars.contains(&c) {
                         self.take1();
-                    } else if self.comments.contains(&c) {
+                    } else if self.inline_comments.contains(&c) {
                         self.take_until_newline();
                     } else if c == '.' {
                         let word = self.take_ident();
                         return Ok(Some(Token::Directive(word)));
+                    } else if c == '\'' || c == '"' || c == '{' {
+                        let expr = self.take_expr()?;
+                        return Ok(Some(Token::Ident(expr)));
                     } else {
                         let word = self.take_ident();
                         return Ok(Some(Token::Ident(word)));
@@ -802,6 +1338,27 @@ impl Tokenizer {
         value
     }
 
+    /// Takes an expression enclosed in quotes or braces, including the delimiters.
+    fn take_expr(&mut self) -> Result<Substr, TokenizerError> {
+        let close = match self.peek() {
+            Some('{') => '}',
+            Some('"') => '"',
+            _ => '\'',
+        };
+        match self.rem[1..].find(|c| c == close || is_newline(c)) {
+            Some(idx) if self.rem[1 + idx..].starts_with(close) => {
+                let expr = Substr(self.rem.substr(..idx + 2));
+                self.rem = Substr(self.rem.substr(idx + 2..));
+                Ok(expr)
+            }
+            _ => {
+                let c = self.peek().unwrap();
+                self.err("unterminated expression", c)?;
+                unreachable!()
+            }
+        }
+    }
+
     fn take_ws(&mut self) {
         let (rest, _) = take_while::<_, _, ()>(is_space)(self.rem.clone()).unwrap();
         self.rem = rest;
@@ -964,7 +1521,7 @@ impl Params {
         self.values.get(k)
     }
 
-    /// An iterator over all key-value pairs.
+    /// An iterator over all key-value pairs, in the order they were inserted.
     pub fn iter(&self) -> impl Iterator<Item = (&Substr, &Substr)> {
         self.values.iter()
     }
@@ -77,67 +77,105 @@ impl ShortPropagator {
         let order = dfs_postorder(&subckts, blackbox);
 
         for name in order.iter() {
-            let subckt = subckts[name];
-            let mut manager = CellShortManager::new();
-            for p in subckt.ports.iter() {
-                manager.register_node(p.clone(), NodePriority::Io);
-            }
+            val.analyze_subckt(subckts[name], &subckts, blackbox);
+        }
 
-            for c in subckt.components.iter() {
-                match c {
-                    Component::Mos(m) => {
-                        for node in [&m.d, &m.g, &m.s, &m.b] {
-                            manager.register_node(node.clone(), NodePriority::Default);
-                        }
+        val
+    }
+
+    /// Analyzes shorts in `subckt`, which may be absent from the netlist's subcircuits
+    /// (e.g. a cell containing the netlist's top-level components).
+    ///
+    /// The subcircuits instantiated by `subckt` must already have been analyzed.
+    pub(crate) fn analyze_subckt(
+        &mut self,
+        subckt: &Subckt,
+        subckts: &HashMap<SubcktName, &Subckt>,
+        blackbox: &HashSet<SubcktName>,
+    ) {
+        let mut manager = CellShortManager::new();
+        for p in subckt.ports.iter() {
+            manager.register_node(p.clone(), NodePriority::Io);
+        }
+
+        for c in subckt.components.iter() {
+            match c {
+                Component::Mos(m) => {
+                    for node in [&m.d, &m.g, &m.s, &m.b] {
+                        manager.register_node(node.clone(), NodePriority::Default);
                     }
-                    Component::Res(r) => {
-                        for node in [&r.pos, &r.neg] {
-                            manager.register_node(node.clone(), NodePriority::Default);
-                        }
+                }
+                Component::Res(r) => {
+                    for node in [&r.pos, &r.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
                     }
-                    Component::Diode(d) => {
-                        for node in [&d.pos, &d.neg] {
-                            manager.register_node(node.clone(), NodePriority::Default);
-                        }
+                }
+                Component::Diode(d) => {
+                    for node in [&d.pos, &d.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
                     }
-                    Component::Cap(c) => {
-                        for node in [&c.pos, &c.neg] {
-                            manager.register_node(node.clone(), NodePriority::Default);
-                        }
+                }
+                Component::Cap(c) => {
+                    for node in [&c.pos, &c.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                }
+                Component::Vsource(v) | Component::Isource(v) => {
+                    for node in [&v.pos, &v.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
                     }
-                    Component::Instance(inst) => {
-                        for node in inst.ports.iter() {
-                            manager.register_node(node.clone(), NodePriority::Default);
+                }
+                Component::Ind(l) => {
+                    for node in [&l.pos, &l.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                }
+                Component::MutualInd(_) => {}
+                Component::Vcvs(e) | Component::Vccs(e) => {
+                    for node in [&e.pos, &e.neg, &e.ctrl_pos, &e.ctrl_neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                }
+                Component::Cccs(f) | Component::Ccvs(f) => {
+                    for node in [&f.pos, &f.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                }
+                Component::Behavioral(b) => {
+                    for node in [&b.pos, &b.neg] {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                }
+                Component::Instance(inst) => {
+                    for node in inst.ports.iter() {
+                        manager.register_node(node.clone(), NodePriority::Default);
+                    }
+                    // We do not support propagation of shorts in blackbox/missing subcircuits.
+                    if !blackbox.contains(&inst.child) {
+                        let child_subckt = match subckts.get(&inst.child) {
+                            None => continue,
+                            Some(&s) => s,
+                        };
+                        let mut port_to_connected_node = HashMap::new();
+                        assert_eq!(inst.ports.len(), child_subckt.ports.len());
+                        for (node, cport) in inst.ports.iter().zip(child_subckt.ports.iter()) {
+                            port_to_connected_node.insert(cport, node);
                         }
-                        // We do not support propagation of shorts in blackbox/missing subcircuits.
-                        if !blackbox.contains(&inst.child) {
-                            let child_subckt = match subckts.get(&inst.child) {
-                                None => continue,
-                                Some(&s) => s,
-                            };
-                            let mut port_to_connected_node = HashMap::new();
-                            assert_eq!(inst.ports.len(), child_subckt.ports.len());
-                            for (node, cport) in inst.ports.iter().zip(child_subckt.ports.iter()) {
-                                port_to_connected_node.insert(cport, node);
-                            }
-                            let child_shorts = val.get_or_add_cell(inst.child.clone());
-                            for (node, cport) in inst.ports.iter().zip(child_subckt.ports.iter()) {
-                                let croot = child_shorts.root(cport);
-                                manager.connect(node, port_to_connected_node[&croot]);
-                            }
+                        let child_shorts = self.get_or_add_cell(inst.child.clone());
+                        for (node, cport) in inst.ports.iter().zip(child_subckt.ports.iter()) {
+                            let croot = child_shorts.root(cport);
+                            manager.connect(node, port_to_connected_node[&croot]);
                         }
                     }
                 }
             }
+        }
 
-            for (n1, n2) in subckt.connects.iter() {
-                manager.connect(n1, n2);
-            }
-
-            val.set_cell(name.clone(), manager);
+        for (n1, n2) in subckt.connects.iter() {
+            manager.connect(n1, n2);
         }
 
-        val
+        self.set_cell(subckt.name.clone(), manager);
     }
 
     fn new() -> Self {
@@ -190,10 +228,14 @@ impl CellShortManager {
     }
 
     /// The node to which the given node is shorted.
+    ///
+    /// Nodes that were never registered, such as global nodes that a cell
+    /// only reaches through its children, are their own root.
     pub fn root(&mut self, node: &Node) -> Node {
-        let k = self.node_to_key[node];
-        let v = self.uf.probe_value(k);
-        v.source
+        match self.node_to_key.get(node) {
+            Some(&k) => self.uf.probe_value(k).source,
+            None => node.clone(),
+        }
     }
 }
 
@@ -2,6 +2,8 @@ use super::*;
 
 use crate::netlist::NetlistOptions;
 use crate::Primitive;
+use rust_decimal_macros::dec;
+use scir::ParamValue;
 use std::path::PathBuf;
 use substrate::schematic::netlist::ConvertibleNetlister;
 
@@ -47,6 +49,37 @@ fn spice_resistor_tokens() {
     );
 }
 
+pub const SPICE_PARAMS: &str = r#"
+.subckt my_res a b w=1u l='2*w'
+R1 a b 100 w='w' l={l}
+.ends
+
+.subckt top x y PARAMS: w0=2u
+X1 x y my_res W='2 * w0'
+.ends
+"#;
+
+#[test]
+fn spice_params_tokens() {
+    let tok = Tokenizer::new(Dialect::Spice, "X1 x y my_res W='2 * w0' l={w0 + 1}\n");
+    let toks = tok.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        toks[4..],
+        vec![
+            Token::Ident(Substr("W".into())),
+            Token::Equals,
+            Token::Ident(Substr("'2 * w0'".into())),
+            Token::Ident(Substr("l".into())),
+            Token::Equals,
+            Token::Ident(Substr("{w0 + 1}".into())),
+            Token::LineEnd,
+        ]
+    );
+
+    let tok = Tokenizer::new(Dialect::Spice, "X1 x y my_res W='2 * w0\n");
+    assert!(tok.into_iter().collect::<Result<Vec<_>, _>>().is_err());
+}
+
 #[test]
 fn parse_dff() {
     let parsed = Parser::parse_file(Dialect::Spice, test_data("spice/dff.spice")).unwrap();
@@ -56,7 +89,7 @@ fn parse_dff() {
             name,
             ports,
             components,
-            connects: _,
+            ..
         }) => {
             assert_eq!(*name, "openram_dff".into());
             assert_eq!(
@@ -88,7 +121,7 @@ fn parse_dff() {
                     assert_eq!(
                         inst.params,
                         Params {
-                            values: HashMap::from_iter([
+                            values: IndexMap::from_iter([
                                 ("w".into(), "3".into()),
                                 ("l".into(), "0.15".into())
                             ]),
@@ -111,7 +144,7 @@ fn parse_pex_netlist() {
             name,
             ports,
             components,
-            connects: _,
+            ..
         }) => {
             assert_eq!(*name, "sram22_512x64m4w8".into());
             assert!(ports.contains(&"VDD".into()));
@@ -241,3 +274,513 @@ fn convert_cdl_rdac_to_scir() {
         )
         .expect("failed to export SPICE");
 }
+
+#[test]
+fn convert_params_to_scir() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_PARAMS).unwrap();
+    match &parsed.ast.elems[1] {
+        Elem::Subckt(subckt) => {
+            assert_eq!(subckt.ports, vec!["x".into(), "y".into()]);
+            assert_eq!(subckt.params.get("w0"), Some(&"2u".into()));
+        }
+        _ => panic!("match failed"),
+    }
+
+    let lib = parsed.to_scir().unwrap();
+    let issues = lib.validate();
+    assert_eq!(issues.num_errors(), 0);
+    assert_eq!(issues.num_warnings(), 0);
+
+    let my_res = lib.cell_named("my_res");
+    let params: Vec<_> = my_res.params().collect();
+    assert_eq!(params.len(), 2);
+    assert_eq!(*params[0].0, "w");
+    assert_eq!(*params[0].1, ParamValue::Numeric(dec!(0.000001)));
+    assert_eq!(*params[1].0, "l");
+    assert_eq!(
+        *params[1].1,
+        ParamValue::Expr(scir::param::Expr::parse("2*w").unwrap())
+    );
+
+    let top = lib.cell_named("top");
+    let x1 = top.instance_named("1");
+    assert_eq!(x1.params().len(), 1);
+    assert_eq!(x1.param("w").unwrap().to_string(), "2*w0");
+
+    let mut path = scir::InstancePath::new("top");
+    path.push("1");
+    let values = lib.eval_params(&path).unwrap();
+    assert_eq!(values.get("w"), Some(&dec!(0.000004)));
+    assert_eq!(values.get("l"), Some(&dec!(0.000008)));
+
+    let mut buf = Vec::new();
+    Spice
+        .write_scir_netlist(&lib, &mut buf, NetlistOptions::default())
+        .unwrap();
+    let netlist = String::from_utf8(buf).unwrap();
+    assert!(netlist.contains(".SUBCKT my_res a b w=0.000001 l='2*w'"));
+    assert!(netlist.contains("R1 a b 100 l='l' w='w'"));
+    assert!(netlist.contains(".SUBCKT top x y w0=0.000002"));
+    assert!(netlist.contains("X1 x y my_res w='2*w0'"));
+
+    let reparsed = Parser::parse(Dialect::Spice, netlist.as_str()).unwrap();
+    let relib = reparsed.to_scir().unwrap();
+    assert_eq!(relib.eval_params(&path).unwrap(), values);
+}
+
+#[test]
+fn convert_undeclared_param_to_scir() {
+    let parsed = Parser::parse(
+        Dialect::Spice,
+        ".subckt my_res a b\nR1 a b 100\n.ends\n.subckt top x y\nX1 x y my_res w=1\n.ends\n",
+    )
+    .unwrap();
+    assert!(matches!(
+        parsed.to_scir(),
+        Err(conv::ConvError::UndeclaredParam { .. })
+    ));
+}
+
+#[test]
+fn convert_mixed_case_params_to_scir() {
+    let parsed = Parser::parse(
+        Dialect::Spice,
+        r#".param VDD=1.8
+.subckt my_res a b w=1u l='2*W'
+R1 a b 100 l='L'
+.ends
+.subckt top x y
+X1 x y my_res W='vdd*1u'
+.ends
+"#,
+    )
+    .unwrap();
+    let lib = parsed.to_scir().unwrap();
+    let issues = lib.validate();
+    assert_eq!(issues.num_errors(), 0);
+    assert_eq!(issues.num_warnings(), 0);
+
+    let top = lib.cell_named("top");
+    assert!(top.param("VDD").is_some());
+    assert!(top.param("vdd").is_none());
+
+    let mut path = scir::InstancePath::new("top");
+    path.push("1");
+    let values = lib.eval_params(&path).unwrap();
+    assert_eq!(values.get("w"), Some(&dec!(0.0000018)));
+    assert_eq!(values.get("l"), Some(&dec!(0.0000036)));
+
+    let my_res = lib.cell_named("my_res");
+    let r1 = my_res.instance_named("1");
+    let scir::ChildId::Primitive(id) = r1.child() else {
+        panic!("expected a primitive instance");
+    };
+    let Primitive::Res2 { params, .. } = lib.primitive(id) else {
+        panic!("expected a resistor");
+    };
+    assert_eq!(params.values().next().unwrap().to_string(), "l");
+}
+
+#[test]
+fn spice_inline_comments_tokens() {
+    let tok = Tokenizer::new(
+        Dialect::Spice,
+        "R1 a b 100 $ a comment\nR2 a b r=\"2*x\" ; another comment\n",
+    );
+    let toks = tok.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        toks,
+        vec![
+            Token::Ident(Substr("R1".into())),
+            Token::Ident(Substr("a".into())),
+            Token::Ident(Substr("b".into())),
+            Token::Ident(Substr("100".into())),
+            Token::LineEnd,
+            Token::Ident(Substr("R2".into())),
+            Token::Ident(Substr("a".into())),
+            Token::Ident(Substr("b".into())),
+            Token::Ident(Substr("r".into())),
+            Token::Equals,
+            Token::Ident(Substr("\"2*x\"".into())),
+            Token::LineEnd,
+        ]
+    );
+}
+
+pub const SPICE_PARAMS_MODELS_GLOBALS: &str = r#"* Netlist title
+.param vdd_nom=1.8 wmin='2*lmin'
+.param lmin=0.15
+.global vdd
+
+.model nch nmos (level=54 vth0=0.4)
+.model dmod d is=1e-14
+
+.subckt leaf a b
+.param wn='wmin*2'
+M1 a b 0 0 nch w='wn' l=lmin
+.ends leaf
+
+.subckt mid a b
+X1 a b leaf
+R2 a vdd 1k
+.ends
+
+.subckt top x y
+X1 x y mid
+X2 y x leaf
+.ends
+
+.end
+this line is ignored
+"#;
+
+#[test]
+fn parse_params_models_globals() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_PARAMS_MODELS_GLOBALS).unwrap();
+    let elems = &parsed.ast.elems;
+    assert_eq!(elems.len(), 8);
+    match &elems[0] {
+        Elem::Param(params) => {
+            assert_eq!(params.get("vdd_nom"), Some(&"1.8".into()));
+            assert_eq!(params.get("wmin"), Some(&"'2*lmin'".into()));
+        }
+        _ => panic!("match failed"),
+    }
+    assert_eq!(elems[2], Elem::Global(vec!["vdd".into()]));
+    match &elems[3] {
+        Elem::Model(Model { name, kind, params }) => {
+            assert_eq!(*name, "nch".into());
+            assert_eq!(*kind, "nmos".into());
+            assert_eq!(params.get("level"), Some(&"54".into()));
+            assert_eq!(params.get("vth0"), Some(&"0.4".into()));
+        }
+        _ => panic!("match failed"),
+    }
+    match &elems[5] {
+        Elem::Subckt(subckt) => {
+            assert_eq!(subckt.local_params.get("wn"), Some(&"'wmin*2'".into()));
+            assert!(subckt.params.iter().next().is_none());
+        }
+        _ => panic!("match failed"),
+    }
+}
+
+#[test]
+fn convert_params_and_globals_to_scir() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_PARAMS_MODELS_GLOBALS).unwrap();
+    let lib = parsed.to_scir().unwrap();
+    // Global nodes are exposed by every subcircuit that uses them.
+    let port_names = |name: &str| {
+        let cell = lib.cell_named(name);
+        cell.ports()
+            .map(|port| cell.signal(port.signal()).name.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(port_names("leaf"), vec!["a", "b"]);
+    assert_eq!(port_names("mid"), vec!["a", "b", "vdd"]);
+    assert_eq!(port_names("top"), vec!["x", "y", "vdd"]);
+
+    // Local parameters are substituted into the expressions that use them.
+    let leaf = lib.cell_named("leaf");
+    assert!(leaf.param("wn").is_none());
+    let scir::ChildId::Primitive(id) = leaf.instance_named("1").child() else {
+        panic!("expected a primitive instance");
+    };
+    let Primitive::Mos { params, .. } = lib.primitive(id) else {
+        panic!("expected a MOSFET");
+    };
+    assert_eq!(
+        params.get(&unicase::UniCase::new(arcstr::literal!("w"))),
+        Some(&ParamValue::Expr(
+            scir::param::Expr::parse("wmin*2").unwrap()
+        ))
+    );
+
+    // Referenced top-level parameters are declared by the cells that use them.
+    assert_eq!(leaf.param("lmin"), Some(&ParamValue::Numeric(dec!(0.15))));
+    assert!(leaf.param("vdd_nom").is_none());
+    let mut path = scir::InstancePath::new("top");
+    path.push("2");
+    let values = lib.eval_params(&path).unwrap();
+    assert_eq!(values.get("wmin"), Some(&dec!(0.30)));
+}
+
+#[test]
+fn convert_local_params_to_scir() {
+    let netlist = r#".subckt my_res a b
+.param r='rl' rl='2*rs' rs=50
+R1 a b 100 w='r'
+.ends
+.subckt top x y
+X1 x y my_res
+.ends
+"#;
+    let lib = Parser::parse(Dialect::Spice, netlist)
+        .unwrap()
+        .to_scir()
+        .unwrap();
+    let my_res = lib.cell_named("my_res");
+    assert_eq!(my_res.params().count(), 0);
+    let scir::ChildId::Primitive(id) = my_res.instance_named("1").child() else {
+        panic!("expected a primitive instance");
+    };
+    let Primitive::Res2 { params, .. } = lib.primitive(id) else {
+        panic!("expected a resistor");
+    };
+    assert_eq!(
+        params.get(&unicase::UniCase::new(arcstr::literal!("w"))),
+        Some(&ParamValue::Numeric(dec!(100)))
+    );
+
+    // Local parameters cannot be overridden by instances.
+    let overridden = netlist.replace("X1 x y my_res", "X1 x y my_res r=1k");
+    assert!(matches!(
+        Parser::parse(Dialect::Spice, overridden.as_str())
+            .unwrap()
+            .to_scir(),
+        Err(conv::ConvError::UndeclaredParam { .. })
+    ));
+
+    let cyclic = netlist.replace("rs=50", "rs='r/2'");
+    assert!(matches!(
+        Parser::parse(Dialect::Spice, cyclic.as_str())
+            .unwrap()
+            .to_scir(),
+        Err(conv::ConvError::CyclicParam(_))
+    ));
+}
+
+#[test]
+fn parse_lib_sections() {
+    let path = test_data("spice/corners.lib");
+    let resistance = |parsed: &ParsedSpice| {
+        let lib = parsed.to_scir().unwrap();
+        lib.cell_named("corner_res").param("rsh").cloned()
+    };
+
+    let parsed = Parser::parse_file_section(Dialect::Spice, &path, "ff").unwrap();
+    assert_eq!(resistance(&parsed), Some(ParamValue::Numeric(dec!(80))));
+
+    let parsed = Parser::parse(
+        Dialect::Spice,
+        format!(".lib '{}' tt\n", path.display()).as_str(),
+    )
+    .unwrap();
+    assert_eq!(resistance(&parsed), Some(ParamValue::Numeric(dec!(100))));
+
+    assert!(matches!(
+        Parser::parse_file_section(Dialect::Spice, &path, "ss"),
+        Err(ParserError::MissingSection { .. })
+    ));
+}
+
+pub const SPICE_SOURCES: &str = r#"
+.subckt sources vdd out in
+Vdd vdd 0 DC 'vdd_nom' AC 1 90
+Vpulse in 0 PULSE(0 1.8 1n 100p 100p 5n 10n)
+Isin out 0 sin (0 1m 1meg)
+Ipwl out 0 pwl(0 0 1n 1u) dc=2u
+Vsense out mid 0
+Lp mid 0 1n
+Ls vdd 0 2n
+Kps Lp Ls 0.95
+Eamp out 0 in 0 10
+Ggm out 0 in 0 '2*gm'
+Fmir out 0 Vsense 2
+Htr out 0 Vsense 1k
+Bdbl out 0 V={v(in)*2}
+.ends
+"#;
+
+#[test]
+fn parse_sources() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_SOURCES).unwrap();
+    let Elem::Subckt(subckt) = &parsed.ast.elems[0] else {
+        panic!("match failed");
+    };
+    assert_eq!(subckt.components.len(), 13);
+
+    match &subckt.components[0] {
+        Component::Vsource(Source { spec, .. }) => {
+            assert_eq!(spec.dc, Some("'vdd_nom'".into()));
+            assert_eq!(spec.ac_mag, Some("1".into()));
+            assert_eq!(spec.ac_phase, Some("90".into()));
+            assert_eq!(spec.waveform, None);
+        }
+        _ => panic!("match failed"),
+    }
+    match &subckt.components[1] {
+        Component::Vsource(Source { spec, .. }) => {
+            let waveform = spec.waveform.as_ref().unwrap();
+            assert_eq!(waveform.kind, WaveformKind::Pulse);
+            assert_eq!(waveform.args.len(), 7);
+            assert_eq!(waveform.args[0], "0".into());
+            assert_eq!(waveform.args[6], "10n".into());
+        }
+        _ => panic!("match failed"),
+    }
+    match &subckt.components[2] {
+        Component::Isource(Source { spec, .. }) => {
+            let waveform = spec.waveform.as_ref().unwrap();
+            assert_eq!(waveform.kind, WaveformKind::Sin);
+            assert_eq!(waveform.args, vec!["0".into(), "1m".into(), "1meg".into()]);
+        }
+        _ => panic!("match failed"),
+    }
+    match &subckt.components[3] {
+        Component::Isource(Source { spec, .. }) => {
+            assert_eq!(spec.waveform.as_ref().unwrap().kind, WaveformKind::Pwl);
+            assert_eq!(spec.dc, Some("2u".into()));
+        }
+        _ => panic!("match failed"),
+    }
+    assert!(matches!(
+        &subckt.components[7],
+        Component::MutualInd(MutualInd { ind1, ind2, .. }) if *ind1 == "Lp".into() && *ind2 == "Ls".into()
+    ));
+    assert!(matches!(
+        &subckt.components[9],
+        Component::Vccs(VoltageControlled { ctrl_pos, gain, .. }) if *ctrl_pos == "in".into() && *gain == "'2*gm'".into()
+    ));
+    assert!(matches!(
+        &subckt.components[11],
+        Component::Ccvs(CurrentControlled { vsource, .. }) if *vsource == "Vsense".into()
+    ));
+    match &subckt.components[12] {
+        Component::Behavioral(b) => {
+            assert_eq!(b.kind, BehavioralKind::Voltage);
+            assert_eq!(b.expr, "{v(in)*2}".into());
+        }
+        _ => panic!("match failed"),
+    }
+
+    assert!(matches!(
+        Parser::parse(Dialect::Spice, "V1 a 0 DC 1 EXP(0 1)\n"),
+        Err(ParserError::InvalidLine { .. })
+    ));
+}
+
+#[test]
+fn convert_sources_to_scir() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_SOURCES).unwrap();
+    let lib = parsed.to_scir().unwrap();
+    let cell = lib.cell_named("sources");
+
+    match lib.primitive(cell.instance_named("dd").child().unwrap_primitive()) {
+        Primitive::Vsource2 { spec } => {
+            assert_eq!(
+                spec.dc,
+                Some(ParamValue::Expr(
+                    scir::param::Expr::parse("vdd_nom").unwrap()
+                ))
+            );
+            assert_eq!(spec.ac_phase, Some(ParamValue::Numeric(dec!(90))));
+        }
+        _ => panic!("match failed"),
+    }
+    match lib.primitive(cell.instance_named("sin").child().unwrap_primitive()) {
+        Primitive::Isource2 { spec } => {
+            assert_eq!(
+                spec.waveform.as_ref().unwrap().args[2],
+                ParamValue::Numeric(dec!(1e6))
+            );
+        }
+        _ => panic!("match failed"),
+    }
+    assert!(matches!(
+        lib.primitive(cell.instance_named("ps").child().unwrap_primitive()),
+        Primitive::MutualInd { ind1, ind2, coupling } if ind1 == "p" && ind2 == "s" && *coupling == ParamValue::Numeric(dec!(0.95))
+    ));
+    assert!(matches!(
+        lib.primitive(cell.instance_named("tr").child().unwrap_primitive()),
+        Primitive::Ccvs { vsource, .. } if vsource == "sense"
+    ));
+    assert!(matches!(
+        lib.primitive(cell.instance_named("dbl").child().unwrap_primitive()),
+        Primitive::Behavioral { kind: BehavioralKind::Voltage, expr } if expr == "v(in)*2"
+    ));
+}
+
+#[test]
+fn convert_parameterized_coupling_to_scir() {
+    let parsed = Parser::parse(
+        Dialect::Spice,
+        ".param kc=0.5\nL1 a 0 1n\nL2 b 0 1n\nK3 L1 L2 {kc}\n.end\n",
+    )
+    .unwrap();
+    let lib = parsed.to_scir().unwrap();
+    let top = lib.cell(lib.top_cell().unwrap());
+    assert!(matches!(
+        lib.primitive(top.instance_named("3").child().unwrap_primitive()),
+        Primitive::MutualInd { coupling, .. }
+            if *coupling == ParamValue::Expr(scir::param::Expr::parse("kc").unwrap())
+    ));
+}
+
+pub const SPICE_TESTBENCH: &str = r#"* RC testbench
+.param lval=1n
+.subckt rc a b
+R1 a b 1k
+.ends
+V1 in 0 DC 1.8 AC 1
+L2 in mid {lval}
+X3 mid 0 rc
+.end
+"#;
+
+#[test]
+fn convert_testbench_to_scir() {
+    let parsed = Parser::parse(Dialect::Spice, SPICE_TESTBENCH).unwrap();
+    let lib = parsed.to_scir().unwrap();
+    let issues = lib.validate();
+    assert_eq!(issues.num_errors(), 0);
+
+    // Top-level components are converted to the top cell.
+    let top = lib.cell(lib.top_cell().unwrap());
+    assert_eq!(top.name(), conv::DEFAULT_TOP_CELL_NAME);
+    let ports: Vec<_> = top
+        .ports()
+        .map(|port| top.signal(port.signal()).name.clone())
+        .collect();
+    assert_eq!(ports, vec!["0"]);
+    assert_eq!(top.instances().count(), 3);
+    let scir::ChildId::Primitive(id) = top.instance_named("2").child() else {
+        panic!("expected a primitive instance");
+    };
+    let Primitive::Ind2 { value } = lib.primitive(id) else {
+        panic!("expected an inductor");
+    };
+    assert_eq!(
+        *value,
+        ParamValue::Expr(scir::param::Expr::parse("lval").unwrap())
+    );
+    assert_eq!(
+        top.param("lval"),
+        Some(&ParamValue::Numeric(dec!(0.000000001)))
+    );
+
+    let mut buf = Vec::new();
+    Spice
+        .write_scir_netlist(
+            &lib,
+            &mut buf,
+            NetlistOptions::new(
+                crate::netlist::NetlistKind::Testbench(crate::netlist::RenameGround::Yes(
+                    "0".into(),
+                )),
+                &[],
+            ),
+        )
+        .unwrap();
+    let netlist = String::from_utf8(buf).unwrap();
+    assert!(!netlist.contains(conv::DEFAULT_TOP_CELL_NAME));
+    assert!(netlist.contains("L2 in mid 'lval'"));
+    assert!(netlist.contains("X3 mid 0 rc"));
+
+    let mut renamed = conv::ScirConverter::new(&parsed.ast);
+    renamed.top_cell_name("rc");
+    assert!(matches!(
+        renamed.convert(),
+        Err(conv::ConvError::DuplicateTopCell(_))
+    ));
+}
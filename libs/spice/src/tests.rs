@@ -6,9 +6,13 @@ use crate::{BlackboxContents, BlackboxElement, Primitive, Spice};
 use arcstr::ArcStr;
 use itertools::Itertools;
 use scir::schema::Schema;
-use scir::{Cell, Concat, Direction, IndexOwned, Instance, LibraryBuilder, SignalInfo, Slice};
-use std::collections::HashMap;
+use scir::{
+    Cell, Concat, Direction, IndexOwned, Instance, Library, LibraryBuilder, ParamValue, SignalInfo,
+    Slice,
+};
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use unicase::UniCase;
 
 #[test]
 fn scir_netlists_correctly() {
@@ -260,3 +264,237 @@ fn spice_like_netlist() {
         assert!(netlist.contains(fragment));
     }
 }
+
+#[test]
+fn netlist_reduction() {
+    let mut lib = Spice::scir_lib_from_str(
+        r#"
+.subckt top a b vss
+r1 a x 100
+r2 x y 200
+r3 y b 300
+r4 b z 0
+c5 a z 1p
+m6 a g vss vss nmos w=1 l=0.15
+m7 vss g a vss nmos w=1 l=0.15
+m8 a g vss vss nmos w=2 l=0.15
+r9 dangling1 dangling2 1k
+.ends
+
+.subckt unused p
+r1 p p 1
+.ends
+"#,
+    )
+    .into_builder();
+    let top = lib.cell_id_named("top");
+    lib.set_top(top);
+
+    lib.merge_parallel_devices();
+    lib.reduce_series_resistors();
+    lib.collapse_zero_ohm_resistors();
+    lib.remove_dangling();
+    lib.remove_unreferenced_cells();
+
+    let lib = lib.build().unwrap();
+    assert!(lib.try_cell_id_named("unused").is_none());
+    let cell = lib.cell(top);
+    let mut names: Vec<_> = cell
+        .instances()
+        .map(|(_, inst)| inst.name().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["1", "5", "6", "8"]);
+    let mut signals: Vec<_> = cell.signals().map(|(_, info)| info.name.clone()).collect();
+    signals.sort();
+    assert_eq!(signals, vec!["a", "b", "g", "vss"]);
+
+    let prim = |name: &str| lib.primitive(cell.instance_named(name).child().unwrap_primitive());
+    match prim("6") {
+        Primitive::Mos { params, .. } => {
+            assert_eq!(
+                params.get(&unicase::UniCase::new(arcstr::literal!("m"))),
+                Some(&scir::ParamValue::Numeric(rust_decimal::Decimal::TWO))
+            );
+        }
+        _ => panic!("expected a MOSFET"),
+    }
+    match prim("1") {
+        Primitive::Res2 { value, .. } => {
+            assert_eq!(*value, crate::ComponentValue::Fixed(600.into()))
+        }
+        _ => panic!("expected a resistor"),
+    }
+    let r1 = cell.instance_named("1");
+    let ends: HashSet<_> = ["1", "2"]
+        .into_iter()
+        .map(|port| {
+            cell.signal(r1.connection(port).index(0).signal())
+                .name
+                .clone()
+        })
+        .collect();
+    assert_eq!(
+        ends,
+        HashSet::from([arcstr::literal!("a"), arcstr::literal!("b")])
+    );
+    let c1 = cell.instance_named("5");
+    assert_eq!(cell.signal(c1.connection("2").index(0).signal()).name, "b");
+}
+
+#[test]
+fn electrical_rule_checks() {
+    let lib = Spice::scir_lib_from_str(
+        r#"
+.subckt top vdd vss out
+m1 out g vss vss nmos w=1 l=0.15
+m2 out g vdd vdd pmos w=1 l=0.15
+m3 out out out fb nfet w=1 l=0.15
+m4 out vss vss vdd nmos w=1 l=0.15
+r5 vdd vss 1k
+m6 vdd out vss vss nmos w=1 l=0.15
+c7 vdd vss 1p
+r8 vdd vss 0
+l9 vdd vss 1n
+.ends
+"#,
+    )
+    .into_builder();
+
+    let options = scir::erc::ErcOptions::new()
+        .with_supply("vdd")
+        .with_ground("vss");
+    let issues = lib.check_erc(&options);
+    assert_eq!(issues.num_errors(), 2);
+    assert_eq!(issues.num_warnings(), 5);
+    let mut messages: Vec<_> = issues.iter().map(|issue| issue.to_string()).collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec![
+            "bulk of NMOS instance `4` is tied to the wrong rail: top/vdd",
+            "drain and source of instance `3` are shorted together: top/out",
+            "instance `6` connects supply top/vdd directly to ground top/vss",
+            "instance `8` connects supply top/vdd directly to ground top/vss",
+            "instance `9` connects supply top/vdd directly to ground top/vss",
+            "net is connected only to MOSFET bulk terminals: top/fb",
+            "net is connected only to MOSFET gates: top/g",
+        ]
+    );
+}
+
+#[test]
+fn sources_netlist_round_trip() {
+    let lib = Spice::scir_lib_from_str(
+        r#"
+.subckt sources vdd out in
+Vdd vdd 0 DC 'vdd_nom' AC 1
+Vpulse in 0 PULSE(0 1.8 1n 100p 100p 5n 10n)
+Ipwl out 0 pwl(0 0 1n 1u)
+Vsense out mid 0
+Lp mid 0 1n
+Ls vdd 0 2n
+Kps Lp Ls 0.95
+Eamp out 0 in 0 10
+Fmir out 0 Vsense 2
+Bdbl out 0 V={v(in)*2}
+.ends
+"#,
+    );
+
+    let mut buf: Vec<u8> = Vec::new();
+    let netlister = NetlisterInstance::new(&Spice, &lib, &mut buf, Default::default());
+    netlister.export().unwrap();
+    let string = String::from_utf8(buf).unwrap();
+
+    for line in [
+        "Vdd vdd 0 DC 'vdd_nom' AC 1",
+        "Vpulse in 0 PULSE(0 1.8 0.000000001 0.000000000100 0.000000000100 0.000000005 0.000000010)",
+        "Ipwl out 0 PWL(0 0 0.000000001 0.000001)",
+        "Vsense out mid DC 0",
+        "Lp mid 0 0.000000001",
+        "Kps Lp Ls 0.95",
+        "Eamp out 0 in 0 10",
+        "Fmir out 0 Vsense 2",
+        "Bdbl out 0 V={v(in)*2}",
+    ] {
+        assert_eq!(string.matches(line).count(), 1, "missing `{line}`");
+    }
+
+    // The netlist can be parsed back into an equivalent library.
+    let reparsed = Spice::scir_lib_from_str(&string);
+    let cell = reparsed.cell_named("sources");
+    assert_eq!(cell.instances().count(), 10);
+}
+
+#[test]
+fn flatten_updates_instance_refs() {
+    let mut lib = Spice::scir_lib_from_str(
+        r#"
+.subckt xfmr p s
+Vsense p mid 0
+Lp mid 0 1n
+Ls s 0 2n
+Kps Lp Ls 0.95
+Fmir s 0 Vsense 2
+.ends
+.subckt top a b
+X1 a b xfmr
+X2 b a xfmr
+.ends
+"#,
+    )
+    .into_builder();
+    let top = lib.cell_id_named("top");
+    lib.flatten_cell(top);
+    let lib = lib.build().unwrap();
+
+    let top = lib.cell(top);
+    for inst in ["1", "2"] {
+        match lib.primitive(
+            top.instance_named(&format!("{inst}.ps"))
+                .child()
+                .unwrap_primitive(),
+        ) {
+            Primitive::MutualInd { ind1, ind2, .. } => {
+                assert_eq!(*ind1, format!("{inst}.p"));
+                assert_eq!(*ind2, format!("{inst}.s"));
+            }
+            _ => panic!("expected a mutual inductance"),
+        }
+        match lib.primitive(
+            top.instance_named(&format!("{inst}.mir"))
+                .child()
+                .unwrap_primitive(),
+        ) {
+            Primitive::Cccs { vsource, .. } => assert_eq!(*vsource, format!("{inst}.sense")),
+            _ => panic!("expected a current-controlled current source"),
+        }
+    }
+}
+
+#[test]
+fn scir_json_round_trip() {
+    let lib = Spice::scir_lib_from_str(
+        r#"
+.subckt leaf a b
+R1 a b 1k m=2
+Vsrc a 0 DC 1 PWL(0 0 1n 1)
+Bexp b 0 I={v(a)/2}
+.ends
+"#,
+    );
+    let json = lib.to_json().unwrap();
+    let lib2 = Library::<Spice>::from_json(&json).unwrap();
+    assert_eq!(lib2.to_json().unwrap(), json);
+    let leaf = lib2.cell_named("leaf");
+    match lib2.primitive(leaf.instance_named("1").child().unwrap_primitive()) {
+        Primitive::Res2 { params, .. } => {
+            assert_eq!(
+                params.get(&UniCase::new(arcstr::literal!("M"))),
+                Some(&ParamValue::Numeric(2.into()))
+            );
+        }
+        _ => panic!("expected a resistor"),
+    }
+}
@@ -1,8 +1,14 @@
 use scir::schema::Schema;
-use scir::{CellId, Library};
+use scir::{CellId, Library, SignalInfo};
 use std::io::Write;
 use std::path::Path;
 
+pub mod netlist;
+pub mod parser;
+
+#[cfg(test)]
+mod tests;
+
 pub fn export_verilog_shells<S: Schema, W: Write>(
     lib: &Library<S>,
     cells: &[CellId],
@@ -11,17 +17,16 @@ pub fn export_verilog_shells<S: Schema, W: Write>(
     for cell in cells {
         let cell = lib.cell(*cell);
 
-        writeln!(out, "module {} (", cell.name())?;
+        writeln!(out, "module {} (", escape_identifier(cell.name()))?;
 
         writeln!(
             out,
             "{}",
             cell.ports()
                 .map(|port| {
-                    // TODO: Handle bus signals.
                     let signal = cell.signal(port.signal());
                     let name = escape_identifier(&signal.name);
-                    format!("   {} {}", port.direction(), name)
+                    format!("   {} {}{}", port.direction(), bus_range(signal), name)
                 })
                 .collect::<Vec<_>>()
                 .join(",\n")
@@ -32,15 +37,13 @@ pub fn export_verilog_shells<S: Schema, W: Write>(
     Ok(())
 }
 
+/// Escapes `name` if it is not a valid simple Verilog identifier.
+///
+/// Simple identifiers start with a letter or underscore,
+/// followed by letters, digits, underscores and dollar signs,
+/// and are not [reserved keywords](is_keyword).
 pub fn escape_identifier(name: &str) -> String {
-    if name.contains('<')
-        || name.contains('>')
-        || name.contains('.')
-        || name.contains('/')
-        || name.contains('\\')
-        || name.contains('+')
-        || name.contains(',')
-    {
+    if !is_simple_identifier(name) {
         // Verilog escaped identifiers begin with a backslash and end in whitespace.
         format!("\\{name} ")
     } else {
@@ -48,6 +51,160 @@ pub fn escape_identifier(name: &str) -> String {
     }
 }
 
+/// Returns true if `name` is a valid simple Verilog identifier.
+pub(crate) fn is_simple_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !is_keyword(name)
+}
+
+/// The reserved keywords of Verilog-2005, in sorted order.
+const KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Returns true if `name` is a reserved Verilog keyword.
+///
+/// Keywords are case-sensitive, so `Module` is not a keyword.
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.binary_search(&name).is_ok()
+}
+
+/// The range declaration of a signal, followed by a space,
+/// or an empty string for single-bit signals.
+pub(crate) fn bus_range(signal: &SignalInfo) -> String {
+    match signal.width {
+        Some(width) => format!("[{}:0] ", width - 1),
+        None => String::new(),
+    }
+}
+
 pub fn export_all_verilog_shells<S: Schema, W: Write>(
     lib: &Library<S>,
     out: &mut W,
@@ -0,0 +1,389 @@
+//! Structural Verilog netlist export.
+//!
+//! Every cell in a SCIR library is written as a Verilog module containing
+//! wire declarations and module instances with named port connections.
+//! Primitives are mapped to Verilog modules by the schema's
+//! [`HasVerilogNetlist`] implementation.
+
+use std::io::Write;
+use std::path::Path;
+
+use arcstr::ArcStr;
+use scir::schema::{NoSchema, Schema, StringSchema};
+use scir::{Cell, ChildId, Concat, Library, ParamValue, Slice};
+
+use crate::{bus_range, escape_identifier, is_simple_identifier};
+
+/// The error type for Verilog netlist export.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An error writing the netlist.
+    #[error("error writing Verilog netlist: {0}")]
+    Io(#[from] std::io::Error),
+    /// An instance of a primitive that cannot be represented in Verilog.
+    #[error("instance `{instance}` in cell `{cell}` instantiates a primitive with no Verilog equivalent")]
+    UnsupportedPrimitive {
+        /// The name of the cell containing the instance.
+        cell: ArcStr,
+        /// The name of the instance.
+        instance: ArcStr,
+    },
+    /// A module port that is not connected by the instance that instantiates it.
+    #[error("port `{port}` of instance `{instance}` in cell `{cell}` is not connected")]
+    UnconnectedPort {
+        /// The name of the cell containing the instance.
+        cell: ArcStr,
+        /// The name of the instance.
+        instance: ArcStr,
+        /// The name of the unconnected port.
+        port: ArcStr,
+    },
+}
+
+/// The result type for Verilog netlist export.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A Verilog module instantiated in place of a SCIR primitive.
+#[derive(Debug, Clone)]
+pub struct VerilogPrimitive {
+    module: ArcStr,
+    ports: Option<Vec<ArcStr>>,
+    params: Vec<(ArcStr, ParamValue)>,
+}
+
+impl VerilogPrimitive {
+    /// Instantiates `module`, connecting each port of the primitive
+    /// to the module port with the same name.
+    pub fn named(module: impl Into<ArcStr>) -> Self {
+        Self {
+            module: module.into(),
+            ports: None,
+            params: Vec::new(),
+        }
+    }
+
+    /// Instantiates `module`, connecting the given primitive ports in order.
+    pub fn ordered(module: impl Into<ArcStr>, ports: Vec<ArcStr>) -> Self {
+        Self {
+            module: module.into(),
+            ports: Some(ports),
+            params: Vec::new(),
+        }
+    }
+
+    /// Overrides the given module parameter.
+    pub fn with_param(mut self, name: impl Into<ArcStr>, value: impl Into<ParamValue>) -> Self {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A schema whose primitives can be exported to structural Verilog.
+pub trait HasVerilogNetlist: Schema {
+    /// Returns the Verilog module to instantiate in place of `primitive`.
+    ///
+    /// Returns [`None`] if the primitive has no structural Verilog equivalent
+    /// (e.g. a resistor), in which case export fails with [`Error::UnsupportedPrimitive`].
+    fn verilog_primitive(primitive: &Self::Primitive) -> Option<VerilogPrimitive>;
+}
+
+impl HasVerilogNetlist for NoSchema {
+    fn verilog_primitive(_primitive: &Self::Primitive) -> Option<VerilogPrimitive> {
+        None
+    }
+}
+
+impl HasVerilogNetlist for StringSchema {
+    fn verilog_primitive(primitive: &Self::Primitive) -> Option<VerilogPrimitive> {
+        Some(VerilogPrimitive::named(primitive.clone()))
+    }
+}
+
+impl HasVerilogNetlist for spice::Spice {
+    /// Raw instances are exported as instances of the module with the same name.
+    ///
+    /// Ports are connected by name if all port names are simple Verilog identifiers,
+    /// and in order otherwise (e.g. for subcircuits imported from SPICE with numbered ports).
+    fn verilog_primitive(primitive: &Self::Primitive) -> Option<VerilogPrimitive> {
+        match primitive {
+            spice::Primitive::RawInstance {
+                ports,
+                cell,
+                params,
+            }
+            | spice::Primitive::RawInstanceWithCell {
+                ports,
+                cell,
+                params,
+                ..
+            } => {
+                let mut prim = if ports.iter().all(|port| is_simple_identifier(port)) {
+                    VerilogPrimitive::named(cell.clone())
+                } else {
+                    VerilogPrimitive::ordered(cell.clone(), ports.clone())
+                };
+                let mut params: Vec<_> = params.iter().collect();
+                params.sort_by_key(|(key, _)| *key);
+                for (key, value) in params {
+                    prim = prim.with_param(ArcStr::clone(key), value.clone());
+                }
+                Some(prim)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Exports every cell in `lib` as a structural Verilog module.
+pub fn export_verilog_netlist<S: HasVerilogNetlist, W: Write>(
+    lib: &Library<S>,
+    out: &mut W,
+) -> Result<()> {
+    for (_, cell) in lib.cells() {
+        export_module(lib, cell, out)?;
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Exports every cell in `lib` as a structural Verilog module to the file at `path`.
+pub fn export_verilog_netlist_to_file<S: HasVerilogNetlist, P: AsRef<Path>>(
+    lib: &Library<S>,
+    path: P,
+) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut f = std::io::BufWriter::new(std::fs::File::create(path)?);
+    export_verilog_netlist(lib, &mut f)
+}
+
+fn export_module<S: HasVerilogNetlist, W: Write>(
+    lib: &Library<S>,
+    cell: &Cell,
+    out: &mut W,
+) -> Result<()> {
+    write!(out, "module {}", escape_identifier(cell.name()))?;
+    let params: Vec<_> = cell.params().collect();
+    if !params.is_empty() {
+        writeln!(out, " #(")?;
+        for (i, (name, value)) in params.iter().enumerate() {
+            let sep = if i + 1 < params.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    parameter {} = {}{}",
+                escape_identifier(name),
+                param_value(value),
+                sep
+            )?;
+        }
+        write!(out, ")")?;
+    }
+
+    let ports: Vec<_> = cell.ports().collect();
+    if ports.is_empty() {
+        writeln!(out, " ();")?;
+    } else {
+        writeln!(out, " (")?;
+        for (i, port) in ports.iter().enumerate() {
+            let signal = cell.signal(port.signal());
+            let sep = if i + 1 < ports.len() { "," } else { "" };
+            writeln!(
+                out,
+                "    {} {}{}{}",
+                port.direction(),
+                bus_range(signal),
+                escape_identifier(&signal.name),
+                sep
+            )?;
+        }
+        writeln!(out, ");")?;
+    }
+
+    let mut wires: Vec<_> = cell
+        .signals()
+        .map(|(_, info)| info)
+        .filter(|info| !info.is_port())
+        .collect();
+    wires.sort_by_key(|info| info.id);
+    if !wires.is_empty() {
+        writeln!(out)?;
+    }
+    for info in wires {
+        writeln!(
+            out,
+            "    wire {}{};",
+            bus_range(info),
+            escape_identifier(&info.name)
+        )?;
+    }
+
+    for (_, inst) in cell.instances() {
+        writeln!(out)?;
+        // Each port is given as the module port name, if connected by name,
+        // and the name of the corresponding SCIR port.
+        let (module, params, ports): (_, _, Vec<(Option<ArcStr>, ArcStr)>) = match inst.child() {
+            ChildId::Cell(id) => {
+                let child = lib.cell(id);
+                let ports = child
+                    .ports()
+                    .map(|port| {
+                        let name = &child.signal(port.signal()).name;
+                        (Some(name.clone()), name.clone())
+                    })
+                    .collect();
+                let mut params: Vec<_> = inst
+                    .params()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                params.sort_by(|a, b| a.0.cmp(&b.0));
+                (child.name().clone(), params, ports)
+            }
+            ChildId::Primitive(id) => {
+                let prim = S::verilog_primitive(lib.primitive(id)).ok_or_else(|| {
+                    Error::UnsupportedPrimitive {
+                        cell: cell.name().clone(),
+                        instance: inst.name().clone(),
+                    }
+                })?;
+                let ports = match prim.ports {
+                    Some(ports) => ports.into_iter().map(|port| (None, port)).collect(),
+                    None => {
+                        let mut ports: Vec<_> = inst.connections().keys().cloned().collect();
+                        ports.sort();
+                        ports
+                            .into_iter()
+                            .map(|port| (Some(port.clone()), port))
+                            .collect()
+                    }
+                };
+                (prim.module, prim.params, ports)
+            }
+        };
+        let connections = ports
+            .iter()
+            .map(|(name, port)| {
+                let conn = inst
+                    .connections()
+                    .get(port)
+                    .ok_or_else(|| Error::UnconnectedPort {
+                        cell: cell.name().clone(),
+                        instance: inst.name().clone(),
+                        port: port.clone(),
+                    })?;
+                Ok((name.as_ref(), conn))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        write_instance(
+            out,
+            cell,
+            &module,
+            inst.name(),
+            &params,
+            connections.into_iter(),
+        )?;
+    }
+
+    writeln!(out, "endmodule")?;
+    Ok(())
+}
+
+/// Writes a module instance.
+///
+/// Each connection is given as an optional port name and the connected signals.
+/// Connections without a port name are connected by position.
+fn write_instance<'a, W: Write>(
+    out: &mut W,
+    cell: &Cell,
+    module: &str,
+    name: &str,
+    params: &[(ArcStr, ParamValue)],
+    connections: impl Iterator<Item = (Option<&'a ArcStr>, &'a Concat)>,
+) -> Result<()> {
+    write!(out, "    {}", escape_identifier(module))?;
+    if !params.is_empty() {
+        write!(out, " #(")?;
+        for (i, (key, value)) in params.iter().enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            write!(out, ".{}({})", escape_identifier(key), param_value(value))?;
+        }
+        write!(out, ")")?;
+    }
+    write!(out, " {} (", escape_identifier(name))?;
+    for (i, (port, conn)) in connections.enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        let conn = concat_expr(cell, conn);
+        match port {
+            Some(port) => write!(out, "\n        .{}({})", escape_identifier(port), conn)?,
+            None => write!(out, "\n        {}", conn)?,
+        }
+    }
+    writeln!(out, "\n    );")?;
+    Ok(())
+}
+
+/// Formats a parameter value as a Verilog expression.
+fn param_value(value: &ParamValue) -> String {
+    match value {
+        ParamValue::String(s) => string_literal(s),
+        value => value.to_string(),
+    }
+}
+
+/// Formats `value` as a Verilog string literal.
+///
+/// Characters without a Verilog escape sequence are written as octal escapes of their UTF-8 bytes.
+fn string_literal(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            ' '..='~' => out.push(c),
+            c => {
+                let mut buf = [0; 4];
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("\\{byte:03o}"));
+                }
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Formats a [`Concat`] as a Verilog expression.
+///
+/// The first part of a SCIR concatenation holds the least significant bits,
+/// whereas Verilog concatenations list the most significant part first.
+fn concat_expr(cell: &Cell, concat: &Concat) -> String {
+    let parts: Vec<_> = concat.parts().map(|part| slice_expr(cell, part)).collect();
+    if parts.len() == 1 {
+        parts.into_iter().next().unwrap()
+    } else {
+        let parts: Vec<_> = parts.into_iter().rev().collect();
+        format!("{{{}}}", parts.join(", "))
+    }
+}
+
+/// Formats a [`Slice`] as a Verilog expression.
+fn slice_expr(cell: &Cell, slice: &Slice) -> String {
+    let info = cell.signal(slice.signal());
+    let name = escape_identifier(&info.name);
+    match slice.range() {
+        None => name,
+        Some(range) if Some(range.width()) == info.width && range.start() == 0 => name,
+        Some(range) if range.width() == 1 => format!("{}[{}]", name, range.start()),
+        Some(range) => format!("{}[{}:{}]", name, range.end() - 1, range.start()),
+    }
+}
@@ -0,0 +1,787 @@
+//! Convert structural Verilog netlists to SCIR.
+//!
+//! Modules defined in the netlist become SCIR cells.
+//! Instances of modules without a definition (e.g. standard cells)
+//! become [`Primitive::RawInstance`]s, which can then be converted to
+//! PDK-specific primitives using [`scir::Library::convert_schema`].
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use arcstr::ArcStr;
+use indexmap::IndexSet;
+use rust_decimal::Decimal;
+use scir::{CellId, Concat, Direction, IndexOwned, ParamValue, SignalId, Slice, SliceOne};
+use spice::{Primitive, Spice};
+use thiserror::Error;
+use unicase::UniCase;
+
+use super::{Ast, Bit, Connections, Expr, Instance, Module, NetKind, Range};
+
+/// A Verilog netlist conversion result.
+pub type ConvResult<T> = std::result::Result<T, ConvError>;
+
+/// A Verilog netlist conversion error.
+#[derive(Debug, Error)]
+pub enum ConvError {
+    /// The requested module is not defined in the netlist.
+    #[error("module `{0}` is not defined")]
+    MissingModule(ArcStr),
+    /// A module instantiates itself, directly or indirectly.
+    #[error("module `{0}` instantiates itself")]
+    RecursiveModule(ArcStr),
+    /// A net is used without being declared.
+    #[error("net `{net}` is not declared in module `{module}`")]
+    UndeclaredNet {
+        /// The name of the module.
+        module: ArcStr,
+        /// The name of the net.
+        net: ArcStr,
+    },
+    /// A bit or part select is out of the range of the net.
+    #[error("index {index} is out of range for net `{net}` in module `{module}`")]
+    InvalidIndex {
+        /// The name of the module.
+        module: ArcStr,
+        /// The name of the net.
+        net: ArcStr,
+        /// The offending index.
+        index: i64,
+    },
+    /// The two sides of an assignment have different widths.
+    #[error("assignment in module `{module}` assigns {rhs} bits to {lhs} bits")]
+    AssignWidthMismatch {
+        /// The name of the module.
+        module: ArcStr,
+        /// The width of the left hand side.
+        lhs: usize,
+        /// The width of the right hand side.
+        rhs: usize,
+    },
+    /// A port connection has the wrong width.
+    #[error("instance `{inst}` (in module `{parent}`) connects {found} bits to port `{port}`, which has {expected} bits")]
+    PortWidthMismatch {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the module containing the instance.
+        parent: ArcStr,
+        /// The name of the port.
+        port: ArcStr,
+        /// The width of the port.
+        expected: usize,
+        /// The width of the connection.
+        found: usize,
+    },
+    /// Two ports of a module are connected together.
+    #[error("ports `{port1}` and `{port2}` of module `{module}` are connected together")]
+    ShortedPorts {
+        /// The name of the module.
+        module: ArcStr,
+        /// The name of the first port.
+        port1: ArcStr,
+        /// The name of the second port.
+        port2: ArcStr,
+    },
+    /// Logic 0 and logic 1 are connected together.
+    #[error("module `{0}` connects logic 0 to logic 1")]
+    ConflictingConstants(ArcStr),
+    /// A constant is used, but no net is available to represent it.
+    #[error("module `{0}` uses a constant, but has no tie net or supply net for it")]
+    UnresolvedConstant(ArcStr),
+    /// The port order of an instantiated leaf module is unknown.
+    #[error("instance `{inst}` of `{child}` (in module `{parent}`) requires the port order of `{child}`, which is unknown")]
+    UnknownPortOrder {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the module being instantiated.
+        child: ArcStr,
+        /// The name of the module containing the instance.
+        parent: ArcStr,
+    },
+    /// An instance connects a port that the instantiated module does not have.
+    #[error("instance `{inst}` of `{child}` (in module `{parent}`) connects port `{port}`, which `{child}` does not have")]
+    UnknownPort {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the module being instantiated.
+        child: ArcStr,
+        /// The name of the module containing the instance.
+        parent: ArcStr,
+        /// The name of the port.
+        port: ArcStr,
+    },
+    /// An instance has more connections than the instantiated module has ports.
+    #[error("instance `{inst}` of `{child}` (in module `{parent}`) has too many connections")]
+    IncorrectConnections {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the module being instantiated.
+        child: ArcStr,
+        /// The name of the module containing the instance.
+        parent: ArcStr,
+    },
+    /// An instance overrides parameters that cannot be represented in SCIR.
+    ///
+    /// Only instances of leaf modules may override parameters, and only by name.
+    #[error(
+        "instance `{inst}` of `{child}` (in module `{parent}`) has unsupported parameter overrides"
+    )]
+    UnsupportedParams {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the module being instantiated.
+        child: ArcStr,
+        /// The name of the module containing the instance.
+        parent: ArcStr,
+    },
+    /// Netlist conversion produced invalid SCIR.
+    #[error("netlist conversion produced SCIR containing errors: {0}")]
+    InvalidScir(Box<scir::Issues>),
+}
+
+/// Converts a parsed Verilog netlist to [`scir`].
+///
+/// Every module defined in the netlist is converted to a SCIR cell, unless blackboxed.
+/// Instances of undefined or blackboxed modules become [`Primitive::RawInstance`]s.
+/// Their ports are ordered as declared by [`ScirConverter::port_order`] or
+/// [`ScirConverter::port_orders_from_spice`], or as in the module declaration
+/// for blackboxed modules. Instances of leaf modules whose port order is unknown
+/// produce a [`ConvError::UnknownPortOrder`] error.
+pub struct ScirConverter<'a> {
+    ast: &'a Ast,
+    lib: scir::LibraryBuilder<Spice>,
+    blackbox_cells: HashSet<ArcStr>,
+    port_orders: HashMap<ArcStr, Vec<ArcStr>>,
+    globals: HashMap<ArcStr, ArcStr>,
+    global_nets: IndexSet<ArcStr>,
+    tie_low: Option<ArcStr>,
+    tie_high: Option<ArcStr>,
+    modules: HashMap<ArcStr, &'a Module>,
+    ids: HashMap<ArcStr, CellId>,
+    ports: HashMap<ArcStr, Vec<(ArcStr, usize)>>,
+    in_progress: HashSet<ArcStr>,
+}
+
+impl<'a> ScirConverter<'a> {
+    /// Create a new SCIR converter.
+    pub fn new(ast: &'a Ast) -> Self {
+        Self {
+            ast,
+            lib: scir::LibraryBuilder::new(),
+            blackbox_cells: Default::default(),
+            port_orders: Default::default(),
+            globals: Default::default(),
+            global_nets: Default::default(),
+            tie_low: None,
+            tie_high: None,
+            modules: ast.modules.iter().map(|m| (m.name.clone(), m)).collect(),
+            ids: Default::default(),
+            ports: Default::default(),
+            in_progress: Default::default(),
+        }
+    }
+
+    /// Blackboxes the given module.
+    ///
+    /// Instances of blackboxed modules are converted to primitives,
+    /// even if the module is defined in the netlist.
+    pub fn blackbox(&mut self, module: impl Into<ArcStr>) {
+        self.blackbox_cells.insert(module.into());
+    }
+
+    /// Sets the port order of the leaf module `module`.
+    ///
+    /// Instances of `module` connect their ports in this order,
+    /// and may connect ports by position.
+    pub fn port_order(
+        &mut self,
+        module: impl Into<ArcStr>,
+        ports: impl IntoIterator<Item = impl Into<ArcStr>>,
+    ) {
+        self.port_orders
+            .insert(module.into(), ports.into_iter().map(Into::into).collect());
+    }
+
+    /// Sets the port orders of leaf modules from the subcircuit definitions
+    /// in a parsed SPICE or CDL netlist, such as a standard cell library.
+    pub fn port_orders_from_spice(&mut self, ast: &spice::parser::Ast) {
+        for elem in ast.elems.iter() {
+            if let spice::parser::Elem::Subckt(subckt) = elem {
+                self.port_order(
+                    subckt.name.as_str(),
+                    subckt.ports.iter().map(|port| port.as_str()),
+                );
+            }
+        }
+    }
+
+    /// Connects port `port` of every leaf instance to the global net `net`,
+    /// unless the instance connects the port explicitly.
+    ///
+    /// Structural netlists produced by synthesis usually omit power connections,
+    /// so this is used to connect standard cell supply ports (e.g. `VPWR`).
+    /// Every converted module gets an `inout` port named `net`,
+    /// which is connected to the parent's `net` in every instance of the module.
+    pub fn connect_global(&mut self, port: impl Into<ArcStr>, net: impl Into<ArcStr>) {
+        let net = net.into();
+        self.global_nets.insert(net.clone());
+        self.globals.insert(port.into(), net);
+    }
+
+    /// Connects logic 0 constants to the net named `net`.
+    ///
+    /// Nets declared with `supply0` also represent logic 0.
+    pub fn tie_low(&mut self, net: impl Into<ArcStr>) {
+        self.tie_low = Some(net.into());
+    }
+
+    /// Connects logic 1 constants to the net named `net`.
+    ///
+    /// Nets declared with `supply1` also represent logic 1.
+    pub fn tie_high(&mut self, net: impl Into<ArcStr>) {
+        self.tie_high = Some(net.into());
+    }
+
+    /// Consumes the converter, yielding a SCIR [library](scir::Library).
+    pub fn convert(mut self) -> ConvResult<scir::Library<Spice>> {
+        for module in self.ast.modules.iter() {
+            if !self.blackbox_cells.contains(&module.name) {
+                self.convert_module(&module.name)?;
+            }
+        }
+        self.lib
+            .build()
+            .map_err(|issues| ConvError::InvalidScir(Box::new(issues)))
+    }
+
+    /// Consumes the converter, yielding an unconnected
+    /// [`ScirBinding`](substrate::schematic::ScirBinding) of the module named `module`.
+    pub fn convert_cell(
+        self,
+        module: &str,
+    ) -> ConvResult<substrate::schematic::ScirBinding<Spice>> {
+        let lib = self.convert()?;
+        let id = lib
+            .try_cell_id_named(module)
+            .ok_or_else(|| ConvError::MissingModule(module.into()))?;
+        Ok(substrate::schematic::ScirBinding::new(lib, id))
+    }
+
+    fn convert_module(&mut self, name: &ArcStr) -> ConvResult<CellId> {
+        if let Some(&id) = self.ids.get(name) {
+            return Ok(id);
+        }
+        let module = *self
+            .modules
+            .get(name)
+            .ok_or_else(|| ConvError::MissingModule(name.clone()))?;
+        if !self.in_progress.insert(name.clone()) {
+            return Err(ConvError::RecursiveModule(name.clone()));
+        }
+
+        let mut cell = scir::Cell::new(module.name.clone());
+        let mut nets = Nets::default();
+        for net in module.nets.values() {
+            let bits = match net.range {
+                Some(range) => {
+                    let bus = cell.add_bus(net.name.clone(), range.width());
+                    nets.buses.insert(bus.signal(), bus);
+                    (0..range.width()).map(|i| bus.index(i)).collect()
+                }
+                None => vec![cell.add_node(net.name.clone())],
+            };
+            nets.add_net(net.name.clone(), net.range, bits);
+            match net.kind {
+                NetKind::Wire => {}
+                NetKind::Supply0 => nets.tie(&net.name, LOW),
+                NetKind::Supply1 => nets.tie(&net.name, HIGH),
+            }
+        }
+        for net in self.global_nets.iter() {
+            if !nets.nets.contains_key(net) {
+                let node = cell.add_node(net.clone());
+                nets.add_net(net.clone(), None, vec![node]);
+            }
+        }
+        if let Some(net) = &self.tie_low {
+            nets.tie(net, LOW);
+        }
+        if let Some(net) = &self.tie_high {
+            nets.tie(net, HIGH);
+        }
+
+        for assign in module.assigns.iter() {
+            let lhs = nets.expr_bits(module, &assign.lhs)?;
+            let mut rhs = nets.expr_bits(module, &assign.rhs)?;
+            if let Expr::Const(_) = assign.rhs {
+                rhs.resize(lhs.len(), Some(LOW));
+            }
+            if lhs.len() != rhs.len() {
+                return Err(ConvError::AssignWidthMismatch {
+                    module: module.name.clone(),
+                    lhs: lhs.len(),
+                    rhs: rhs.len(),
+                });
+            }
+            for (a, b) in lhs.into_iter().zip(rhs) {
+                if let (Some(a), Some(b)) = (a, b) {
+                    nets.union(a, b);
+                }
+            }
+        }
+        if nets.find(LOW) == nets.find(HIGH) {
+            return Err(ConvError::ConflictingConstants(module.name.clone()));
+        }
+
+        // Ports, including global nets, in the order they are exposed.
+        let mut ports: Vec<(ArcStr, usize)> = module
+            .ports
+            .iter()
+            .map(|port| {
+                (
+                    port.clone(),
+                    module.nets[port].range.map_or(1, |r| r.width()),
+                )
+            })
+            .collect();
+        for net in self.global_nets.iter() {
+            if !module.ports.contains(net) {
+                ports.push((net.clone(), 1));
+            }
+        }
+        for (port, _) in ports.iter() {
+            let direction = module
+                .nets
+                .get(port)
+                .and_then(|net| net.direction)
+                .unwrap_or(Direction::InOut);
+            cell.expose_port(cell.signal_named(port).id, direction);
+        }
+        nets.choose_representatives(module, ports.iter().map(|(port, _)| port))?;
+
+        let mut floating = 0;
+        for inst in module.instances.iter() {
+            let sinst = if self.modules.contains_key(&inst.module)
+                && !self.blackbox_cells.contains(&inst.module)
+            {
+                self.convert_module_instance(module, inst, &mut cell, &nets, &mut floating)?
+            } else {
+                self.convert_leaf_instance(module, inst, &mut cell, &nets, &mut floating)?
+            };
+            cell.add_instance(sinst);
+        }
+
+        let id = self.lib.add_cell(cell);
+        self.ids.insert(name.clone(), id);
+        self.ports.insert(name.clone(), ports);
+        self.in_progress.remove(name);
+        Ok(id)
+    }
+
+    fn convert_module_instance(
+        &mut self,
+        parent: &Module,
+        inst: &Instance,
+        cell: &mut scir::Cell,
+        nets: &Nets,
+        floating: &mut usize,
+    ) -> ConvResult<scir::Instance> {
+        if !inst.params.is_empty() {
+            return Err(ConvError::UnsupportedParams {
+                inst: inst.name.clone(),
+                child: inst.module.clone(),
+                parent: parent.name.clone(),
+            });
+        }
+        let child = self.convert_module(&inst.module)?;
+        let ports = &self.ports[&inst.module];
+        let connections = self.connections(parent, inst, ports.iter().map(|(port, _)| port))?;
+
+        let mut sinst = scir::Instance::new(inst.name.clone(), child);
+        for (port, width) in ports {
+            let bits = match connections.get(port) {
+                Some(Some(expr)) => {
+                    self.connection_bits(parent, inst, port, *width, expr, nets, cell, floating)?
+                }
+                _ if self.global_nets.contains(port) => vec![nets.net_bit(port)],
+                _ => float(parent, cell, *width, floating),
+            };
+            sinst.connect(port.clone(), nets.concat(bits));
+        }
+        Ok(sinst)
+    }
+
+    fn convert_leaf_instance(
+        &mut self,
+        parent: &Module,
+        inst: &Instance,
+        cell: &mut scir::Cell,
+        nets: &Nets,
+        floating: &mut usize,
+    ) -> ConvResult<scir::Instance> {
+        // The declared ports of the leaf module and their widths, if known.
+        let declared: Option<Vec<(ArcStr, Option<usize>)>> =
+            if let Some(ports) = self.port_orders.get(&inst.module) {
+                Some(ports.iter().map(|port| (port.clone(), None)).collect())
+            } else {
+                self.modules.get(&inst.module).map(|module| {
+                    module
+                        .ports
+                        .iter()
+                        .map(|port| {
+                            let width = module.nets[port].range.map_or(1, |r| r.width());
+                            (port.clone(), Some(width))
+                        })
+                        .collect()
+                })
+            };
+        let ports = declared.ok_or_else(|| ConvError::UnknownPortOrder {
+            inst: inst.name.clone(),
+            child: inst.module.clone(),
+            parent: parent.name.clone(),
+        })?;
+        let connections = self.connections(parent, inst, ports.iter().map(|(port, _)| port))?;
+
+        let mut params = HashMap::new();
+        for (name, value) in inst.params.iter() {
+            let Some(name) = name else {
+                return Err(ConvError::UnsupportedParams {
+                    inst: inst.name.clone(),
+                    child: inst.module.clone(),
+                    parent: parent.name.clone(),
+                });
+            };
+            params.insert(UniCase::new(name.clone()), param_value(value));
+        }
+
+        let primitive = self.lib.add_primitive(Primitive::RawInstance {
+            ports: ports.iter().map(|(port, _)| port.clone()).collect(),
+            cell: inst.module.clone(),
+            params,
+        });
+        let mut sinst = scir::Instance::new(inst.name.clone(), primitive);
+        for (port, width) in ports.iter() {
+            let bits = match connections.get(port) {
+                Some(Some(expr)) => {
+                    let width = match width {
+                        Some(width) => *width,
+                        None => nets.expr_bits(parent, expr)?.len(),
+                    };
+                    self.connection_bits(parent, inst, port, width, expr, nets, cell, floating)?
+                }
+                _ => match self.globals.get(port) {
+                    Some(net) => vec![nets.net_bit(net)],
+                    None => float(parent, cell, width.unwrap_or(1), floating),
+                },
+            };
+            sinst.connect(port.clone(), nets.concat(bits));
+        }
+        Ok(sinst)
+    }
+
+    /// Maps each port of an instance to its connection.
+    fn connections<'b>(
+        &self,
+        parent: &Module,
+        inst: &'b Instance,
+        ports: impl Iterator<Item = &'b ArcStr> + Clone,
+    ) -> ConvResult<HashMap<ArcStr, Option<&'b Expr>>> {
+        match &inst.connections {
+            Connections::Named(connections) => connections
+                .iter()
+                .map(|(port, expr)| {
+                    if ports.clone().any(|p| p == port) {
+                        Ok((port.clone(), expr.as_ref()))
+                    } else {
+                        Err(ConvError::UnknownPort {
+                            inst: inst.name.clone(),
+                            child: inst.module.clone(),
+                            parent: parent.name.clone(),
+                            port: port.clone(),
+                        })
+                    }
+                })
+                .collect(),
+            Connections::Ordered(connections) => {
+                if connections.len() > ports.clone().count() {
+                    return Err(ConvError::IncorrectConnections {
+                        inst: inst.name.clone(),
+                        child: inst.module.clone(),
+                        parent: parent.name.clone(),
+                    });
+                }
+                Ok(ports
+                    .cloned()
+                    .zip(connections.iter().map(Option::as_ref))
+                    .collect())
+            }
+        }
+    }
+
+    /// Resolves the bits connected to a port of the given width.
+    ///
+    /// Constants are zero-extended or truncated to the width of the port.
+    /// Unknown and high impedance bits are connected to new floating nodes.
+    #[allow(clippy::too_many_arguments)]
+    fn connection_bits(
+        &self,
+        parent: &Module,
+        inst: &Instance,
+        port: &ArcStr,
+        width: usize,
+        expr: &Expr,
+        nets: &Nets,
+        cell: &mut scir::Cell,
+        floating: &mut usize,
+    ) -> ConvResult<Vec<SliceOne>> {
+        let mut bits = nets.expr_bits(parent, expr)?;
+        if let Expr::Const(_) = expr {
+            bits.resize(width, Some(LOW));
+        }
+        if bits.len() != width {
+            return Err(ConvError::PortWidthMismatch {
+                inst: inst.name.clone(),
+                parent: parent.name.clone(),
+                port: port.clone(),
+                expected: width,
+                found: bits.len(),
+            });
+        }
+        bits.into_iter()
+            .map(|bit| match bit {
+                Some(bit) => nets
+                    .resolve(bit)
+                    .ok_or_else(|| ConvError::UnresolvedConstant(parent.name.clone())),
+                None => Ok(float_node(parent, cell, floating)),
+            })
+            .collect()
+    }
+}
+
+/// Index of the node representing logic 0.
+const LOW: usize = 0;
+/// Index of the node representing logic 1.
+const HIGH: usize = 1;
+
+/// The bits of the nets of a module, grouped into sets of bits connected by assignments.
+struct Nets {
+    /// The range of each net and the node indices of its bits, least significant first.
+    nets: HashMap<ArcStr, (Option<Range>, Vec<usize>)>,
+    /// The SCIR bit corresponding to each node, or [`None`] for constant nodes.
+    bits: Vec<Option<SliceOne>>,
+    /// Union-find parent pointers.
+    parents: Vec<usize>,
+    /// The SCIR bit representing each set of connected nodes, indexed by root node.
+    representatives: HashMap<usize, SliceOne>,
+    /// Every bus in the module, used to merge consecutive bits into slices.
+    buses: HashMap<SignalId, Slice>,
+}
+
+impl Default for Nets {
+    fn default() -> Self {
+        Self {
+            nets: HashMap::new(),
+            bits: vec![None, None],
+            parents: vec![LOW, HIGH],
+            representatives: HashMap::new(),
+            buses: HashMap::new(),
+        }
+    }
+}
+
+impl Nets {
+    fn add_net(&mut self, name: ArcStr, range: Option<Range>, bits: Vec<SliceOne>) {
+        let start = self.bits.len();
+        let indices = (start..start + bits.len()).collect();
+        self.parents.extend(start..start + bits.len());
+        self.bits.extend(bits.into_iter().map(Some));
+        self.nets.insert(name, (range, indices));
+    }
+
+    /// Connects every bit of the net `name`, if it exists, to the constant node `node`.
+    fn tie(&mut self, name: &ArcStr, node: usize) {
+        if let Some((_, indices)) = self.nets.get(name) {
+            for bit in indices.clone() {
+                self.union(bit, node);
+            }
+        }
+    }
+
+    fn find(&self, mut node: usize) -> usize {
+        while self.parents[node] != node {
+            node = self.parents[node];
+        }
+        node
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        // Constant nodes always remain roots, so that they can be compared directly.
+        if b <= HIGH {
+            self.parents[a] = b;
+        } else {
+            self.parents[b] = a;
+        }
+    }
+
+    /// Chooses the SCIR bit representing each set of connected nodes.
+    ///
+    /// Port bits take precedence over internal nets.
+    fn choose_representatives<'b>(
+        &mut self,
+        module: &Module,
+        ports: impl Iterator<Item = &'b ArcStr>,
+    ) -> ConvResult<()> {
+        let mut port_of: HashMap<usize, ArcStr> = HashMap::new();
+        for port in ports {
+            for &bit in self.nets[port].1.iter() {
+                let root = self.find(bit);
+                if let Some(other) = port_of.get(&root) {
+                    return Err(ConvError::ShortedPorts {
+                        module: module.name.clone(),
+                        port1: other.clone(),
+                        port2: port.clone(),
+                    });
+                }
+                port_of.insert(root, port.clone());
+                self.representatives.insert(root, self.bits[bit].unwrap());
+            }
+        }
+        for net in module.nets.keys() {
+            for &bit in self.nets[net].1.iter() {
+                let root = self.find(bit);
+                self.representatives
+                    .entry(root)
+                    .or_insert_with(|| self.bits[bit].unwrap());
+            }
+        }
+        Ok(())
+    }
+
+    /// The SCIR bit representing the given node, or [`None`] if the node
+    /// is a constant that is not connected to any net.
+    fn resolve(&self, node: usize) -> Option<SliceOne> {
+        self.representatives.get(&self.find(node)).copied()
+    }
+
+    /// The bit representing the single-bit net `name`.
+    fn net_bit(&self, name: &ArcStr) -> SliceOne {
+        self.resolve(self.nets[name].1[0]).unwrap()
+    }
+
+    /// The nodes of an expression, least significant first,
+    /// or [`None`] for unknown or high impedance bits.
+    fn expr_bits(&self, module: &Module, expr: &Expr) -> ConvResult<Vec<Option<usize>>> {
+        let net = |name: &ArcStr| {
+            self.nets.get(name).ok_or_else(|| ConvError::UndeclaredNet {
+                module: module.name.clone(),
+                net: name.clone(),
+            })
+        };
+        let bit = |name: &ArcStr, index: i64| {
+            let (range, indices) = net(name)?;
+            range
+                .and_then(|range| range.offset(index))
+                .map(|offset| Some(indices[offset]))
+                .ok_or_else(|| ConvError::InvalidIndex {
+                    module: module.name.clone(),
+                    net: name.clone(),
+                    index,
+                })
+        };
+        Ok(match expr {
+            Expr::Net(name) => net(name)?.1.iter().map(|&i| Some(i)).collect(),
+            Expr::Index(name, index) => vec![bit(name, *index)?],
+            Expr::Slice(name, range) => {
+                let step = if range.msb >= range.lsb { 1 } else { -1 };
+                (0..range.width() as i64)
+                    .map(|i| bit(name, range.lsb + i * step))
+                    .collect::<ConvResult<_>>()?
+            }
+            Expr::Const(bits) => bits
+                .iter()
+                .map(|bit| match bit {
+                    Bit::Zero => Some(LOW),
+                    Bit::One => Some(HIGH),
+                    Bit::X | Bit::Z => None,
+                })
+                .collect(),
+            Expr::Concat(parts) => {
+                let mut bits = Vec::new();
+                for part in parts.iter().rev() {
+                    bits.extend(self.expr_bits(module, part)?);
+                }
+                bits
+            }
+            Expr::Replicate(count, inner) => {
+                let bits = self.expr_bits(module, inner)?;
+                bits.repeat(*count)
+            }
+        })
+    }
+
+    /// Concatenates bits, merging consecutive bits of a bus into a single slice.
+    fn concat(&self, bits: Vec<SliceOne>) -> Concat {
+        let mut parts: Vec<Slice> = Vec::new();
+        // The current run of consecutive bus bits: the bus, start index and width.
+        let mut run: Option<(SignalId, usize, usize)> = None;
+        let flush = |run: &mut Option<(SignalId, usize, usize)>, parts: &mut Vec<Slice>| {
+            if let Some((signal, start, width)) = run.take() {
+                parts.push(self.buses[&signal].index(start..start + width));
+            }
+        };
+        for bit in bits {
+            match (bit.index(), &mut run) {
+                (Some(index), Some((signal, start, width)))
+                    if *signal == bit.signal() && *start + *width == index =>
+                {
+                    *width += 1;
+                }
+                (Some(index), _) => {
+                    flush(&mut run, &mut parts);
+                    run = Some((bit.signal(), index, 1));
+                }
+                (None, _) => {
+                    flush(&mut run, &mut parts);
+                    parts.push(bit.into());
+                }
+            }
+        }
+        flush(&mut run, &mut parts);
+        Concat::new(parts)
+    }
+}
+
+/// Creates `width` new floating nodes in `cell`.
+fn float(module: &Module, cell: &mut scir::Cell, width: usize, count: &mut usize) -> Vec<SliceOne> {
+    (0..width)
+        .map(|_| float_node(module, cell, count))
+        .collect()
+}
+
+/// Creates a new floating node in `cell`, with a name that is not used by any net of `module`.
+fn float_node(module: &Module, cell: &mut scir::Cell, count: &mut usize) -> SliceOne {
+    loop {
+        let name = arcstr::format!("__floating{}", count);
+        *count += 1;
+        if !module.nets.contains_key(&name) && cell.try_signal_named(&name).is_none() {
+            return cell.add_node(name);
+        }
+    }
+}
+
+/// Converts a Verilog parameter value to a [`ParamValue`].
+///
+/// Numbers become numeric values and strings lose their quotes.
+fn param_value(value: &ArcStr) -> ParamValue {
+    if let Some(s) = value.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return ParamValue::String(s.into());
+    }
+    if let Ok(value) = Decimal::from_str(value) {
+        return ParamValue::Numeric(value);
+    }
+    match super::parse_const(value).and_then(|bits| super::const_value(&bits)) {
+        Some(value) => ParamValue::Numeric(value.into()),
+        None => ParamValue::String(value.clone()),
+    }
+}
@@ -0,0 +1,883 @@
+//! Parser for structural Verilog netlists.
+//!
+//! Supports the structural subset of Verilog produced by synthesis tools:
+//! modules with ANSI or non-ANSI port declarations, net declarations with ranges,
+//! continuous assignments used as aliases, and module instances with named or
+//! ordered connections, concatenations, replications and constants.
+//! Behavioral constructs (e.g. `always` blocks) are rejected.
+
+use std::path::{Path, PathBuf};
+
+use arcstr::ArcStr;
+use indexmap::IndexMap;
+use scir::Direction;
+use thiserror::Error;
+
+pub mod conv;
+
+/// The abstract syntax tree (AST) of a parsed Verilog netlist.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Ast {
+    /// The modules in the netlist, in the order they were declared.
+    pub modules: Vec<Module>,
+}
+
+/// A Verilog module.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Module {
+    /// The name of the module.
+    pub name: ArcStr,
+    /// The names of the ports of the module, in order.
+    pub ports: Vec<ArcStr>,
+    /// The nets declared in the module, including ports.
+    pub nets: IndexMap<ArcStr, Net>,
+    /// Continuous assignments.
+    pub assigns: Vec<Assign>,
+    /// Module instances.
+    pub instances: Vec<Instance>,
+}
+
+/// A net declaration.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Net {
+    /// The name of the net.
+    pub name: ArcStr,
+    /// The direction of the net, if it is a port.
+    pub direction: Option<Direction>,
+    /// The range of the net, if it is a bus.
+    pub range: Option<Range>,
+    /// The kind of net.
+    pub kind: NetKind,
+}
+
+/// The kind of a net.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum NetKind {
+    /// An ordinary wire.
+    #[default]
+    Wire,
+    /// A net tied to logic 0.
+    Supply0,
+    /// A net tied to logic 1.
+    Supply1,
+}
+
+/// A bus range, such as `[7:0]`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Range {
+    /// The index of the most significant bit.
+    pub msb: i64,
+    /// The index of the least significant bit.
+    pub lsb: i64,
+}
+
+impl Range {
+    /// The number of bits in the range.
+    pub fn width(&self) -> usize {
+        (self.msb - self.lsb).unsigned_abs() as usize + 1
+    }
+
+    /// The offset of the bit with index `index` from the least significant bit,
+    /// or [`None`] if the index is outside the range.
+    pub fn offset(&self, index: i64) -> Option<usize> {
+        let (lo, hi) = (self.msb.min(self.lsb), self.msb.max(self.lsb));
+        (lo..=hi)
+            .contains(&index)
+            .then(|| (index - self.lsb).unsigned_abs() as usize)
+    }
+}
+
+/// A continuous assignment, `assign lhs = rhs;`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Assign {
+    /// The left hand side of the assignment.
+    pub lhs: Expr,
+    /// The right hand side of the assignment.
+    pub rhs: Expr,
+}
+
+/// A module instance.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instance {
+    /// The name of the instance.
+    pub name: ArcStr,
+    /// The name of the instantiated module.
+    pub module: ArcStr,
+    /// Parameter overrides, with the parameter name if given by name.
+    ///
+    /// Values are kept as written.
+    pub params: Vec<(Option<ArcStr>, ArcStr)>,
+    /// Port connections.
+    pub connections: Connections,
+}
+
+/// The port connections of a module instance.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Connections {
+    /// Connections by port name, e.g. `.A(x)`.
+    ///
+    /// Ports left explicitly unconnected (e.g. `.A()`) have no expression.
+    Named(Vec<(ArcStr, Option<Expr>)>),
+    /// Connections by port position.
+    ///
+    /// Ports left explicitly unconnected have no expression.
+    Ordered(Vec<Option<Expr>>),
+}
+
+/// A net expression.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    /// An entire net.
+    Net(ArcStr),
+    /// A single bit of a bus, e.g. `a[3]`.
+    Index(ArcStr, i64),
+    /// A range of bits of a bus, e.g. `a[3:0]`.
+    Slice(ArcStr, Range),
+    /// A constant, with bits ordered from least to most significant.
+    Const(Vec<Bit>),
+    /// A concatenation, e.g. `{a, b}`, with the most significant part first.
+    Concat(Vec<Expr>),
+    /// A replication, e.g. `{4{a}}`.
+    Replicate(usize, Box<Expr>),
+}
+
+/// A bit of a constant.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Bit {
+    /// Logic 0.
+    Zero,
+    /// Logic 1.
+    One,
+    /// An unknown value.
+    X,
+    /// High impedance.
+    Z,
+}
+
+/// An error arising from parsing a Verilog netlist.
+#[derive(Debug, Error)]
+pub enum ParserError {
+    /// A syntax error.
+    #[error("syntax error on line {line}: {message}")]
+    Syntax {
+        /// The line on which the error occurred.
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+    /// Error trying to read the given file.
+    #[error("failed to read file at path `{path:?}`: {err:?}")]
+    FailedToRead {
+        /// The path we attempted to read.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        err: std::io::Error,
+    },
+}
+
+/// A Verilog token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    /// An identifier or keyword.
+    ///
+    /// Escaped identifiers are stored without the leading backslash.
+    Ident(ArcStr),
+    /// A number, as written.
+    Number(ArcStr),
+    /// A string literal, including the quotes.
+    Str(ArcStr),
+    /// A punctuation character.
+    Punct(char),
+}
+
+/// A structural Verilog parser.
+pub struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Parses the given Verilog source.
+    pub fn parse(src: &str) -> Result<Ast, ParserError> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+        };
+        let mut ast = Ast::default();
+        while parser.peek().is_some() {
+            ast.modules.push(parser.parse_module()?);
+        }
+        Ok(ast)
+    }
+
+    /// Parses the Verilog file at the given path.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Ast, ParserError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(|err| ParserError::FailedToRead {
+            path: path.into(),
+            err,
+        })?;
+        Self::parse(&src)
+    }
+
+    fn err<T>(&self, message: impl Into<String>) -> Result<T, ParserError> {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line);
+        Err(ParserError::Syntax {
+            line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    fn next(&mut self) -> Result<Token, ParserError> {
+        match self.tokens.get(self.pos) {
+            Some((tok, _)) => {
+                self.pos += 1;
+                Ok(tok.clone())
+            }
+            None => self.err("unexpected end of file"),
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn is_keyword(&self, kw: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(id)) if id == kw)
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.is_punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        let found = self.is_keyword(kw);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), ParserError> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            self.err(format!("expected `{c}`"))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<ArcStr, ParserError> {
+        match self.peek() {
+            Some(Token::Ident(id)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Ok(id)
+            }
+            _ => self.err("expected an identifier"),
+        }
+    }
+
+    fn expect_int(&mut self) -> Result<i64, ParserError> {
+        match self.next()? {
+            Token::Number(n) => match n.replace('_', "").parse() {
+                Ok(n) => Ok(n),
+                Err(_) => {
+                    self.pos -= 1;
+                    self.err(format!("expected an integer, found `{n}`"))
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                self.err("expected an integer")
+            }
+        }
+    }
+
+    /// Skips tokens up to and including the next `;`.
+    fn skip_statement(&mut self) -> Result<(), ParserError> {
+        while self.next()? != Token::Punct(';') {}
+        Ok(())
+    }
+
+    /// Skips a parenthesized group, assuming the opening parenthesis was consumed,
+    /// returning the tokens inside as text.
+    fn skip_parens(&mut self) -> Result<ArcStr, ParserError> {
+        let mut depth = 1;
+        let mut text = String::new();
+        loop {
+            let tok = self.next()?;
+            match tok {
+                Token::Punct('(') => depth += 1,
+                Token::Punct(')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(text.into());
+                    }
+                }
+                _ => {}
+            }
+            text.push_str(&token_text(&tok));
+        }
+    }
+
+    fn parse_module(&mut self) -> Result<Module, ParserError> {
+        if !self.eat_keyword("module") && !self.eat_keyword("macromodule") {
+            return self.err("expected `module`");
+        }
+        let mut module = Module {
+            name: self.expect_ident()?,
+            ..Default::default()
+        };
+
+        if self.eat_punct('#') {
+            self.expect_punct('(')?;
+            self.skip_parens()?;
+        }
+        if self.eat_punct('(') && !self.eat_punct(')') {
+            if self.peek_direction().is_some() {
+                self.parse_ansi_ports(&mut module)?;
+            } else {
+                loop {
+                    module.ports.push(self.expect_ident()?);
+                    if self.eat_punct(')') {
+                        break;
+                    }
+                    self.expect_punct(',')?;
+                }
+            }
+        }
+        self.expect_punct(';')?;
+
+        loop {
+            let kw = match self.peek() {
+                Some(Token::Ident(kw)) => kw.clone(),
+                Some(_) => return self.err("expected a module item"),
+                None => return self.err("missing `endmodule`"),
+            };
+            match kw.as_str() {
+                "endmodule" => {
+                    self.pos += 1;
+                    break;
+                }
+                "input" | "output" | "inout" => {
+                    let direction = self.peek_direction().unwrap();
+                    self.pos += 1;
+                    self.eat_net_type();
+                    let range = self.parse_range()?;
+                    loop {
+                        let name = self.expect_ident()?;
+                        let net = module.nets.entry(name.clone()).or_insert(Net {
+                            name,
+                            direction: None,
+                            range: None,
+                            kind: NetKind::Wire,
+                        });
+                        net.direction = Some(direction);
+                        net.range = range.or(net.range);
+                        if !self.eat_punct(',') {
+                            break;
+                        }
+                    }
+                    self.expect_punct(';')?;
+                }
+                "wire" | "reg" | "logic" | "tri" | "wand" | "wor" | "supply0" | "supply1" => {
+                    self.pos += 1;
+                    let kind = match kw.as_str() {
+                        "supply0" => NetKind::Supply0,
+                        "supply1" => NetKind::Supply1,
+                        _ => NetKind::Wire,
+                    };
+                    self.eat_keyword("signed");
+                    let range = self.parse_range()?;
+                    loop {
+                        let name = self.expect_ident()?;
+                        let net = module.nets.entry(name.clone()).or_insert(Net {
+                            name: name.clone(),
+                            direction: None,
+                            range: None,
+                            kind,
+                        });
+                        net.kind = kind;
+                        net.range = range.or(net.range);
+                        if self.eat_punct('=') {
+                            let rhs = self.parse_expr()?;
+                            module.assigns.push(Assign {
+                                lhs: Expr::Net(name),
+                                rhs,
+                            });
+                        }
+                        if !self.eat_punct(',') {
+                            break;
+                        }
+                    }
+                    self.expect_punct(';')?;
+                }
+                "assign" => {
+                    self.pos += 1;
+                    loop {
+                        let lhs = self.parse_expr()?;
+                        self.expect_punct('=')?;
+                        let rhs = self.parse_expr()?;
+                        module.assigns.push(Assign { lhs, rhs });
+                        if !self.eat_punct(',') {
+                            break;
+                        }
+                    }
+                    self.expect_punct(';')?;
+                }
+                "parameter" | "localparam" | "defparam" | "genvar" | "timeunit"
+                | "timeprecision" => {
+                    self.skip_statement()?;
+                }
+                "specify" => {
+                    while !self.eat_keyword("endspecify") {
+                        self.next()?;
+                    }
+                }
+                "always" | "always_comb" | "always_ff" | "always_latch" | "initial"
+                | "function" | "task" | "generate" => {
+                    return self.err(format!(
+                        "`{kw}` is not supported in structural Verilog netlists"
+                    ));
+                }
+                _ => self.parse_instances(&mut module)?,
+            }
+        }
+
+        for port in module.ports.iter() {
+            if module
+                .nets
+                .get(port)
+                .and_then(|net| net.direction)
+                .is_none()
+            {
+                return self.err(format!(
+                    "port `{}` of module `{}` has no direction",
+                    port, module.name
+                ));
+            }
+        }
+
+        Ok(module)
+    }
+
+    fn peek_direction(&self) -> Option<Direction> {
+        match self.peek() {
+            Some(Token::Ident(id)) => match id.as_str() {
+                "input" => Some(Direction::Input),
+                "output" => Some(Direction::Output),
+                "inout" => Some(Direction::InOut),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Skips an optional net type and signedness in a port declaration.
+    fn eat_net_type(&mut self) {
+        for kw in ["wire", "reg", "logic", "tri"] {
+            if self.eat_keyword(kw) {
+                break;
+            }
+        }
+        self.eat_keyword("signed");
+    }
+
+    fn parse_ansi_ports(&mut self, module: &mut Module) -> Result<(), ParserError> {
+        let mut direction = Direction::InOut;
+        let mut range = None;
+        loop {
+            if let Some(dir) = self.peek_direction() {
+                self.pos += 1;
+                direction = dir;
+                self.eat_net_type();
+                range = self.parse_range()?;
+            }
+            let name = self.expect_ident()?;
+            module.ports.push(name.clone());
+            module.nets.insert(
+                name.clone(),
+                Net {
+                    name,
+                    direction: Some(direction),
+                    range,
+                    kind: NetKind::Wire,
+                },
+            );
+            if self.eat_punct(')') {
+                return Ok(());
+            }
+            self.expect_punct(',')?;
+        }
+    }
+
+    fn parse_range(&mut self) -> Result<Option<Range>, ParserError> {
+        if !self.eat_punct('[') {
+            return Ok(None);
+        }
+        let msb = self.expect_int()?;
+        self.expect_punct(':')?;
+        let lsb = self.expect_int()?;
+        self.expect_punct(']')?;
+        Ok(Some(Range { msb, lsb }))
+    }
+
+    fn parse_instances(&mut self, module: &mut Module) -> Result<(), ParserError> {
+        let child = self.expect_ident()?;
+        let mut params = Vec::new();
+        if self.eat_punct('#') {
+            self.expect_punct('(')?;
+            if !self.eat_punct(')') {
+                loop {
+                    if self.eat_punct('.') {
+                        let name = self.expect_ident()?;
+                        self.expect_punct('(')?;
+                        params.push((Some(name), self.skip_parens()?));
+                    } else {
+                        let mut value = String::new();
+                        while !self.is_punct(',') && !self.is_punct(')') {
+                            value.push_str(&token_text(&self.next()?));
+                        }
+                        params.push((None, value.into()));
+                    }
+                    if self.eat_punct(')') {
+                        break;
+                    }
+                    self.expect_punct(',')?;
+                }
+            }
+        }
+
+        loop {
+            let name = self.expect_ident()?;
+            if self.is_punct('[') {
+                return self.err("arrays of instances are not supported");
+            }
+            self.expect_punct('(')?;
+            let connections = if self.eat_punct(')') {
+                Connections::Ordered(Vec::new())
+            } else if self.is_punct('.') {
+                let mut connections = Vec::new();
+                loop {
+                    self.expect_punct('.')?;
+                    let port = self.expect_ident()?;
+                    self.expect_punct('(')?;
+                    let expr = if self.eat_punct(')') {
+                        None
+                    } else {
+                        let expr = self.parse_expr()?;
+                        self.expect_punct(')')?;
+                        Some(expr)
+                    };
+                    connections.push((port, expr));
+                    if self.eat_punct(')') {
+                        break;
+                    }
+                    self.expect_punct(',')?;
+                }
+                Connections::Named(connections)
+            } else {
+                let mut connections = Vec::new();
+                loop {
+                    if self.is_punct(',') || self.is_punct(')') {
+                        connections.push(None);
+                    } else {
+                        connections.push(Some(self.parse_expr()?));
+                    }
+                    if self.eat_punct(')') {
+                        break;
+                    }
+                    self.expect_punct(',')?;
+                }
+                Connections::Ordered(connections)
+            };
+            module.instances.push(Instance {
+                name,
+                module: child.clone(),
+                params: params.clone(),
+                connections,
+            });
+            if !self.eat_punct(',') {
+                break;
+            }
+        }
+        self.expect_punct(';')
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, ParserError> {
+        match self.next()? {
+            Token::Ident(name) => {
+                if !self.eat_punct('[') {
+                    return Ok(Expr::Net(name));
+                }
+                let msb = self.expect_int()?;
+                let expr = if self.eat_punct(':') {
+                    let lsb = self.expect_int()?;
+                    Expr::Slice(name, Range { msb, lsb })
+                } else {
+                    Expr::Index(name, msb)
+                };
+                self.expect_punct(']')?;
+                Ok(expr)
+            }
+            Token::Number(n) => match parse_const(&n) {
+                Some(bits) => Ok(Expr::Const(bits)),
+                None => {
+                    self.pos -= 1;
+                    self.err(format!("invalid constant `{n}`"))
+                }
+            },
+            Token::Punct('{') => {
+                let first = self.parse_expr()?;
+                if self.eat_punct('{') {
+                    let count = match &first {
+                        Expr::Const(bits) => const_value(bits),
+                        _ => None,
+                    };
+                    let Some(count) = count else {
+                        return self.err("invalid replication count");
+                    };
+                    let mut parts = vec![self.parse_expr()?];
+                    while self.eat_punct(',') {
+                        parts.push(self.parse_expr()?);
+                    }
+                    self.expect_punct('}')?;
+                    self.expect_punct('}')?;
+                    let inner = if parts.len() == 1 {
+                        parts.pop().unwrap()
+                    } else {
+                        Expr::Concat(parts)
+                    };
+                    return Ok(Expr::Replicate(count, Box::new(inner)));
+                }
+                let mut parts = vec![first];
+                while self.eat_punct(',') {
+                    parts.push(self.parse_expr()?);
+                }
+                self.expect_punct('}')?;
+                Ok(Expr::Concat(parts))
+            }
+            _ => {
+                self.pos -= 1;
+                self.err("expected an expression")
+            }
+        }
+    }
+}
+
+/// Returns the source text of a token.
+fn token_text(tok: &Token) -> String {
+    match tok {
+        Token::Ident(id) => id.to_string(),
+        Token::Number(n) | Token::Str(n) => n.to_string(),
+        Token::Punct(c) => c.to_string(),
+    }
+}
+
+/// Returns the value of a constant, if it has no unknown or high impedance bits.
+fn const_value(bits: &[Bit]) -> Option<usize> {
+    let mut value = 0usize;
+    for bit in bits.iter().rev() {
+        value = value.checked_mul(2)?;
+        match bit {
+            Bit::Zero => {}
+            Bit::One => value += 1,
+            Bit::X | Bit::Z => return None,
+        }
+    }
+    Some(value)
+}
+
+/// Parses a Verilog constant, such as `8'hff`, `1'b0` or `12`,
+/// into bits ordered from least to most significant.
+fn parse_const(s: &str) -> Option<Vec<Bit>> {
+    let s = s.replace('_', "");
+    let Some((size, value)) = s.split_once('\'') else {
+        let value: u64 = s.parse().ok()?;
+        return Some(
+            (0..32)
+                .map(|i| {
+                    if (value >> i) & 1 == 1 {
+                        Bit::One
+                    } else {
+                        Bit::Zero
+                    }
+                })
+                .collect(),
+        );
+    };
+    let size: Option<usize> = if size.is_empty() {
+        None
+    } else {
+        Some(size.parse().ok()?)
+    };
+    let value = value.strip_prefix(['s', 'S']).unwrap_or(value);
+    let mut chars = value.chars();
+    let base = chars.next()?.to_ascii_lowercase();
+    let digits = chars.as_str();
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut bits = Vec::new();
+    if base == 'd' {
+        let value: u64 = digits.parse().ok()?;
+        let width = size.unwrap_or(32);
+        bits.extend((0..width.min(64)).map(|i| {
+            if (value >> i) & 1 == 1 {
+                Bit::One
+            } else {
+                Bit::Zero
+            }
+        }));
+    } else {
+        let bits_per_digit = match base {
+            'b' => 1,
+            'o' => 3,
+            'h' => 4,
+            _ => return None,
+        };
+        for c in digits.chars().rev() {
+            let digit = match c.to_ascii_lowercase() {
+                'x' => {
+                    bits.extend(std::iter::repeat(Bit::X).take(bits_per_digit));
+                    continue;
+                }
+                'z' | '?' => {
+                    bits.extend(std::iter::repeat(Bit::Z).take(bits_per_digit));
+                    continue;
+                }
+                c => c.to_digit(1 << bits_per_digit)?,
+            };
+            bits.extend((0..bits_per_digit).map(|i| {
+                if (digit >> i) & 1 == 1 {
+                    Bit::One
+                } else {
+                    Bit::Zero
+                }
+            }));
+        }
+    }
+
+    if let Some(size) = size {
+        // Extend with zeros, or with the most significant bit if it is unknown.
+        let fill = match bits.last() {
+            Some(Bit::X) => Bit::X,
+            Some(Bit::Z) => Bit::Z,
+            _ => Bit::Zero,
+        };
+        bits.resize(size, fill);
+    }
+    Some(bits)
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, ParserError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let chars: Vec<char> = src.chars().collect();
+    let mut i = 0;
+    let err = |line: usize, message: &str| {
+        Err(ParserError::Syntax {
+            line,
+            message: message.to_string(),
+        })
+    };
+    let take_while = |i: &mut usize, f: &dyn Fn(char) -> bool| {
+        let start = *i;
+        while *i < chars.len() && f(chars[*i]) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && next == Some('/') {
+            take_while(&mut i, &|c| c != '\n');
+        } else if c == '/' && next == Some('*') || c == '(' && next == Some('*') {
+            // Block comments and attributes are skipped.
+            let close = if c == '/' { '/' } else { ')' };
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    Some('*') if chars.get(i + 1) == Some(&close) => break,
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                    None => return err(line, "unterminated comment"),
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '`' {
+            // Compiler directives are skipped.
+            take_while(&mut i, &|c| c != '\n');
+        } else if c == '\\' {
+            i += 1;
+            let name = take_while(&mut i, &|c| !c.is_whitespace());
+            if name.is_empty() {
+                return err(line, "empty escaped identifier");
+            }
+            tokens.push((Token::Ident(name.into()), line));
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let name = take_while(&mut i, &|c| {
+                c.is_ascii_alphanumeric() || c == '_' || c == '$'
+            });
+            tokens.push((Token::Ident(name.into()), line));
+        } else if c.is_ascii_digit() || c == '\'' {
+            let mut n = take_while(&mut i, &|c| c.is_ascii_digit() || c == '_');
+            if chars.get(i) == Some(&'\'') {
+                i += 1;
+                n.push('\'');
+                n.push_str(&take_while(&mut i, &|c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '?'
+                }));
+            } else if chars.get(i) == Some(&'.') {
+                // Real numbers may appear in parameter values.
+                i += 1;
+                n.push('.');
+                n.push_str(&take_while(&mut i, &|c| c.is_ascii_alphanumeric()));
+            }
+            tokens.push((Token::Number(n.into()), line));
+        } else if c == '"' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return err(line, "unterminated string");
+            }
+            i += 1;
+            let s: String = chars[start..i].iter().collect();
+            tokens.push((Token::Str(s.into()), line));
+        } else if "()[]{},;:.=#+-*/".contains(c) {
+            tokens.push((Token::Punct(c), line));
+            i += 1;
+        } else {
+            return err(line, &format!("unexpected character `{c}`"));
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+
+use arcstr::ArcStr;
+use rust_decimal_macros::dec;
+use scir::param::Expr;
+use scir::schema::StringSchema;
+use scir::{Cell, Concat, Direction, IndexOwned, Instance, LibraryBuilder, ParamValue};
+use spice::Spice;
+use unicase::UniCase;
+
+use crate::netlist::{export_verilog_netlist, Error};
+use crate::parser::conv::{ConvError, ScirConverter};
+use crate::parser::{Bit, Connections, Expr as VExpr, Parser, Range};
+use crate::{escape_identifier, export_all_verilog_shells, KEYWORDS};
+
+fn inverter_chain() -> LibraryBuilder<StringSchema> {
+    let mut lib = LibraryBuilder::<StringSchema>::new();
+    let inv = lib.add_primitive("sky130_fd_sc_hd__inv_1".into());
+
+    let mut chain = Cell::new("chain");
+    let din = chain.add_node("din");
+    let dout = chain.add_bus("dout", 2);
+    let x = chain.add_node("x.int");
+    chain.add_param("n", dec!(2));
+    for (name, a, y) in [("i0", din, x), ("i1", x, dout.index(0))] {
+        let mut i = Instance::new(name, inv);
+        i.connect("A", a);
+        i.connect("Y", y);
+        chain.add_instance(i);
+    }
+    let mut i = Instance::new("i2", inv);
+    i.connect("A", dout.index(0));
+    i.connect("Y", dout.index(1));
+    chain.add_instance(i);
+    chain.expose_port(din, Direction::Input);
+    chain.expose_port(dout, Direction::Output);
+    let chain = lib.add_cell(chain);
+
+    let mut top = Cell::new("top");
+    let a = top.add_bus("a", 4);
+    let mut c = Instance::new("c", chain);
+    c.connect("din", a.index(0));
+    c.connect("dout", Concat::new(vec![a.index(3).into(), a.index(1..2)]));
+    c.set_param("n", Expr::parse("2*3").unwrap());
+    top.add_instance(c);
+    top.expose_port(a, Direction::InOut);
+    lib.add_cell(top);
+
+    lib
+}
+
+#[test]
+fn export_structural_verilog() {
+    let lib = inverter_chain().build().unwrap();
+    let mut buf = Vec::new();
+    export_verilog_netlist(&lib, &mut buf).unwrap();
+    let verilog = String::from_utf8(buf).unwrap();
+
+    assert_eq!(
+        verilog,
+        r#"module chain #(
+    parameter n = 2
+) (
+    input din,
+    output [1:0] dout
+);
+
+    wire \x.int ;
+
+    sky130_fd_sc_hd__inv_1 i0 (
+        .A(din),
+        .Y(\x.int )
+    );
+
+    sky130_fd_sc_hd__inv_1 i1 (
+        .A(\x.int ),
+        .Y(dout[0])
+    );
+
+    sky130_fd_sc_hd__inv_1 i2 (
+        .A(dout[0]),
+        .Y(dout[1])
+    );
+endmodule
+
+module top (
+    inout [3:0] a
+);
+
+    chain #(.n(2*3)) c (
+        .din(a[0]),
+        .dout({a[1], a[3]})
+    );
+endmodule
+
+"#
+    );
+}
+
+#[test]
+fn export_spice_primitives() {
+    let mut lib = LibraryBuilder::<Spice>::new();
+    let raw = lib.add_primitive(spice::Primitive::RawInstance {
+        ports: vec!["1".into(), "2".into()],
+        cell: ArcStr::from("blackbox"),
+        params: Default::default(),
+    });
+    let res = lib.add_primitive(spice::Primitive::Cap2 { value: dec!(1) });
+    let mut cell = Cell::new("cell");
+    let p = cell.add_bus("p", 2);
+    let mut x = Instance::new("x", raw);
+    x.connect("1", p.index(1));
+    x.connect("2", p.index(0));
+    cell.add_instance(x);
+    cell.expose_port(p, Direction::InOut);
+    let cell = lib.add_cell(cell);
+
+    let mut buf = Vec::new();
+    export_verilog_netlist(&lib.clone().build().unwrap(), &mut buf).unwrap();
+    let verilog = String::from_utf8(buf).unwrap();
+    assert!(verilog.contains("blackbox x (\n        p[1],\n        p[0]\n    );"));
+
+    let mut c = Instance::new("c", res);
+    c.connect("1", p.index(0));
+    c.connect("2", p.index(1));
+    let mut cell_with_cap = lib.cell(cell).clone();
+    cell_with_cap.add_instance(c);
+    lib.overwrite_cell_with_id(cell, cell_with_cap);
+    assert!(matches!(
+        export_verilog_netlist(&lib.build().unwrap(), &mut Vec::new()),
+        Err(Error::UnsupportedPrimitive { .. })
+    ));
+}
+
+#[test]
+fn export_spice_primitive_params_and_connections() {
+    let export = |ports: &[&str]| {
+        let mut lib = LibraryBuilder::<Spice>::new();
+        let raw = lib.add_primitive(spice::Primitive::RawInstance {
+            ports: vec!["1".into(), "2".into()],
+            cell: ArcStr::from("blackbox"),
+            params: HashMap::from_iter([(
+                UniCase::new(ArcStr::from("mode")),
+                ParamValue::String("a \"b\"\\c\u{b5}".into()),
+            )]),
+        });
+        let mut cell = Cell::new("cell");
+        let p = cell.add_bus("p", 2);
+        let mut x = Instance::new("x", raw);
+        for (i, port) in ports.iter().enumerate() {
+            x.connect(*port, p.index(i));
+        }
+        cell.add_instance(x);
+        cell.expose_port(p, Direction::InOut);
+        lib.add_cell(cell);
+
+        let mut buf = Vec::new();
+        export_verilog_netlist(&lib.build().unwrap(), &mut buf)?;
+        Ok(String::from_utf8(buf).unwrap())
+    };
+
+    let verilog = export(&["1", "2"]).unwrap();
+    assert!(verilog.contains(r#"blackbox #(.mode("a \"b\"\\c\302\265")) x ("#));
+    assert!(matches!(
+        export(&["1"]),
+        Err(Error::UnconnectedPort { port, .. }) if port == "2"
+    ));
+}
+
+#[test]
+fn export_shells_with_buses() {
+    let lib = inverter_chain().build().unwrap();
+    let mut buf = Vec::new();
+    export_all_verilog_shells(&lib, &mut buf).unwrap();
+    let verilog = String::from_utf8(buf).unwrap();
+    assert!(verilog.contains("   output [1:0] dout\n"));
+    assert_eq!(escape_identifier("a[0]"), "\\a[0] ");
+    assert_eq!(escape_identifier("_a$1"), "_a$1");
+    assert_eq!(escape_identifier("module"), "\\module ");
+    assert_eq!(escape_identifier("wire"), "\\wire ");
+    assert_eq!(escape_identifier("Wire"), "Wire");
+    assert!(KEYWORDS.windows(2).all(|w| w[0] < w[1]));
+}
+
+const YOSYS_NETLIST: &str = r#"
+/* Generated by Yosys */
+`timescale 1ns / 1ps
+
+(* top = 1 *)
+module counter(clk, rst, count);
+  input clk;
+  input rst;
+  output [1:0] count;
+  wire [1:0] next;
+  wire _0_;
+  wire \count_reg[0].q ;
+  sky130_fd_sc_hd__inv_1 _1_ (.A(count[0]), .Y(next[0]));
+  sky130_fd_sc_hd__xor2_1 _2_ (.A(count[0]), .B(count[1]), .X(next[1]));
+  sky130_fd_sc_hd__dfrtp_1 \count_reg[0]  (.CLK(clk), .D(next[0]), .Q(\count_reg[0].q ), .RESET_B(_0_));
+  sky130_fd_sc_hd__dfrtp_1 \count_reg[1]  (.CLK(clk), .D(next[1]), .Q(count[1]), .RESET_B(_0_));
+  sky130_fd_sc_hd__inv_1 _3_ (.A(rst), .Y(_0_));
+  sky130_fd_sc_hd__conb_1 tie (.HI(), .LO());
+  half h0 (1'b0, count[1:0]);
+  assign count[0] = \count_reg[0].q ;
+endmodule
+
+module half(input a, input [1:0] b);
+  sky130_fd_sc_hd__and2_1 _0_ (.A(a), .B(b[1]), .X());
+endmodule
+"#;
+
+#[test]
+fn parse_structural_verilog() {
+    let ast = Parser::parse(YOSYS_NETLIST).unwrap();
+    assert_eq!(ast.modules.len(), 2);
+
+    let counter = &ast.modules[0];
+    assert_eq!(counter.name, "counter");
+    assert_eq!(counter.ports, vec!["clk", "rst", "count"]);
+    assert_eq!(counter.nets["count"].range, Some(Range { msb: 1, lsb: 0 }));
+    assert_eq!(counter.nets["count"].direction, Some(Direction::Output));
+    assert!(counter.nets.contains_key("count_reg[0].q"));
+    assert_eq!(counter.instances.len(), 7);
+    assert_eq!(counter.instances[2].name, "count_reg[0]");
+    assert_eq!(counter.instances[2].module, "sky130_fd_sc_hd__dfrtp_1");
+    let Connections::Ordered(conns) = &counter.instances[6].connections else {
+        panic!("expected ordered connections");
+    };
+    assert_eq!(
+        conns,
+        &vec![
+            Some(VExpr::Const(vec![Bit::Zero])),
+            Some(VExpr::Slice("count".into(), Range { msb: 1, lsb: 0 })),
+        ]
+    );
+    assert_eq!(counter.assigns.len(), 1);
+
+    let half = &ast.modules[1];
+    assert_eq!(half.ports, vec!["a", "b"]);
+    assert_eq!(half.nets["b"].range, Some(Range { msb: 1, lsb: 0 }));
+
+    let ast =
+        Parser::parse("module m(a); input [3:0] a; foo f(.A({2{a[0]}}), .B(4'hA)); endmodule")
+            .unwrap();
+    let Connections::Named(conns) = &ast.modules[0].instances[0].connections else {
+        panic!("expected named connections");
+    };
+    assert_eq!(
+        conns[0].1,
+        Some(VExpr::Replicate(2, Box::new(VExpr::Index("a".into(), 0))))
+    );
+    assert_eq!(
+        conns[1].1,
+        Some(VExpr::Const(vec![Bit::Zero, Bit::One, Bit::Zero, Bit::One]))
+    );
+
+    assert!(Parser::parse("module m(a); input a; always @(a) begin end endmodule").is_err());
+    assert!(Parser::parse("module m(a); endmodule").is_err());
+}
+
+/// Subcircuit declarations of the Sky130 standard cells used by [`YOSYS_NETLIST`].
+const SKY130_STDCELLS: &str = r#"
+.subckt sky130_fd_sc_hd__inv_1 A VGND VNB VPB VPWR Y
+.ends
+.subckt sky130_fd_sc_hd__xor2_1 A B VGND VNB VPB VPWR X
+.ends
+.subckt sky130_fd_sc_hd__dfrtp_1 CLK D RESET_B VGND VNB VPB VPWR Q
+.ends
+.subckt sky130_fd_sc_hd__conb_1 VGND VNB VPB VPWR HI LO
+.ends
+.subckt sky130_fd_sc_hd__and2_1 A B VGND VNB VPB VPWR X
+.ends
+"#;
+
+fn sky130_stdcells() -> spice::parser::Ast {
+    spice::parser::Parser::parse(spice::parser::Dialect::Spice, SKY130_STDCELLS)
+        .unwrap()
+        .ast
+}
+
+fn convert_counter() -> scir::Library<Spice> {
+    let ast = Parser::parse(YOSYS_NETLIST).unwrap();
+    let mut conv = ScirConverter::new(&ast);
+    conv.port_orders_from_spice(&sky130_stdcells());
+    conv.connect_global("VPWR", "vdd");
+    conv.connect_global("VPB", "vdd");
+    conv.connect_global("VGND", "vss");
+    conv.connect_global("VNB", "vss");
+    conv.tie_low("vss");
+    conv.convert().unwrap()
+}
+
+#[test]
+fn convert_structural_verilog_to_scir() {
+    let lib = convert_counter();
+    let counter = lib.cell_named("counter");
+    let ports: Vec<_> = counter
+        .ports()
+        .map(|port| counter.signal(port.signal()).name.clone())
+        .collect();
+    assert_eq!(ports, vec!["clk", "rst", "count", "vdd", "vss"]);
+
+    let count = counter.signal_named("count").id;
+    let vss = counter.signal_named("vss").id;
+    let vdd = counter.signal_named("vdd").id;
+
+    let inv = counter.instance_named("_1_");
+    let scir::ChildId::Primitive(id) = inv.child() else {
+        panic!("expected a primitive instance");
+    };
+    let spice::Primitive::RawInstance { ports, cell, .. } = lib.primitive(id) else {
+        panic!("expected a raw instance");
+    };
+    assert_eq!(cell, "sky130_fd_sc_hd__inv_1");
+    assert_eq!(ports, &vec!["A", "VGND", "VNB", "VPB", "VPWR", "Y"]);
+    assert_eq!(inv.connection("VPWR").parts().next().unwrap().signal(), vdd);
+    assert_eq!(inv.connection("VNB").parts().next().unwrap().signal(), vss);
+
+    // `count[0]` is aliased to the register output by an assignment.
+    let dff = counter.instance_named("count_reg[0]");
+    let q = dff.connection("Q");
+    assert_eq!(q.width(), 1);
+    let q = q.parts().next().unwrap();
+    assert_eq!(q.signal(), count);
+    assert_eq!(q.range().unwrap().start(), 0);
+    let scir::ChildId::Primitive(id) = dff.child() else {
+        panic!("expected a primitive instance");
+    };
+    let spice::Primitive::RawInstance { ports, .. } = lib.primitive(id) else {
+        panic!("expected a raw instance");
+    };
+    assert_eq!(
+        ports,
+        &vec!["CLK", "D", "RESET_B", "VGND", "VNB", "VPB", "VPWR", "Q"]
+    );
+    assert_eq!(dff.connection("VPWR").parts().next().unwrap().signal(), vdd);
+
+    // Constants are connected to the tie-low net; vectors are connected as slices.
+    let half = counter.instance_named("h0");
+    let a = half.connection("a").parts().next().unwrap();
+    assert_eq!(a.signal(), vss);
+    let b: Vec<_> = half.connection("b").parts().copied().collect();
+    assert_eq!(b, vec![counter.signal_named("count").slice()]);
+    assert_eq!(half.connection("vdd").parts().next().unwrap().signal(), vdd);
+
+    // Unconnected ports are connected to new floating nodes.
+    let tie = counter.instance_named("tie");
+    let scir::ChildId::Primitive(id) = tie.child() else {
+        panic!("expected a primitive instance");
+    };
+    let spice::Primitive::RawInstance { ports, .. } = lib.primitive(id) else {
+        panic!("expected a raw instance");
+    };
+    assert_eq!(ports, &vec!["VGND", "VNB", "VPB", "VPWR", "HI", "LO"]);
+    let hi = tie.connection("HI").parts().next().unwrap().signal();
+    let lo = tie.connection("LO").parts().next().unwrap().signal();
+    assert_ne!(hi, lo);
+    assert!(!counter.signal(hi).is_port());
+}
+
+#[test]
+fn convert_structural_verilog_errors() {
+    let ast = Parser::parse(YOSYS_NETLIST).unwrap();
+    let mut conv = ScirConverter::new(&ast);
+    conv.port_orders_from_spice(&sky130_stdcells());
+    assert!(matches!(
+        conv.convert(),
+        Err(ConvError::UnresolvedConstant(module)) if module == "counter"
+    ));
+
+    let ast = Parser::parse("module m(a); input [1:0] a; foo f(a[2]); endmodule").unwrap();
+    let mut conv = ScirConverter::new(&ast);
+    conv.port_order("foo", ["A"]);
+    assert!(matches!(
+        conv.convert(),
+        Err(ConvError::InvalidIndex { index: 2, .. })
+    ));
+
+    let ast = Parser::parse("module m(a); input a; foo f(a); endmodule").unwrap();
+    assert!(matches!(
+        ScirConverter::new(&ast).convert(),
+        Err(ConvError::UnknownPortOrder { .. })
+    ));
+
+    let ast = Parser::parse("module m(a); input a; foo f(.A(a)); endmodule").unwrap();
+    assert!(matches!(
+        ScirConverter::new(&ast).convert(),
+        Err(ConvError::UnknownPortOrder { .. })
+    ));
+
+    let ast = Parser::parse("module m(a, b); input a; output b; assign b = a; endmodule").unwrap();
+    assert!(matches!(
+        ScirConverter::new(&ast).convert(),
+        Err(ConvError::ShortedPorts { .. })
+    ));
+}
+
+#[test]
+fn convert_structural_verilog_to_sky130() {
+    let lib = convert_counter()
+        .convert_schema::<sky130pdk::Sky130Pdk>()
+        .unwrap()
+        .build()
+        .unwrap();
+    let counter = lib.cell_named("counter");
+    let scir::ChildId::Primitive(id) = counter.instance_named("_2_").child() else {
+        panic!("expected a primitive instance");
+    };
+    let sky130pdk::Primitive::RawInstance { cell, ports, .. } = lib.primitive(id) else {
+        panic!("expected a raw instance");
+    };
+    assert_eq!(cell, "sky130_fd_sc_hd__xor2_1");
+    assert_eq!(ports, &vec!["A", "B", "VGND", "VNB", "VPB", "VPWR", "X"]);
+}
@@ -27,6 +27,7 @@ use crate::io::{Flatten, Flipped, HasNameTree};
 use crate::layout::element::RawCell;
 use crate::layout::error::{GdsExportError, LayoutError};
 use crate::layout::gds::{GdsExporter, GdsImporter, ImportedGds};
+use crate::layout::klayout;
 use crate::layout::CellBuilder as LayoutCellBuilder;
 use crate::layout::{Cell as LayoutCell, CellHandle as LayoutCellHandle};
 use crate::layout::{Layout, LayoutContext};
@@ -602,6 +603,13 @@ impl<PDK: Pdk> PdkContext<PDK> {
         Ok(())
     }
 
+    /// Writes a KLayout layer properties file for the PDK's layers.
+    ///
+    /// See [`klayout::save_lyp`] for details.
+    pub fn write_lyp(&self, path: impl AsRef<Path>) -> Result<()> {
+        klayout::save_lyp(&*self.layers, path)
+    }
+
     /// Reads a layout from a GDS file.
     pub fn read_gds(&self, path: impl AsRef<Path>) -> Result<ImportedGds> {
         let lib = gds::GdsLibrary::load(path)?;
@@ -0,0 +1,336 @@
+//! Utilities for viewing layouts and check results in [KLayout](https://www.klayout.de).
+//!
+//! Supports generating layer properties (`.lyp`) files from a PDK's [`Layers`]
+//! and writing marker databases (`.lyrdb`) that can be overlaid on exported GDS files.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use arcstr::ArcStr;
+use geometry::prelude::{Point, Polygon, Rect};
+use indexmap::IndexMap;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::error::Result;
+use crate::pdk::layers::{LayerFamilyInfo, LayerId, Layers};
+
+/// Colors assigned to layer families, in order of declaration.
+const PALETTE: [&str; 12] = [
+    "#ff8000", "#0080ff", "#00c000", "#ff0080", "#8000ff", "#c0c000", "#00c0c0", "#ff4040",
+    "#4040ff", "#808080", "#c08040", "#40c080",
+];
+
+/// Writes a KLayout layer properties file for the given layer set.
+///
+/// Each layer with a GDS layer spec is listed under its name.
+/// Layers in the same layer family share a color;
+/// drawing layers are stippled, while pin and label layers are hollow.
+/// Layers without a GDS layer spec are omitted.
+pub fn write_lyp<L: Layers + ?Sized>(layers: &L, mut writer: impl Write) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(writer, "<layer-properties>")?;
+    for (i, family) in layers.flatten().iter().enumerate() {
+        let color = PALETTE[i % PALETTE.len()];
+        for layer in family.layers.iter() {
+            let Some(gds) = layer.gds else {
+                continue;
+            };
+            let dither = if is_marker_layer(family, layer.id) {
+                "I1"
+            } else {
+                "I9"
+            };
+            writeln!(writer, " <properties>")?;
+            writeln!(writer, "  <frame-color>{color}</frame-color>")?;
+            writeln!(writer, "  <fill-color>{color}</fill-color>")?;
+            writeln!(writer, "  <frame-brightness>0</frame-brightness>")?;
+            writeln!(writer, "  <fill-brightness>0</fill-brightness>")?;
+            writeln!(writer, "  <dither-pattern>{dither}</dither-pattern>")?;
+            writeln!(writer, "  <valid>true</valid>")?;
+            writeln!(writer, "  <visible>true</visible>")?;
+            writeln!(writer, "  <transparent>false</transparent>")?;
+            writeln!(writer, "  <width>1</width>")?;
+            writeln!(writer, "  <marked>false</marked>")?;
+            writeln!(writer, "  <animation>0</animation>")?;
+            writeln!(writer, "  <name>{}</name>", escape(&layer.name))?;
+            writeln!(writer, "  <source>{}/{}@1</source>", gds.0, gds.1)?;
+            writeln!(writer, " </properties>")?;
+        }
+    }
+    writeln!(writer, "</layer-properties>")?;
+    Ok(())
+}
+
+/// Saves a KLayout layer properties file for the given layer set to `path`.
+///
+/// See [`write_lyp`] for details.
+pub fn save_lyp<L: Layers + ?Sized>(layers: &L, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_lyp(layers, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn is_marker_layer(family: &LayerFamilyInfo, id: LayerId) -> bool {
+    id != family.primary && (family.pin == Some(id) || family.label == Some(id))
+}
+
+/// The geometry of a [`Marker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerShape {
+    /// A rectangle.
+    Rect(Rect),
+    /// A polygon.
+    Polygon(Polygon),
+    /// A line segment between two points.
+    ///
+    /// Useful for flagging connections, such as unrouted nets.
+    Edge(Point, Point),
+}
+
+impl From<Rect> for MarkerShape {
+    fn from(value: Rect) -> Self {
+        Self::Rect(value)
+    }
+}
+
+impl From<Polygon> for MarkerShape {
+    fn from(value: Polygon) -> Self {
+        Self::Polygon(value)
+    }
+}
+
+/// A single check result with a location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    shape: MarkerShape,
+    comment: Option<ArcStr>,
+}
+
+impl Marker {
+    /// Creates a new marker with the given geometry.
+    pub fn new(shape: impl Into<MarkerShape>) -> Self {
+        Self {
+            shape: shape.into(),
+            comment: None,
+        }
+    }
+
+    /// Attaches a comment to the marker, which is displayed alongside it in KLayout.
+    pub fn with_comment(mut self, comment: impl Into<ArcStr>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// The geometry of the marker.
+    #[inline]
+    pub fn shape(&self) -> &MarkerShape {
+        &self.shape
+    }
+
+    /// The comment attached to the marker, if any.
+    #[inline]
+    pub fn comment(&self) -> Option<&ArcStr> {
+        self.comment.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Category {
+    description: ArcStr,
+    markers: Vec<Marker>,
+}
+
+/// A KLayout marker database (`.lyrdb`).
+///
+/// Markers are grouped into categories, such as the name of a violated design rule,
+/// and are located in the top cell of the layout they annotate.
+#[derive(Debug, Clone)]
+pub struct MarkerDatabase {
+    description: ArcStr,
+    top_cell: ArcStr,
+    db_units: Decimal,
+    categories: IndexMap<ArcStr, Category>,
+}
+
+impl MarkerDatabase {
+    /// Creates a new, empty marker database for the layout cell named `top_cell`.
+    ///
+    /// Marker coordinates are given in layout database units of `db_units` meters,
+    /// which is typically [`Pdk::LAYOUT_DB_UNITS`](crate::pdk::Pdk::LAYOUT_DB_UNITS).
+    pub fn new(top_cell: impl Into<ArcStr>, db_units: Decimal) -> Self {
+        Self {
+            description: ArcStr::new(),
+            top_cell: top_cell.into(),
+            db_units,
+            categories: IndexMap::new(),
+        }
+    }
+
+    /// Sets the description of the database.
+    pub fn set_description(&mut self, description: impl Into<ArcStr>) {
+        self.description = description.into();
+    }
+
+    /// Adds a category with the given description.
+    ///
+    /// If the category already exists, its description is replaced.
+    pub fn add_category(&mut self, name: impl Into<ArcStr>, description: impl Into<ArcStr>) {
+        self.categories.entry(name.into()).or_default().description = description.into();
+    }
+
+    /// Adds a marker to the given category, creating the category if it does not exist.
+    pub fn add_marker(&mut self, category: impl Into<ArcStr>, marker: Marker) {
+        self.categories
+            .entry(category.into())
+            .or_default()
+            .markers
+            .push(marker);
+    }
+
+    /// Returns an iterator over the markers in the given category.
+    pub fn markers(&self, category: &str) -> impl Iterator<Item = &Marker> {
+        self.categories
+            .get(category)
+            .into_iter()
+            .flat_map(|c| c.markers.iter())
+    }
+
+    /// The total number of markers in the database.
+    pub fn num_markers(&self) -> usize {
+        self.categories.values().map(|c| c.markers.len()).sum()
+    }
+
+    /// Writes the database in KLayout's `.lyrdb` format.
+    pub fn write(&self, mut writer: impl Write) -> Result<()> {
+        let top_cell = escape(&self.top_cell);
+        writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+        writeln!(writer, "<report-database>")?;
+        writeln!(
+            writer,
+            " <description>{}</description>",
+            escape(&self.description)
+        )?;
+        writeln!(writer, " <original-file/>")?;
+        writeln!(writer, " <generator>substrate</generator>")?;
+        writeln!(writer, " <top-cell>{top_cell}</top-cell>")?;
+        writeln!(writer, " <tags/>")?;
+        writeln!(writer, " <categories>")?;
+        for (name, category) in self.categories.iter() {
+            writeln!(writer, "  <category>")?;
+            writeln!(writer, "   <name>{}</name>", escape(name))?;
+            writeln!(
+                writer,
+                "   <description>{}</description>",
+                escape(&category.description)
+            )?;
+            writeln!(writer, "   <categories/>")?;
+            writeln!(writer, "  </category>")?;
+        }
+        writeln!(writer, " </categories>")?;
+        writeln!(writer, " <cells>")?;
+        writeln!(writer, "  <cell>")?;
+        writeln!(writer, "   <name>{top_cell}</name>")?;
+        writeln!(writer, "   <variant/>")?;
+        writeln!(writer, "   <references/>")?;
+        writeln!(writer, "  </cell>")?;
+        writeln!(writer, " </cells>")?;
+        writeln!(writer, " <items>")?;
+        for (name, category) in self.categories.iter() {
+            for marker in category.markers.iter() {
+                writeln!(writer, "  <item>")?;
+                writeln!(writer, "   <tags/>")?;
+                // Item categories are paths, so names containing dots must be quoted.
+                writeln!(writer, "   <category>{}</category>", escape(&quote(name)))?;
+                writeln!(writer, "   <cell>{top_cell}</cell>")?;
+                writeln!(writer, "   <visited>false</visited>")?;
+                writeln!(writer, "   <multiplicity>1</multiplicity>")?;
+                writeln!(writer, "   <values>")?;
+                writeln!(
+                    writer,
+                    "    <value>{}</value>",
+                    escape(&self.shape_value(&marker.shape))
+                )?;
+                if let Some(comment) = &marker.comment {
+                    writeln!(
+                        writer,
+                        "    <value>{}</value>",
+                        escape(&format!("text: {}", quote(comment)))
+                    )?;
+                }
+                writeln!(writer, "   </values>")?;
+                writeln!(writer, "  </item>")?;
+            }
+        }
+        writeln!(writer, " </items>")?;
+        writeln!(writer, "</report-database>")?;
+        Ok(())
+    }
+
+    /// Saves the database to `path` in KLayout's `.lyrdb` format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Formats a marker shape as a KLayout database value, in microns.
+    fn shape_value(&self, shape: &MarkerShape) -> String {
+        let mut value = String::new();
+        let mut points = |kind: &str, points: &[Point]| {
+            value.push_str(kind);
+            value.push_str(": (");
+            for (i, p) in points.iter().enumerate() {
+                if i > 0 {
+                    value.push(';');
+                }
+                write!(value, "{},{}", self.microns(p.x), self.microns(p.y)).unwrap();
+            }
+            value.push(')');
+        };
+        match shape {
+            MarkerShape::Rect(rect) => points("box", &[rect.lower_left(), rect.upper_right()]),
+            MarkerShape::Polygon(polygon) => points("polygon", polygon.points()),
+            MarkerShape::Edge(p0, p1) => points("edge", &[*p0, *p1]),
+        }
+        value
+    }
+
+    /// Converts a length in database units to microns.
+    fn microns(&self, value: i64) -> Decimal {
+        (Decimal::from(value) * self.db_units * dec!(1e6)).normalize()
+    }
+}
+
+/// Escapes the XML special characters in `s`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes `s` as a KLayout string literal.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
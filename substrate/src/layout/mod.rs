@@ -37,6 +37,7 @@ pub mod bbox;
 pub mod element;
 pub mod error;
 pub mod gds;
+pub mod klayout;
 pub mod tiling;
 pub mod tracks;
 
@@ -113,6 +113,309 @@ pub mod tran {
     }
 }
 
+/// DC operating point data definitions.
+pub mod op {
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+
+    /// A voltage measurement from a DC operating point simulation.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct Voltage(pub f64);
+
+    impl Deref for Voltage {
+        type Target = f64;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A current measurement from a DC operating point simulation.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct Current(pub f64);
+
+    impl Deref for Current {
+        type Target = f64;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// DC sweep data definitions.
+pub mod dc {
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// A series of voltage measurements from a DC sweep simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Voltage(pub Arc<Vec<f64>>);
+
+    impl Deref for Voltage {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A series of current measurements from a DC sweep simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Current(pub Arc<Vec<f64>>);
+
+    impl Deref for Current {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The values of the swept variable at each point of a DC sweep simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Sweep(pub Arc<Vec<f64>>);
+
+    impl Deref for Sweep {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The values of the outer swept variable at each point of a nested DC sweep simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct NestedSweep(pub Arc<Vec<f64>>);
+
+    impl Deref for NestedSweep {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// Periodic steady-state data definitions.
+pub mod pss {
+    use num::complex::Complex64;
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The time points spanning one period of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Time(pub Arc<Vec<f64>>);
+
+    impl Deref for Time {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A series of voltage vs time measurements over one period of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Voltage(pub Arc<Vec<f64>>);
+
+    impl Deref for Voltage {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A series of current vs time measurements over one period of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Current(pub Arc<Vec<f64>>);
+
+    impl Deref for Current {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The harmonic frequencies of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The complex amplitude of a voltage at each harmonic of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct HarmonicVoltage(pub Arc<Vec<Complex64>>);
+
+    impl Deref for HarmonicVoltage {
+        type Target = Vec<Complex64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The complex amplitude of a current at each harmonic of a periodic steady-state simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct HarmonicCurrent(pub Arc<Vec<Complex64>>);
+
+    impl Deref for HarmonicCurrent {
+        type Target = Vec<Complex64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// Periodic AC data definitions.
+pub mod pac {
+    use num::complex::Complex64;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The input frequency points associated with a periodic AC simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A series of voltage vs input frequency measurements for each sideband of a periodic AC simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Voltage(pub HashMap<i64, Arc<Vec<Complex64>>>);
+
+    impl Deref for Voltage {
+        type Target = HashMap<i64, Arc<Vec<Complex64>>>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// A series of current vs input frequency measurements for each sideband of a periodic AC simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Current(pub HashMap<i64, Arc<Vec<Complex64>>>);
+
+    impl Deref for Current {
+        type Target = HashMap<i64, Arc<Vec<Complex64>>>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// Periodic noise data definitions.
+pub mod pnoise {
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The frequency points associated with a periodic noise simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The output periodic noise spectral density (V/sqrt(Hz)) at each frequency point.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct OutputNoise(pub Arc<Vec<f64>>);
+
+    impl Deref for OutputNoise {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The input-referred periodic noise spectral density at each frequency point.
+    ///
+    /// Measured in V/sqrt(Hz) for voltage inputs and A/sqrt(Hz) for current inputs.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct InputNoise(pub Arc<Vec<f64>>);
+
+    impl Deref for InputNoise {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The contribution of a single device to the output periodic noise power
+    /// spectral density (V^2/Hz) at each frequency point.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Contribution(pub Arc<Vec<f64>>);
+
+    impl Deref for Contribution {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// Noise data definitions.
+pub mod noise {
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The frequency points associated with a noise simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The output noise spectral density (V/sqrt(Hz)) at each frequency point.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct OutputNoise(pub Arc<Vec<f64>>);
+
+    impl Deref for OutputNoise {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The input-referred noise spectral density at each frequency point.
+    ///
+    /// Measured in V/sqrt(Hz) for voltage inputs and A/sqrt(Hz) for current inputs.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct InputNoise(pub Arc<Vec<f64>>);
+
+    impl Deref for InputNoise {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The contribution of a single device to the output noise power
+    /// spectral density (V^2/Hz) at each frequency point.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Contribution(pub Arc<Vec<f64>>);
+
+    impl Deref for Contribution {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
 /// AC data definitions.
 pub mod ac {
     use num::complex::Complex64;
@@ -153,3 +456,292 @@ pub mod ac {
         }
     }
 }
+
+/// Stability analysis data definitions.
+pub mod stb {
+    use num::complex::Complex64;
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The frequency points associated with a stability simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// The loop gain of a feedback loop vs frequency.
+    ///
+    /// Margins are measured relative to the low-frequency phase, so the loop gain
+    /// may be reported with either sign.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct LoopGain {
+        /// The frequency points (Hz).
+        pub freq: Arc<Vec<f64>>,
+        /// The complex loop gain at each frequency point.
+        pub gain: Arc<Vec<Complex64>>,
+    }
+
+    impl Deref for LoopGain {
+        type Target = Vec<Complex64>;
+        fn deref(&self) -> &Self::Target {
+            &self.gain
+        }
+    }
+
+    impl LoopGain {
+        /// The magnitude of the loop gain (dB) at each frequency point.
+        pub fn magnitude_db(&self) -> Vec<f64> {
+            self.gain.iter().map(|g| 20. * g.norm().log10()).collect()
+        }
+
+        /// The phase of the loop gain (degrees) at each frequency point.
+        ///
+        /// The phase at the first frequency point lies in (-180, 180] degrees. Subsequent
+        /// points are unwrapped so that the phase changes by less than 180 degrees
+        /// between consecutive frequency points.
+        pub fn phase_deg(&self) -> Vec<f64> {
+            let mut phase: Vec<f64> = Vec::with_capacity(self.gain.len());
+            for g in self.gain.iter() {
+                let mut p = g.arg().to_degrees();
+                if let Some(&prev) = phase.last() {
+                    p -= 360. * ((p - prev) / 360.).round();
+                }
+                phase.push(p);
+            }
+            phase
+        }
+
+        /// The frequency (Hz) at which the magnitude of the loop gain first falls below unity.
+        ///
+        /// Returns [`None`] if the loop gain never crosses unity.
+        pub fn unity_gain_freq(&self) -> Option<f64> {
+            let (idx, frac) = falling_crossing(&self.magnitude_db(), 0.)?;
+            Some(self.interp_freq(idx, frac))
+        }
+
+        /// The phase margin (degrees), measured at the unity gain frequency.
+        ///
+        /// The phase margin is 180 degrees minus the phase shift accumulated
+        /// since the low-frequency phase.
+        ///
+        /// Returns [`None`] if the loop gain never crosses unity.
+        pub fn phase_margin(&self) -> Option<f64> {
+            let (idx, frac) = falling_crossing(&self.magnitude_db(), 0.)?;
+            let phase = self.phase_deg();
+            Some(180. + lerp(phase[idx], phase[idx + 1], frac) - reference_phase(&phase))
+        }
+
+        /// The gain margin (dB), measured where the phase of the loop gain
+        /// first falls 180 degrees below the low-frequency phase.
+        ///
+        /// Returns [`None`] if the phase never falls that far.
+        pub fn gain_margin(&self) -> Option<f64> {
+            let phase = self.phase_deg();
+            let (idx, frac) = falling_crossing(&phase, reference_phase(&phase) - 180.)?;
+            let mag = self.magnitude_db();
+            Some(-lerp(mag[idx], mag[idx + 1], frac))
+        }
+
+        /// Interpolates between frequency points `idx` and `idx + 1`,
+        /// logarithmically if both points are positive.
+        fn interp_freq(&self, idx: usize, frac: f64) -> f64 {
+            let (f0, f1) = (self.freq[idx], self.freq[idx + 1]);
+            if f0 > 0. && f1 > 0. {
+                10f64.powf(lerp(f0.log10(), f1.log10(), frac))
+            } else {
+                lerp(f0, f1, frac)
+            }
+        }
+    }
+
+    /// The low-frequency phase (degrees) of an unwrapped phase response,
+    /// rounded to the nearest multiple of 180 degrees.
+    ///
+    /// The phase at the first frequency point may already include some shift from
+    /// low-frequency poles, so only the sign of the low-frequency loop gain is kept.
+    fn reference_phase(phase: &[f64]) -> f64 {
+        phase.first().map_or(0., |p| 180. * (p / 180.).round())
+    }
+
+    /// Finds the first index `i` such that `values` falls from at least `target` at `i`
+    /// to below `target` at `i + 1`, along with the fractional position of the crossing.
+    fn falling_crossing(values: &[f64], target: f64) -> Option<(usize, f64)> {
+        values
+            .windows(2)
+            .position(|w| w[0] >= target && w[1] < target)
+            .map(|idx| {
+                let (y0, y1) = (values[idx], values[idx + 1]);
+                (idx, (target - y0) / (y1 - y0))
+            })
+    }
+
+    #[inline]
+    fn lerp(y0: f64, y1: f64, frac: f64) -> f64 {
+        y0 + frac * (y1 - y0)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use approx::assert_relative_eq;
+
+        /// The loop gain of a three-pole amplifier with DC gain `a0` and poles at `poles`.
+        fn three_pole(a0: f64, poles: [f64; 3]) -> LoopGain {
+            let freq: Vec<f64> = (0..=800).map(|i| 10f64.powf(i as f64 / 100.)).collect();
+            let gain = freq
+                .iter()
+                .map(|&f| {
+                    poles.iter().fold(Complex64::new(a0, 0.), |acc, &p| {
+                        acc / Complex64::new(1., f / p)
+                    })
+                })
+                .collect();
+            LoopGain {
+                freq: Arc::new(freq),
+                gain: Arc::new(gain),
+            }
+        }
+
+        #[test]
+        fn loop_gain_margins() {
+            let a0 = 1000.;
+            let poles = [1e2, 1e6, 1e7];
+            let lg = three_pole(a0, poles);
+
+            // Compute the expected margins analytically.
+            let mag = |f: f64| {
+                poles
+                    .iter()
+                    .fold(a0, |acc, p| acc / (1. + (f / p).powi(2)).sqrt())
+            };
+            let phase =
+                |f: f64| -> f64 { poles.iter().map(|p| -(f / p).atan().to_degrees()).sum() };
+            let bisect = |mut lo: f64, mut hi: f64, g: &dyn Fn(f64) -> f64| {
+                for _ in 0..200 {
+                    let mid = (lo * hi).sqrt();
+                    if g(mid) > 0. {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                lo
+            };
+            let ugf = bisect(1., 1e8, &|f| mag(f) - 1.);
+            let f180 = bisect(1., 1e8, &|f| phase(f) + 180.);
+
+            assert_relative_eq!(lg.unity_gain_freq().unwrap(), ugf, max_relative = 1e-3);
+            assert_relative_eq!(lg.phase_margin().unwrap(), 180. + phase(ugf), epsilon = 0.1);
+            assert_relative_eq!(
+                lg.gain_margin().unwrap(),
+                -20. * mag(f180).log10(),
+                epsilon = 0.1
+            );
+        }
+
+        #[test]
+        fn loop_gain_margins_with_inverted_sign() {
+            let lg = three_pole(1000., [1e2, 1e6, 1e7]);
+            let inverted = LoopGain {
+                freq: lg.freq.clone(),
+                gain: Arc::new(lg.gain.iter().map(|g| -g).collect()),
+            };
+            assert_relative_eq!(inverted.phase_deg()[0], 180., epsilon = 1.);
+
+            assert_relative_eq!(
+                inverted.phase_margin().unwrap(),
+                lg.phase_margin().unwrap(),
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(
+                inverted.gain_margin().unwrap(),
+                lg.gain_margin().unwrap(),
+                epsilon = 1e-9
+            );
+
+            // Rotating by -179 degrees starts the phase just above -180 degrees instead.
+            let rotation = Complex64::from_polar(1., -179f64.to_radians());
+            let rotated = LoopGain {
+                freq: lg.freq.clone(),
+                gain: Arc::new(lg.gain.iter().map(|g| g * rotation).collect()),
+            };
+            assert_relative_eq!(rotated.phase_deg()[0], -180., epsilon = 1.);
+            assert_relative_eq!(
+                rotated.phase_margin().unwrap(),
+                lg.phase_margin().unwrap() + 1.,
+                epsilon = 1e-9
+            );
+        }
+
+        #[test]
+        fn loop_gain_without_crossings() {
+            let lg = three_pole(0.5, [1e2, 1e6, 1e7]);
+            assert_eq!(lg.unity_gain_freq(), None);
+            assert_eq!(lg.phase_margin(), None);
+
+            let lg = three_pole(1000., [1e7, 1e8, 1e9]);
+            assert_eq!(lg.gain_margin(), None);
+        }
+    }
+}
+
+/// S-parameter data definitions.
+pub mod sp {
+    use num::complex::Complex64;
+    use serde::{Deserialize, Serialize};
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// The frequency points associated with an S-parameter simulation.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Freq(pub Arc<Vec<f64>>);
+
+    impl Deref for Freq {
+        type Target = Vec<f64>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    /// An N-port S-matrix vs frequency.
+    ///
+    /// Port indices are zero-based, so `S11` is `s(0, 0)`.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SParams {
+        /// The frequency points (Hz).
+        pub freq: Arc<Vec<f64>>,
+        /// The reference impedance (ohms).
+        pub z0: f64,
+        /// The number of ports.
+        pub num_ports: usize,
+        /// The S-matrix at each frequency point, stored in row-major order.
+        pub matrices: Arc<Vec<Vec<Complex64>>>,
+    }
+
+    impl SParams {
+        /// The S-matrix at frequency point `idx`, stored in row-major order.
+        #[inline]
+        pub fn matrix(&self, idx: usize) -> &[Complex64] {
+            &self.matrices[idx]
+        }
+
+        /// The S-parameter from port `j` to port `i` at frequency point `idx`.
+        #[inline]
+        pub fn get(&self, idx: usize, i: usize, j: usize) -> Complex64 {
+            self.matrices[idx][i * self.num_ports + j]
+        }
+
+        /// The S-parameter from port `j` to port `i` at each frequency point.
+        pub fn s(&self, i: usize, j: usize) -> Vec<Complex64> {
+            (0..self.freq.len())
+                .map(|idx| self.get(idx, i, j))
+                .collect()
+        }
+    }
+}
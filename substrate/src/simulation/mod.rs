@@ -10,10 +10,11 @@ use serde::Serialize;
 
 use crate::block::Block;
 use crate::context::{Context, Installation};
+use crate::io::schematic::NodePath;
 use crate::io::TestbenchIo;
-use crate::schematic::conv::RawLib;
+use crate::schematic::conv::{ConvertedNodePath, RawLib};
 use crate::schematic::schema::Schema;
-use crate::schematic::{Cell, ExportsNestedData, Schematic};
+use crate::schematic::{Cell, ExportsNestedData, InstancePath, Schematic};
 use crate::simulation::data::SaveTb;
 use codegen::simulator_tuples;
 use substrate::simulation::data::FromSaved;
@@ -129,6 +130,22 @@ impl<S: Simulator, T: Testbench<S>> SimController<S, T> {
         Ok(O::from_saved(&output, &key))
     }
 
+    /// Converts a Substrate [`InstancePath`] to a SCIR [`scir::InstancePath`]
+    /// within the testbench's SCIR library.
+    ///
+    /// Useful for analyses that refer to instances, such as sweeps of source values.
+    pub fn convert_instance_path(&self, path: &InstancePath) -> Option<scir::InstancePath> {
+        self.ctx.lib.convert_instance_path(path)
+    }
+
+    /// Converts a Substrate [`NodePath`] to a [`ConvertedNodePath`]
+    /// within the testbench's SCIR library.
+    ///
+    /// Useful for analyses that refer to nodes, such as the output of a noise analysis.
+    pub fn convert_node_path(&self, path: &NodePath) -> Option<ConvertedNodePath> {
+        self.ctx.lib.convert_node_path(path)
+    }
+
     /// Set an option by mutating the given options.
     pub fn set_option<O>(&self, opt: O, options: &mut S::Options)
     where
@@ -0,0 +1,109 @@
+use geometry::prelude::{Point, Rect};
+use rust_decimal_macros::dec;
+use substrate::context::PdkContext;
+use substrate::layout::klayout::{Marker, MarkerDatabase, MarkerShape};
+
+use crate::paths::get_path;
+use crate::shared::pdk::ExamplePdkA;
+
+#[test]
+fn klayout_layer_properties() {
+    let ctx = PdkContext::new(ExamplePdkA);
+    let path = get_path("klayout_layer_properties", "layers.lyp");
+    ctx.write_lyp(&path)
+        .expect("failed to write layer properties");
+
+    let lyp = std::fs::read_to_string(&path).unwrap();
+    for (name, source) in [
+        ("poly_a", "66/20@1"),
+        ("met_1_drawing_a", "68/20@1"),
+        ("met_1_pin_a", "68/16@1"),
+        ("met_1_label_a", "68/5@1"),
+        ("met2", "69/20@1"),
+    ] {
+        assert!(lyp.contains(&format!(
+            "  <name>{name}</name>\n  <source>{source}</source>"
+        )));
+    }
+    assert_eq!(lyp.matches("<properties>").count(), 5);
+}
+
+#[test]
+fn klayout_marker_database() {
+    let mut db = MarkerDatabase::new("top", dec!(1e-9));
+    db.set_description("DRC results");
+    db.add_category("met1.width", "met1 width < 0.14um");
+    db.add_marker(
+        "met1.width",
+        Marker::new(Rect::from_sides(0, 0, 100, 1_500)).with_comment("width 0.1um"),
+    );
+    db.add_marker(
+        "unrouted",
+        Marker::new(MarkerShape::Edge(Point::new(0, 0), Point::new(2_000, 250))),
+    );
+    assert_eq!(db.num_markers(), 2);
+    assert_eq!(db.markers("met1.width").count(), 1);
+
+    let path = get_path("klayout_marker_database", "drc.lyrdb");
+    db.save(&path).expect("failed to write marker database");
+
+    let lyrdb = std::fs::read_to_string(&path).unwrap();
+    assert!(lyrdb.contains("<top-cell>top</top-cell>"));
+    assert!(lyrdb.contains("<description>met1 width &lt; 0.14um</description>"));
+    assert!(lyrdb.contains("<value>box: (0,0;0.1,1.5)</value>"));
+    assert!(lyrdb.contains("<value>text: &apos;width 0.1um&apos;</value>"));
+    assert!(lyrdb.contains("<category>&apos;unrouted&apos;</category>"));
+    assert!(lyrdb.contains("<value>edge: (0,0;2,0.25)</value>"));
+}
+
+/// Resolves the category path of a marker database item the way KLayout does:
+/// unquoted names are split at dots, while quoted names are taken verbatim.
+fn category_path(category: &str) -> Vec<String> {
+    let category = category.replace("&apos;", "'");
+    let mut path = Vec::new();
+    let mut chars = category.chars().peekable();
+    while chars.peek().is_some() {
+        let mut component = String::new();
+        if chars.peek() == Some(&'\'') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => component.extend(chars.next()),
+                    '\'' => break,
+                    c => component.push(c),
+                }
+            }
+            chars.next_if_eq(&'.');
+        } else {
+            for c in chars.by_ref() {
+                if c == '.' {
+                    break;
+                }
+                component.push(c);
+            }
+        }
+        path.push(component);
+    }
+    path
+}
+
+#[test]
+fn klayout_marker_database_dotted_categories() {
+    let mut db = MarkerDatabase::new("top", dec!(1e-9));
+    db.add_category("met1.width", "met1 width < 0.14um");
+    db.add_marker(
+        "met1.width",
+        Marker::new(Rect::from_sides(0, 0, 100, 1_500)),
+    );
+
+    let mut lyrdb = Vec::new();
+    db.write(&mut lyrdb)
+        .expect("failed to write marker database");
+    let lyrdb = String::from_utf8(lyrdb).unwrap();
+
+    let items = &lyrdb[lyrdb.find("<items>").unwrap()..];
+    let start = items.find("<category>").unwrap() + "<category>".len();
+    let end = items.find("</category>").unwrap();
+    assert_eq!(category_path(&items[start..end]), vec!["met1.width"]);
+    assert!(lyrdb.contains("<name>met1.width</name>"));
+}
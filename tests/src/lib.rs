@@ -8,6 +8,7 @@ pub mod cache;
 pub mod derive;
 pub mod gds;
 pub mod hard_macro;
+pub mod klayout;
 pub mod layout;
 pub mod netlist;
 pub mod paths;
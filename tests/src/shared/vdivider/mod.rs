@@ -33,7 +33,7 @@ pub struct Vdivider {
 
 impl Vdivider {
     #[inline]
-    fn new(r1: impl Into<Decimal>, r2: impl Into<Decimal>) -> Self {
+    pub fn new(r1: impl Into<Decimal>, r2: impl Into<Decimal>) -> Self {
         Self {
             r1: Resistor::new(r1),
             r2: Resistor::new(r2),
@@ -1,20 +1,155 @@
+use ::spectre::analysis::tran::Tran;
+use ::spectre::blocks::{Iprobe, Vsource};
+use ::spectre::{Options, Spectre};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
-use spectre::analysis::tran::Tran;
-use spectre::blocks::{Iprobe, Vsource};
-use spectre::{Options, Spectre};
 use spice::Spice;
 use substrate::block::Block;
 use substrate::io::schematic::HardwareType;
 use substrate::io::Signal;
-use substrate::io::TestbenchIo;
-use substrate::schematic::{Cell, CellBuilder, ExportsNestedData, Instance, NestedData, Schematic};
+use substrate::io::{TestbenchIo, TwoTerminalIo};
+use substrate::schematic::schema::Schema;
+use substrate::schematic::{
+    Cell, CellBuilder, ExportsNestedData, HasNestedView, Instance, InstancePath, NestedData,
+    NestedInstance, Schematic,
+};
 use substrate::simulation::data::{tran, FromSaved, Save, SaveTb};
 use substrate::simulation::{SimulationContext, Simulator, Testbench};
 
 use crate::hard_macro::VdividerDuplicateSubckt;
 use crate::shared::vdivider::{Resistor, Vdivider, VdividerArray};
 
+pub mod ngspice;
+pub mod spectre;
+
+/// Markers that select the analysis run by a [`VdividerAnalysisTb`].
+///
+/// The analysis settings are the same for every simulator.
+pub mod analyses {
+    use serde::{Deserialize, Serialize};
+
+    /// A DC operating point analysis.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct Op;
+
+    /// A DC sweep of the source from 0 V to 1.8 V in steps of 0.6 V,
+    /// nested within a sweep of the temperature over 25 and 75 degrees C.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct DcSweep;
+
+    /// A DC sweep of the source from 0.1 V to 10 V with 3 logarithmically spaced
+    /// points per decade.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct DcLogSweep;
+
+    /// An operating point analysis swept over source voltages of 0.6 V, 1.2 V and 1.8 V.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct ParamSweep;
+
+    /// A noise analysis at the divider output from 1 Hz to 1 MHz,
+    /// referred to the source.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct Noise;
+
+    /// Four Monte Carlo iterations of an operating point analysis with seed 42.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct MonteCarlo;
+
+    /// A periodic steady-state analysis at the source frequency with 5 harmonics,
+    /// followed by periodic AC and noise analyses from 1 kHz to 100 kHz.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+    pub struct Pss;
+}
+
+/// A [`Vdivider`] whose supply is driven by the voltage source `vsource`.
+///
+/// The marker `A` from [`analyses`] selects the analysis that the testbench runs.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Block)]
+#[substrate(io = "TestbenchIo")]
+pub struct VdividerAnalysisTb<V, A> {
+    pub vsource: V,
+    pub dut: Vdivider,
+    pub analysis: A,
+}
+
+impl<V, A> VdividerAnalysisTb<V, A> {
+    #[inline]
+    pub fn new(vsource: V, dut: Vdivider, analysis: A) -> Self {
+        Self {
+            vsource,
+            dut,
+            analysis,
+        }
+    }
+}
+
+pub struct VdividerAnalysisTbData<V: ExportsNestedData> {
+    pub vsource: Instance<V>,
+    pub dut: Instance<Vdivider>,
+}
+
+pub struct VdividerAnalysisTbDataNestedView<V: ExportsNestedData> {
+    pub vsource: NestedInstance<V>,
+    pub dut: NestedInstance<Vdivider>,
+}
+
+// `#[derive(NestedData)]` requires the source block itself to implement `HasNestedView`.
+impl<V: ExportsNestedData> HasNestedView for VdividerAnalysisTbData<V> {
+    type NestedView = VdividerAnalysisTbDataNestedView<V>;
+
+    fn nested_view(&self, parent: &InstancePath) -> Self::NestedView {
+        VdividerAnalysisTbDataNestedView {
+            vsource: self.vsource.nested_view(parent),
+            dut: self.dut.nested_view(parent),
+        }
+    }
+}
+
+impl<V: ExportsNestedData> HasNestedView for VdividerAnalysisTbDataNestedView<V> {
+    type NestedView = Self;
+
+    fn nested_view(&self, parent: &InstancePath) -> Self::NestedView {
+        VdividerAnalysisTbDataNestedView {
+            vsource: self.vsource.nested_view(parent),
+            dut: self.dut.nested_view(parent),
+        }
+    }
+}
+
+impl<V: ExportsNestedData, A> ExportsNestedData for VdividerAnalysisTb<V, A>
+where
+    Self: Block,
+{
+    type NestedData = VdividerAnalysisTbData<V>;
+}
+
+impl<S: Schema, V, A> Schematic<S> for VdividerAnalysisTb<V, A>
+where
+    Self: Block<Io = TestbenchIo>,
+    V: Block<Io = TwoTerminalIo> + Schematic<S> + Clone,
+    Vdivider: Schematic<S>,
+{
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as HardwareType>::Bundle,
+        cell: &mut CellBuilder<S>,
+    ) -> substrate::error::Result<Self::NestedData> {
+        let vdd = cell.signal("vdd", Signal);
+        let out = cell.signal("out", Signal);
+        let dut = cell.instantiate(self.dut);
+
+        cell.connect(dut.io().pwr.vdd, vdd);
+        cell.connect(dut.io().pwr.vss, io.vss);
+        cell.connect(dut.io().out, out);
+
+        let vsource = cell.instantiate(self.vsource.clone());
+        cell.connect(vsource.io().p, vdd);
+        cell.connect(vsource.io().n, io.vss);
+
+        Ok(VdividerAnalysisTbData { vsource, dut })
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, Block)]
 #[substrate(io = "TestbenchIo")]
 pub struct VdividerTb;
@@ -0,0 +1,268 @@
+use ngspice::ac::Sweep;
+use ngspice::blocks::Vsource;
+use ngspice::dc::{self, DcSweep, SweepPoints, SweepVar};
+use ngspice::montecarlo::{self, MonteCarlo};
+use ngspice::noise::{self, Noise};
+use ngspice::op::Op;
+use ngspice::sweep::{self, Sweep as ParamSweep, SweepVar as ParamSweepVar};
+use ngspice::{Ngspice, Options};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use substrate::schematic::Cell;
+use substrate::simulation::data::{self as data, op, FromSaved, Save, SaveTb};
+use substrate::simulation::{SimController, SimulationContext, Simulator, Testbench};
+
+use super::{analyses, VdividerAnalysisTb};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct OpOutput {
+    pub vout: op::Voltage,
+    /// The current through `r1`, saved by instance.
+    pub ir1: op::Current,
+    /// The current into the positive terminal of `r2`.
+    pub ir2_terminal: op::Current,
+}
+
+impl SaveTb<Ngspice, Op, OpOutput> for VdividerAnalysisTb<Vsource, analyses::Op> {
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <OpOutput as FromSaved<Ngspice, Op>>::SavedKey {
+        OpOutputSavedKey {
+            vout: op::Voltage::save(ctx, to_save.dut.io().out, opts),
+            ir1: op::Current::save(ctx, &to_save.dut.r1, opts),
+            ir2_terminal: op::Current::save(ctx, to_save.dut.r2.io().p, opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::Op> {
+    type Output = OpOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        sim.simulate(Options::default(), Op)
+            .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct DcSweepOutput {
+    pub vdd: data::dc::Sweep,
+    pub temp: data::dc::NestedSweep,
+    pub vout: data::dc::Voltage,
+    pub ir1: data::dc::Current,
+}
+
+impl SaveTb<Ngspice, DcSweep, DcSweepOutput> for VdividerAnalysisTb<Vsource, analyses::DcSweep> {
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <DcSweepOutput as FromSaved<Ngspice, DcSweep>>::SavedKey {
+        DcSweepOutputSavedKey {
+            vdd: data::dc::Sweep::save(ctx, (), opts),
+            temp: data::dc::NestedSweep::save(ctx, (), opts),
+            vout: data::dc::Voltage::save(ctx, to_save.dut.io().out, opts),
+            ir1: data::dc::Current::save(ctx, &to_save.dut.r1, opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::DcSweep> {
+    type Output = DcSweepOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            DcSweep {
+                sweep: dc::Sweep {
+                    var: SweepVar::Source(vsource),
+                    points: SweepPoints::Linear {
+                        start: dec!(0),
+                        stop: dec!(1.8),
+                        step: dec!(0.6),
+                    },
+                },
+                nested: Some(dc::Sweep {
+                    var: SweepVar::Temp,
+                    points: SweepPoints::Linear {
+                        start: dec!(25),
+                        stop: dec!(75),
+                        step: dec!(50),
+                    },
+                }),
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct DcLogSweepOutput {
+    pub vdd: data::dc::Sweep,
+    pub vout: data::dc::Voltage,
+}
+
+impl SaveTb<Ngspice, DcSweep, DcLogSweepOutput>
+    for VdividerAnalysisTb<Vsource, analyses::DcLogSweep>
+{
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <DcLogSweepOutput as FromSaved<Ngspice, DcSweep>>::SavedKey {
+        DcLogSweepOutputSavedKey {
+            vdd: data::dc::Sweep::save(ctx, (), opts),
+            vout: data::dc::Voltage::save(ctx, to_save.dut.io().out, opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::DcLogSweep> {
+    type Output = DcLogSweepOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            DcSweep {
+                sweep: dc::Sweep {
+                    var: SweepVar::Source(vsource),
+                    points: SweepPoints::Decade {
+                        start: dec!(0.1),
+                        stop: dec!(10),
+                        points: 3,
+                    },
+                },
+                nested: None,
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct ParamSweepOutput {
+    pub vout: sweep::Output<op::Voltage>,
+}
+
+impl SaveTb<Ngspice, ParamSweep<Op>, ParamSweepOutput>
+    for VdividerAnalysisTb<Vsource, analyses::ParamSweep>
+{
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <ParamSweepOutput as FromSaved<Ngspice, ParamSweep<Op>>>::SavedKey {
+        ParamSweepOutputSavedKey {
+            vout: sweep::Output::<op::Voltage>::save(ctx, to_save.dut.io().out, opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::ParamSweep> {
+    type Output = ParamSweepOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            ParamSweep {
+                var: ParamSweepVar::Source(vsource),
+                values: vec![dec!(0.6), dec!(1.2), dec!(1.8)],
+                analysis: Op,
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct NoiseOutput {
+    pub freq: data::noise::Freq,
+    pub output: data::noise::OutputNoise,
+    pub input: data::noise::InputNoise,
+}
+
+impl SaveTb<Ngspice, Noise, NoiseOutput> for VdividerAnalysisTb<Vsource, analyses::Noise> {
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        _to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <NoiseOutput as FromSaved<Ngspice, Noise>>::SavedKey {
+        NoiseOutputSavedKey {
+            freq: data::noise::Freq::save(ctx, (), opts),
+            output: data::noise::OutputNoise::save(ctx, (), opts),
+            input: data::noise::InputNoise::save(ctx, (), opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::Noise> {
+    type Output = NoiseOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        let output = sim
+            .convert_node_path(sim.tb.data().dut.io().out.path().as_ref())
+            .unwrap();
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            Noise {
+                output: output.into(),
+                output_ref: None,
+                input_source: noise::Source::Scir(vsource),
+                start: dec!(1),
+                stop: dec!(1e6),
+                sweep: Sweep::Decade(10),
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct MonteCarloOutput {
+    pub vout: montecarlo::Output<op::Voltage>,
+}
+
+impl SaveTb<Ngspice, MonteCarlo<Op>, MonteCarloOutput>
+    for VdividerAnalysisTb<Vsource, analyses::MonteCarlo>
+{
+    fn save_tb(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &Cell<Self>,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <MonteCarloOutput as FromSaved<Ngspice, MonteCarlo<Op>>>::SavedKey {
+        MonteCarloOutputSavedKey {
+            vout: montecarlo::Output::<op::Voltage>::save(ctx, to_save.dut.io().out, opts),
+        }
+    }
+}
+
+impl Testbench<Ngspice> for VdividerAnalysisTb<Vsource, analyses::MonteCarlo> {
+    type Output = MonteCarloOutput;
+
+    fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+        sim.simulate(
+            Options::default(),
+            MonteCarlo {
+                numruns: 4,
+                seed: Some(42),
+                firstrun: None,
+                analysis: Op,
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
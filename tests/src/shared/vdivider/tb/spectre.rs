@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use spectre::analysis::ac::Sweep;
+use spectre::analysis::dc::{self, DcSweep, SweepPoints, SweepVar};
+use spectre::analysis::noise::{Device, Noise};
+use spectre::analysis::op::Op;
+use spectre::analysis::pac::Pac;
+use spectre::analysis::pnoise::Pnoise;
+use spectre::analysis::pss::Pss;
+use spectre::analysis::sweep::{self, Sweep as ParamSweep};
+use spectre::blocks::Vsource;
+use spectre::{Options, Primitive, SimSignal, Spectre};
+use substrate::block::Block;
+use substrate::io::schematic::HardwareType;
+use substrate::io::TwoTerminalIo;
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::schematic::{Cell, CellBuilder, ExportsNestedData, PrimitiveBinding, Schematic};
+use substrate::simulation::data::{self as data, op, pac, pnoise, pss, FromSaved, Save, SaveTb};
+use substrate::simulation::{SimController, SimulationContext, Simulator, Testbench};
+
+use super::{analyses, VdividerAnalysisTb};
+
+/// A sinusoidal voltage source with a periodic AC magnitude of 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Block)]
+#[substrate(io = "TwoTerminalIo")]
+pub struct SineVsource {
+    pub ampl: Decimal,
+    pub freq: Decimal,
+}
+
+impl ExportsNestedData for SineVsource {
+    type NestedData = ();
+}
+
+impl Schematic<Spectre> for SineVsource {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as HardwareType>::Bundle,
+        cell: &mut CellBuilder<Spectre>,
+    ) -> substrate::error::Result<Self::NestedData> {
+        let mut prim = PrimitiveBinding::new(Primitive::RawInstance {
+            cell: arcstr::literal!("vsource"),
+            ports: vec![arcstr::literal!("p"), arcstr::literal!("n")],
+            params: HashMap::from_iter([
+                (
+                    arcstr::literal!("type"),
+                    scir::ParamValue::String(arcstr::literal!("sine")),
+                ),
+                (arcstr::literal!("ampl"), self.ampl.into()),
+                (arcstr::literal!("freq"), self.freq.into()),
+                (arcstr::literal!("pacmag"), dec!(1).into()),
+            ]),
+        });
+        prim.connect("p", io.p);
+        prim.connect("n", io.n);
+        cell.set_primitive(prim);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct OpOutput {
+    pub vout: op::Voltage,
+    /// The current into the positive terminal of `r1`.
+    pub ir1: op::Current,
+    /// The current into the positive terminal of `r2`.
+    pub ir2: op::Current,
+}
+
+impl SaveTb<Spectre, Op, OpOutput> for VdividerAnalysisTb<Vsource, analyses::Op> {
+    fn save_tb(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &Cell<Self>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> <OpOutput as FromSaved<Spectre, Op>>::SavedKey {
+        OpOutputSavedKey {
+            vout: op::Voltage::save(ctx, to_save.dut.io().out, opts),
+            ir1: op::Current::save(ctx, to_save.dut.r1.io().p, opts),
+            ir2: op::Current::save(ctx, to_save.dut.r2.io().p, opts),
+        }
+    }
+}
+
+impl Testbench<Spectre> for VdividerAnalysisTb<Vsource, analyses::Op> {
+    type Output = OpOutput;
+
+    fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+        sim.simulate(Options::default(), Op)
+            .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct DcSweepOutput {
+    pub vdd: data::dc::Sweep,
+    pub temp: data::dc::NestedSweep,
+    pub vout: data::dc::Voltage,
+    pub ir1: data::dc::Current,
+}
+
+impl SaveTb<Spectre, DcSweep, DcSweepOutput> for VdividerAnalysisTb<Vsource, analyses::DcSweep> {
+    fn save_tb(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &Cell<Self>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> <DcSweepOutput as FromSaved<Spectre, DcSweep>>::SavedKey {
+        DcSweepOutputSavedKey {
+            vdd: data::dc::Sweep::save(ctx, (), opts),
+            temp: data::dc::NestedSweep::save(ctx, (), opts),
+            vout: data::dc::Voltage::save(ctx, to_save.dut.io().out, opts),
+            ir1: data::dc::Current::save(ctx, to_save.dut.r1.io().p, opts),
+        }
+    }
+}
+
+impl Testbench<Spectre> for VdividerAnalysisTb<Vsource, analyses::DcSweep> {
+    type Output = DcSweepOutput;
+
+    fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            DcSweep {
+                sweep: dc::Sweep {
+                    var: SweepVar::Source(vsource),
+                    points: SweepPoints::Linear {
+                        start: dec!(0),
+                        stop: dec!(1.8),
+                        step: dec!(0.6),
+                    },
+                },
+                nested: Some(dc::Sweep {
+                    var: SweepVar::Temp,
+                    points: SweepPoints::List(vec![dec!(25), dec!(75)]),
+                }),
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct ParamSweepOutput {
+    pub vout: sweep::Output<op::Voltage>,
+}
+
+impl SaveTb<Spectre, ParamSweep<Op>, ParamSweepOutput>
+    for VdividerAnalysisTb<Vsource, analyses::ParamSweep>
+{
+    fn save_tb(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &Cell<Self>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> <ParamSweepOutput as FromSaved<Spectre, ParamSweep<Op>>>::SavedKey {
+        ParamSweepOutputSavedKey {
+            vout: sweep::Output::<op::Voltage>::save(ctx, to_save.dut.io().out, opts),
+        }
+    }
+}
+
+impl Testbench<Spectre> for VdividerAnalysisTb<Vsource, analyses::ParamSweep> {
+    type Output = ParamSweepOutput;
+
+    fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            ParamSweep {
+                var: SweepVar::Source(vsource),
+                values: vec![dec!(0.6), dec!(1.2), dec!(1.8)],
+                analysis: Op,
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromSaved)]
+pub struct NoiseOutput {
+    pub freq: data::noise::Freq,
+    pub output: data::noise::OutputNoise,
+    pub input: data::noise::InputNoise,
+    pub r1: data::noise::Contribution,
+    pub r2: data::noise::Contribution,
+}
+
+impl SaveTb<Spectre, Noise, NoiseOutput> for VdividerAnalysisTb<Vsource, analyses::Noise> {
+    fn save_tb(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &Cell<Self>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> <NoiseOutput as FromSaved<Spectre, Noise>>::SavedKey {
+        NoiseOutputSavedKey {
+            freq: data::noise::Freq::save(ctx, (), opts),
+            output: data::noise::OutputNoise::save(ctx, (), opts),
+            input: data::noise::InputNoise::save(ctx, (), opts),
+            r1: data::noise::Contribution::save(ctx, &to_save.dut.r1, opts),
+            r2: data::noise::Contribution::save(ctx, &to_save.dut.r2, opts),
+        }
+    }
+}
+
+impl Testbench<Spectre> for VdividerAnalysisTb<Vsource, analyses::Noise> {
+    type Output = NoiseOutput;
+
+    fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+        let output = sim
+            .convert_node_path(sim.tb.data().dut.io().out.path().as_ref())
+            .unwrap();
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+        sim.simulate(
+            Options::default(),
+            Noise {
+                output: output.into(),
+                output_ref: None,
+                input_source: Device::Scir(vsource),
+                start: dec!(1),
+                stop: dec!(1e6),
+                sweep: Sweep::Decade(10),
+                errpreset: None,
+            },
+        )
+        .expect("failed to run simulation")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PssOutput {
+    pub time: pss::Time,
+    pub vin: pss::Voltage,
+    pub vout: pss::Voltage,
+    pub vin_harmonics: pss::HarmonicVoltage,
+    pub vout_harmonics: pss::HarmonicVoltage,
+    pub pac_freq: pac::Freq,
+    pub pac_vout: pac::Voltage,
+    pub pnoise_freq: pnoise::Freq,
+    pub pnoise_output: pnoise::OutputNoise,
+}
+
+impl Testbench<Spectre> for VdividerAnalysisTb<SineVsource, analyses::Pss> {
+    type Output = PssOutput;
+
+    fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+        let voltage = |path: ConvertedNodePath| {
+            SimSignal::ScirVoltage(match path {
+                ConvertedNodePath::Cell(path) => path,
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => scir::SliceOnePath::new(instances, scir::NamedSliceOne::new(port)),
+            })
+        };
+        let vin = sim
+            .convert_node_path(sim.tb.data().dut.io().pwr.vdd.path().as_ref())
+            .unwrap();
+        let vout = sim
+            .convert_node_path(sim.tb.data().dut.io().out.path().as_ref())
+            .unwrap();
+        let vsource = sim
+            .convert_instance_path(sim.tb.data().vsource.path())
+            .unwrap();
+
+        let mut opts = Options::default();
+        let vin_key = opts.save_pss_voltage(voltage(vin));
+        let vout_key = opts.save_pss_voltage(voltage(vout.clone()));
+        let pac_vout_key = opts.save_pac_voltage(voltage(vout.clone()));
+
+        let (pss_output, pac_output, pnoise_output) = sim
+            .simulate_default(
+                opts,
+                (
+                    Pss {
+                        fund: self.vsource.freq,
+                        harms: Some(5),
+                        tstab: None,
+                        errpreset: None,
+                    },
+                    Pac {
+                        start: dec!(1e3),
+                        stop: dec!(1e5),
+                        sweep: Sweep::Decade(2),
+                        sidebands: vec![-1, 0, 1],
+                    },
+                    Pnoise {
+                        output: vout.into(),
+                        output_ref: None,
+                        input_source: Device::Scir(vsource),
+                        start: dec!(1e3),
+                        stop: dec!(1e5),
+                        sweep: Sweep::Decade(2),
+                        maxsideband: Some(1),
+                    },
+                ),
+            )
+            .expect("failed to run simulation");
+
+        PssOutput {
+            time: pss::Time::from_saved(&pss_output, &()),
+            vin: pss::Voltage::from_saved(&pss_output, &vin_key),
+            vout: pss::Voltage::from_saved(&pss_output, &vout_key),
+            vin_harmonics: pss::HarmonicVoltage::from_saved(&pss_output, &vin_key),
+            vout_harmonics: pss::HarmonicVoltage::from_saved(&pss_output, &vout_key),
+            pac_freq: pac::Freq::from_saved(&pac_output, &()),
+            pac_vout: pac::Voltage::from_saved(&pac_output, &pac_vout_key),
+            pnoise_freq: pnoise::Freq::from_saved(&pnoise_output, &()),
+            pnoise_output: pnoise::OutputNoise::from_saved(&pnoise_output, &()),
+        }
+    }
+}
@@ -1,20 +1,30 @@
+use std::sync::Arc;
+
 use approx::relative_eq;
-use ngspice::blocks::Vsource;
+use ngspice::ac::{Ac, Sweep};
+use ngspice::blocks::{AcSource, Vsource};
+use ngspice::shared::SharedNgspice;
 use ngspice::tran::Tran;
-use ngspice::{Ngspice, Options};
+use ngspice::{Backend, Ngspice, Options};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use substrate::block::Block;
+use substrate::context::Context;
 use substrate::io::schematic::HardwareType;
 use substrate::io::{Signal, TestbenchIo};
-use substrate::schematic::primitives::Resistor;
+use substrate::schematic::primitives::{Capacitor, Resistor};
 use substrate::schematic::{Cell, CellBuilder, ExportsNestedData, Instance, NestedData, Schematic};
-use substrate::simulation::data::{tran, FromSaved, Save, SaveTb};
+use substrate::simulation::data::{ac, tran, FromSaved, Save, SaveTb};
 use substrate::simulation::{SimController, SimulationContext, Simulator, Testbench};
 use test_log::test;
 
 use crate::paths::get_path;
 use crate::shared::pdk::sky130_open_ctx;
+use crate::shared::vdivider::tb::ngspice::{
+    DcLogSweepOutput, DcSweepOutput, MonteCarloOutput, NoiseOutput, OpOutput, ParamSweepOutput,
+};
+use crate::shared::vdivider::tb::{analyses, VdividerAnalysisTb};
+use crate::shared::vdivider::Vdivider;
 
 #[test]
 fn ngspice_can_save_voltages_and_currents() {
@@ -125,3 +135,303 @@ fn ngspice_can_save_voltages_and_currents() {
         });
     }
 }
+
+#[test]
+fn ngspice_can_run_ac_analysis() {
+    #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+    #[substrate(io = "TestbenchIo")]
+    struct RcAcTb;
+
+    #[derive(NestedData)]
+    struct RcAcTbData {
+        r: Instance<Resistor>,
+    }
+
+    impl ExportsNestedData for RcAcTb {
+        type NestedData = RcAcTbData;
+    }
+
+    impl Schematic<Ngspice> for RcAcTb {
+        fn schematic(
+            &self,
+            io: &<<Self as Block>::Io as HardwareType>::Bundle,
+            cell: &mut CellBuilder<Ngspice>,
+        ) -> substrate::error::Result<Self::NestedData> {
+            let vin = cell.signal("vin", Signal);
+            let vout = cell.signal("vout", Signal);
+            let r = cell.instantiate(Resistor::new(dec!(1000)));
+            let c = cell.instantiate(Capacitor::new(dec!(1e-9)));
+
+            cell.connect(r.io().p, vin);
+            cell.connect(r.io().n, vout);
+            cell.connect(c.io().p, vout);
+            cell.connect(c.io().n, io.vss);
+
+            let vsource = cell.instantiate(Vsource::ac(AcSource {
+                dc: dec!(0),
+                mag: dec!(1),
+                phase: dec!(0),
+            }));
+            cell.connect(vsource.io().p, vin);
+            cell.connect(vsource.io().n, io.vss);
+
+            Ok(RcAcTbData { r })
+        }
+    }
+
+    #[derive(FromSaved, Serialize, Deserialize)]
+    struct RcAcTbOutput {
+        freq: ac::Freq,
+        vout: ac::Voltage,
+        ir: ac::Current,
+    }
+
+    impl SaveTb<Ngspice, Ac, RcAcTbOutput> for RcAcTb {
+        fn save_tb(
+            ctx: &SimulationContext<Ngspice>,
+            to_save: &Cell<Self>,
+            opts: &mut <Ngspice as Simulator>::Options,
+        ) -> <RcAcTbOutput as FromSaved<Ngspice, Ac>>::SavedKey {
+            RcAcTbOutputSavedKey {
+                freq: ac::Freq::save(ctx, (), opts),
+                vout: ac::Voltage::save(ctx, to_save.data().r.io().n, opts),
+                ir: ac::Current::save(ctx, to_save.data().r.io().p, opts),
+            }
+        }
+    }
+
+    impl Testbench<Ngspice> for RcAcTb {
+        type Output = RcAcTbOutput;
+
+        fn run(&self, sim: SimController<Ngspice, Self>) -> Self::Output {
+            sim.simulate(
+                Options::default(),
+                Ac {
+                    start: dec!(1e3),
+                    stop: dec!(1e9),
+                    sweep: Sweep::Decade(10),
+                },
+            )
+            .expect("failed to run simulation")
+        }
+    }
+
+    let test_name = "ngspice_can_run_ac_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let RcAcTbOutput { freq, vout, ir } = ctx.simulate(RcAcTb, sim_dir).unwrap();
+
+    // 10 points per decade from 1 kHz to 1 GHz.
+    assert_eq!(freq.len(), 61);
+    assert_eq!(freq.len(), vout.len());
+    assert_eq!(freq.len(), ir.len());
+    assert!(relative_eq!(freq[0], 1e3));
+    assert!(relative_eq!(freq[30], 1e6, max_relative = 1e-9));
+    assert!(relative_eq!(freq[60], 1e9, max_relative = 1e-9));
+
+    // The output of an RC low-pass filter is 1 / (1 + jwRC),
+    // so its magnitude and phase are checked at a few frequencies
+    // below, near and above the corner frequency of 159 kHz.
+    let (r, c) = (1e3, 1e-9);
+    for idx in [0, 20, 26, 40] {
+        let (f, v, i) = (freq[idx], vout[idx], ir[idx]);
+        let x = 2. * std::f64::consts::PI * f * r * c;
+        let (mag, phase) = (1. / (1. + x * x).sqrt(), -x.atan());
+        assert!(
+            relative_eq!(v.norm(), mag, max_relative = 1e-4),
+            "found |vout| = {}, expected {mag} at {f} Hz",
+            v.norm()
+        );
+        assert!(
+            relative_eq!(v.arg(), phase, epsilon = 1e-4),
+            "found arg(vout) = {}, expected {phase} at {f} Hz",
+            v.arg()
+        );
+        // The resistor current is (1 - vout) / R.
+        let expected = (x * x / (1. + x * x)).sqrt() / r;
+        assert!(
+            relative_eq!(i.norm(), expected, max_relative = 1e-4),
+            "found |ir| = {}, expected {expected} at {f} Hz",
+            i.norm()
+        );
+    }
+}
+
+/// Runs an operating point analysis of a 100 ohm / 300 ohm divider driven by 1.8 V.
+fn check_vdivider_op(ctx: &Context, test_name: &str) {
+    let sim_dir = get_path(test_name, "sim/");
+    let OpOutput {
+        vout,
+        ir1,
+        ir2_terminal,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::Op,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert!(relative_eq!(*vout, 1.8 * 3. / 4.));
+    assert!(relative_eq!(*ir1, 1.8 / 400.));
+    assert!(relative_eq!(*ir2_terminal, 1.8 / 400.));
+}
+
+#[test]
+fn ngspice_can_run_op_analysis() {
+    check_vdivider_op(&sky130_open_ctx(), "ngspice_can_run_op_analysis");
+}
+
+#[test]
+fn ngspice_can_run_op_analysis_in_process() {
+    let ngspice = SharedNgspice::load_default().expect("failed to load ngspice shared library");
+    let ctx = Context::builder()
+        .install(Ngspice::new(Backend::Shared(Arc::new(ngspice))))
+        .build();
+    check_vdivider_op(&ctx, "ngspice_can_run_op_analysis_in_process");
+}
+
+#[test]
+fn ngspice_can_run_dc_sweep_analysis() {
+    let test_name = "ngspice_can_run_dc_sweep_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let DcSweepOutput {
+        vdd,
+        temp,
+        vout,
+        ir1,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::DcSweep,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_eq!(vdd.len(), 8);
+    assert_eq!(temp.len(), 8);
+    for i in 0..8 {
+        assert!(relative_eq!(vdd[i], 0.6 * (i % 4) as f64, epsilon = 1e-9));
+        assert!(relative_eq!(temp[i], if i < 4 { 25. } else { 75. }));
+        assert!(relative_eq!(vout[i], vdd[i] * 3. / 4., epsilon = 1e-9));
+        assert!(relative_eq!(ir1[i], vdd[i] / 400., epsilon = 1e-9));
+    }
+}
+
+#[test]
+fn ngspice_can_run_log_dc_sweep_analysis() {
+    let test_name = "ngspice_can_run_log_dc_sweep_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let DcLogSweepOutput { vdd, vout } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::DcLogSweep,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_eq!(vdd.len(), 7);
+    for i in 0..7 {
+        assert!(relative_eq!(
+            vdd[i],
+            0.1 * 10f64.powf(i as f64 / 3.),
+            max_relative = 1e-9
+        ));
+        assert!(relative_eq!(vout[i], vdd[i] * 3. / 4., max_relative = 1e-9));
+    }
+}
+
+#[test]
+fn ngspice_can_run_noise_analysis() {
+    let test_name = "ngspice_can_run_noise_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let NoiseOutput {
+        freq,
+        output,
+        input,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::ac(AcSource {
+                    dc: dec!(0),
+                    mag: dec!(1),
+                    phase: dec!(0),
+                }),
+                Vdivider::new(1000, 1000),
+                analyses::Noise,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    // The output sees the thermal noise of both resistors in parallel.
+    let expected = (4. * 1.380649e-23 * 300.15 * 500f64).sqrt();
+    assert_eq!(freq.len(), output.len());
+    assert_eq!(freq.len(), input.len());
+    for (output, input) in output.iter().zip(input.iter()) {
+        assert!(relative_eq!(*output, expected, max_relative = 1e-3));
+        assert!(relative_eq!(*input, 2. * expected, max_relative = 1e-3));
+    }
+}
+
+#[test]
+fn ngspice_can_run_parametric_sweep() {
+    let test_name = "ngspice_can_run_parametric_sweep";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let ParamSweepOutput { vout } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::ParamSweep,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_eq!(vout.len(), 3);
+    for (i, vout) in vout.iter().enumerate() {
+        assert!(relative_eq!(
+            **vout,
+            0.6 * (i + 1) as f64 * 3. / 4.,
+            epsilon = 1e-9
+        ));
+    }
+}
+
+#[test]
+fn ngspice_can_run_monte_carlo_analysis() {
+    let test_name = "ngspice_can_run_monte_carlo_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_open_ctx();
+    let MonteCarloOutput { vout } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::MonteCarlo,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    // The testbench has no statistical parameters,
+    // so every iteration matches the nominal result.
+    assert_eq!(vout.len(), 4);
+    for vout in vout.iter() {
+        assert!(relative_eq!(**vout, 1.8 * 3. / 4., epsilon = 1e-9));
+    }
+}
@@ -8,8 +8,12 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use sky130pdk::corner::Sky130Corner;
+use spectre::analysis::ac::Sweep;
+use spectre::analysis::noise::Device;
+use spectre::analysis::sp::Sp;
+use spectre::analysis::stb::Stb;
 use spectre::analysis::tran::Tran;
-use spectre::blocks::Vsource;
+use spectre::blocks::{AcSource, Iprobe, Nport, Port, Vsource};
 use spectre::{Options, Primitive, Spectre};
 use spice::{BlackboxContents, BlackboxElement, Spice};
 use substrate::block::Block;
@@ -21,9 +25,9 @@ use substrate::io::{InOut, Signal, TestbenchIo};
 use substrate::io::{Io, TwoTerminalIo};
 use substrate::pdk::corner::Pvt;
 use substrate::schematic::{
-    Cell, CellBuilder, ExportsNestedData, Instance, PrimitiveBinding, Schematic,
+    Cell, CellBuilder, ExportsNestedData, Instance, NestedData, PrimitiveBinding, Schematic,
 };
-use substrate::simulation::data::{tran, FromSaved, Save, SaveTb};
+use substrate::simulation::data::{sp, stb, tran, FromSaved, Save, SaveTb};
 use substrate::simulation::{SimController, SimulationContext, Simulator, Testbench};
 use test_log::test;
 
@@ -31,9 +35,15 @@ use crate::paths::test_data;
 use crate::shared::inverter::tb::InverterTb;
 use crate::shared::inverter::Inverter;
 use crate::shared::pdk::sky130_commercial_ctx;
-use crate::shared::vdivider::tb::{VdividerArrayTb, VdividerDuplicateSubcktTb};
+use crate::shared::vdivider::tb::spectre::{
+    DcSweepOutput, NoiseOutput, OpOutput, ParamSweepOutput, PssOutput, SineVsource,
+};
+use crate::shared::vdivider::tb::{
+    analyses, VdividerAnalysisTb, VdividerArrayTb, VdividerDuplicateSubcktTb,
+};
+use crate::shared::vdivider::Vdivider;
 use crate::{paths::get_path, shared::vdivider::tb::VdividerTb};
-use substrate::schematic::primitives::{RawInstance, Resistor};
+use substrate::schematic::primitives::{Capacitor, RawInstance, Resistor};
 
 #[test]
 fn vdivider_tran() {
@@ -452,3 +462,442 @@ fn spectre_rc_zin_ac() {
     assert_relative_eq!(z.re, -17.286407017773225);
     assert_relative_eq!(z.im, 130.3364383055986);
 }
+
+#[test]
+fn spectre_can_run_op_analysis() {
+    let test_name = "spectre_can_run_op_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let OpOutput { vout, ir1, ir2 } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::Op,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_relative_eq!(*vout, 1.8 * 3. / 4.);
+    assert_relative_eq!(*ir1, 1.8 / 400.);
+    assert_relative_eq!(*ir2, 1.8 / 400.);
+}
+
+#[test]
+fn spectre_can_run_dc_sweep_analysis() {
+    let test_name = "spectre_can_run_dc_sweep_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let DcSweepOutput {
+        vdd,
+        temp,
+        vout,
+        ir1,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::DcSweep,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_eq!(vdd.len(), 8);
+    assert_eq!(temp.len(), 8);
+    for i in 0..8 {
+        assert_relative_eq!(vdd[i], 0.6 * (i % 4) as f64, epsilon = 1e-9);
+        assert_relative_eq!(temp[i], if i < 4 { 25. } else { 75. });
+        assert_relative_eq!(vout[i], vdd[i] * 3. / 4., epsilon = 1e-9);
+        assert_relative_eq!(ir1[i], vdd[i] / 400., epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn spectre_can_run_parametric_sweep() {
+    let test_name = "spectre_can_run_parametric_sweep";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let ParamSweepOutput { vout } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::dc(dec!(1.8)),
+                Vdivider::new(100, 300),
+                analyses::ParamSweep,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    assert_eq!(vout.len(), 3);
+    for (i, vout) in vout.iter().enumerate() {
+        assert_relative_eq!(**vout, 0.6 * (i + 1) as f64 * 3. / 4., epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn spectre_can_run_noise_analysis() {
+    let test_name = "spectre_can_run_noise_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let NoiseOutput {
+        freq,
+        output,
+        input,
+        r1,
+        r2,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                Vsource::Ac(AcSource {
+                    dc: dec!(0),
+                    mag: dec!(1),
+                    phase: dec!(0),
+                }),
+                Vdivider::new(1000, 1000),
+                analyses::Noise,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    // The output sees the thermal noise of both resistors in parallel.
+    let four_kt: f64 = 4. * 1.380649e-23 * 300.15;
+    let expected = (four_kt * 500.).sqrt();
+    assert_eq!(freq.len(), output.len());
+    for i in 0..freq.len() {
+        assert_relative_eq!(output[i], expected, max_relative = 1e-3);
+        assert_relative_eq!(input[i], 2. * expected, max_relative = 1e-3);
+        assert_relative_eq!(r1[i], four_kt * 250., max_relative = 1e-3);
+        assert_relative_eq!(r2[i], four_kt * 250., max_relative = 1e-3);
+    }
+}
+
+#[test]
+fn spectre_can_run_pss_analyses() {
+    let test_name = "spectre_can_run_pss_analyses";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let PssOutput {
+        time,
+        vin,
+        vout,
+        vin_harmonics,
+        vout_harmonics,
+        pac_freq,
+        pac_vout,
+        pnoise_freq,
+        pnoise_output,
+    } = ctx
+        .simulate(
+            VdividerAnalysisTb::new(
+                SineVsource {
+                    ampl: dec!(1),
+                    freq: dec!(1e6),
+                },
+                Vdivider::new(1000, 1000),
+                analyses::Pss,
+            ),
+            sim_dir,
+        )
+        .unwrap();
+
+    // The steady state of the divider is half of the input in both domains.
+    assert_eq!(time.len(), vout.len());
+    for i in 0..time.len() {
+        assert_relative_eq!(vout[i], vin[i] / 2., epsilon = 1e-6);
+    }
+    assert_eq!(vin_harmonics.len(), vout_harmonics.len());
+    for i in 0..vout_harmonics.len() {
+        assert_relative_eq!(
+            vout_harmonics[i].re,
+            vin_harmonics[i].re / 2.,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            vout_harmonics[i].im,
+            vin_harmonics[i].im / 2.,
+            epsilon = 1e-6
+        );
+    }
+
+    // A linear time-invariant circuit does not convert between sidebands.
+    assert_eq!(pac_vout.len(), 3);
+    for i in 0..pac_freq.len() {
+        assert_relative_eq!(pac_vout[&0][i].re, 0.5, max_relative = 1e-3);
+        assert_relative_eq!(pac_vout[&0][i].im, 0., epsilon = 1e-6);
+        assert_relative_eq!(pac_vout[&-1][i].norm(), 0., epsilon = 1e-6);
+        assert_relative_eq!(pac_vout[&1][i].norm(), 0., epsilon = 1e-6);
+    }
+
+    let four_kt: f64 = 4. * 1.380649e-23 * 300.15;
+    let expected = (four_kt * 500.).sqrt();
+    assert_eq!(pnoise_freq.len(), pnoise_output.len());
+    for i in 0..pnoise_freq.len() {
+        assert_relative_eq!(pnoise_output[i], expected, max_relative = 1e-3);
+    }
+}
+
+#[test]
+fn spectre_can_run_stb_analysis() {
+    #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+    #[substrate(io = "TestbenchIo")]
+    struct SinglePoleLoopTb;
+
+    #[derive(NestedData)]
+    struct SinglePoleLoopTbData {
+        probe: Instance<Iprobe>,
+    }
+
+    impl ExportsNestedData for SinglePoleLoopTb {
+        type NestedData = SinglePoleLoopTbData;
+    }
+
+    impl Schematic<Spectre> for SinglePoleLoopTb {
+        fn schematic(
+            &self,
+            io: &<<Self as Block>::Io as HardwareType>::Bundle,
+            cell: &mut CellBuilder<Spectre>,
+        ) -> substrate::error::Result<Self::NestedData> {
+            let out = cell.signal("out", Signal);
+            let filt = cell.signal("filt", Signal);
+            let fb = cell.signal("fb", Signal);
+
+            // An inverting amplifier with a gain of 1000, followed by an RC low-pass filter.
+            let amp = cell.instantiate(RawInstance::with_params(
+                arcstr::literal!("vcvs"),
+                vec![
+                    arcstr::literal!("p"),
+                    arcstr::literal!("n"),
+                    arcstr::literal!("ps"),
+                    arcstr::literal!("ns"),
+                ],
+                HashMap::from_iter([(arcstr::literal!("gain"), dec!(1000).into())]),
+            ));
+            cell.connect(amp.io()[0], out);
+            cell.connect(amp.io()[1], io.vss);
+            cell.connect(amp.io()[2], io.vss);
+            cell.connect(amp.io()[3], fb);
+
+            let r = cell.instantiate(Resistor::new(dec!(1000)));
+            cell.connect(r.io().p, out);
+            cell.connect(r.io().n, filt);
+            let c = cell.instantiate(Capacitor::new(dec!(1e-6)));
+            cell.connect(c.io().p, filt);
+            cell.connect(c.io().n, io.vss);
+
+            let probe = cell.instantiate(Iprobe);
+            cell.connect(probe.io().p, filt);
+            cell.connect(probe.io().n, fb);
+
+            Ok(SinglePoleLoopTbData { probe })
+        }
+    }
+
+    #[derive(FromSaved, Serialize, Deserialize)]
+    struct SinglePoleLoopTbOutput {
+        freq: stb::Freq,
+        loop_gain: stb::LoopGain,
+    }
+
+    impl SaveTb<Spectre, Stb, SinglePoleLoopTbOutput> for SinglePoleLoopTb {
+        fn save_tb(
+            ctx: &SimulationContext<Spectre>,
+            _to_save: &Cell<Self>,
+            opts: &mut <Spectre as Simulator>::Options,
+        ) -> <SinglePoleLoopTbOutput as FromSaved<Spectre, Stb>>::SavedKey {
+            SinglePoleLoopTbOutputSavedKey {
+                freq: stb::Freq::save(ctx, (), opts),
+                loop_gain: stb::LoopGain::save(ctx, (), opts),
+            }
+        }
+    }
+
+    impl Testbench<Spectre> for SinglePoleLoopTb {
+        type Output = SinglePoleLoopTbOutput;
+
+        fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+            let probe = sim
+                .convert_instance_path(sim.tb.data().probe.path())
+                .unwrap();
+            sim.simulate(
+                Options::default(),
+                Stb {
+                    start: dec!(1),
+                    stop: dec!(1e9),
+                    sweep: Sweep::Decade(100),
+                    probe: Device::Scir(probe),
+                    errpreset: None,
+                },
+            )
+            .expect("failed to run simulation")
+        }
+    }
+
+    let test_name = "spectre_can_run_stb_analysis";
+    let sim_dir = get_path(test_name, "sim/");
+    let ctx = sky130_commercial_ctx();
+    let SinglePoleLoopTbOutput { freq, loop_gain } =
+        ctx.simulate(SinglePoleLoopTb, sim_dir).unwrap();
+
+    let a0 = 1000.;
+    let pole = 1. / (2. * std::f64::consts::PI * 1e-3);
+    assert_eq!(freq.len(), loop_gain.len());
+    assert_relative_eq!(loop_gain[0].re, a0, max_relative = 1e-3);
+
+    let ugf = pole * (a0 * a0 - 1f64).sqrt();
+    assert_relative_eq!(
+        loop_gain.unity_gain_freq().unwrap(),
+        ugf,
+        max_relative = 1e-2
+    );
+    assert_relative_eq!(
+        loop_gain.phase_margin().unwrap(),
+        180. - (ugf / pole).atan().to_degrees(),
+        epsilon = 0.5
+    );
+    assert_eq!(loop_gain.gain_margin(), None);
+}
+
+#[test]
+fn spectre_can_run_sp_analysis() {
+    /// A two-port testbench around either a series resistor or an n-port black box.
+    #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Block)]
+    #[substrate(io = "TestbenchIo")]
+    enum TwoPortTb {
+        SeriesResistor,
+        Nport(std::path::PathBuf),
+    }
+
+    #[derive(NestedData)]
+    struct TwoPortTbData {
+        p1: Instance<Port>,
+        p2: Instance<Port>,
+    }
+
+    impl ExportsNestedData for TwoPortTb {
+        type NestedData = TwoPortTbData;
+    }
+
+    impl Schematic<Spectre> for TwoPortTb {
+        fn schematic(
+            &self,
+            io: &<<Self as Block>::Io as HardwareType>::Bundle,
+            cell: &mut CellBuilder<Spectre>,
+        ) -> substrate::error::Result<Self::NestedData> {
+            let a = cell.signal("a", Signal);
+            let b = cell.signal("b", Signal);
+
+            match self {
+                TwoPortTb::SeriesResistor => {
+                    let r = cell.instantiate(Resistor::new(dec!(50)));
+                    cell.connect(r.io().p, a);
+                    cell.connect(r.io().n, b);
+                }
+                TwoPortTb::Nport(path) => {
+                    let nport = cell.instantiate(Nport::new(2, path));
+                    cell.connect(nport.io().ports[0].p, a);
+                    cell.connect(nport.io().ports[0].n, io.vss);
+                    cell.connect(nport.io().ports[1].p, b);
+                    cell.connect(nport.io().ports[1].n, io.vss);
+                }
+            }
+
+            let p1 = cell.instantiate(Port::default());
+            cell.connect(p1.io().p, a);
+            cell.connect(p1.io().n, io.vss);
+            let p2 = cell.instantiate(Port::default());
+            cell.connect(p2.io().p, b);
+            cell.connect(p2.io().n, io.vss);
+
+            Ok(TwoPortTbData { p1, p2 })
+        }
+    }
+
+    #[derive(FromSaved, Serialize, Deserialize)]
+    struct TwoPortTbOutput {
+        freq: sp::Freq,
+        params: sp::SParams,
+    }
+
+    impl SaveTb<Spectre, Sp, TwoPortTbOutput> for TwoPortTb {
+        fn save_tb(
+            ctx: &SimulationContext<Spectre>,
+            _to_save: &Cell<Self>,
+            opts: &mut <Spectre as Simulator>::Options,
+        ) -> <TwoPortTbOutput as FromSaved<Spectre, Sp>>::SavedKey {
+            TwoPortTbOutputSavedKey {
+                freq: sp::Freq::save(ctx, (), opts),
+                params: sp::SParams::save(ctx, (), opts),
+            }
+        }
+    }
+
+    impl Testbench<Spectre> for TwoPortTb {
+        type Output = TwoPortTbOutput;
+
+        fn run(&self, sim: SimController<Spectre, Self>) -> Self::Output {
+            let ports = [sim.tb.data().p1.path(), sim.tb.data().p2.path()]
+                .into_iter()
+                .map(|path| Device::Scir(sim.convert_instance_path(path).unwrap()))
+                .collect();
+            sim.simulate(
+                Options::default(),
+                Sp {
+                    start: dec!(1e6),
+                    stop: dec!(1e9),
+                    sweep: Sweep::Decade(10),
+                    ports,
+                },
+            )
+            .expect("failed to run simulation")
+        }
+    }
+
+    let test_name = "spectre_can_run_sp_analysis";
+    let ctx = sky130_commercial_ctx();
+    let TwoPortTbOutput { freq, params } = ctx
+        .simulate(
+            TwoPortTb::SeriesResistor,
+            get_path(test_name, "sim_resistor/"),
+        )
+        .unwrap();
+
+    // A series resistor R between two ports with reference impedance Z0
+    // has S11 = R / (R + 2 Z0) and S21 = 2 Z0 / (R + 2 Z0).
+    assert_eq!(params.num_ports, 2);
+    assert_eq!(freq.len(), params.freq.len());
+    for idx in 0..freq.len() {
+        for (i, j, expected) in [
+            (0, 0, 1. / 3.),
+            (1, 0, 2. / 3.),
+            (0, 1, 2. / 3.),
+            (1, 1, 1. / 3.),
+        ] {
+            assert_relative_eq!(params.get(idx, i, j).re, expected, max_relative = 1e-3);
+            assert_relative_eq!(params.get(idx, i, j).im, 0., epsilon = 1e-6);
+        }
+    }
+
+    // Feeding the results back through an n-port reproduces the same S-parameters.
+    let touchstone = get_path(test_name, "resistor.s2p");
+    spectre::touchstone::write(&touchstone, &params).unwrap();
+    let TwoPortTbOutput {
+        params: nport_params,
+        ..
+    } = ctx
+        .simulate(
+            TwoPortTb::Nport(touchstone),
+            get_path(test_name, "sim_nport/"),
+        )
+        .unwrap();
+    assert_eq!(nport_params.num_ports, 2);
+    for idx in 0..freq.len() {
+        for (expected, actual) in params.matrix(idx).iter().zip(nport_params.matrix(idx)) {
+            assert_relative_eq!(expected.re, actual.re, epsilon = 1e-4);
+            assert_relative_eq!(expected.im, actual.im, epsilon = 1e-4);
+        }
+    }
+}
@@ -30,6 +30,17 @@ pub struct Pulse {
     pub num_pulses: Option<Decimal>,
 }
 
+/// Data associated with an AC [`Vsource`].
+#[derive(Serialize, Deserialize, Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct AcSource {
+    /// The DC value.
+    pub dc: Decimal,
+    /// The small-signal magnitude.
+    pub mag: Decimal,
+    /// The small-signal phase, **in degrees**.
+    pub phase: Decimal,
+}
+
 /// A voltage source.
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq, Block)]
 #[substrate(io = "TwoTerminalIo")]
@@ -38,6 +49,8 @@ pub enum Vsource {
     Dc(Decimal),
     /// A pulse voltage source.
     Pulse(Pulse),
+    /// A DC voltage source with an AC small-signal component.
+    Ac(AcSource),
 }
 
 impl Vsource {
@@ -50,6 +63,11 @@ impl Vsource {
     pub fn pulse(value: Pulse) -> Self {
         Self::Pulse(value)
     }
+
+    /// Creates a new AC voltage source.
+    pub fn ac(value: AcSource) -> Self {
+        Self::Ac(value)
+    }
 }
 
 impl ExportsNestedData for Vsource {
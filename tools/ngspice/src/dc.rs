@@ -0,0 +1,552 @@
+//! ngspice DC sweep analysis options and data structures.
+
+use crate::error::{Error, Result};
+use crate::{Ngspice, ProbeStmt, SaveStmt};
+use arcstr::ArcStr;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::io::schematic::{NestedNode, NestedTerminal, NodePath, TerminalPath};
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::schematic::primitives::Resistor;
+use substrate::schematic::NestedInstance;
+use substrate::simulation::data::{dc, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A quantity swept by a [`DcSweep`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SweepVar {
+    /// The DC value of the independent voltage or current source with the given netlist name.
+    RawSource(ArcStr),
+    /// The DC value of the independent voltage or current source at the given SCIR instance path.
+    ///
+    /// SCIR instance paths can be obtained using
+    /// [`SimController::convert_instance_path`](substrate::simulation::SimController::convert_instance_path).
+    Source(scir::InstancePath),
+    /// The circuit temperature (degrees C).
+    Temp,
+    /// The netlist parameter with the given name.
+    Param(ArcStr),
+}
+
+/// The points at which a swept variable is evaluated.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SweepPoints {
+    /// Linearly spaced points from `start` to `stop` with increment `step`.
+    Linear {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The increment between consecutive points.
+        step: Decimal,
+    },
+    /// `points` logarithmically spaced points from `start` to `stop`.
+    Logarithmic {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The total number of points.
+        points: usize,
+    },
+    /// Logarithmically spaced points from `start` to `stop` with `points` points **per decade**.
+    Decade {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The number of points per decade.
+        points: usize,
+    },
+    /// An explicit list of points.
+    List(Vec<Decimal>),
+}
+
+impl SweepPoints {
+    /// Returns the values of the swept variable, in sweep order.
+    ///
+    /// Returns [`Error::InvalidSweep`] if the points are not well defined,
+    /// for example if a linear sweep has a zero step or never reaches `stop`,
+    /// or if a logarithmic sweep does not have positive bounds.
+    pub fn values(&self) -> Result<Vec<Decimal>> {
+        let values = match self {
+            SweepPoints::Linear { start, stop, step } => {
+                if step.is_zero() || (stop - start).is_sign_negative() != step.is_sign_negative() {
+                    return Err(Error::InvalidSweep);
+                }
+                let n = ((stop - start) / step)
+                    .floor()
+                    .to_usize()
+                    .ok_or(Error::InvalidSweep)?;
+                (0..=n).map(|i| start + Decimal::from(i) * step).collect()
+            }
+            SweepPoints::Logarithmic {
+                start,
+                stop,
+                points,
+            } => {
+                let (start, stop) = log_bounds(*start, *stop)?;
+                match points {
+                    0 => return Err(Error::InvalidSweep),
+                    1 => vec![start],
+                    n => (0..*n)
+                        .map(|i| start * (stop / start).powf(i as f64 / (n - 1) as f64))
+                        .collect(),
+                }
+                .into_iter()
+                .map(log_point)
+                .collect::<Result<_>>()?
+            }
+            SweepPoints::Decade {
+                start,
+                stop,
+                points,
+            } => {
+                let (start, stop) = log_bounds(*start, *stop)?;
+                if *points == 0 {
+                    return Err(Error::InvalidSweep);
+                }
+                // Allow for rounding error so that a sweep over whole decades includes `stop`.
+                let n = ((stop / start).log10() * *points as f64 + 1e-9).floor() as usize;
+                (0..=n)
+                    .map(|i| log_point(start * 10f64.powf(i as f64 / *points as f64)))
+                    .collect::<Result<_>>()?
+            }
+            SweepPoints::List(values) => values.clone(),
+        };
+        if values.is_empty() {
+            return Err(Error::InvalidSweep);
+        }
+        Ok(values)
+    }
+}
+
+fn log_bounds(start: Decimal, stop: Decimal) -> Result<(f64, f64)> {
+    if start <= Decimal::ZERO || stop <= Decimal::ZERO {
+        return Err(Error::InvalidSweep);
+    }
+    Ok((start.to_f64().unwrap(), stop.to_f64().unwrap()))
+}
+
+fn log_point(value: f64) -> Result<Decimal> {
+    Decimal::from_f64(value)
+        .map(|value| value.round_sf(12).unwrap_or(value).normalize())
+        .ok_or(Error::InvalidSweep)
+}
+
+/// A sweep of a single variable.
+///
+/// ngspice natively supports linear DC sweeps of sources and the temperature.
+/// Sweeps of netlist parameters and logarithmic or list sweeps are run
+/// as an operating point analysis per sweep point, with each point
+/// simulated by a separate ngspice run.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Sweep {
+    /// The swept variable.
+    pub var: SweepVar,
+    /// The sweep points.
+    pub points: SweepPoints,
+}
+
+impl Sweep {
+    /// Returns `true` if ngspice can run this sweep as part of a `.dc` statement.
+    pub(crate) fn is_native(&self) -> bool {
+        !matches!(self.var, SweepVar::Param(_)) && matches!(self.points, SweepPoints::Linear { .. })
+    }
+}
+
+/// A DC sweep analysis.
+///
+/// The values of the inner and outer swept variables are returned
+/// with one entry per simulated point, with the inner sweep varying fastest.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DcSweep {
+    /// The inner sweep.
+    pub sweep: Sweep,
+    /// An optional outer sweep.
+    ///
+    /// The inner sweep is run once for every point of the outer sweep.
+    pub nested: Option<Sweep>,
+}
+
+impl DcSweep {
+    /// Returns `true` if ngspice can run this analysis as a single `.dc` statement.
+    pub(crate) fn is_native(&self) -> bool {
+        self.sweep.is_native() && self.nested.as_ref().map_or(true, Sweep::is_native)
+    }
+}
+
+/// The result of a DC sweep analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The values of the inner swept variable.
+    pub sweep: Arc<Vec<f64>>,
+    /// The values of the outer swept variable, if a nested sweep was run.
+    pub nested: Option<Arc<Vec<f64>>>,
+    /// A map from signal name to values.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Ngspice, DcSweep> for Output {
+    type SavedKey = ();
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Ngspice, DcSweep, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Ngspice, DcSweep> for dc::Sweep {
+    type SavedKey = ();
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        dc::Sweep(output.sweep.clone())
+    }
+}
+
+impl Save<Ngspice, DcSweep, ()> for dc::Sweep {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Ngspice, DcSweep> for dc::NestedSweep {
+    type SavedKey = ();
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        dc::NestedSweep(
+            output
+                .nested
+                .clone()
+                .expect("DC sweep analysis did not have a nested sweep"),
+        )
+    }
+}
+
+impl Save<Ngspice, DcSweep, ()> for dc::NestedSweep {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved DC sweep voltage.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltageSavedKey(pub(crate) u64);
+
+impl FromSaved<Ngspice, DcSweep> for dc::Voltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<DcSweep as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        dc::Voltage(
+            output
+                .raw_values
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SaveStmt})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_voltage(to_save)
+    }
+}
+
+impl Save<Ngspice, DcSweep, &SliceOnePath> for dc::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: &SliceOnePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_voltage(SaveStmt::ScirVoltage(to_save.clone()))
+    }
+}
+
+impl Save<Ngspice, DcSweep, &ConvertedNodePath> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Ngspice, DcSweep, &NodePath> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &NodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_node_path(to_save).unwrap(), opts)
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; NodePath})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedNode; &NestedNode; NestedTerminal; &NestedTerminal})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+#[impl_dispatch({TerminalPath; &TerminalPath})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.as_ref(), opts)
+    }
+}
+
+/// An identifier for a saved DC sweep current.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSavedKey(pub(crate) Vec<u64>);
+
+impl FromSaved<Ngspice, DcSweep> for dc::Current {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<DcSweep as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let currents: Vec<Arc<Vec<f64>>> = key
+            .0
+            .iter()
+            .map(|key| {
+                output
+                    .raw_values
+                    .get(output.saved_values.get(key).unwrap())
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        let mut total_current = vec![0.; output.sweep.len()];
+        for dc_current in currents {
+            for (i, current) in dc_current.iter().enumerate() {
+                total_current[i] += *current;
+            }
+        }
+        dc::Current(Arc::new(total_current))
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SaveStmt})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Current {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_current(to_save)
+    }
+}
+
+#[impl_dispatch({
+    &NestedInstance<Resistor>;
+    NestedInstance<Resistor>
+})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_current(SaveStmt::ResistorCurrent(
+            ctx.lib.convert_instance_path(to_save.path()).unwrap(),
+        ))
+    }
+}
+
+impl Save<Ngspice, DcSweep, &SliceOnePath> for dc::Current {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: &SliceOnePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.probe_dc_current(ProbeStmt::ScirCurrent(to_save.clone()))
+    }
+}
+
+impl Save<Ngspice, DcSweep, &ConvertedNodePath> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Ngspice, DcSweep, &TerminalPath> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &TerminalPath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        CurrentSavedKey(
+            ctx.lib
+                .convert_terminal_path(to_save)
+                .unwrap()
+                .into_iter()
+                .flat_map(|path| Self::save(ctx, path, opts).0)
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; TerminalPath})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedTerminal; &NestedTerminal})]
+impl<T> Save<Ngspice, DcSweep, T> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for DcSweep {
+    type Output = Output;
+}
+
+impl SupportedBy<Ngspice> for DcSweep {
+    fn into_input(self, inputs: &mut Vec<<Ngspice as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Ngspice as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn sweep_points_values() {
+        let linear = SweepPoints::Linear {
+            start: dec!(1.8),
+            stop: dec!(0),
+            step: dec!(-0.6),
+        };
+        assert_eq!(
+            linear.values().unwrap(),
+            vec![dec!(1.8), dec!(1.2), dec!(0.6), dec!(0)]
+        );
+
+        let log = SweepPoints::Logarithmic {
+            start: dec!(1),
+            stop: dec!(100),
+            points: 3,
+        };
+        assert_eq!(log.values().unwrap(), vec![dec!(1), dec!(10), dec!(100)]);
+
+        let decade = SweepPoints::Decade {
+            start: dec!(0.1),
+            stop: dec!(10),
+            points: 2,
+        };
+        let values = decade.values().unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[0], dec!(0.1));
+        assert_eq!(values[2], dec!(1));
+        assert_eq!(values[4], dec!(10));
+
+        let list = SweepPoints::List(vec![dec!(3), dec!(1)]);
+        assert_eq!(list.values().unwrap(), vec![dec!(3), dec!(1)]);
+    }
+
+    #[test]
+    fn invalid_sweep_points() {
+        for points in [
+            SweepPoints::Linear {
+                start: dec!(0),
+                stop: dec!(1),
+                step: dec!(0),
+            },
+            SweepPoints::Linear {
+                start: dec!(0),
+                stop: dec!(1),
+                step: dec!(-0.1),
+            },
+            SweepPoints::Decade {
+                start: dec!(0),
+                stop: dec!(1),
+                points: 10,
+            },
+            SweepPoints::List(Vec::new()),
+        ] {
+            assert!(matches!(points.values(), Err(Error::InvalidSweep)));
+        }
+    }
+}
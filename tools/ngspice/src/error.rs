@@ -28,4 +28,16 @@ pub enum Error {
     /// Error caching results.
     #[error("error generating ngspice results")]
     Caching(#[from] Arc<cache::error::Error>),
+    /// A Monte Carlo iteration's seed does not fit in the C `int` read by ngspice.
+    #[error("Monte Carlo seeds must not exceed {}", i32::MAX)]
+    InvalidSeed,
+    /// Error loading the ngspice shared library.
+    #[error("error loading ngspice shared library")]
+    SharedLibrary(#[from] libloading::Error),
+    /// A DC sweep has no points, or its points are not well defined.
+    #[error("invalid DC sweep points")]
+    InvalidSweep,
+    /// The ngspice shared library exited after an unrecoverable error.
+    #[error("ngspice exited; the shared library must be reloaded")]
+    NgspiceExited,
 }
@@ -1,22 +1,42 @@
 //! ngspice plugin for Substrate.
+//!
+//! Simulations run the `ngspice` binary in a separate process by default.
+//! To run simulations in-process using the ngspice shared library instead,
+//! install a simulator created with [`Ngspice::new`] and [`Backend::Shared`].
+//!
+//! [`Ngspice`] has a private field holding its [`Backend`], so it can no longer be
+//! constructed with a struct expression (`Ngspice {}`). Use [`Ngspice::default`],
+//! which uses [`Backend::Process`], or [`Ngspice::new`].
 #![warn(missing_docs)]
 
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 #[cfg(any(unix, target_os = "redox"))]
 use std::os::unix::prelude::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::ac::{Ac, Sweep};
 use crate::blocks::Vsource;
+use crate::dc::{DcSweep, SweepPoints, SweepVar};
+use crate::montecarlo::MonteCarlo;
+use crate::noise::Noise;
+use crate::op::Op;
+use crate::shared::{Plot, SharedNgspice};
+use crate::sweep::SweepVar as ParamSweepVar;
 use crate::tran::Tran;
 use arcstr::ArcStr;
 use cache::error::TryInnerError;
 use cache::CacheableWithState;
 use error::*;
+use num::complex::Complex64;
 use nutlex::parser::Data;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use scir::schema::{FromSchema, NoSchema, NoSchemaError};
-use scir::{ChildId, Library, NetlistLibConversion, SignalInfo, SignalPathTail, SliceOnePath};
+use scir::{
+    ChildId, Library, NetlistLibConversion, ParamValue, SignalInfo, SignalPathTail, SliceOnePath,
+};
 use serde::{Deserialize, Serialize};
 use spice::netlist::{
     HasSpiceLikeNetlist, Include, NetlistKind, NetlistOptions, NetlisterInstance, RenameGround,
@@ -26,15 +46,22 @@ use substrate::block::Block;
 use substrate::context::Installation;
 use substrate::execute::Executor;
 use substrate::io::schematic::HardwareType;
-use substrate::schematic::primitives::{RawInstance, Resistor};
+use substrate::schematic::primitives::{Capacitor, RawInstance, Resistor};
 use substrate::schematic::schema::Schema;
 use substrate::schematic::{CellBuilder, PrimitiveBinding, Schematic};
-use substrate::simulation::{SimulationContext, Simulator};
+use substrate::simulation::{SimulationContext, Simulator, SupportedBy};
 use templates::{write_run_script, RunScriptContext};
 use unicase::UniCase;
 
+pub mod ac;
 pub mod blocks;
+pub mod dc;
 pub mod error;
+pub mod montecarlo;
+pub mod noise;
+pub mod op;
+pub mod shared;
+pub mod sweep;
 pub(crate) mod templates;
 pub mod tran;
 
@@ -210,8 +237,31 @@ impl From<ProbeStmt> for SavedData {
 }
 
 /// ngspice simulator global configuration.
+///
+/// The default configuration runs simulations using [`Backend::Process`].
 #[derive(Debug, Clone, Default)]
-pub struct Ngspice {}
+pub struct Ngspice {
+    backend: Backend,
+}
+
+/// The means by which ngspice simulations are run.
+#[derive(Debug, Clone, Default)]
+pub enum Backend {
+    /// Runs the `ngspice` binary in a separate process,
+    /// reading results from the rawfile it writes.
+    #[default]
+    Process,
+    /// Runs ngspice in-process using its shared library,
+    /// reading results directly from memory.
+    Shared(Arc<SharedNgspice>),
+}
+
+impl Ngspice {
+    /// Creates a new ngspice simulator that runs simulations using the given backend.
+    pub fn new(backend: Backend) -> Self {
+        Self { backend }
+    }
+}
 
 /// ngspice per-simulation options.
 ///
@@ -260,6 +310,60 @@ impl Options {
     pub fn probe_tran_current(&mut self, save: impl Into<ProbeStmt>) -> tran::CurrentSavedKey {
         tran::CurrentSavedKey(vec![self.save_inner(save.into())])
     }
+
+    /// Marks an AC voltage to be saved in all AC analyses.
+    pub fn save_ac_voltage(&mut self, save: impl Into<SaveStmt>) -> ac::VoltageSavedKey {
+        ac::VoltageSavedKey(self.save_inner(save.into()))
+    }
+
+    /// Marks an AC current to be saved in all AC analyses.
+    pub fn save_ac_current(&mut self, save: impl Into<SaveStmt>) -> ac::CurrentSavedKey {
+        ac::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
+
+    /// Marks an AC terminal current to be probed in all AC analyses.
+    ///
+    /// Unlike [`Options::save_ac_current`], this emits a `.probe` statement, which makes ngspice
+    /// insert zero-volt sources at the probed device terminals. This allows measuring terminal
+    /// currents of devices that do not otherwise report them.
+    pub fn probe_ac_current(&mut self, save: impl Into<ProbeStmt>) -> ac::CurrentSavedKey {
+        ac::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
+
+    /// Marks a DC sweep voltage to be saved in all DC sweep analyses.
+    pub fn save_dc_voltage(&mut self, save: impl Into<SaveStmt>) -> dc::VoltageSavedKey {
+        dc::VoltageSavedKey(self.save_inner(save.into()))
+    }
+
+    /// Marks a DC sweep current to be saved in all DC sweep analyses.
+    pub fn save_dc_current(&mut self, save: impl Into<SaveStmt>) -> dc::CurrentSavedKey {
+        dc::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
+
+    /// Marks a DC sweep terminal current to be probed in all DC sweep analyses.
+    ///
+    /// See [`Options::probe_ac_current`] for how probing differs from saving.
+    pub fn probe_dc_current(&mut self, save: impl Into<ProbeStmt>) -> dc::CurrentSavedKey {
+        dc::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
+
+    /// Marks a DC operating point voltage to be saved in all DC operating point analyses.
+    pub fn save_op_voltage(&mut self, save: impl Into<SaveStmt>) -> op::VoltageSavedKey {
+        op::VoltageSavedKey(self.save_inner(save.into()))
+    }
+
+    /// Marks a DC operating point current to be saved in all DC operating point analyses.
+    pub fn save_op_current(&mut self, save: impl Into<SaveStmt>) -> op::CurrentSavedKey {
+        op::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
+
+    /// Marks a DC operating point terminal current to be probed in all DC operating point
+    /// analyses.
+    ///
+    /// See [`Options::probe_ac_current`] for how probing differs from saving.
+    pub fn probe_op_current(&mut self, save: impl Into<ProbeStmt>) -> op::CurrentSavedKey {
+        op::CurrentSavedKey(vec![self.save_inner(save.into())])
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
@@ -267,6 +371,118 @@ struct CachedSim {
     simulation_netlist: Vec<u8>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum CachedData {
+    Tran(HashMap<String, Vec<f64>>),
+    Ac(HashMap<String, Vec<Complex64>>),
+    Op(HashMap<String, f64>),
+    Dc {
+        sweep: Vec<f64>,
+        nested: Option<Vec<f64>>,
+        signals: HashMap<String, Vec<f64>>,
+    },
+    Noise {
+        freq: Vec<f64>,
+        signals: HashMap<String, Vec<f64>>,
+    },
+    // The outer vec is indexed by sweep point.
+    // The inner vec length equals the length of the inner analysis.
+    Sweep(Vec<Vec<CachedData>>),
+    // The outer vec has length `numruns`.
+    // The inner vec length equals the length of the inner analysis.
+    MonteCarlo(Vec<Vec<CachedData>>),
+}
+
+impl CachedData {
+    fn into_output(
+        self,
+        lib: &Library<Ngspice>,
+        conv: &NetlistLibConversion,
+        saves: &HashMap<SavedData, u64>,
+    ) -> Output {
+        let saved_values = saves
+            .iter()
+            .map(|(k, v)| (*v, k.to_data_string(lib, conv)))
+            .collect();
+        match self {
+            CachedData::Tran(mut raw_values) => tran::Output {
+                time: Arc::new(raw_values.remove("time").unwrap()),
+                raw_values: raw_values
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values,
+            }
+            .into(),
+            CachedData::Ac(mut raw_values) => ac::Output {
+                // The frequency vector is stored as a complex signal with zero imaginary part.
+                freq: Arc::new(
+                    raw_values
+                        .remove("frequency")
+                        .unwrap()
+                        .into_iter()
+                        .map(|f| f.re)
+                        .collect(),
+                ),
+                raw_values: raw_values
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values,
+            }
+            .into(),
+            CachedData::Op(raw_values) => op::Output {
+                raw_values: raw_values
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), v))
+                    .collect(),
+                saved_values,
+            }
+            .into(),
+            CachedData::Dc {
+                sweep,
+                nested,
+                signals,
+            } => dc::Output {
+                sweep: Arc::new(sweep),
+                nested: nested.map(Arc::new),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values,
+            }
+            .into(),
+            CachedData::Noise { freq, signals } => noise::Output {
+                freq: Arc::new(freq),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Sweep(data) => Output::Sweep(sweep::Output(
+                data.into_iter()
+                    .map(|data| {
+                        data.into_iter()
+                            .map(|d| d.into_output(lib, conv, saves))
+                            .collect()
+                    })
+                    .collect(),
+            )),
+            CachedData::MonteCarlo(data) => Output::MonteCarlo(montecarlo::Output(
+                data.into_iter()
+                    .map(|data| {
+                        data.into_iter()
+                            .map(|d| d.into_output(lib, conv, saves))
+                            .collect()
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
 struct CachedSimState {
     input: Vec<Input>,
     netlist: PathBuf,
@@ -276,10 +492,11 @@ struct CachedSimState {
     run_script: PathBuf,
     work_dir: PathBuf,
     executor: Arc<dyn Executor>,
+    backend: Backend,
 }
 
 impl CacheableWithState<CachedSimState> for CachedSim {
-    type Output = Vec<HashMap<String, Vec<f64>>>;
+    type Output = Vec<CachedData>;
     type Error = Arc<Error>;
 
     fn generate_with_state(
@@ -296,53 +513,129 @@ impl CacheableWithState<CachedSimState> for CachedSim {
                 run_script,
                 work_dir,
                 executor,
+                backend,
             } = state;
-            write_run_script(
-                RunScriptContext {
-                    netlist: &netlist,
-                    raw_output_file: &output_file,
-                    log_path: &log,
-                    err_path: &err_log,
-                    bashrc: None,
-                    flags: "",
-                },
-                &run_script,
-            )?;
-
-            let mut perms = std::fs::metadata(&run_script)?.permissions();
-            #[cfg(any(unix, target_os = "redox"))]
-            perms.set_mode(0o744);
-            std::fs::set_permissions(&run_script, perms)?;
-
-            let mut command = std::process::Command::new("/bin/bash");
-            command.arg(&run_script).current_dir(&work_dir);
-            executor
-                .execute(command, Default::default())
-                .map_err(|_| Error::NgspiceError)?;
-
-            let contents = std::fs::read(&output_file)?;
-            let rawfile = nutlex::parse(
-                &contents,
-                nutlex::Options {
-                    endianness: nutlex::ByteOrder::LittleEndian,
-                },
-            )?;
+            // Declared here so that the parsed analyses can borrow from them.
+            let contents;
+            let plots;
+            let analyses = match backend {
+                Backend::Process => {
+                    write_run_script(
+                        RunScriptContext {
+                            netlist: &netlist,
+                            raw_output_file: &output_file,
+                            log_path: &log,
+                            err_path: &err_log,
+                            bashrc: None,
+                            flags: "",
+                        },
+                        &run_script,
+                    )?;
+
+                    let mut perms = std::fs::metadata(&run_script)?.permissions();
+                    #[cfg(any(unix, target_os = "redox"))]
+                    perms.set_mode(0o744);
+                    std::fs::set_permissions(&run_script, perms)?;
+
+                    let mut command = std::process::Command::new("/bin/bash");
+                    command.arg(&run_script).current_dir(&work_dir);
+                    executor
+                        .execute(command, Default::default())
+                        .map_err(|_| Error::NgspiceError)?;
+
+                    contents = std::fs::read(&output_file)?;
+                    nutlex::parse(
+                        &contents,
+                        nutlex::Options {
+                            endianness: nutlex::ByteOrder::LittleEndian,
+                        },
+                    )?
+                    .analyses
+                }
+                Backend::Shared(ngspice) => {
+                    plots = ngspice.simulate(&self.simulation_netlist)?;
+                    plots.iter().map(Plot::to_analysis).collect()
+                }
+            };
 
             let mut raw_outputs = Vec::with_capacity(input.len());
 
-            for (an, results) in input.iter().zip(rawfile.analyses.into_iter()) {
-                match an {
-                    Input::Tran(_) => match results.data {
-                        Data::Real(real) => raw_outputs.push(HashMap::from_iter(
+            let mut analyses = analyses.into_iter().peekable();
+            for an in input.iter() {
+                let results = analyses.next().ok_or(Error::NgspiceError)?;
+                match (an, results.data) {
+                    (Input::Tran(_), Data::Real(real)) => {
+                        raw_outputs.push(CachedData::Tran(HashMap::from_iter(
                             results
                                 .variables
                                 .into_iter()
                                 .map(|var| (var.name.to_string(), real[var.idx].clone())),
-                        )),
-                        _ => {
-                            return Err(Error::NgspiceError);
+                        )))
+                    }
+                    (Input::Op(_), Data::Real(real)) => {
+                        raw_outputs.push(CachedData::Op(HashMap::from_iter(
+                            results
+                                .variables
+                                .into_iter()
+                                .map(|var| (var.name.to_string(), real[var.idx][0])),
+                        )))
+                    }
+                    (Input::Dc(dc), Data::Real(real)) => {
+                        // The first vector of a DC sweep holds the values of the inner sweep variable.
+                        let sweep = real[0].clone();
+                        let nested = dc
+                            .nested
+                            .as_ref()
+                            .map(|outer| outer_sweep_values(&sweep, outer));
+                        raw_outputs.push(CachedData::Dc {
+                            sweep,
+                            nested,
+                            signals: HashMap::from_iter(
+                                results
+                                    .variables
+                                    .into_iter()
+                                    .skip(1)
+                                    .map(|var| (var.name.to_string(), real[var.idx].clone())),
+                            ),
+                        })
+                    }
+                    (Input::Noise(_), Data::Real(real)) => {
+                        // Noise analyses are followed by a plot of integrated noise,
+                        // which is not exposed.
+                        if analyses
+                            .peek()
+                            .is_some_and(|next| next.plotname.starts_with("Integrated Noise"))
+                        {
+                            analyses.next();
                         }
-                    },
+                        let mut signals: HashMap<String, Vec<f64>> = HashMap::from_iter(
+                            results
+                                .variables
+                                .into_iter()
+                                .map(|var| (var.name.to_string(), real[var.idx].clone())),
+                        );
+                        raw_outputs.push(CachedData::Noise {
+                            freq: signals.remove("frequency").ok_or(Error::NgspiceError)?,
+                            signals,
+                        })
+                    }
+                    (Input::Ac(_), Data::Complex(complex)) => raw_outputs.push(CachedData::Ac(
+                        HashMap::from_iter(results.variables.into_iter().map(|var| {
+                            let signal = &complex[var.idx];
+                            (
+                                var.name.to_string(),
+                                signal
+                                    .real
+                                    .iter()
+                                    .zip(signal.imag.iter())
+                                    .map(|(re, im)| Complex64::new(*re, *im))
+                                    .collect(),
+                            )
+                        })),
+                    )),
+                    _ => {
+                        return Err(Error::NgspiceError);
+                    }
                 }
             }
 
@@ -360,8 +653,6 @@ impl Ngspice {
         input: Vec<Input>,
     ) -> Result<Vec<Output>> {
         std::fs::create_dir_all(&ctx.work_dir)?;
-        let netlist = ctx.work_dir.join("netlist.spice");
-        let mut f = std::fs::File::create(&netlist)?;
         let mut w = Vec::new();
 
         let mut includes = options.includes.into_iter().collect::<Vec<_>>();
@@ -389,21 +680,184 @@ impl Ngspice {
             writeln!(w)?;
         }
 
-        writeln!(w)?;
-        for an in input.iter() {
-            an.netlist(&mut w)?;
+        let raw_outputs = self.run_analyses(ctx, &w, &conv, &ctx.work_dir, &[], &input)?;
+
+        let conv = Arc::new(conv);
+        let outputs = raw_outputs
+            .into_iter()
+            .map(|raw_values| raw_values.into_output(&ctx.lib.scir, &conv, &options.saves))
+            .collect();
+
+        Ok(outputs)
+    }
+
+    /// Runs the given analyses on the netlisted testbench in `work_dir`.
+    ///
+    /// Each point of a parametric sweep, each point of a DC sweep that ngspice
+    /// cannot run natively, and each Monte Carlo iteration is simulated
+    /// by a separate ngspice run in its own subdirectory of `work_dir`,
+    /// with the settings of the run given by `point`.
+    fn run_analyses(
+        &self,
+        ctx: &SimulationContext<Ngspice>,
+        netlist: &[u8],
+        conv: &NetlistLibConversion,
+        work_dir: &Path,
+        point: &[RunSetting],
+        input: &[Input],
+    ) -> Result<Vec<CachedData>> {
+        let analyses = input
+            .iter()
+            .filter(|an| match an {
+                Input::Dc(dc) => dc.is_native(),
+                Input::Sweep(_) | Input::MonteCarlo(_) => false,
+                _ => true,
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut outputs = if analyses.is_empty() {
+            Vec::new()
+        } else {
+            let mut w = netlist.to_vec();
             writeln!(w)?;
+            write_run_settings(&mut w, &ctx.lib.scir, conv, point)?;
+            for an in analyses.iter() {
+                an.netlist(&mut w, &ctx.lib.scir, conv)?;
+                writeln!(w)?;
+            }
+            self.run_netlist(ctx, w, work_dir, analyses)?
+        }
+        .into_iter();
+
+        let mut raw_outputs = Vec::with_capacity(input.len());
+        for (i, an) in input.iter().enumerate() {
+            match an {
+                Input::Sweep(sweep) => {
+                    let mut data = Vec::with_capacity(sweep.values.len());
+                    for (idx, value) in sweep.values.iter().enumerate() {
+                        let mut point = point.to_vec();
+                        point.push(RunSetting::Sweep(&sweep.var, *value));
+                        data.push(self.run_analyses(
+                            ctx,
+                            netlist,
+                            conv,
+                            &work_dir.join(format!("sweep{i}")).join(idx.to_string()),
+                            &point,
+                            &sweep.analysis,
+                        )?);
+                    }
+                    raw_outputs.push(CachedData::Sweep(data));
+                }
+                Input::Dc(dc) if !dc.is_native() => {
+                    // Every point is run as an operating point analysis,
+                    // with the inner sweep varying fastest.
+                    let inner_var = param_sweep_var(&dc.sweep.var);
+                    let inner = dc.sweep.points.values()?;
+                    let (outer_var, outer) = match &dc.nested {
+                        Some(nested) => (
+                            Some(param_sweep_var(&nested.var)),
+                            nested.points.values()?.into_iter().map(Some).collect(),
+                        ),
+                        None => (None, vec![None]),
+                    };
+                    let mut sweep = Vec::with_capacity(inner.len() * outer.len());
+                    let mut nested = Vec::with_capacity(inner.len() * outer.len());
+                    let mut signals: HashMap<String, Vec<f64>> = HashMap::new();
+                    for outer_value in outer {
+                        for inner_value in inner.iter() {
+                            let mut point = point.to_vec();
+                            if let (Some(var), Some(value)) = (&outer_var, outer_value) {
+                                point.push(RunSetting::Sweep(var, value));
+                                nested.push(value.to_f64().unwrap());
+                            }
+                            point.push(RunSetting::Sweep(&inner_var, *inner_value));
+                            sweep.push(inner_value.to_f64().unwrap());
+                            let data = self.run_analyses(
+                                ctx,
+                                netlist,
+                                conv,
+                                &work_dir
+                                    .join(format!("dc{i}"))
+                                    .join((sweep.len() - 1).to_string()),
+                                &point,
+                                &[Input::Op(Op)],
+                            )?;
+                            let Some(CachedData::Op(values)) = data.into_iter().next() else {
+                                return Err(Error::NgspiceError);
+                            };
+                            for (name, value) in values {
+                                signals.entry(name).or_default().push(value);
+                            }
+                        }
+                    }
+                    raw_outputs.push(CachedData::Dc {
+                        sweep,
+                        nested: dc.nested.as_ref().map(|_| nested),
+                        signals,
+                    });
+                }
+                Input::MonteCarlo(mc) => {
+                    let seed = mc.seed.unwrap_or(montecarlo::DEFAULT_SEED);
+                    let firstrun = mc.firstrun.unwrap_or_default();
+                    // ngspice reads the seed as a C `int`, so check every seed
+                    // before running any iteration.
+                    let seeds = (firstrun..firstrun + mc.numruns)
+                        .map(|iter| {
+                            seed.checked_add(iter as u64)
+                                .filter(|seed| *seed <= i32::MAX as u64)
+                                .ok_or(Error::InvalidSeed)
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let mut data = Vec::with_capacity(mc.numruns);
+                    for (iter, seed) in (firstrun..).zip(seeds) {
+                        let mut point = point.to_vec();
+                        point.push(RunSetting::Seed(seed));
+                        data.push(
+                            self.run_analyses(
+                                ctx,
+                                netlist,
+                                conv,
+                                &work_dir
+                                    .join(format!("montecarlo{i}"))
+                                    .join(iter.to_string()),
+                                &point,
+                                &mc.analysis,
+                            )?,
+                        );
+                    }
+                    raw_outputs.push(CachedData::MonteCarlo(data));
+                }
+                _ => raw_outputs.push(outputs.next().unwrap()),
+            }
+        }
+        Ok(raw_outputs)
+    }
+
+    /// Runs ngspice on the given netlist, which must not contain
+    /// parametric sweeps or Monte Carlo analyses.
+    fn run_netlist(
+        &self,
+        ctx: &SimulationContext<Ngspice>,
+        w: Vec<u8>,
+        work_dir: &Path,
+        input: Vec<Input>,
+    ) -> Result<Vec<CachedData>> {
+        std::fs::create_dir_all(work_dir)?;
+        let netlist = work_dir.join("netlist.spice");
+        // The shared library backend reads the netlist from memory.
+        if let Backend::Process = self.backend {
+            let mut f = std::fs::File::create(&netlist)?;
+            f.write_all(&w)?;
         }
-        f.write_all(&w)?;
 
-        let output_file = ctx.work_dir.join("data.raw");
-        let log = ctx.work_dir.join("ngspice.log");
-        let err_log = ctx.work_dir.join("ngspice.err");
-        let run_script = ctx.work_dir.join("simulate.sh");
-        let work_dir = ctx.work_dir.clone();
+        let output_file = work_dir.join("data.raw");
+        let log = work_dir.join("ngspice.log");
+        let err_log = work_dir.join("ngspice.err");
+        let run_script = work_dir.join("simulate.sh");
+        let work_dir = work_dir.to_path_buf();
         let executor = ctx.ctx.executor.clone();
 
-        let raw_outputs = ctx
+        Ok(ctx
             .ctx
             .cache
             .get_with_state(
@@ -420,6 +874,7 @@ impl Ngspice {
                     run_script,
                     work_dir,
                     executor,
+                    backend: self.backend.clone(),
                 },
             )
             .try_inner()
@@ -427,32 +882,67 @@ impl Ngspice {
                 TryInnerError::CacheError(e) => Error::Caching(e),
                 TryInnerError::GeneratorError(e) => Error::Generator(e.clone()),
             })?
-            .clone();
+            .clone())
+    }
+}
 
-        let conv = Arc::new(conv);
-        let outputs = raw_outputs
-            .into_iter()
-            .map(|mut raw_values| {
-                tran::Output {
-                    time: Arc::new(raw_values.remove("time").unwrap()),
-                    raw_values: raw_values
-                        .into_iter()
-                        .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
-                        .collect(),
-                    saved_values: options
-                        .saves
-                        .iter()
-                        .map(|(k, v)| (*v, k.to_data_string(&ctx.lib.scir, &conv)))
-                        .collect(),
-                }
-                .into()
-            })
-            .collect();
+/// A setting applied to a single ngspice run.
+#[derive(Debug, Clone, Copy)]
+enum RunSetting<'a> {
+    /// A swept variable and its value at the current sweep point.
+    Sweep(&'a ParamSweepVar, Decimal),
+    /// The random seed of the current Monte Carlo iteration.
+    Seed(u64),
+}
 
-        Ok(outputs)
+/// Converts a DC sweep variable to the equivalent parametric sweep variable.
+fn param_sweep_var(var: &SweepVar) -> ParamSweepVar {
+    match var {
+        SweepVar::RawSource(source) => ParamSweepVar::RawSource(source.clone()),
+        SweepVar::Source(path) => ParamSweepVar::Source(path.clone()),
+        SweepVar::Temp => ParamSweepVar::Temp,
+        SweepVar::Param(param) => ParamSweepVar::Param(param.clone()),
     }
 }
 
+/// Writes the statements that apply the given settings to an ngspice run.
+fn write_run_settings<W: Write>(
+    out: &mut W,
+    lib: &Library<Ngspice>,
+    conv: &NetlistLibConversion,
+    settings: &[RunSetting],
+) -> Result<()> {
+    let mut alters = Vec::new();
+    for setting in settings {
+        match setting {
+            // Appended after the included files, so overrides definitions in those files.
+            RunSetting::Sweep(ParamSweepVar::Param(param), value) => {
+                writeln!(out, ".param {param}={value}")?
+            }
+            RunSetting::Sweep(ParamSweepVar::Temp, value) => {
+                writeln!(out, ".options temp={value}")?
+            }
+            RunSetting::Sweep(ParamSweepVar::RawSource(source), value) => {
+                alters.push(format!("alter {source} dc = {value}"))
+            }
+            RunSetting::Sweep(ParamSweepVar::Source(path), value) => alters.push(format!(
+                "alter {} dc = {value}",
+                source_path(lib, conv, path)
+            )),
+            RunSetting::Seed(seed) => writeln!(out, ".options seed={seed}")?,
+        }
+    }
+    // In batch mode, the control section is executed before the analyses in the netlist.
+    if !alters.is_empty() {
+        writeln!(out, ".control")?;
+        for alter in alters {
+            writeln!(out, "{alter}")?;
+        }
+        writeln!(out, ".endc")?;
+    }
+    Ok(())
+}
+
 impl scir::schema::Schema for Ngspice {
     type Primitive = Primitive;
 }
@@ -515,6 +1005,22 @@ impl Schematic<Ngspice> for Resistor {
     }
 }
 
+impl Schematic<Ngspice> for Capacitor {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as HardwareType>::Bundle,
+        cell: &mut CellBuilder<Ngspice>,
+    ) -> substrate::error::Result<Self::NestedData> {
+        let mut prim = PrimitiveBinding::new(Primitive::Spice(spice::Primitive::Cap2 {
+            value: self.value(),
+        }));
+        prim.connect("1", io.p);
+        prim.connect("2", io.n);
+        cell.set_primitive(prim);
+        Ok(())
+    }
+}
+
 impl Installation for Ngspice {}
 
 impl Simulator for Ngspice {
@@ -543,6 +1049,21 @@ pub(crate) fn instance_path(
         .join(".")
 }
 
+/// Converts a SCIR instance path to the name by which ngspice refers to an independent source.
+pub(crate) fn source_path(
+    lib: &Library<Ngspice>,
+    conv: &NetlistLibConversion,
+    path: &scir::InstancePath,
+) -> String {
+    let name = instance_path(lib, conv, path);
+    // Sources within subcircuits are referred to as `<type>.<path>`.
+    if path.len() == 1 {
+        name
+    } else {
+        format!("{}.{}", &name[name.rfind('.').unwrap() + 1..][..1], name)
+    }
+}
+
 pub(crate) fn node_voltage_path(
     lib: &Library<Ngspice>,
     conv: &NetlistLibConversion,
@@ -627,6 +1148,18 @@ pub(crate) fn node_current_path(
 pub enum Input {
     /// Transient simulation input.
     Tran(Tran),
+    /// AC simulation input.
+    Ac(Ac),
+    /// DC operating point simulation input.
+    Op(Op),
+    /// DC sweep simulation input.
+    Dc(DcSweep),
+    /// Noise simulation input.
+    Noise(Noise),
+    /// A parametric sweep input.
+    Sweep(sweep::Sweep<Vec<Input>>),
+    /// A Monte Carlo input.
+    MonteCarlo(MonteCarlo<Vec<Input>>),
 }
 
 impl From<Tran> for Input {
@@ -635,11 +1168,59 @@ impl From<Tran> for Input {
     }
 }
 
+impl From<Ac> for Input {
+    fn from(value: Ac) -> Self {
+        Self::Ac(value)
+    }
+}
+
+impl From<Op> for Input {
+    fn from(value: Op) -> Self {
+        Self::Op(value)
+    }
+}
+
+impl From<DcSweep> for Input {
+    fn from(value: DcSweep) -> Self {
+        Self::Dc(value)
+    }
+}
+
+impl From<Noise> for Input {
+    fn from(value: Noise) -> Self {
+        Self::Noise(value)
+    }
+}
+
+impl<A: SupportedBy<Ngspice>> From<sweep::Sweep<A>> for Input {
+    fn from(value: sweep::Sweep<A>) -> Self {
+        Self::Sweep(value.into())
+    }
+}
+
+impl<A: SupportedBy<Ngspice>> From<MonteCarlo<A>> for Input {
+    fn from(value: MonteCarlo<A>) -> Self {
+        Self::MonteCarlo(value.into())
+    }
+}
+
 /// Outputs directly produced by ngspice.
 #[derive(Debug, Clone)]
 pub enum Output {
     /// Transient simulation output.
     Tran(tran::Output),
+    /// AC simulation output.
+    Ac(ac::Output),
+    /// DC operating point simulation output.
+    Op(op::Output),
+    /// DC sweep simulation output.
+    Dc(dc::Output),
+    /// Noise simulation output.
+    Noise(noise::Output),
+    /// Parametric sweep simulation output.
+    Sweep(sweep::Output<Vec<Output>>),
+    /// Monte Carlo simulation output.
+    MonteCarlo(montecarlo::Output<Vec<Output>>),
 }
 
 impl From<tran::Output> for Output {
@@ -648,19 +1229,127 @@ impl From<tran::Output> for Output {
     }
 }
 
+impl From<ac::Output> for Output {
+    fn from(value: ac::Output) -> Self {
+        Self::Ac(value)
+    }
+}
+
+impl From<op::Output> for Output {
+    fn from(value: op::Output) -> Self {
+        Self::Op(value)
+    }
+}
+
+impl From<dc::Output> for Output {
+    fn from(value: dc::Output) -> Self {
+        Self::Dc(value)
+    }
+}
+
+impl From<noise::Output> for Output {
+    fn from(value: noise::Output) -> Self {
+        Self::Noise(value)
+    }
+}
+
 impl TryFrom<Output> for tran::Output {
     type Error = Error;
     fn try_from(value: Output) -> Result<Self> {
         match value {
             Output::Tran(t) => Ok(t),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl TryFrom<Output> for ac::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Ac(ac) => Ok(ac),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl TryFrom<Output> for op::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Op(op) => Ok(op),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl TryFrom<Output> for dc::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Dc(dc) => Ok(dc),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl TryFrom<Output> for noise::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Noise(noise) => Ok(noise),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl From<sweep::Output<Vec<Output>>> for Output {
+    fn from(value: sweep::Output<Vec<Output>>) -> Self {
+        Self::Sweep(value)
+    }
+}
+
+impl TryFrom<Output> for sweep::Output<Vec<Output>> {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Sweep(sweep) => Ok(sweep),
+            _ => Err(Error::NgspiceError),
+        }
+    }
+}
+
+impl From<montecarlo::Output<Vec<Output>>> for Output {
+    fn from(value: montecarlo::Output<Vec<Output>>) -> Self {
+        Self::MonteCarlo(value)
+    }
+}
+
+impl TryFrom<Output> for montecarlo::Output<Vec<Output>> {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::MonteCarlo(mc) => Ok(mc),
+            _ => Err(Error::NgspiceError),
         }
     }
 }
 
 impl Input {
-    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Ngspice>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
         match self {
             Self::Tran(t) => t.netlist(out),
+            Self::Ac(ac) => ac.netlist(out),
+            Self::Op(op) => op.netlist(out),
+            Self::Dc(dc) => dc.netlist(out, lib, conv),
+            Self::Noise(noise) => noise.netlist(out, lib, conv),
+            // Each sweep point and Monte Carlo iteration is simulated by a separate ngspice run.
+            Self::Sweep(_) | Self::MonteCarlo(_) => unreachable!(),
         }
     }
 }
@@ -675,6 +1364,106 @@ impl Tran {
     }
 }
 
+impl Ac {
+    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (kind, pts) = match self.sweep {
+            Sweep::Linear(pts) => ("lin", pts),
+            Sweep::Decade(pts) => ("dec", pts),
+            Sweep::Octave(pts) => ("oct", pts),
+        };
+        write!(out, ".ac {} {} {} {}", kind, pts, self.start, self.stop)?;
+        Ok(())
+    }
+}
+
+impl Op {
+    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+        write!(out, ".op")?;
+        Ok(())
+    }
+}
+
+impl DcSweep {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Ngspice>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        write!(out, ".dc")?;
+        for sweep in std::iter::once(&self.sweep).chain(self.nested.as_ref()) {
+            let var = match &sweep.var {
+                SweepVar::RawSource(name) => name.to_string(),
+                SweepVar::Source(path) => source_path(lib, conv, path),
+                SweepVar::Temp => "TEMP".to_string(),
+                // Parameter sweeps are run as one operating point analysis per point.
+                SweepVar::Param(_) => unreachable!(),
+            };
+            let SweepPoints::Linear { start, stop, step } = &sweep.points else {
+                // Non-linear sweeps are run as one operating point analysis per point.
+                unreachable!()
+            };
+            write!(out, " {} {} {} {}", var, start, stop, step)?;
+        }
+        Ok(())
+    }
+}
+
+impl Noise {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Ngspice>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        let node = |node: &noise::Node| match node {
+            noise::Node::Raw(name) => name.to_string(),
+            noise::Node::Scir(path) => {
+                node_voltage_path(lib, conv, &lib.simplify_path(path.clone()))
+            }
+        };
+        write!(out, ".noise v({}", node(&self.output))?;
+        if let Some(ref output_ref) = self.output_ref {
+            write!(out, ",{}", node(output_ref))?;
+        }
+        let source = match &self.input_source {
+            noise::Source::Raw(name) => name.to_string(),
+            noise::Source::Scir(path) => source_path(lib, conv, path),
+        };
+        let (kind, pts) = match self.sweep {
+            Sweep::Linear(pts) => ("lin", pts),
+            Sweep::Decade(pts) => ("dec", pts),
+            Sweep::Octave(pts) => ("oct", pts),
+        };
+        write!(
+            out,
+            ") {} {} {} {} {}",
+            source, kind, pts, self.start, self.stop
+        )?;
+        Ok(())
+    }
+}
+
+/// Computes the value of the outer sweep variable at each point of a nested native DC sweep,
+/// given the flattened values of the inner sweep variable.
+fn outer_sweep_values(inner: &[f64], outer: &dc::Sweep) -> Vec<f64> {
+    let SweepPoints::Linear { start, step, .. } = outer.points else {
+        unreachable!()
+    };
+    // The inner sweep restarts at its first value for every point of the outer sweep.
+    let n = inner
+        .iter()
+        .skip(1)
+        .position(|v| *v == inner[0])
+        .map(|i| i + 1)
+        .unwrap_or(inner.len());
+    let start = start.to_f64().unwrap();
+    let step = step.to_f64().unwrap();
+    (0..inner.len())
+        .map(|i| start + (i / n) as f64 * step)
+        .collect()
+}
+
 impl HasSpiceLikeNetlist for Ngspice {
     fn write_prelude<W: Write>(&self, out: &mut W, _lib: &Library<Self>) -> std::io::Result<()> {
         writeln!(out, "* Substrate SPICE library")?;
@@ -703,6 +1492,15 @@ impl HasSpiceLikeNetlist for Ngspice {
         Spice.write_end_subckt(out, name)
     }
 
+    fn write_param<W: Write>(
+        &self,
+        out: &mut W,
+        name: &ArcStr,
+        value: &ParamValue,
+    ) -> std::io::Result<()> {
+        Spice.write_param(out, name, value)
+    }
+
     fn write_instance<W: Write>(
         &self,
         out: &mut W,
@@ -736,6 +1534,9 @@ impl HasSpiceLikeNetlist for Ngspice {
                     Vsource::Dc(dc) => {
                         write!(out, " DC {}", dc)?;
                     }
+                    Vsource::Ac(ac) => {
+                        write!(out, " DC {} AC {} {}", ac.dc, ac.mag, ac.phase)?;
+                    }
                     Vsource::Pulse(pulse) => {
                         write!(
                             out,
@@ -0,0 +1,115 @@
+//! ngspice Monte Carlo analysis options and data structures.
+
+use crate::{Input, Ngspice};
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use substrate::simulation::data::{FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+
+/// The seed used for the first Monte Carlo iteration if no seed is specified.
+pub const DEFAULT_SEED: u64 = 1;
+
+/// A Monte Carlo analysis.
+///
+/// Each iteration is simulated by a separate ngspice run with its own random seed,
+/// so statistical functions such as `agauss` in the netlist and included model files
+/// are re-evaluated for every iteration.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarlo<A> {
+    /// Number of Monte Carlo iterations to perform (not including nominal).
+    pub numruns: usize,
+    /// Starting seed for random number generator.
+    ///
+    /// Iteration `i` uses seed `seed + i`, which must not exceed [`i32::MAX`].
+    /// Defaults to [`DEFAULT_SEED`].
+    pub seed: Option<u64>,
+    /// Starting iteration number.
+    ///
+    /// Defaults to 0.
+    pub firstrun: Option<usize>,
+    /// The analysis to run.
+    pub analysis: A,
+}
+
+/// A Monte Carlo simulation output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Output<T>(pub(crate) Vec<T>);
+
+impl<T> Deref for Output<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Output<T> {
+    /// Returns the underlying vector of outputs for each
+    /// iteration of the Monte Carlo simulation.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<A: SupportedBy<Ngspice>> From<MonteCarlo<A>> for MonteCarlo<Vec<Input>> {
+    fn from(value: MonteCarlo<A>) -> Self {
+        let mut analysis = Vec::new();
+        value.analysis.into_input(&mut analysis);
+        MonteCarlo {
+            numruns: value.numruns,
+            seed: value.seed,
+            firstrun: value.firstrun,
+            analysis,
+        }
+    }
+}
+
+impl<A: Analysis, T: FromSaved<Ngspice, A>> FromSaved<Ngspice, MonteCarlo<A>> for Output<T> {
+    type SavedKey = T::SavedKey;
+
+    fn from_saved(output: &<MonteCarlo<A> as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        Output(
+            output
+                .0
+                .iter()
+                .map(|output| T::from_saved(output, key))
+                .collect(),
+        )
+    }
+}
+
+impl<A: SupportedBy<Ngspice>, T, S> Save<Ngspice, MonteCarlo<A>, T> for Output<S>
+where
+    S: Save<Ngspice, A, T>,
+{
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <Self as FromSaved<Ngspice, MonteCarlo<A>>>::SavedKey {
+        S::save(ctx, to_save, opts)
+    }
+}
+
+impl<A: Analysis> Analysis for MonteCarlo<A> {
+    type Output = Output<A::Output>;
+}
+
+impl<A: SupportedBy<Ngspice>> SupportedBy<Ngspice> for MonteCarlo<A> {
+    fn into_input(self, inputs: &mut Vec<<Ngspice as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Ngspice as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        let output: Output<Vec<crate::Output>> = item.try_into().unwrap();
+        Output(
+            output
+                .0
+                .into_iter()
+                .map(|out| A::from_output(&mut out.into_iter()))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,165 @@
+//! ngspice noise analysis options and data structures.
+
+use crate::ac::Sweep;
+use crate::Ngspice;
+use arcstr::ArcStr;
+use rust_decimal::Decimal;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::simulation::data::{noise, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+
+/// A node referenced by a [`Noise`] analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Node {
+    /// The node with the given netlist name.
+    Raw(ArcStr),
+    /// The node at the given SCIR signal path.
+    Scir(SliceOnePath),
+}
+
+impl From<ArcStr> for Node {
+    fn from(value: ArcStr) -> Self {
+        Self::Raw(value)
+    }
+}
+
+impl From<SliceOnePath> for Node {
+    fn from(value: SliceOnePath) -> Self {
+        Self::Scir(value)
+    }
+}
+
+impl From<ConvertedNodePath> for Node {
+    fn from(value: ConvertedNodePath) -> Self {
+        Self::Scir(match value {
+            ConvertedNodePath::Cell(path) => path,
+            ConvertedNodePath::Primitive {
+                instances, port, ..
+            } => SliceOnePath::new(instances, NamedSliceOne::new(port)),
+        })
+    }
+}
+
+/// An independent source referenced by a [`Noise`] analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Source {
+    /// The source with the given netlist name.
+    Raw(ArcStr),
+    /// The source at the given SCIR instance path.
+    ///
+    /// SCIR instance paths can be obtained using
+    /// [`SimController::convert_instance_path`](substrate::simulation::SimController::convert_instance_path).
+    Scir(scir::InstancePath),
+}
+
+/// A noise analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Noise {
+    /// The positive output node.
+    pub output: Node,
+    /// The negative output node.
+    ///
+    /// Defaults to ground.
+    pub output_ref: Option<Node>,
+    /// The independent source to which input-referred noise is referred.
+    pub input_source: Source,
+    /// Start frequency (Hz).
+    pub start: Decimal,
+    /// Stop frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+}
+
+/// The result of a noise analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The frequency points of the noise simulation.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to values.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+}
+
+impl FromSaved<Ngspice, Noise> for Output {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Ngspice, Noise, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Ngspice, Noise> for noise::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Ngspice, Noise, ()> for noise::Freq {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Ngspice, Noise> for noise::OutputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::OutputNoise(output.raw_values.get("onoise_spectrum").unwrap().clone())
+    }
+}
+
+impl Save<Ngspice, Noise, ()> for noise::OutputNoise {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Ngspice, Noise> for noise::InputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::InputNoise(output.raw_values.get("inoise_spectrum").unwrap().clone())
+    }
+}
+
+impl Save<Ngspice, Noise, ()> for noise::InputNoise {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl Analysis for Noise {
+    type Output = Output;
+}
+
+impl SupportedBy<Ngspice> for Noise {
+    fn into_input(self, inputs: &mut Vec<<Ngspice as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Ngspice as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -0,0 +1,274 @@
+//! ngspice DC operating point analysis options and data structures.
+
+use crate::{Ngspice, ProbeStmt, SaveStmt};
+use arcstr::ArcStr;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use substrate::io::schematic::{NestedNode, NestedTerminal, NodePath, TerminalPath};
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::schematic::primitives::Resistor;
+use substrate::schematic::NestedInstance;
+use substrate::simulation::data::{op, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A DC operating point analysis.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Op;
+
+/// The result of a DC operating point analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// A map from signal name to values.
+    pub raw_values: HashMap<ArcStr, f64>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Ngspice, Op> for Output {
+    type SavedKey = ();
+    fn from_saved(output: &<Op as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Ngspice, Op, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        _to_save: (),
+        _opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved DC operating point voltage.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltageSavedKey(pub(crate) u64);
+
+impl FromSaved<Ngspice, Op> for op::Voltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<Op as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        op::Voltage(
+            *output
+                .raw_values
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SaveStmt})]
+impl<T> Save<Ngspice, Op, T> for op::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_op_voltage(to_save)
+    }
+}
+
+impl Save<Ngspice, Op, &SliceOnePath> for op::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: &SliceOnePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_op_voltage(SaveStmt::ScirVoltage(to_save.clone()))
+    }
+}
+
+impl Save<Ngspice, Op, &ConvertedNodePath> for op::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Ngspice, Op, &NodePath> for op::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &NodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_node_path(to_save).unwrap(), opts)
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; NodePath})]
+impl<T> Save<Ngspice, Op, T> for op::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedNode; &NestedNode; NestedTerminal; &NestedTerminal})]
+impl<T> Save<Ngspice, Op, T> for op::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+#[impl_dispatch({TerminalPath; &TerminalPath})]
+impl<T> Save<Ngspice, Op, T> for op::Voltage {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.as_ref(), opts)
+    }
+}
+
+/// An identifier for a saved DC operating point current.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSavedKey(pub(crate) Vec<u64>);
+
+impl FromSaved<Ngspice, Op> for op::Current {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<Op as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        op::Current(
+            key.0
+                .iter()
+                .map(|key| {
+                    output
+                        .raw_values
+                        .get(output.saved_values.get(key).unwrap())
+                        .unwrap()
+                })
+                .sum(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SaveStmt})]
+impl<T> Save<Ngspice, Op, T> for op::Current {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_op_current(to_save)
+    }
+}
+
+#[impl_dispatch({
+    &NestedInstance<Resistor>;
+    NestedInstance<Resistor>
+})]
+impl<T> Save<Ngspice, Op, T> for op::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_op_current(SaveStmt::ResistorCurrent(
+            ctx.lib.convert_instance_path(to_save.path()).unwrap(),
+        ))
+    }
+}
+
+impl Save<Ngspice, Op, &SliceOnePath> for op::Current {
+    fn save(
+        _ctx: &SimulationContext<Ngspice>,
+        to_save: &SliceOnePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.probe_op_current(ProbeStmt::ScirCurrent(to_save.clone()))
+    }
+}
+
+impl Save<Ngspice, Op, &ConvertedNodePath> for op::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Ngspice, Op, &TerminalPath> for op::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: &TerminalPath,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        CurrentSavedKey(
+            ctx.lib
+                .convert_terminal_path(to_save)
+                .unwrap()
+                .into_iter()
+                .flat_map(|path| Self::save(ctx, path, opts).0)
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; TerminalPath})]
+impl<T> Save<Ngspice, Op, T> for op::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedTerminal; &NestedTerminal})]
+impl<T> Save<Ngspice, Op, T> for op::Current {
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for Op {
+    type Output = Output;
+}
+
+impl SupportedBy<Ngspice> for Op {
+    fn into_input(self, inputs: &mut Vec<<Ngspice as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Ngspice as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
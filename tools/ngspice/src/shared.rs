@@ -0,0 +1,471 @@
+//! In-process ngspice backend using the ngspice shared library.
+//!
+//! The library is loaded at runtime, so `libngspice` does not need
+//! to be present when building this crate.
+
+use std::ffi::{c_char, c_int, c_short, c_void, CStr, CString};
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use nutlex::parser::{Analysis, ComplexSignal, Data, Variable};
+
+use crate::error::{Error, Result};
+
+type SendChar = unsafe extern "C" fn(*mut c_char, c_int, *mut c_void) -> c_int;
+type SendStat = unsafe extern "C" fn(*mut c_char, c_int, *mut c_void) -> c_int;
+type ControlledExit = unsafe extern "C" fn(c_int, bool, bool, c_int, *mut c_void) -> c_int;
+type SendData = unsafe extern "C" fn(*mut c_void, c_int, c_int, *mut c_void) -> c_int;
+type SendInitData = unsafe extern "C" fn(*mut c_void, c_int, *mut c_void) -> c_int;
+type BgThreadRunning = unsafe extern "C" fn(bool, c_int, *mut c_void) -> c_int;
+
+type NgSpiceInit = unsafe extern "C" fn(
+    Option<SendChar>,
+    Option<SendStat>,
+    Option<ControlledExit>,
+    Option<SendData>,
+    Option<SendInitData>,
+    Option<BgThreadRunning>,
+    *mut c_void,
+) -> c_int;
+type NgSpiceCommand = unsafe extern "C" fn(*mut c_char) -> c_int;
+type NgSpiceCirc = unsafe extern "C" fn(*mut *mut c_char) -> c_int;
+type NgSpiceAllPlots = unsafe extern "C" fn() -> *mut *mut c_char;
+type NgSpiceAllVecs = unsafe extern "C" fn(*mut c_char) -> *mut *mut c_char;
+type NgGetVecInfo = unsafe extern "C" fn(*mut c_char) -> *mut VectorInfo;
+
+/// Vector type codes used by ngspice.
+const SV_VOLTAGE: c_int = 3;
+const SV_CURRENT: c_int = 4;
+
+/// The name of the plot of constants, which exists regardless of the analyses run.
+const CONST_PLOT: &str = "const";
+
+/// Serializes access to ngspice, which keeps global state
+/// and can only run one circuit at a time per process.
+static NGSPICE_LOCK: Mutex<()> = Mutex::new(());
+
+#[repr(C)]
+struct NgComplex {
+    cx_real: f64,
+    cx_imag: f64,
+}
+
+#[repr(C)]
+struct VectorInfo {
+    v_name: *mut c_char,
+    v_type: c_int,
+    v_flags: c_short,
+    v_realdata: *mut f64,
+    v_compdata: *mut NgComplex,
+    v_length: c_int,
+}
+
+/// State shared with the ngspice callbacks.
+#[derive(Debug, Default)]
+struct CallbackState {
+    /// Whether ngspice has requested to exit.
+    exited: AtomicBool,
+}
+
+/// A handle to a loaded ngspice shared library.
+///
+/// Output and progress messages from ngspice are routed to [`tracing`].
+///
+/// After an unrecoverable error, ngspice requests to exit and can no longer be used.
+/// Every later simulation then fails with [`Error::NgspiceExited`], and the handle
+/// must be dropped to unload the library.
+pub struct SharedNgspice {
+    // Declared first so that the library is unloaded before `state`,
+    // which its callbacks reference, is freed.
+    _lib: libloading::Library,
+    state: Box<CallbackState>,
+    command: NgSpiceCommand,
+    circ: NgSpiceCirc,
+    all_plots: NgSpiceAllPlots,
+    all_vecs: NgSpiceAllVecs,
+    get_vec_info: NgGetVecInfo,
+}
+
+impl Debug for SharedNgspice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedNgspice").finish_non_exhaustive()
+    }
+}
+
+/// A vector saved by ngspice.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Vector {
+    name: String,
+    data: Data<Vec<f64>, ComplexSignal>,
+}
+
+/// The vectors of a plot produced by a single ngspice analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Plot {
+    name: String,
+    vectors: Vec<Vector>,
+}
+
+impl SharedNgspice {
+    /// Loads the ngspice shared library at the given path.
+    ///
+    /// ngspice keeps global state, so the same library should not be
+    /// loaded more than once per process.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        // SAFETY: ngspice has no library initialization routines with preconditions.
+        let lib = unsafe { libloading::Library::new(path.as_ref())? };
+
+        // SAFETY: the function signatures match those declared in `sharedspice.h`,
+        // and the function pointers are only used while `lib` is loaded.
+        let (init, command, circ, all_plots, all_vecs, get_vec_info) = unsafe {
+            (
+                *lib.get::<NgSpiceInit>(b"ngSpice_Init\0")?,
+                *lib.get::<NgSpiceCommand>(b"ngSpice_Command\0")?,
+                *lib.get::<NgSpiceCirc>(b"ngSpice_Circ\0")?,
+                *lib.get::<NgSpiceAllPlots>(b"ngSpice_AllPlots\0")?,
+                *lib.get::<NgSpiceAllVecs>(b"ngSpice_AllVecs\0")?,
+                *lib.get::<NgGetVecInfo>(b"ngGet_Vec_Info\0")?,
+            )
+        };
+
+        let state = Box::<CallbackState>::default();
+        // SAFETY: `state` outlives the library, and the callbacks only access it immutably.
+        let status = unsafe {
+            init(
+                Some(send_char),
+                Some(send_stat),
+                Some(controlled_exit),
+                None,
+                None,
+                None,
+                &*state as *const CallbackState as *mut c_void,
+            )
+        };
+        if status != 0 {
+            return Err(Error::NgspiceError);
+        }
+
+        Ok(Self {
+            _lib: lib,
+            state,
+            command,
+            circ,
+            all_plots,
+            all_vecs,
+            get_vec_info,
+        })
+    }
+
+    /// Loads the ngspice shared library from the system library search path.
+    pub fn load_default() -> Result<Self> {
+        Self::load(libloading::library_filename("ngspice"))
+    }
+
+    /// Runs the analyses in the given netlist, returning the resulting plots
+    /// in the order in which they were created.
+    pub(crate) fn simulate(&self, netlist: &[u8]) -> Result<Vec<Plot>> {
+        let _guard = NGSPICE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Clear the results of previous simulations.
+        self.run_command("destroy all")?;
+        let _cleanup = Cleanup(self);
+        self.load_circuit(netlist)?;
+        self.run_command("run")?;
+        self.plots()
+    }
+
+    /// Returns an error if ngspice has requested to exit.
+    fn check_exited(&self) -> Result<()> {
+        if self.state.exited.load(Ordering::SeqCst) {
+            return Err(Error::NgspiceExited);
+        }
+        Ok(())
+    }
+
+    fn run_command(&self, command: &str) -> Result<()> {
+        self.check_exited()?;
+        let command = CString::new(command).map_err(|_| Error::NgspiceError)?;
+        // SAFETY: ngspice does not modify or retain the command string.
+        let status = unsafe { (self.command)(command.as_ptr() as *mut c_char) };
+        self.check_exited()?;
+        if status != 0 {
+            return Err(Error::NgspiceError);
+        }
+        Ok(())
+    }
+
+    fn load_circuit(&self, netlist: &[u8]) -> Result<()> {
+        self.check_exited()?;
+        let mut lines = netlist
+            .split(|c| *c == b'\n')
+            .map(|line| CString::new(line.strip_suffix(b"\r").unwrap_or(line)))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::NgspiceError)?;
+        // The circuit array must end with a `.end` line.
+        lines.push(CString::new(".end").unwrap());
+        let mut ptrs = lines
+            .iter()
+            .map(|line| line.as_ptr() as *mut c_char)
+            .chain(std::iter::once(std::ptr::null_mut()))
+            .collect::<Vec<_>>();
+        // SAFETY: `ptrs` is a null-terminated array of valid C strings,
+        // which ngspice copies before returning.
+        let status = unsafe { (self.circ)(ptrs.as_mut_ptr()) };
+        self.check_exited()?;
+        if status != 0 {
+            return Err(Error::NgspiceError);
+        }
+        Ok(())
+    }
+
+    fn plots(&self) -> Result<Vec<Plot>> {
+        // SAFETY: ngspice returns a null-terminated array of plot names,
+        // with the most recently created plot first.
+        let mut names = unsafe { strings((self.all_plots)()) };
+        names.retain(|name| name != CONST_PLOT);
+        names.reverse();
+        names.into_iter().map(|name| self.plot(name)).collect()
+    }
+
+    fn plot(&self, name: String) -> Result<Plot> {
+        let plot = CString::new(name.as_str()).map_err(|_| Error::NgspiceError)?;
+        // SAFETY: ngspice returns a null-terminated array of vector names.
+        let vec_names = unsafe { strings((self.all_vecs)(plot.as_ptr() as *mut c_char)) };
+        let mut vectors = vec_names
+            .iter()
+            .map(|vec| self.vector(&format!("{name}.{vec}")))
+            .collect::<Result<Vec<_>>>()?;
+        // Rawfiles list the scale of a plot first.
+        if let Some(idx) = vectors.iter().position(Vector::is_scale) {
+            let scale = vectors.remove(idx);
+            vectors.insert(0, scale);
+        }
+        Ok(Plot { name, vectors })
+    }
+
+    fn vector(&self, path: &str) -> Result<Vector> {
+        let path = CString::new(path).map_err(|_| Error::NgspiceError)?;
+        // SAFETY: the returned vector info is owned by ngspice and
+        // remains valid until the plot is destroyed.
+        unsafe {
+            let info = (self.get_vec_info)(path.as_ptr() as *mut c_char);
+            let info = info.as_ref().ok_or(Error::NgspiceError)?;
+            let len = info.v_length.max(0) as usize;
+            let name = CStr::from_ptr(info.v_name).to_string_lossy();
+            let data = if !info.v_realdata.is_null() {
+                Data::Real(std::slice::from_raw_parts(info.v_realdata, len).to_vec())
+            } else if !info.v_compdata.is_null() {
+                let values = std::slice::from_raw_parts(info.v_compdata, len);
+                Data::Complex(ComplexSignal {
+                    real: values.iter().map(|v| v.cx_real).collect(),
+                    imag: values.iter().map(|v| v.cx_imag).collect(),
+                })
+            } else {
+                return Err(Error::NgspiceError);
+            };
+            Ok(Vector {
+                name: rawfile_name(&name, info.v_type),
+                data,
+            })
+        }
+    }
+}
+
+/// Removes the circuit and plots of a simulation from ngspice when dropped,
+/// including when the simulation fails partway through.
+struct Cleanup<'a>(&'a SharedNgspice);
+
+impl Drop for Cleanup<'_> {
+    fn drop(&mut self) {
+        // Commands are not sent once ngspice has exited.
+        for command in ["destroy all", "remcirc"] {
+            if let Err(e) = self.0.run_command(command) {
+                tracing::warn!("failed to clean up ngspice circuit: {e}");
+                return;
+            }
+        }
+    }
+}
+
+impl Plot {
+    /// Converts this plot to the analysis that ngspice would write to a rawfile.
+    pub(crate) fn to_analysis(&self) -> Analysis<'_> {
+        let variables = self
+            .vectors
+            .iter()
+            .enumerate()
+            .map(|(idx, vector)| Variable {
+                idx,
+                name: &vector.name,
+                unit: "",
+            })
+            .collect();
+        let num_points = self.vectors.first().map(Vector::len).unwrap_or_default();
+        let data = if self.vectors.iter().any(|v| v.data.is_complex()) {
+            Data::Complex(self.vectors.iter().map(Vector::to_complex).collect())
+        } else {
+            Data::Real(
+                self.vectors
+                    .iter()
+                    .map(|v| v.data.clone().unwrap_real())
+                    .collect(),
+            )
+        };
+        Analysis {
+            title: None,
+            date: None,
+            plotname: self.plotname(),
+            flags: if data.is_complex() { "complex" } else { "real" },
+            num_variables: self.vectors.len(),
+            num_points,
+            variables,
+            data,
+        }
+    }
+
+    /// The shared library only exposes the short name of a plot (e.g. `noise2`),
+    /// so the rawfile plot names needed to identify analyses are inferred from its vectors.
+    fn plotname(&self) -> &str {
+        if self.vectors.iter().any(|v| v.name == "onoise_total") {
+            "Integrated Noise"
+        } else {
+            &self.name
+        }
+    }
+}
+
+impl Vector {
+    /// Whether this vector is the scale of its plot,
+    /// such as the time points of a transient analysis or the values of a DC sweep.
+    fn is_scale(&self) -> bool {
+        self.name == "time" || self.name == "frequency" || self.name.ends_with("-sweep")
+    }
+
+    fn len(&self) -> usize {
+        match &self.data {
+            Data::Real(values) => values.len(),
+            Data::Complex(values) => values.real.len(),
+        }
+    }
+
+    fn to_complex(&self) -> ComplexSignal {
+        match &self.data {
+            Data::Real(values) => ComplexSignal {
+                real: values.clone(),
+                imag: vec![0.; values.len()],
+            },
+            Data::Complex(values) => values.clone(),
+        }
+    }
+}
+
+/// Converts the in-memory name of an ngspice vector to the name used in rawfiles.
+fn rawfile_name(name: &str, v_type: c_int) -> String {
+    let name = name.to_lowercase();
+    if name.starts_with("v(") || name.starts_with("i(") {
+        return name;
+    }
+    match v_type {
+        SV_VOLTAGE => format!("v({name})"),
+        SV_CURRENT => format!("i({})", name.strip_suffix("#branch").unwrap_or(&name)),
+        _ => name,
+    }
+}
+
+/// Collects a null-terminated array of C strings.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a null-terminated array of valid C strings.
+unsafe fn strings(ptr: *mut *mut c_char) -> Vec<String> {
+    let mut strings = Vec::new();
+    if ptr.is_null() {
+        return strings;
+    }
+    let mut i = 0;
+    while !(*ptr.add(i)).is_null() {
+        strings.push(CStr::from_ptr(*ptr.add(i)).to_string_lossy().into_owned());
+        i += 1;
+    }
+    strings
+}
+
+/// Routes ngspice output to [`tracing`].
+unsafe extern "C" fn send_char(msg: *mut c_char, _id: c_int, _user: *mut c_void) -> c_int {
+    let msg = CStr::from_ptr(msg).to_string_lossy();
+    if let Some(msg) = msg.strip_prefix("stderr ") {
+        tracing::warn!("{msg}");
+    } else {
+        tracing::debug!("{}", msg.strip_prefix("stdout ").unwrap_or(&msg));
+    }
+    0
+}
+
+/// Routes ngspice simulation progress to [`tracing`].
+unsafe extern "C" fn send_stat(msg: *mut c_char, _id: c_int, _user: *mut c_void) -> c_int {
+    tracing::trace!("{}", CStr::from_ptr(msg).to_string_lossy());
+    0
+}
+
+/// Records that ngspice has requested to exit, which it does after unrecoverable errors.
+///
+/// The library cannot be used afterwards, so the flag is never cleared.
+unsafe extern "C" fn controlled_exit(
+    status: c_int,
+    _immediate: bool,
+    _quit: bool,
+    _id: c_int,
+    user: *mut c_void,
+) -> c_int {
+    tracing::error!("ngspice exited with status {status}");
+    if let Some(state) = (user as *const CallbackState).as_ref() {
+        state.exited.store(true, Ordering::SeqCst);
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_vector_names_to_rawfile_names() {
+        assert_eq!(rawfile_name("time", 1), "time");
+        assert_eq!(rawfile_name("xinst0_n", SV_VOLTAGE), "v(xinst0_n)");
+        assert_eq!(rawfile_name("V1#branch", SV_CURRENT), "i(v1)");
+        assert_eq!(
+            rawfile_name("@r.xinst0.r0[i]", SV_CURRENT),
+            "i(@r.xinst0.r0[i])"
+        );
+        assert_eq!(rawfile_name("inst2:p", SV_CURRENT), "i(inst2:p)");
+    }
+
+    #[test]
+    fn converts_plots_to_analyses() {
+        let plot = Plot {
+            name: "ac1".to_string(),
+            vectors: vec![
+                Vector {
+                    name: "frequency".to_string(),
+                    data: Data::Real(vec![1., 10.]),
+                },
+                Vector {
+                    name: "v(out)".to_string(),
+                    data: Data::Complex(ComplexSignal {
+                        real: vec![1., 0.5],
+                        imag: vec![0., -0.5],
+                    }),
+                },
+            ],
+        };
+        let analysis = plot.to_analysis();
+        assert_eq!(analysis.plotname, "ac1");
+        assert_eq!(analysis.num_points, 2);
+        assert_eq!(analysis.variables[1].name, "v(out)");
+        let data = analysis.data.unwrap_complex();
+        assert_eq!(data[0].imag, vec![0., 0.]);
+        assert_eq!(data[1].real, vec![1., 0.5]);
+    }
+}
@@ -0,0 +1,121 @@
+//! ngspice parametric sweep options and data structures.
+
+use crate::{Input, Ngspice};
+use arcstr::ArcStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use substrate::simulation::data::{FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+
+/// A quantity swept by a parametric [`Sweep`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SweepVar {
+    /// The DC value of the independent voltage or current source with the given netlist name.
+    RawSource(ArcStr),
+    /// The DC value of the independent voltage or current source at the given SCIR instance path.
+    ///
+    /// SCIR instance paths can be obtained using
+    /// [`SimController::convert_instance_path`](substrate::simulation::SimController::convert_instance_path).
+    Source(scir::InstancePath),
+    /// The circuit temperature (degrees C).
+    Temp,
+    /// The netlist parameter with the given name.
+    Param(ArcStr),
+}
+
+/// A parametric sweep.
+///
+/// Runs the inner analysis once for each value of the swept variable.
+/// Each sweep point is simulated by a separate ngspice run.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Sweep<A> {
+    /// The swept variable.
+    pub var: SweepVar,
+    /// The values of the swept variable.
+    pub values: Vec<Decimal>,
+    /// The analysis to run.
+    pub analysis: A,
+}
+
+/// A parametric sweep output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Output<T>(pub(crate) Vec<T>);
+
+impl<T> Deref for Output<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Output<T> {
+    /// Returns the underlying vector of outputs,
+    /// indexed by sweep point.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<A: SupportedBy<Ngspice>> From<Sweep<A>> for Sweep<Vec<Input>> {
+    fn from(value: Sweep<A>) -> Self {
+        let mut analysis = Vec::new();
+        value.analysis.into_input(&mut analysis);
+        Sweep {
+            var: value.var,
+            values: value.values,
+            analysis,
+        }
+    }
+}
+
+impl<A: Analysis, T: FromSaved<Ngspice, A>> FromSaved<Ngspice, Sweep<A>> for Output<T> {
+    type SavedKey = T::SavedKey;
+
+    fn from_saved(output: &<Sweep<A> as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        Output(
+            output
+                .0
+                .iter()
+                .map(|output| T::from_saved(output, key))
+                .collect(),
+        )
+    }
+}
+
+impl<A: SupportedBy<Ngspice>, T, S> Save<Ngspice, Sweep<A>, T> for Output<S>
+where
+    S: Save<Ngspice, A, T>,
+{
+    fn save(
+        ctx: &SimulationContext<Ngspice>,
+        to_save: T,
+        opts: &mut <Ngspice as Simulator>::Options,
+    ) -> <Self as FromSaved<Ngspice, Sweep<A>>>::SavedKey {
+        S::save(ctx, to_save, opts)
+    }
+}
+
+impl<A: Analysis> Analysis for Sweep<A> {
+    type Output = Output<A::Output>;
+}
+
+impl<A: SupportedBy<Ngspice>> SupportedBy<Ngspice> for Sweep<A> {
+    fn into_input(self, inputs: &mut Vec<<Ngspice as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Ngspice as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        let output: Output<Vec<crate::Output>> = item.try_into().unwrap();
+        Output(
+            output
+                .0
+                .into_iter()
+                .map(|out| A::from_output(&mut out.into_iter()))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,393 @@
+//! Spectre DC sweep analysis options and data structures.
+
+use crate::dspf::DspfNode;
+use crate::{InstanceTail, SimSignal, Spectre};
+use arcstr::ArcStr;
+use rust_decimal::Decimal;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::io::schematic::{NestedNode, NestedTerminal, NodePath, TerminalPath};
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::simulation::data::{dc, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A quantity swept by a [`DcSweep`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SweepVar {
+    /// The DC value of the independent voltage or current source with the given netlist name.
+    RawSource(ArcStr),
+    /// The DC value of the independent voltage or current source at the given SCIR instance path.
+    ///
+    /// SCIR instance paths can be obtained using
+    /// [`SimController::convert_instance_path`](substrate::simulation::SimController::convert_instance_path).
+    Source(scir::InstancePath),
+    /// The circuit temperature (degrees C).
+    Temp,
+    /// The netlist parameter with the given name.
+    Param(ArcStr),
+}
+
+/// The points at which a swept variable is evaluated.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SweepPoints {
+    /// Linearly spaced points from `start` to `stop` with increment `step`.
+    Linear {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The increment between consecutive points.
+        step: Decimal,
+    },
+    /// `points` logarithmically spaced points from `start` to `stop`.
+    Logarithmic {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The total number of points.
+        points: usize,
+    },
+    /// Logarithmically spaced points from `start` to `stop` with `points` points **per decade**.
+    Decade {
+        /// The start value.
+        start: Decimal,
+        /// The stop value.
+        stop: Decimal,
+        /// The number of points per decade.
+        points: usize,
+    },
+    /// An explicit list of points.
+    List(Vec<Decimal>),
+}
+
+/// A sweep of a single variable.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Sweep {
+    /// The swept variable.
+    pub var: SweepVar,
+    /// The sweep points.
+    pub points: SweepPoints,
+}
+
+/// A DC sweep analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DcSweep {
+    /// The inner sweep.
+    pub sweep: Sweep,
+    /// An optional outer sweep.
+    ///
+    /// The inner sweep is run once for every point of the outer sweep.
+    pub nested: Option<Sweep>,
+}
+
+/// The result of a DC sweep analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The values of the inner swept variable.
+    pub sweep: Arc<Vec<f64>>,
+    /// The values of the outer swept variable, if a nested sweep was run.
+    pub nested: Option<Arc<Vec<f64>>>,
+    /// A map from signal name to values.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Spectre, DcSweep> for Output {
+    type SavedKey = ();
+
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, DcSweep, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, DcSweep> for dc::Sweep {
+    type SavedKey = ();
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        dc::Sweep(output.sweep.clone())
+    }
+}
+
+impl Save<Spectre, DcSweep, ()> for dc::Sweep {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, DcSweep> for dc::NestedSweep {
+    type SavedKey = ();
+    fn from_saved(output: &<DcSweep as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        dc::NestedSweep(
+            output
+                .nested
+                .clone()
+                .expect("DC sweep analysis did not have a nested sweep"),
+        )
+    }
+}
+
+impl Save<Spectre, DcSweep, ()> for dc::NestedSweep {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved DC sweep voltage.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltageSavedKey(pub(crate) u64);
+
+impl FromSaved<Spectre, DcSweep> for dc::Voltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<DcSweep as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        dc::Voltage(
+            output
+                .raw_values
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SimSignal})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_voltage(to_save)
+    }
+}
+
+impl Save<Spectre, DcSweep, DspfNode> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: DspfNode,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> <Self as FromSaved<Spectre, DcSweep>>::SavedKey {
+        let itail = InstanceTail {
+            instance: ctx
+                .lib
+                .convert_instance_path(&to_save.dspf_instance)
+                .unwrap(),
+            tail: to_save.path.into(),
+        };
+        opts.save_dc_voltage(itail)
+    }
+}
+
+impl Save<Spectre, DcSweep, &SliceOnePath> for dc::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_voltage(SimSignal::ScirVoltage(to_save.clone()))
+    }
+}
+
+impl Save<Spectre, DcSweep, &ConvertedNodePath> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Spectre, DcSweep, &NodePath> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &NodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_node_path(to_save).unwrap(), opts)
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; NodePath})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedNode; &NestedNode; NestedTerminal; &NestedTerminal})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+#[impl_dispatch({TerminalPath; &TerminalPath})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.as_ref(), opts)
+    }
+}
+
+/// An identifier for a saved DC sweep current.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSavedKey(pub(crate) Vec<u64>);
+
+impl FromSaved<Spectre, DcSweep> for dc::Current {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<DcSweep as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let currents: Vec<Arc<Vec<f64>>> = key
+            .0
+            .iter()
+            .map(|key| {
+                output
+                    .raw_values
+                    .get(output.saved_values.get(key).unwrap())
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        let mut total_current = vec![0.; output.sweep.len()];
+        for dc_current in currents {
+            for (i, current) in dc_current.iter().enumerate() {
+                total_current[i] += *current;
+            }
+        }
+        dc::Current(Arc::new(total_current))
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SimSignal})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Current {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_current(to_save)
+    }
+}
+
+impl Save<Spectre, DcSweep, &SliceOnePath> for dc::Current {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_dc_current(SimSignal::ScirCurrent(to_save.clone()))
+    }
+}
+
+impl Save<Spectre, DcSweep, &ConvertedNodePath> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Spectre, DcSweep, &TerminalPath> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &TerminalPath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        CurrentSavedKey(
+            ctx.lib
+                .convert_terminal_path(to_save)
+                .unwrap()
+                .into_iter()
+                .flat_map(|path| Self::save(ctx, path, opts).0)
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; TerminalPath})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedTerminal; &NestedTerminal})]
+impl<T> Save<Spectre, DcSweep, T> for dc::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for DcSweep {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for DcSweep {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -1,4 +1,13 @@
 //! Spectre analyses.
 pub mod ac;
+pub mod dc;
 pub mod montecarlo;
+pub mod noise;
+pub mod op;
+pub mod pac;
+pub mod pnoise;
+pub mod pss;
+pub mod sp;
+pub mod stb;
+pub mod sweep;
 pub mod tran;
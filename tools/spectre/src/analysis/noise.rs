@@ -0,0 +1,267 @@
+//! Spectre noise analysis options and data structures.
+
+use crate::analysis::ac::Sweep;
+use crate::{ErrPreset, Spectre};
+use arcstr::ArcStr;
+use rust_decimal::Decimal;
+use scir::{Library, NamedSliceOne, NetlistLibConversion, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::schematic::{ExportsNestedData, InstancePath, NestedInstance};
+use substrate::simulation::data::{noise, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A node referenced by a [`Noise`] analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Node {
+    /// The node with the given netlist name.
+    Raw(ArcStr),
+    /// The node at the given SCIR signal path.
+    Scir(SliceOnePath),
+}
+
+impl From<ArcStr> for Node {
+    fn from(value: ArcStr) -> Self {
+        Self::Raw(value)
+    }
+}
+
+impl From<SliceOnePath> for Node {
+    fn from(value: SliceOnePath) -> Self {
+        Self::Scir(value)
+    }
+}
+
+impl From<ConvertedNodePath> for Node {
+    fn from(value: ConvertedNodePath) -> Self {
+        Self::Scir(match value {
+            ConvertedNodePath::Cell(path) => path,
+            ConvertedNodePath::Primitive {
+                instances, port, ..
+            } => SliceOnePath::new(instances, NamedSliceOne::new(port)),
+        })
+    }
+}
+
+impl Node {
+    pub(crate) fn to_string(&self, lib: &Library<Spectre>, conv: &NetlistLibConversion) -> String {
+        match self {
+            Node::Raw(name) => name.to_string(),
+            Node::Scir(path) => Spectre::node_voltage_path(lib, conv, path),
+        }
+    }
+}
+
+/// A device referenced by an analysis, such as a [`Noise`] analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum Device {
+    /// The device with the given netlist name.
+    Raw(ArcStr),
+    /// The device at the given SCIR instance path.
+    ///
+    /// SCIR instance paths can be obtained using
+    /// [`SimController::convert_instance_path`](substrate::simulation::SimController::convert_instance_path).
+    Scir(scir::InstancePath),
+}
+
+impl<T: Into<ArcStr>> From<T> for Device {
+    fn from(value: T) -> Self {
+        Self::Raw(value.into())
+    }
+}
+
+impl Device {
+    pub(crate) fn to_string(&self, lib: &Library<Spectre>, conv: &NetlistLibConversion) -> ArcStr {
+        match self {
+            Device::Raw(name) => name.clone(),
+            Device::Scir(path) => ArcStr::from(Spectre::instance_path(lib, conv, path)),
+        }
+    }
+}
+
+/// A noise analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Noise {
+    /// The positive output node.
+    pub output: Node,
+    /// The negative output node.
+    ///
+    /// Defaults to ground.
+    pub output_ref: Option<Node>,
+    /// The independent source to which input-referred noise is referred.
+    pub input_source: Device,
+    /// Start frequency (Hz).
+    pub start: Decimal,
+    /// Stop frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+    /// The error preset.
+    pub errpreset: Option<ErrPreset>,
+}
+
+/// The result of a noise analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The frequency points of the noise simulation.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to values.
+    ///
+    /// Device noise contributions are named `{device}:{source}`,
+    /// with the total contribution of each device named `{device}:total`.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Spectre, Noise> for Output {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Noise, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Noise> for noise::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Spectre, Noise, ()> for noise::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Noise> for noise::OutputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::OutputNoise(output.raw_values.get("out").unwrap().clone())
+    }
+}
+
+impl Save<Spectre, Noise, ()> for noise::OutputNoise {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Noise> for noise::InputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Noise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        noise::InputNoise(output.raw_values.get("in").unwrap().clone())
+    }
+}
+
+impl Save<Spectre, Noise, ()> for noise::InputNoise {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved device noise contribution.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContributionSavedKey(pub(crate) u64);
+
+impl FromSaved<Spectre, Noise> for noise::Contribution {
+    type SavedKey = ContributionSavedKey;
+    fn from_saved(output: &<Noise as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let device = output.saved_values.get(&key.0).unwrap();
+        noise::Contribution(
+            output
+                .raw_values
+                .get(&*format!("{device}:total"))
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; Device})]
+impl<T> Save<Spectre, Noise, T> for noise::Contribution {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_noise_contribution(to_save)
+    }
+}
+
+impl Save<Spectre, Noise, scir::InstancePath> for noise::Contribution {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: scir::InstancePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_noise_contribution(Device::Scir(to_save))
+    }
+}
+
+impl Save<Spectre, Noise, &InstancePath> for noise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &InstancePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_instance_path(to_save).unwrap(), opts)
+    }
+}
+
+impl<B: ExportsNestedData> Save<Spectre, Noise, &NestedInstance<B>> for noise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &NestedInstance<B>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl<B: ExportsNestedData> Save<Spectre, Noise, NestedInstance<B>> for noise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: NestedInstance<B>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for Noise {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Noise {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -0,0 +1,305 @@
+//! Spectre periodic AC analysis options and data structures.
+
+use crate::analysis::ac::Sweep;
+use crate::{SimSignal, Spectre};
+use arcstr::ArcStr;
+use num::complex::Complex64;
+use num::Zero;
+use rust_decimal::Decimal;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::io::schematic::{NestedNode, NestedTerminal, NodePath, TerminalPath};
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::simulation::data::{pac, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A periodic AC (PAC) analysis.
+///
+/// Must be preceded by a [`Pss`](crate::analysis::pss::Pss) analysis in the same simulation.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Pac {
+    /// Start input frequency (Hz).
+    pub start: Decimal,
+    /// Stop input frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+    /// The sidebands to compute.
+    ///
+    /// Sideband `k` corresponds to the output frequency `f_in + k * fund`,
+    /// where `fund` is the fundamental frequency of the preceding PSS analysis.
+    pub sidebands: Vec<i64>,
+}
+
+/// The result of a periodic AC analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The input frequency points of the PAC simulation.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to the values at each sideband.
+    pub raw_values: HashMap<ArcStr, HashMap<i64, Arc<Vec<Complex64>>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Spectre, Pac> for Output {
+    type SavedKey = ();
+
+    fn from_saved(output: &<Pac as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Pac, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pac> for pac::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Pac as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pac::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Spectre, Pac, ()> for pac::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved PAC voltage.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltageSavedKey(pub(crate) u64);
+
+impl FromSaved<Spectre, Pac> for pac::Voltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<Pac as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        pac::Voltage(
+            output
+                .raw_values
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SimSignal})]
+impl<T> Save<Spectre, Pac, T> for pac::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pac_voltage(to_save)
+    }
+}
+
+impl Save<Spectre, Pac, &SliceOnePath> for pac::Voltage {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pac_voltage(SimSignal::ScirVoltage(to_save.clone()))
+    }
+}
+
+impl Save<Spectre, Pac, &ConvertedNodePath> for pac::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Spectre, Pac, &NodePath> for pac::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &NodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_node_path(to_save).unwrap(), opts)
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; NodePath})]
+impl<T> Save<Spectre, Pac, T> for pac::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedNode; &NestedNode; NestedTerminal; &NestedTerminal})]
+impl<T> Save<Spectre, Pac, T> for pac::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+#[impl_dispatch({TerminalPath; &TerminalPath})]
+impl<T> Save<Spectre, Pac, T> for pac::Voltage {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.as_ref(), opts)
+    }
+}
+
+/// An identifier for a saved PAC current.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSavedKey(pub(crate) Vec<u64>);
+
+impl FromSaved<Spectre, Pac> for pac::Current {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<Pac as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let mut total_current: HashMap<i64, Vec<Complex64>> = HashMap::new();
+        for key in key.0.iter() {
+            let sidebands = output
+                .raw_values
+                .get(output.saved_values.get(key).unwrap())
+                .unwrap();
+            for (sideband, pac_current) in sidebands {
+                let total = total_current
+                    .entry(*sideband)
+                    .or_insert_with(|| vec![Complex64::zero(); output.freq.len()]);
+                for (i, current) in pac_current.iter().enumerate() {
+                    total[i] += *current;
+                }
+            }
+        }
+        pac::Current(
+            total_current
+                .into_iter()
+                .map(|(sideband, current)| (sideband, Arc::new(current)))
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; SimSignal})]
+impl<T> Save<Spectre, Pac, T> for pac::Current {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pac_current(to_save)
+    }
+}
+
+impl Save<Spectre, Pac, &SliceOnePath> for pac::Current {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pac_current(SimSignal::ScirCurrent(to_save.clone()))
+    }
+}
+
+impl Save<Spectre, Pac, &ConvertedNodePath> for pac::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+impl Save<Spectre, Pac, &TerminalPath> for pac::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &TerminalPath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        CurrentSavedKey(
+            ctx.lib
+                .convert_terminal_path(to_save)
+                .unwrap()
+                .into_iter()
+                .flat_map(|path| Self::save(ctx, path, opts).0)
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({SliceOnePath; ConvertedNodePath; TerminalPath})]
+impl<T> Save<Spectre, Pac, T> for pac::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({NestedTerminal; &NestedTerminal})]
+impl<T> Save<Spectre, Pac, T> for pac::Current {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for Pac {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Pac {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -0,0 +1,196 @@
+//! Spectre periodic noise analysis options and data structures.
+
+use crate::analysis::ac::Sweep;
+use crate::analysis::noise::{ContributionSavedKey, Device, Node};
+use crate::Spectre;
+use arcstr::ArcStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::schematic::{ExportsNestedData, InstancePath, NestedInstance};
+use substrate::simulation::data::{pnoise, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A periodic noise (PNoise) analysis.
+///
+/// Must be preceded by a [`Pss`](crate::analysis::pss::Pss) analysis in the same simulation.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Pnoise {
+    /// The positive output node.
+    pub output: Node,
+    /// The negative output node.
+    ///
+    /// Defaults to ground.
+    pub output_ref: Option<Node>,
+    /// The independent source to which input-referred noise is referred.
+    pub input_source: Device,
+    /// Start frequency (Hz).
+    pub start: Decimal,
+    /// Stop frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+    /// The maximum sideband included when folding noise onto the output.
+    pub maxsideband: Option<usize>,
+}
+
+/// The result of a periodic noise analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The frequency points of the periodic noise simulation.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to values.
+    ///
+    /// Device noise contributions are named `{device}:{source}`,
+    /// with the total contribution of each device named `{device}:total`.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Spectre, Pnoise> for Output {
+    type SavedKey = ();
+    fn from_saved(output: &<Pnoise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Pnoise, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pnoise> for pnoise::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Pnoise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pnoise::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Spectre, Pnoise, ()> for pnoise::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pnoise> for pnoise::OutputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Pnoise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pnoise::OutputNoise(output.raw_values.get("out").unwrap().clone())
+    }
+}
+
+impl Save<Spectre, Pnoise, ()> for pnoise::OutputNoise {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pnoise> for pnoise::InputNoise {
+    type SavedKey = ();
+    fn from_saved(output: &<Pnoise as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pnoise::InputNoise(output.raw_values.get("in").unwrap().clone())
+    }
+}
+
+impl Save<Spectre, Pnoise, ()> for pnoise::InputNoise {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pnoise> for pnoise::Contribution {
+    type SavedKey = ContributionSavedKey;
+    fn from_saved(output: &<Pnoise as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let device = output.saved_values.get(&key.0).unwrap();
+        pnoise::Contribution(
+            output
+                .raw_values
+                .get(&*format!("{device}:total"))
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({&str; &String; ArcStr; String; Device})]
+impl<T> Save<Spectre, Pnoise, T> for pnoise::Contribution {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_noise_contribution(to_save)
+    }
+}
+
+impl Save<Spectre, Pnoise, scir::InstancePath> for pnoise::Contribution {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: scir::InstancePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_noise_contribution(Device::Scir(to_save))
+    }
+}
+
+impl Save<Spectre, Pnoise, &InstancePath> for pnoise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &InstancePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_instance_path(to_save).unwrap(), opts)
+    }
+}
+
+impl<B: ExportsNestedData> Save<Spectre, Pnoise, &NestedInstance<B>> for pnoise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &NestedInstance<B>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl<B: ExportsNestedData> Save<Spectre, Pnoise, NestedInstance<B>> for pnoise::Contribution {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: NestedInstance<B>,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for Pnoise {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Pnoise {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
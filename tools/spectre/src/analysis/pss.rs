@@ -0,0 +1,372 @@
+//! Spectre periodic steady-state analysis options and data structures.
+
+use crate::{ErrPreset, SimSignal, Spectre};
+use arcstr::ArcStr;
+use num::complex::Complex64;
+use num::Zero;
+use rust_decimal::Decimal;
+use scir::{NamedSliceOne, SliceOnePath};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::io::schematic::{NestedNode, NestedTerminal, NodePath, TerminalPath};
+use substrate::schematic::conv::ConvertedNodePath;
+use substrate::simulation::data::{pss, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+use substrate::type_dispatch::impl_dispatch;
+
+/// A periodic steady-state (PSS) analysis.
+///
+/// Periodic small-signal analyses, such as [`Pac`](crate::analysis::pac::Pac) and
+/// [`Pnoise`](crate::analysis::pnoise::Pnoise), are performed around the steady state
+/// computed by the most recent preceding PSS analysis.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Pss {
+    /// Fundamental frequency (Hz).
+    pub fund: Decimal,
+    /// The number of harmonics of the fundamental to save.
+    pub harms: Option<usize>,
+    /// The duration of the initial transient stabilization (sec).
+    ///
+    /// Defaults to 0.
+    pub tstab: Option<Decimal>,
+
+    /// The error preset.
+    pub errpreset: Option<ErrPreset>,
+}
+
+/// The result of a periodic steady-state analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The time points spanning one period of the steady-state solution.
+    pub time: Arc<Vec<f64>>,
+    /// A map from signal name to time-domain values.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<f64>>>,
+    /// The harmonic frequencies of the steady-state solution.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to the complex amplitude at each harmonic.
+    pub harmonics: HashMap<ArcStr, Arc<Vec<Complex64>>>,
+    /// A map from a save ID to a raw value identifier.
+    pub(crate) saved_values: HashMap<u64, ArcStr>,
+}
+
+impl FromSaved<Spectre, Pss> for Output {
+    type SavedKey = ();
+
+    fn from_saved(output: &<Pss as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Pss, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pss> for pss::Time {
+    type SavedKey = ();
+    fn from_saved(output: &<Pss as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pss::Time(output.time.clone())
+    }
+}
+
+impl Save<Spectre, Pss, ()> for pss::Time {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Pss> for pss::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Pss as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        pss::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Spectre, Pss, ()> for pss::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+/// An identifier for a saved PSS voltage.
+///
+/// Can be used to recover both a [`pss::Voltage`] and a [`pss::HarmonicVoltage`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoltageSavedKey(pub(crate) u64);
+
+impl FromSaved<Spectre, Pss> for pss::Voltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<Pss as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        pss::Voltage(
+            output
+                .raw_values
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+impl FromSaved<Spectre, Pss> for pss::HarmonicVoltage {
+    type SavedKey = VoltageSavedKey;
+    fn from_saved(output: &<Pss as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        pss::HarmonicVoltage(
+            output
+                .harmonics
+                .get(output.saved_values.get(&key.0).unwrap())
+                .unwrap()
+                .clone(),
+        )
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage}, {&str; &String; ArcStr; String; SimSignal})]
+impl<V, T> Save<Spectre, Pss, T> for V {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pss_voltage(to_save)
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage})]
+impl<V> Save<Spectre, Pss, &SliceOnePath> for V {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pss_voltage(SimSignal::ScirVoltage(to_save.clone()))
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage})]
+impl<V> Save<Spectre, Pss, &ConvertedNodePath> for V {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage})]
+impl<V> Save<Spectre, Pss, &NodePath> for V {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &NodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, ctx.lib.convert_node_path(to_save).unwrap(), opts)
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage}, {SliceOnePath; ConvertedNodePath; NodePath})]
+impl<V, T> Save<Spectre, Pss, T> for V {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage}, {NestedNode; &NestedNode; NestedTerminal; &NestedTerminal})]
+impl<V, T> Save<Spectre, Pss, T> for V {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+#[impl_dispatch({pss::Voltage; pss::HarmonicVoltage}, {TerminalPath; &TerminalPath})]
+impl<V, T> Save<Spectre, Pss, T> for V {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.as_ref(), opts)
+    }
+}
+
+/// An identifier for a saved PSS current.
+///
+/// Can be used to recover both a [`pss::Current`] and a [`pss::HarmonicCurrent`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentSavedKey(pub(crate) Vec<u64>);
+
+impl FromSaved<Spectre, Pss> for pss::Current {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<Pss as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let currents: Vec<Arc<Vec<f64>>> = key
+            .0
+            .iter()
+            .map(|key| {
+                output
+                    .raw_values
+                    .get(output.saved_values.get(key).unwrap())
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        let mut total_current = vec![0.; output.time.len()];
+        for pss_current in currents {
+            for (i, current) in pss_current.iter().enumerate() {
+                total_current[i] += *current;
+            }
+        }
+        pss::Current(Arc::new(total_current))
+    }
+}
+
+impl FromSaved<Spectre, Pss> for pss::HarmonicCurrent {
+    type SavedKey = CurrentSavedKey;
+    fn from_saved(output: &<Pss as Analysis>::Output, key: &Self::SavedKey) -> Self {
+        let currents: Vec<Arc<Vec<Complex64>>> = key
+            .0
+            .iter()
+            .map(|key| {
+                output
+                    .harmonics
+                    .get(output.saved_values.get(key).unwrap())
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        let mut total_current = vec![Complex64::zero(); output.freq.len()];
+        for pss_current in currents {
+            for (i, current) in pss_current.iter().enumerate() {
+                total_current[i] += *current;
+            }
+        }
+        pss::HarmonicCurrent(Arc::new(total_current))
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent}, {&str; &String; ArcStr; String; SimSignal})]
+impl<C, T> Save<Spectre, Pss, T> for C {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pss_current(to_save)
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent})]
+impl<C> Save<Spectre, Pss, &SliceOnePath> for C {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        to_save: &SliceOnePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        opts.save_pss_current(SimSignal::ScirCurrent(to_save.clone()))
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent})]
+impl<C> Save<Spectre, Pss, &ConvertedNodePath> for C {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &ConvertedNodePath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(
+            ctx,
+            match to_save {
+                ConvertedNodePath::Cell(path) => path.clone(),
+                ConvertedNodePath::Primitive {
+                    instances, port, ..
+                } => SliceOnePath::new(instances.clone(), NamedSliceOne::new(port.clone())),
+            },
+            opts,
+        )
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent})]
+impl<C> Save<Spectre, Pss, &TerminalPath> for C {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: &TerminalPath,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        CurrentSavedKey(
+            ctx.lib
+                .convert_terminal_path(to_save)
+                .unwrap()
+                .into_iter()
+                .flat_map(|path| Self::save(ctx, path, opts).0)
+                .collect(),
+        )
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent}, {SliceOnePath; ConvertedNodePath; TerminalPath})]
+impl<C, T> Save<Spectre, Pss, T> for C {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, &to_save, opts)
+    }
+}
+
+#[impl_dispatch({pss::Current; pss::HarmonicCurrent}, {NestedTerminal; &NestedTerminal})]
+impl<C, T> Save<Spectre, Pss, T> for C {
+    fn save(
+        ctx: &SimulationContext<Spectre>,
+        to_save: T,
+        opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+        Self::save(ctx, to_save.path(), opts)
+    }
+}
+
+impl Analysis for Pss {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Pss {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -0,0 +1,96 @@
+//! Spectre S-parameter analysis options and data structures.
+
+use crate::analysis::ac::Sweep;
+use crate::analysis::noise::Device;
+use crate::Spectre;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use substrate::simulation::data::{sp, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+
+/// An S-parameter (SP) analysis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Sp {
+    /// Start frequency (Hz).
+    pub start: Decimal,
+    /// Stop frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+    /// The [`Port`](crate::blocks::Port) instances of the network, in order.
+    ///
+    /// Port `i` of the resulting S-matrix corresponds to `ports[i]`.
+    pub ports: Vec<Device>,
+}
+
+/// The result of an S-parameter analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The S-matrix at each frequency point.
+    pub params: sp::SParams,
+}
+
+impl FromSaved<Spectre, Sp> for Output {
+    type SavedKey = ();
+
+    fn from_saved(output: &<Sp as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Sp, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Sp> for sp::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Sp as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        sp::Freq(output.params.freq.clone())
+    }
+}
+
+impl Save<Spectre, Sp, ()> for sp::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Sp> for sp::SParams {
+    type SavedKey = ();
+    fn from_saved(output: &<Sp as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        output.params.clone()
+    }
+}
+
+impl Save<Spectre, Sp, ()> for sp::SParams {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl Analysis for Sp {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Sp {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -0,0 +1,111 @@
+//! Spectre stability analysis options and data structures.
+
+use crate::analysis::ac::Sweep;
+use crate::analysis::noise::Device;
+use crate::{ErrPreset, Spectre};
+use arcstr::ArcStr;
+use num::complex::Complex64;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use substrate::simulation::data::{stb, FromSaved, Save};
+use substrate::simulation::{Analysis, SimulationContext, Simulator, SupportedBy};
+
+/// A stability (STB) analysis.
+///
+/// Computes the loop gain of the feedback loop broken by a probe,
+/// such as an [`Iprobe`](crate::blocks::Iprobe).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Stb {
+    /// Start frequency (Hz).
+    pub start: Decimal,
+    /// Stop frequency (Hz).
+    pub stop: Decimal,
+    /// The sweep kind and number of points.
+    pub sweep: Sweep,
+    /// The probe instance placed in the feedback loop.
+    pub probe: Device,
+
+    /// The error preset.
+    pub errpreset: Option<ErrPreset>,
+}
+
+/// The result of a stability analysis.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The frequency points of the stability simulation.
+    pub freq: Arc<Vec<f64>>,
+    /// A map from signal name to values.
+    ///
+    /// The loop gain is named `loopGain`.
+    pub raw_values: HashMap<ArcStr, Arc<Vec<Complex64>>>,
+}
+
+impl FromSaved<Spectre, Stb> for Output {
+    type SavedKey = ();
+
+    fn from_saved(output: &<Stb as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        (*output).clone()
+    }
+}
+
+impl Save<Spectre, Stb, ()> for Output {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Stb> for stb::Freq {
+    type SavedKey = ();
+    fn from_saved(output: &<Stb as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        stb::Freq(output.freq.clone())
+    }
+}
+
+impl Save<Spectre, Stb, ()> for stb::Freq {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl FromSaved<Spectre, Stb> for stb::LoopGain {
+    type SavedKey = ();
+    fn from_saved(output: &<Stb as Analysis>::Output, _key: &Self::SavedKey) -> Self {
+        stb::LoopGain {
+            freq: output.freq.clone(),
+            gain: output.raw_values.get("loopGain").unwrap().clone(),
+        }
+    }
+}
+
+impl Save<Spectre, Stb, ()> for stb::LoopGain {
+    fn save(
+        _ctx: &SimulationContext<Spectre>,
+        _to_save: (),
+        _opts: &mut <Spectre as Simulator>::Options,
+    ) -> Self::SavedKey {
+    }
+}
+
+impl Analysis for Stb {
+    type Output = Output;
+}
+
+impl SupportedBy<Spectre> for Stb {
+    fn into_input(self, inputs: &mut Vec<<Spectre as Simulator>::Input>) {
+        inputs.push(self.into());
+    }
+    fn from_output(
+        outputs: &mut impl Iterator<Item = <Spectre as Simulator>::Output>,
+    ) -> <Self as Analysis>::Output {
+        let item = outputs.next().unwrap();
+        item.try_into().unwrap()
+    }
+}
@@ -324,6 +324,67 @@ impl Schematic<Spectre> for Iprobe {
     }
 }
 
+/// An S-parameter port.
+///
+/// Ports are referenced by [`Sp`](crate::analysis::sp::Sp) analyses.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Port {
+    /// The reference resistance of the port (ohms).
+    pub r: Decimal,
+}
+
+impl Port {
+    /// Creates a new port with the given reference resistance.
+    #[inline]
+    pub fn new(r: Decimal) -> Self {
+        Self { r }
+    }
+}
+
+impl Default for Port {
+    fn default() -> Self {
+        Self::new(Decimal::from(50))
+    }
+}
+
+impl Block for Port {
+    type Io = TwoTerminalIo;
+
+    fn id() -> arcstr::ArcStr {
+        arcstr::literal!("port")
+    }
+    fn name(&self) -> arcstr::ArcStr {
+        // `port` is a reserved Spectre keyword,
+        // so we call this block `userport`.
+        arcstr::format!("userport")
+    }
+    fn io(&self) -> Self::Io {
+        Default::default()
+    }
+}
+
+impl ExportsNestedData for Port {
+    type NestedData = ();
+}
+
+impl Schematic<Spectre> for Port {
+    fn schematic(
+        &self,
+        io: &<<Self as Block>::Io as HardwareType>::Bundle,
+        cell: &mut CellBuilder<Spectre>,
+    ) -> substrate::error::Result<Self::NestedData> {
+        let mut prim = PrimitiveBinding::new(Primitive::RawInstance {
+            cell: arcstr::literal!("port"),
+            ports: vec!["p".into(), "n".into()],
+            params: HashMap::from_iter([(arcstr::literal!("r"), ParamValue::Numeric(self.r))]),
+        });
+        prim.connect("p", io.p);
+        prim.connect("n", io.n);
+        cell.set_primitive(prim);
+        Ok(())
+    }
+}
+
 /// An n-port black box.
 #[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub struct Nport {
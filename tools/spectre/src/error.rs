@@ -22,6 +22,12 @@ pub enum Error {
     /// Error parsing output files.
     #[error("error parsing Spectre output file")]
     Parse,
+    /// Error parsing a Touchstone file.
+    #[error("error parsing Touchstone file: {0}")]
+    Touchstone(String),
+    /// An analysis that cannot be run inside a sweep or Monte Carlo analysis.
+    #[error("{0} analyses cannot be run inside a sweep or Monte Carlo analysis")]
+    UnsupportedNestedAnalysis(&'static str),
     /// Error generating results.
     #[error("error generating spectre results")]
     Generator(#[from] Arc<Error>),
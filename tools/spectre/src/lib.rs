@@ -10,10 +10,27 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::analysis::ac::{Ac, Sweep};
+use crate::analysis::dc::{DcSweep, SweepPoints, SweepVar};
 use crate::analysis::montecarlo;
 use crate::analysis::montecarlo::MonteCarlo;
+use crate::analysis::noise::{Device, Noise};
+use crate::analysis::op::Op;
+use crate::analysis::pac::Pac;
+use crate::analysis::pnoise::Pnoise;
+use crate::analysis::pss::Pss;
+use crate::analysis::sp::Sp;
+use crate::analysis::stb::Stb;
+use crate::analysis::sweep;
 
 use analysis::ac;
+use analysis::dc;
+use analysis::noise;
+use analysis::op;
+use analysis::pac;
+use analysis::pnoise;
+use analysis::pss;
+use analysis::sp;
+use analysis::stb;
 use analysis::tran;
 use analysis::tran::Tran;
 use arcstr::ArcStr;
@@ -23,10 +40,14 @@ use error::*;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use num::complex::Complex64;
+use parser::ParsedSpectre;
+use psf::SweptData;
 use psfparser::analysis::ac::AcData;
 use psfparser::analysis::transient::TransientData;
 use regex::Regex;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
+use scir::rename::RenameSchema;
 use scir::schema::{FromSchema, NoSchema, NoSchemaError};
 use scir::{
     Library, NamedSliceOne, NetlistLibConversion, ParamValue, SignalInfo, Slice, SliceOnePath,
@@ -56,10 +77,15 @@ pub mod analysis;
 pub mod blocks;
 pub mod dspf;
 pub mod error;
+pub mod parser;
+pub(crate) mod psf;
 pub(crate) mod templates;
+#[cfg(test)]
+mod tests;
+pub mod touchstone;
 
 /// Spectre primitives.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Primitive {
     /// A raw instance with an associated cell.
     RawInstance {
@@ -68,6 +94,7 @@ pub enum Primitive {
         /// The ordered ports of the instance.
         ports: Vec<ArcStr>,
         /// Parameters associated with the instance.
+        #[serde(serialize_with = "scir::serialization::serialize_sorted")]
         params: HashMap<ArcStr, ParamValue>,
     },
     /// A raw instance with an associated cell represented in SPF format.
@@ -185,6 +212,7 @@ pub struct Spectre {}
 pub struct Options {
     includes: HashSet<Include>,
     saves: HashMap<SimSignal, u64>,
+    noise_contributions: HashMap<Device, u64>,
     ics: HashMap<SimSignal, Decimal>,
     next_save_key: u64,
     /// The simulation temperature.
@@ -283,6 +311,62 @@ impl Options {
         ac::CurrentSavedKey(vec![self.save_inner(save)])
     }
 
+    /// Marks a DC sweep voltage to be saved in all DC sweep analyses.
+    pub fn save_dc_voltage(&mut self, save: impl Into<SimSignal>) -> dc::VoltageSavedKey {
+        dc::VoltageSavedKey(self.save_inner(save))
+    }
+
+    /// Marks a DC sweep current to be saved in all DC sweep analyses.
+    pub fn save_dc_current(&mut self, save: impl Into<SimSignal>) -> dc::CurrentSavedKey {
+        dc::CurrentSavedKey(vec![self.save_inner(save)])
+    }
+
+    /// Marks a PSS voltage to be saved in all PSS analyses.
+    pub fn save_pss_voltage(&mut self, save: impl Into<SimSignal>) -> pss::VoltageSavedKey {
+        pss::VoltageSavedKey(self.save_inner(save))
+    }
+
+    /// Marks a PSS current to be saved in all PSS analyses.
+    pub fn save_pss_current(&mut self, save: impl Into<SimSignal>) -> pss::CurrentSavedKey {
+        pss::CurrentSavedKey(vec![self.save_inner(save)])
+    }
+
+    /// Marks a PAC voltage to be saved in all PAC analyses.
+    pub fn save_pac_voltage(&mut self, save: impl Into<SimSignal>) -> pac::VoltageSavedKey {
+        pac::VoltageSavedKey(self.save_inner(save))
+    }
+
+    /// Marks a PAC current to be saved in all PAC analyses.
+    pub fn save_pac_current(&mut self, save: impl Into<SimSignal>) -> pac::CurrentSavedKey {
+        pac::CurrentSavedKey(vec![self.save_inner(save)])
+    }
+
+    /// Marks the noise contribution of a device to be saved in all noise and periodic noise analyses.
+    pub fn save_noise_contribution(
+        &mut self,
+        device: impl Into<Device>,
+    ) -> noise::ContributionSavedKey {
+        let device = device.into();
+        noise::ContributionSavedKey(if let Some(key) = self.noise_contributions.get(&device) {
+            *key
+        } else {
+            let save_key = self.next_save_key;
+            self.next_save_key += 1;
+            self.noise_contributions.insert(device, save_key);
+            save_key
+        })
+    }
+
+    /// Marks a DC operating point voltage to be saved in all DC operating point analyses.
+    pub fn save_op_voltage(&mut self, save: impl Into<SimSignal>) -> op::VoltageSavedKey {
+        op::VoltageSavedKey(self.save_inner(save))
+    }
+
+    /// Marks a DC operating point current to be saved in all DC operating point analyses.
+    pub fn save_op_current(&mut self, save: impl Into<SimSignal>) -> op::CurrentSavedKey {
+        op::CurrentSavedKey(vec![self.save_inner(save)])
+    }
+
     /// Set the simulation temperature.
     pub fn set_temp(&mut self, temp: Decimal) {
         self.temp = Some(temp);
@@ -394,9 +478,41 @@ enum CachedData {
         freq: Vec<f64>,
         signals: HashMap<String, Vec<Complex64>>,
     },
+    Op(HashMap<String, f64>),
+    Dc {
+        sweep: Vec<f64>,
+        nested: Option<Vec<f64>>,
+        signals: HashMap<String, Vec<f64>>,
+    },
+    Noise {
+        freq: Vec<f64>,
+        signals: HashMap<String, Vec<f64>>,
+    },
+    Pss {
+        time: Vec<f64>,
+        signals: HashMap<String, Vec<f64>>,
+        freq: Vec<f64>,
+        harmonics: HashMap<String, Vec<Complex64>>,
+    },
+    Pac {
+        freq: Vec<f64>,
+        signals: HashMap<String, HashMap<i64, Vec<Complex64>>>,
+    },
+    Pnoise {
+        freq: Vec<f64>,
+        signals: HashMap<String, Vec<f64>>,
+    },
+    Stb {
+        freq: Vec<f64>,
+        signals: HashMap<String, Vec<Complex64>>,
+    },
+    Sp(substrate::simulation::data::sp::SParams),
     // The outer vec has length `numruns`.
     // The inner vec length equals the length of the inner analysis.
     MonteCarlo(Vec<Vec<CachedData>>),
+    // The outer vec is indexed by sweep point.
+    // The inner vec length equals the length of the inner analysis.
+    Sweep(Vec<Vec<CachedData>>),
 }
 
 impl CachedData {
@@ -405,6 +521,7 @@ impl CachedData {
         ctx: &SimulationContext<Spectre>,
         conv: &NetlistLibConversion,
         saves: &HashMap<SimSignal, u64>,
+        noise_contributions: &HashMap<Device, u64>,
     ) -> Output {
         match self {
             CachedData::Tran(mut raw_values) => tran::Output {
@@ -431,11 +548,120 @@ impl CachedData {
                     .collect(),
             }
             .into(),
+            CachedData::Op(signals) => op::Output {
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), v))
+                    .collect(),
+                saved_values: saves
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Dc {
+                sweep,
+                nested,
+                signals,
+            } => dc::Output {
+                sweep: Arc::new(sweep),
+                nested: nested.map(Arc::new),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values: saves
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Noise { freq, signals } => noise::Output {
+                freq: Arc::new(freq),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values: noise_contributions
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Pss {
+                time,
+                signals,
+                freq,
+                harmonics,
+            } => pss::Output {
+                time: Arc::new(time),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                freq: Arc::new(freq),
+                harmonics: harmonics
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values: saves
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Pac { freq, signals } => pac::Output {
+                freq: Arc::new(freq),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| {
+                        (
+                            ArcStr::from(k),
+                            v.into_iter().map(|(sb, v)| (sb, Arc::new(v))).collect(),
+                        )
+                    })
+                    .collect(),
+                saved_values: saves
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Pnoise { freq, signals } => pnoise::Output {
+                freq: Arc::new(freq),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+                saved_values: noise_contributions
+                    .iter()
+                    .map(|(k, v)| (*v, k.to_string(&ctx.lib.scir, conv)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Stb { freq, signals } => stb::Output {
+                freq: Arc::new(freq),
+                raw_values: signals
+                    .into_iter()
+                    .map(|(k, v)| (ArcStr::from(k), Arc::new(v)))
+                    .collect(),
+            }
+            .into(),
+            CachedData::Sp(params) => sp::Output { params }.into(),
             CachedData::MonteCarlo(data) => Output::MonteCarlo(montecarlo::Output(
                 data.into_iter()
                     .map(|data| {
                         data.into_iter()
-                            .map(|d| d.into_output(ctx, conv, saves))
+                            .map(|d| d.into_output(ctx, conv, saves, noise_contributions))
+                            .collect()
+                    })
+                    .collect(),
+            )),
+            CachedData::Sweep(data) => Output::Sweep(sweep::Output(
+                data.into_iter()
+                    .map(|data| {
+                        data.into_iter()
+                            .map(|d| d.into_output(ctx, conv, saves, noise_contributions))
                             .collect()
                     })
                     .collect(),
@@ -515,6 +741,55 @@ impl ConvertibleNetlister<Spectre> for Spectre {
 }
 
 impl Spectre {
+    /// Converts [`ParsedSpectre`] to a [`Library`].
+    pub fn scir_lib_from_parsed(parsed: &ParsedSpectre) -> Library<Spectre> {
+        let conv = parser::conv::ScirConverter::new(&parsed.ast);
+        conv.convert().unwrap()
+    }
+
+    /// Converts a Spectre string to a [`Library`].
+    pub fn scir_lib_from_str(source: &str) -> Library<Spectre> {
+        let parsed = parser::Parser::parse(source).unwrap();
+        Spectre::scir_lib_from_parsed(&parsed)
+    }
+
+    /// Converts a Spectre file to a [`Library`].
+    pub fn scir_lib_from_file(path: impl AsRef<Path>) -> Library<Spectre> {
+        let parsed = parser::Parser::parse_file(path).unwrap();
+        Spectre::scir_lib_from_parsed(&parsed)
+    }
+
+    /// Converts [`ParsedSpectre`] to an unconnected [`ScirBinding`](substrate::schematic::ScirBinding)
+    /// associated with the cell named `cell_name`.
+    pub fn scir_cell_from_parsed(
+        parsed: &ParsedSpectre,
+        cell_name: &str,
+    ) -> substrate::schematic::ScirBinding<Spectre> {
+        let lib = Spectre::scir_lib_from_parsed(parsed);
+        let cell_id = lib.cell_id_named(cell_name);
+        substrate::schematic::ScirBinding::new(lib, cell_id)
+    }
+
+    /// Converts a Spectre string to an unconnected [`ScirBinding`](substrate::schematic::ScirBinding)
+    /// associated with the cell named `cell_name`.
+    pub fn scir_cell_from_str(
+        source: &str,
+        cell_name: &str,
+    ) -> substrate::schematic::ScirBinding<Spectre> {
+        let parsed = parser::Parser::parse(source).unwrap();
+        Spectre::scir_cell_from_parsed(&parsed, cell_name)
+    }
+
+    /// Converts a Spectre file to an unconnected [`ScirBinding`](substrate::schematic::ScirBinding)
+    /// associated with the cell named `cell_name`.
+    pub fn scir_cell_from_file(
+        path: impl AsRef<Path>,
+        cell_name: &str,
+    ) -> substrate::schematic::ScirBinding<Spectre> {
+        let parsed = parser::Parser::parse_file(path).unwrap();
+        Spectre::scir_cell_from_parsed(&parsed, cell_name)
+    }
+
     fn simulate(
         &self,
         ctx: &SimulationContext<Self>,
@@ -565,7 +840,12 @@ impl Spectre {
 
         writeln!(w)?;
         for (i, an) in input.iter().enumerate() {
-            an.netlist(&mut w, &subanalysis_name("analysis", i))?;
+            an.netlist(
+                &mut w,
+                &subanalysis_name("analysis", i),
+                &ctx.lib.scir,
+                &conv,
+            )?;
             writeln!(w)?;
         }
         f.write_all(&w)?;
@@ -604,7 +884,9 @@ impl Spectre {
         let conv = Arc::new(conv);
         let outputs = raw_outputs
             .into_iter()
-            .map(|raw_values| raw_values.into_output(ctx, &conv, &options.saves))
+            .map(|raw_values| {
+                raw_values.into_output(ctx, &conv, &options.saves, &options.noise_contributions)
+            })
             .collect();
 
         Ok(outputs)
@@ -692,6 +974,20 @@ impl scir::schema::Schema for Spectre {
     type Primitive = Primitive;
 }
 
+impl RenameSchema for Spectre {
+    fn rename_instance_refs(
+        primitive: &Primitive,
+        rename: &dyn Fn(&str) -> Option<ArcStr>,
+    ) -> Option<Primitive> {
+        match primitive {
+            Primitive::Spice(primitive) => {
+                Spice::rename_instance_refs(primitive, rename).map(Primitive::Spice)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl FromSchema<NoSchema> for Spectre {
     type Error = NoSchemaError;
 
@@ -828,8 +1124,26 @@ pub enum Input {
     Tran(Tran),
     /// AC simulation input.
     Ac(Ac),
+    /// DC operating point simulation input.
+    Op(Op),
+    /// DC sweep simulation input.
+    Dc(DcSweep),
+    /// Noise simulation input.
+    Noise(Noise),
+    /// Periodic steady-state simulation input.
+    Pss(Pss),
+    /// Periodic AC simulation input.
+    Pac(Pac),
+    /// Periodic noise simulation input.
+    Pnoise(Pnoise),
+    /// Stability simulation input.
+    Stb(Stb),
+    /// S-parameter simulation input.
+    Sp(Sp),
     /// A Monte Carlo input.
     MonteCarlo(MonteCarlo<Vec<Input>>),
+    /// A parametric sweep input.
+    Sweep(sweep::Sweep<Vec<Input>>),
 }
 
 impl From<Tran> for Input {
@@ -844,12 +1158,66 @@ impl From<Ac> for Input {
     }
 }
 
+impl From<Op> for Input {
+    fn from(value: Op) -> Self {
+        Self::Op(value)
+    }
+}
+
+impl From<DcSweep> for Input {
+    fn from(value: DcSweep) -> Self {
+        Self::Dc(value)
+    }
+}
+
+impl From<Noise> for Input {
+    fn from(value: Noise) -> Self {
+        Self::Noise(value)
+    }
+}
+
+impl From<Pss> for Input {
+    fn from(value: Pss) -> Self {
+        Self::Pss(value)
+    }
+}
+
+impl From<Pac> for Input {
+    fn from(value: Pac) -> Self {
+        Self::Pac(value)
+    }
+}
+
+impl From<Pnoise> for Input {
+    fn from(value: Pnoise) -> Self {
+        Self::Pnoise(value)
+    }
+}
+
+impl From<Stb> for Input {
+    fn from(value: Stb) -> Self {
+        Self::Stb(value)
+    }
+}
+
+impl From<Sp> for Input {
+    fn from(value: Sp) -> Self {
+        Self::Sp(value)
+    }
+}
+
 impl<A: SupportedBy<Spectre>> From<MonteCarlo<A>> for Input {
     fn from(value: MonteCarlo<A>) -> Self {
         Self::MonteCarlo(value.into())
     }
 }
 
+impl<A: SupportedBy<Spectre>> From<sweep::Sweep<A>> for Input {
+    fn from(value: sweep::Sweep<A>) -> Self {
+        Self::Sweep(value.into())
+    }
+}
+
 /// Outputs directly produced by Spectre.
 #[derive(Debug, Clone)]
 pub enum Output {
@@ -857,8 +1225,26 @@ pub enum Output {
     Tran(tran::Output),
     /// AC simulation output.
     Ac(ac::Output),
+    /// DC operating point simulation output.
+    Op(op::Output),
+    /// DC sweep simulation output.
+    Dc(dc::Output),
+    /// Noise simulation output.
+    Noise(noise::Output),
+    /// Periodic steady-state simulation output.
+    Pss(pss::Output),
+    /// Periodic AC simulation output.
+    Pac(pac::Output),
+    /// Periodic noise simulation output.
+    Pnoise(pnoise::Output),
+    /// Stability simulation output.
+    Stb(stb::Output),
+    /// S-parameter simulation output.
+    Sp(sp::Output),
     /// Monte Carlo simulation output.
     MonteCarlo(montecarlo::Output<Vec<Output>>),
+    /// Parametric sweep output.
+    Sweep(sweep::Output<Vec<Output>>),
 }
 
 impl From<tran::Output> for Output {
@@ -873,6 +1259,54 @@ impl From<ac::Output> for Output {
     }
 }
 
+impl From<op::Output> for Output {
+    fn from(value: op::Output) -> Self {
+        Self::Op(value)
+    }
+}
+
+impl From<dc::Output> for Output {
+    fn from(value: dc::Output) -> Self {
+        Self::Dc(value)
+    }
+}
+
+impl From<noise::Output> for Output {
+    fn from(value: noise::Output) -> Self {
+        Self::Noise(value)
+    }
+}
+
+impl From<pss::Output> for Output {
+    fn from(value: pss::Output) -> Self {
+        Self::Pss(value)
+    }
+}
+
+impl From<pac::Output> for Output {
+    fn from(value: pac::Output) -> Self {
+        Self::Pac(value)
+    }
+}
+
+impl From<pnoise::Output> for Output {
+    fn from(value: pnoise::Output) -> Self {
+        Self::Pnoise(value)
+    }
+}
+
+impl From<stb::Output> for Output {
+    fn from(value: stb::Output) -> Self {
+        Self::Stb(value)
+    }
+}
+
+impl From<sp::Output> for Output {
+    fn from(value: sp::Output) -> Self {
+        Self::Sp(value)
+    }
+}
+
 impl TryFrom<Output> for tran::Output {
     type Error = Error;
     fn try_from(value: Output) -> Result<Self> {
@@ -893,6 +1327,86 @@ impl TryFrom<Output> for ac::Output {
     }
 }
 
+impl TryFrom<Output> for op::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Op(op) => Ok(op),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for dc::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Dc(dc) => Ok(dc),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for noise::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Noise(noise) => Ok(noise),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for pss::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Pss(pss) => Ok(pss),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for pac::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Pac(pac) => Ok(pac),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for pnoise::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Pnoise(pnoise) => Ok(pnoise),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for stb::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Stb(stb) => Ok(stb),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
+impl TryFrom<Output> for sp::Output {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Sp(sp) => Ok(sp),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
 impl From<montecarlo::Output<Vec<Output>>> for Output {
     fn from(value: montecarlo::Output<Vec<Output>>) -> Self {
         Self::MonteCarlo(value)
@@ -909,13 +1423,44 @@ impl TryFrom<Output> for montecarlo::Output<Vec<Output>> {
     }
 }
 
+impl From<sweep::Output<Vec<Output>>> for Output {
+    fn from(value: sweep::Output<Vec<Output>>) -> Self {
+        Self::Sweep(value)
+    }
+}
+
+impl TryFrom<Output> for sweep::Output<Vec<Output>> {
+    type Error = Error;
+    fn try_from(value: Output) -> Result<Self> {
+        match value {
+            Output::Sweep(sweep) => Ok(sweep),
+            _ => Err(Error::SpectreError),
+        }
+    }
+}
+
 impl Input {
-    fn netlist<W: Write>(&self, out: &mut W, name: &str) -> Result<()> {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
         write!(out, "{name} ")?;
         match self {
             Self::Tran(t) => t.netlist(out),
             Input::Ac(ac) => ac.netlist(out),
-            Self::MonteCarlo(mc) => mc.netlist(out, name),
+            Input::Op(op) => op.netlist(out),
+            Input::Dc(dc) => dc.netlist(out, name, lib, conv),
+            Input::Noise(noise) => noise.netlist(out, lib, conv),
+            Input::Pss(pss) => pss.netlist(out),
+            Input::Pac(pac) => pac.netlist(out),
+            Input::Pnoise(pnoise) => pnoise.netlist(out, lib, conv),
+            Input::Stb(stb) => stb.netlist(out, lib, conv),
+            Input::Sp(sp) => sp.netlist(out, name, lib, conv),
+            Self::MonteCarlo(mc) => mc.netlist(out, name, lib, conv),
+            Self::Sweep(sweep) => sweep.netlist(out, name, lib, conv),
         }
     }
 }
@@ -954,12 +1499,299 @@ impl Ac {
     }
 }
 
+impl Op {
+    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+        write!(out, "dc")?;
+        Ok(())
+    }
+}
+
+impl Noise {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        let output_ref = self
+            .output_ref
+            .as_ref()
+            .map(|node| node.to_string(lib, conv))
+            .unwrap_or_else(|| "0".to_string());
+        write!(
+            out,
+            "({} {output_ref}) noise start={} stop={}",
+            self.output.to_string(lib, conv),
+            self.start,
+            self.stop
+        )?;
+        match self.sweep {
+            Sweep::Linear(pts) => write!(out, " lin={pts}")?,
+            Sweep::Logarithmic(pts) => write!(out, " log={pts}")?,
+            Sweep::Decade(pts) => write!(out, " dec={pts}")?,
+        };
+        write!(out, " iprobe={}", self.input_source.to_string(lib, conv))?;
+        if let Some(errpreset) = self.errpreset {
+            write!(out, " errpreset={errpreset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Pss {
+    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+        write!(out, "pss fund={}", self.fund)?;
+        if let Some(harms) = self.harms {
+            write!(out, " harms={harms}")?;
+        }
+        if let Some(tstab) = self.tstab {
+            write!(out, " tstab={tstab}")?;
+        }
+        if let Some(errpreset) = self.errpreset {
+            write!(out, " errpreset={errpreset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Pac {
+    fn netlist<W: Write>(&self, out: &mut W) -> Result<()> {
+        write!(out, "pac start={} stop={}", self.start, self.stop)?;
+        match self.sweep {
+            Sweep::Linear(pts) => write!(out, " lin={pts}")?,
+            Sweep::Logarithmic(pts) => write!(out, " log={pts}")?,
+            Sweep::Decade(pts) => write!(out, " dec={pts}")?,
+        };
+        // Report all sidebands against the input frequency so that they share a sweep.
+        write!(
+            out,
+            " sidevec=[{}] freqaxis=in",
+            self.sidebands.iter().join(" ")
+        )?;
+        Ok(())
+    }
+}
+
+impl Pnoise {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        let output_ref = self
+            .output_ref
+            .as_ref()
+            .map(|node| node.to_string(lib, conv))
+            .unwrap_or_else(|| "0".to_string());
+        write!(
+            out,
+            "({} {output_ref}) pnoise start={} stop={}",
+            self.output.to_string(lib, conv),
+            self.start,
+            self.stop
+        )?;
+        match self.sweep {
+            Sweep::Linear(pts) => write!(out, " lin={pts}")?,
+            Sweep::Logarithmic(pts) => write!(out, " log={pts}")?,
+            Sweep::Decade(pts) => write!(out, " dec={pts}")?,
+        };
+        if let Some(maxsideband) = self.maxsideband {
+            write!(out, " maxsideband={maxsideband}")?;
+        }
+        write!(out, " iprobe={}", self.input_source.to_string(lib, conv))?;
+        Ok(())
+    }
+}
+
+impl Stb {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        write!(out, "stb start={} stop={}", self.start, self.stop)?;
+        match self.sweep {
+            Sweep::Linear(pts) => write!(out, " lin={pts}")?,
+            Sweep::Logarithmic(pts) => write!(out, " log={pts}")?,
+            Sweep::Decade(pts) => write!(out, " dec={pts}")?,
+        };
+        write!(out, " probe={}", self.probe.to_string(lib, conv))?;
+        if let Some(errpreset) = self.errpreset {
+            write!(out, " errpreset={errpreset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Sp {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        write!(out, "sp start={} stop={}", self.start, self.stop)?;
+        match self.sweep {
+            Sweep::Linear(pts) => write!(out, " lin={pts}")?,
+            Sweep::Logarithmic(pts) => write!(out, " log={pts}")?,
+            Sweep::Decade(pts) => write!(out, " dec={pts}")?,
+        };
+        write!(
+            out,
+            " ports=[{}]",
+            self.ports
+                .iter()
+                .map(|port| port.to_string(lib, conv))
+                .join(" ")
+        )?;
+        // Spectre runs in the simulation working directory,
+        // so this places the Touchstone file next to the PSF output.
+        write!(
+            out,
+            " file=\"psf/{}\" datafmt=touchstone datatype=realimag",
+            sp_file_name(name, self)
+        )?;
+        Ok(())
+    }
+}
+
+fn sp_file_name(name: &str, analysis: &Sp) -> String {
+    format!("{name}.s{}p", analysis.ports.len())
+}
+
+impl DcSweep {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        if let Some(ref outer) = self.nested {
+            // The outer sweep points are listed explicitly so that the
+            // number of points and their values are known when parsing.
+            write!(out, "sweep")?;
+            outer.netlist_var(out, lib, conv)?;
+            write!(out, " values=[{}]", outer.points.values().iter().join(" "))?;
+            write!(out, " {{\n\t{} dc", subanalysis_name(name, 0))?;
+            self.sweep.netlist(out, lib, conv)?;
+            write!(out, "\n}}")?;
+        } else {
+            write!(out, "dc")?;
+            self.sweep.netlist(out, lib, conv)?;
+        }
+        Ok(())
+    }
+}
+
+impl dc::Sweep {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        self.netlist_var(out, lib, conv)?;
+        match &self.points {
+            SweepPoints::Linear { start, stop, step } => {
+                write!(out, " start={start} stop={stop} step={step}")?
+            }
+            SweepPoints::Logarithmic {
+                start,
+                stop,
+                points,
+            } => write!(out, " start={start} stop={stop} log={points}")?,
+            SweepPoints::Decade {
+                start,
+                stop,
+                points,
+            } => write!(out, " start={start} stop={stop} dec={points}")?,
+            SweepPoints::List(values) => write!(out, " values=[{}]", values.iter().join(" "))?,
+        }
+        Ok(())
+    }
+
+    fn netlist_var<W: Write>(
+        &self,
+        out: &mut W,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        match &self.var {
+            SweepVar::RawSource(source) => write!(out, " dev={source} param=dc")?,
+            SweepVar::Source(path) => write!(
+                out,
+                " dev={} param=dc",
+                Spectre::instance_path(lib, conv, path)
+            )?,
+            SweepVar::Temp => write!(out, " param=temp")?,
+            SweepVar::Param(param) => write!(out, " param={param}")?,
+        }
+        Ok(())
+    }
+}
+
+impl SweepPoints {
+    /// Returns the values of all sweep points.
+    fn values(&self) -> Vec<f64> {
+        match self {
+            SweepPoints::Linear { start, stop, step } => {
+                let count = if step.is_zero() {
+                    0
+                } else {
+                    ((*stop - *start) / *step).floor().to_usize().unwrap_or(0)
+                };
+                (0..=count)
+                    .map(|idx| (*start + Decimal::from(idx) * *step).to_f64().unwrap())
+                    .collect()
+            }
+            SweepPoints::Logarithmic {
+                start,
+                stop,
+                points,
+            } => {
+                let (start, stop) = (start.to_f64().unwrap(), stop.to_f64().unwrap());
+                (0..*points)
+                    .map(|idx| {
+                        start * (stop / start).powf(idx as f64 / (*points as f64 - 1.).max(1.))
+                    })
+                    .collect()
+            }
+            SweepPoints::Decade {
+                start,
+                stop,
+                points,
+            } => {
+                let (start, stop) = (start.to_f64().unwrap(), stop.to_f64().unwrap());
+                let count = ((stop / start).log10() * *points as f64 + 1e-9).floor() as usize;
+                (0..=count)
+                    .map(|idx| start * 10f64.powf(idx as f64 / *points as f64))
+                    .collect()
+            }
+            SweepPoints::List(values) => values.iter().map(|v| v.to_f64().unwrap()).collect(),
+        }
+    }
+}
+
 fn subanalysis_name(prefix: &str, idx: usize) -> String {
     format!("{prefix}_{idx}")
 }
 
 fn parse_analysis(output_dir: &Path, name: &str, analysis: &Input) -> Result<CachedData> {
-    Ok(if let Input::MonteCarlo(analysis) = analysis {
+    Ok(if let Input::Dc(analysis) = analysis {
+        parse_dc_sweep(output_dir, name, analysis)?
+    } else if let Input::Pss(_) = analysis {
+        parse_pss(output_dir, name)?
+    } else if let Input::Pac(analysis) = analysis {
+        parse_pac(output_dir, name, analysis)?
+    } else if let Input::Sp(analysis) = analysis {
+        CachedData::Sp(touchstone::read(
+            output_dir.join(sp_file_name(name, analysis)),
+        )?)
+    } else if let Input::MonteCarlo(analysis) = analysis {
         let mut data = Vec::new();
         for iter in 1..analysis.numruns + 1 {
             let mut mc_data = Vec::new();
@@ -975,16 +1807,56 @@ fn parse_analysis(output_dir: &Path, name: &str, analysis: &Input) -> Result<Cac
             data.push(mc_data);
         }
         CachedData::MonteCarlo(data)
+    } else if let Input::Sweep(analysis) = analysis {
+        // Each sweep point writes its own copy of the inner analyses' results.
+        let mut data = Vec::new();
+        for idx in 0..analysis.values.len() {
+            let mut point_data = Vec::new();
+            for (i, inner) in analysis.analysis.iter().enumerate() {
+                let new_name = format!("{name}-{idx:0>3}_{}", subanalysis_name(name, i));
+                point_data.push(parse_analysis(output_dir, &new_name, inner)?);
+            }
+            data.push(point_data);
+        }
+        CachedData::Sweep(data)
     } else {
         let file_name = match analysis {
             Input::Tran(_) => {
                 format!("{name}.tran.tran")
             }
             Input::Ac(_) => format!("{name}.ac"),
-            Input::MonteCarlo(_) => unreachable!(),
+            Input::Op(_) => format!("{name}.dc"),
+            Input::Noise(_) => format!("{name}.noise"),
+            Input::Pnoise(_) => format!("{name}.pnoise"),
+            Input::Stb(_) => format!("{name}.stb"),
+            Input::Dc(_)
+            | Input::Pss(_)
+            | Input::Pac(_)
+            | Input::Sp(_)
+            | Input::MonteCarlo(_)
+            | Input::Sweep(_) => {
+                unreachable!()
+            }
         };
         let psf_path = output_dir.join(file_name);
         let psf = std::fs::read(psf_path)?;
+        if let Input::Op(_) = analysis {
+            return Ok(CachedData::Op(psf::parse_non_swept(&psf)?));
+        }
+        if let Input::Noise(_) = analysis {
+            let SweptData { sweep, signals } = psf::parse_swept(&psf)?;
+            return Ok(CachedData::Noise {
+                freq: sweep,
+                signals,
+            });
+        }
+        if let Input::Pnoise(_) = analysis {
+            let SweptData { sweep, signals } = psf::parse_swept(&psf)?;
+            return Ok(CachedData::Pnoise {
+                freq: sweep,
+                signals,
+            });
+        }
         let ast = psfparser::binary::parse(&psf).map_err(|_| Error::Parse)?;
 
         match analysis {
@@ -999,15 +1871,134 @@ fn parse_analysis(output_dir: &Path, name: &str, analysis: &Input) -> Result<Cac
                     signals: values.signals,
                 }
             }
-            Input::MonteCarlo(_) => {
+            Input::Stb(_) => {
+                let values = AcData::from_binary(ast);
+                CachedData::Stb {
+                    freq: values.freq,
+                    signals: values.signals,
+                }
+            }
+            Input::Op(_)
+            | Input::Dc(_)
+            | Input::Noise(_)
+            | Input::Pss(_)
+            | Input::Pac(_)
+            | Input::Pnoise(_)
+            | Input::Sp(_)
+            | Input::MonteCarlo(_)
+            | Input::Sweep(_) => {
                 unreachable!()
             }
         }
     })
 }
 
+/// Parses a swept PSF file containing real-valued signals using [`psfparser`].
+fn parse_swept(path: impl AsRef<Path>) -> Result<SweptData> {
+    let psf = std::fs::read(path)?;
+    let ast = psfparser::binary::parse(&psf).map_err(|_| Error::Parse)?;
+    let sweep_name = ast.sweeps.first().ok_or(Error::Parse)?.name.to_string();
+    let mut signals = TransientData::from_binary(ast).signals;
+    let sweep = signals.remove(&sweep_name).ok_or(Error::Parse)?;
+    Ok(SweptData { sweep, signals })
+}
+
+fn parse_dc_sweep(output_dir: &Path, name: &str, analysis: &DcSweep) -> Result<CachedData> {
+    let Some(ref outer) = analysis.nested else {
+        let SweptData { sweep, signals } = parse_swept(output_dir.join(format!("{name}.dc")))?;
+        return Ok(CachedData::Dc {
+            sweep,
+            nested: None,
+            signals,
+        });
+    };
+
+    // Each point of the outer sweep writes its own copy of the inner sweep's results.
+    let inner_name = subanalysis_name(name, 0);
+    let mut sweep = Vec::new();
+    let mut nested = Vec::new();
+    let mut signals: HashMap<String, Vec<f64>> = HashMap::new();
+    for (idx, value) in outer.points.values().into_iter().enumerate() {
+        let data = parse_swept(output_dir.join(format!("{name}-{idx:0>3}_{inner_name}.dc")))?;
+        nested.extend(std::iter::repeat(value).take(data.sweep.len()));
+        sweep.extend(data.sweep);
+        for (signal, values) in data.signals {
+            signals.entry(signal).or_default().extend(values);
+        }
+    }
+    Ok(CachedData::Dc {
+        sweep,
+        nested: Some(nested),
+        signals,
+    })
+}
+
+fn parse_pss(output_dir: &Path, name: &str) -> Result<CachedData> {
+    let SweptData {
+        sweep: time,
+        signals,
+    } = parse_swept(output_dir.join(format!("{name}.td.pss")))?;
+    let psf = std::fs::read(output_dir.join(format!("{name}.fd.pss")))?;
+    let ast = psfparser::binary::parse(&psf).map_err(|_| Error::Parse)?;
+    let harmonics = AcData::from_binary(ast);
+    Ok(CachedData::Pss {
+        time,
+        signals,
+        freq: harmonics.freq,
+        harmonics: harmonics.signals,
+    })
+}
+
+fn parse_pac(output_dir: &Path, name: &str, analysis: &Pac) -> Result<CachedData> {
+    let psf = std::fs::read(output_dir.join(format!("{name}.pac")))?;
+    let ast = psfparser::binary::parse(&psf).map_err(|_| Error::Parse)?;
+    let AcData { freq, signals } = AcData::from_binary(ast);
+
+    // Each sideband is written as a full sweep of the input frequency,
+    // in the order the sidebands were requested.
+    let num_sidebands = analysis.sidebands.len();
+    if num_sidebands == 0 || freq.len() % num_sidebands != 0 {
+        return Err(Error::Parse);
+    }
+    let num_points = freq.len() / num_sidebands;
+    Ok(CachedData::Pac {
+        freq: freq[..num_points].to_vec(),
+        signals: signals
+            .into_iter()
+            .map(|(signal, values)| {
+                (
+                    signal,
+                    analysis
+                        .sidebands
+                        .iter()
+                        .copied()
+                        .zip(values.chunks(num_points).map(|chunk| chunk.to_vec()))
+                        .collect(),
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Checks that the given analyses can be run once per sweep point or Monte Carlo iteration.
+///
+/// S-parameter analyses write to a fixed Touchstone file name, so every point of the
+/// enclosing analysis would overwrite the results of the previous one.
+fn check_nested_analyses(analyses: &[Input]) -> Result<()> {
+    if analyses.iter().any(|an| matches!(an, Input::Sp(_))) {
+        return Err(Error::UnsupportedNestedAnalysis("S-parameter"));
+    }
+    Ok(())
+}
+
 impl MonteCarlo<Vec<Input>> {
-    fn netlist<W: Write>(&self, out: &mut W, name: &str) -> Result<()> {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
         write!(
             out,
             "montecarlo variations={} numruns={} savefamilyplots=yes",
@@ -1021,10 +2012,39 @@ impl MonteCarlo<Vec<Input>> {
         }
         write!(out, " {{")?;
 
+        check_nested_analyses(&self.analysis)?;
         for (i, an) in self.analysis.iter().enumerate() {
             let name = subanalysis_name(name, i);
             write!(out, "\n\t")?;
-            an.netlist(out, &name)?;
+            an.netlist(out, &name, lib, conv)?;
+        }
+        write!(out, "\n}}")?;
+
+        Ok(())
+    }
+}
+
+impl sweep::Sweep<Vec<Input>> {
+    fn netlist<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        lib: &Library<Spectre>,
+        conv: &NetlistLibConversion,
+    ) -> Result<()> {
+        write!(out, "sweep")?;
+        dc::Sweep {
+            var: self.var.clone(),
+            points: SweepPoints::List(self.values.clone()),
+        }
+        .netlist(out, lib, conv)?;
+        write!(out, " {{")?;
+
+        check_nested_analyses(&self.analysis)?;
+        for (i, an) in self.analysis.iter().enumerate() {
+            let name = subanalysis_name(name, i);
+            write!(out, "\n\t")?;
+            an.netlist(out, &name, lib, conv)?;
         }
         write!(out, "\n}}")?;
 
@@ -1091,6 +2111,30 @@ impl HasSpiceLikeNetlist for Spectre {
         Ok(())
     }
 
+    fn write_subckt_params<W: Write>(
+        &self,
+        out: &mut W,
+        params: &[(&ArcStr, &ParamValue)],
+    ) -> std::io::Result<()> {
+        write!(out, "\nparameters")?;
+        for (name, value) in params {
+            self.write_param(out, name, value)?;
+        }
+        Ok(())
+    }
+
+    fn write_top_params<W: Write>(
+        &self,
+        out: &mut W,
+        params: &[(&ArcStr, &ParamValue)],
+    ) -> std::io::Result<()> {
+        write!(out, "parameters")?;
+        for (name, value) in params {
+            self.write_param(out, name, value)?;
+        }
+        Ok(())
+    }
+
     fn write_end_subckt<W: Write>(&self, out: &mut W, name: &ArcStr) -> std::io::Result<()> {
         write!(out, "ends {}", name)
     }
@@ -1133,7 +2177,7 @@ impl HasSpiceLikeNetlist for Spectre {
                     .collect();
                 let name = self.write_instance(out, name, connections, cell)?;
                 for (key, value) in params.iter().sorted_by_key(|(key, _)| *key) {
-                    write!(out, " {key}={value}")?;
+                    self.write_param(out, key, value)?;
                 }
                 name
             }
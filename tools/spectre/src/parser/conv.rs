@@ -0,0 +1,335 @@
+//! Convert Spectre netlists to other formats.
+//!
+//! Currently, we only support converting to SCIR.
+
+use std::collections::{HashMap, HashSet};
+
+use arcstr::ArcStr;
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_decimal::{Decimal, MathematicalOps};
+use scir::param::Expr;
+use scir::ParamValue;
+use thiserror::Error;
+
+use super::{unquote, Ast, Elem, Params, Subckt};
+use crate::{Primitive, Spectre};
+
+/// A Spectre netlist conversion result.
+pub type ConvResult<T> = std::result::Result<T, ConvError>;
+
+/// A Spectre netlist conversion error.
+#[derive(Debug, Error)]
+pub enum ConvError {
+    /// Incorrect (missing/extra) connections for an instance.
+    #[error("incorrect (missing/extra) connections for instance {inst} of cell `{child}` (in cell `{parent}`)")]
+    IncorrectConnections {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the cell being instantiated.
+        child: ArcStr,
+        /// The name of the cell containing the offending instance.
+        parent: ArcStr,
+    },
+    /// An instance of a non-blackbox cell sets a parameter that the cell does not declare.
+    #[error("instance {inst} of cell `{child}` (in cell `{parent}`) sets parameter `{param}`, but `{child}` does not declare this parameter")]
+    UndeclaredParam {
+        /// The name of the instance.
+        inst: ArcStr,
+        /// The name of the cell being instantiated.
+        child: ArcStr,
+        /// The name of the cell containing the offending instance.
+        parent: ArcStr,
+        /// The name of the parameter.
+        param: ArcStr,
+    },
+    /// Attempted to export a blackboxed subcircuit.
+    #[error("cannot export a blackboxed subcircuit")]
+    ExportBlackbox,
+    /// Error converting the SPICE-syntax blocks of the netlist.
+    #[error("error converting SPICE-syntax blocks: {0}")]
+    Spice(#[from] spice::parser::conv::ConvError),
+    /// Netlist conversion produced invalid SCIR.
+    #[error("netlist conversion produced SCIR containing errors: {0}")]
+    InvalidScir(Box<scir::Issues>),
+}
+
+/// Converts a parsed Spectre netlist to [`scir`].
+///
+/// The converter only converts subcircuits.
+/// Top-level instances, parameters and models are ignored.
+///
+/// Subcircuits defined in SPICE-syntax blocks are converted using the
+/// [`spice`] converter, and wrap their primitives in [`Primitive::Spice`].
+/// Instances of masters that are not defined as subcircuits
+/// (e.g. models or built-in primitives such as `resistor`)
+/// become [`Primitive::RawInstance`]s with ports named `1`, `2`, etc.
+pub struct ScirConverter<'a> {
+    ast: &'a Ast,
+    lib: scir::LibraryBuilder<Spectre>,
+    blackbox_cells: HashSet<ArcStr>,
+    subckts: HashMap<ArcStr, &'a Subckt>,
+    ids: HashMap<ArcStr, scir::CellId>,
+}
+
+impl<'a> ScirConverter<'a> {
+    /// Create a new SCIR converter.
+    pub fn new(ast: &'a Ast) -> Self {
+        Self {
+            ast,
+            lib: scir::LibraryBuilder::new(),
+            blackbox_cells: Default::default(),
+            subckts: Default::default(),
+            ids: Default::default(),
+        }
+    }
+
+    /// Blackboxes the given cell.
+    pub fn blackbox(&mut self, cell_name: impl Into<ArcStr>) {
+        self.blackbox_cells.insert(cell_name.into());
+    }
+
+    /// Consumes the converter, yielding a SCIR [library](scir::Library).
+    pub fn convert(mut self) -> ConvResult<scir::Library<Spectre>> {
+        self.convert_spice()?;
+        self.subckts = map_subckts(self.ast);
+        for elem in self.ast.elems.iter() {
+            if let Elem::Subckt(subckt) = elem {
+                let subckt = self.subckts[&subckt.name];
+                match self.convert_subckt(subckt) {
+                    // Export blackbox errors can be ignored; we just skip
+                    // exporting a SCIR cell for blackboxed subcircuits.
+                    Ok(_) | Err(ConvError::ExportBlackbox) => (),
+                    Err(e) => return Err(e),
+                };
+            }
+        }
+        let lib = self
+            .lib
+            .build()
+            .map_err(|issues| ConvError::InvalidScir(Box::new(issues)))?;
+        Ok(lib)
+    }
+
+    /// Converts the subcircuits in SPICE-syntax blocks, adding them to the library.
+    fn convert_spice(&mut self) -> ConvResult<()> {
+        let elems: Vec<_> = self
+            .ast
+            .elems
+            .iter()
+            .filter_map(|elem| match elem {
+                Elem::Spice(ast) => Some(ast.elems.iter().cloned()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        if elems.is_empty() {
+            return Ok(());
+        }
+        let ast = spice::parser::Ast { elems };
+        let mut conv = spice::parser::conv::ScirConverter::new(&ast);
+        for cell in self.blackbox_cells.iter() {
+            conv.blackbox(cell.as_str());
+        }
+        self.lib = conv
+            .convert()?
+            .convert_schema::<Spectre>()
+            .expect("SPICE primitives are always valid Spectre primitives");
+        Ok(())
+    }
+
+    fn convert_subckt(&mut self, subckt: &Subckt) -> ConvResult<scir::CellId> {
+        if let Some(&id) = self.ids.get(&subckt.name) {
+            return Ok(id);
+        }
+
+        if self.blackbox_cells.contains(&subckt.name) {
+            return Err(ConvError::ExportBlackbox);
+        }
+
+        let parent_name = subckt.name.clone();
+        let scope: HashSet<_> = subckt.params.iter().map(|(k, _)| k.clone()).collect();
+
+        let mut cell = scir::Cell::new(subckt.name.clone());
+        for (k, v) in subckt.params.iter() {
+            cell.add_param(k.clone(), str_as_param_value(v, &scope));
+        }
+        let mut nodes: HashMap<ArcStr, scir::SliceOne> = HashMap::new();
+        let mut node = |name: &ArcStr, cell: &mut scir::Cell| {
+            if let Some(&node) = nodes.get(name) {
+                return node;
+            }
+            let id = cell.add_node(name.clone());
+            nodes.insert(name.clone(), id);
+            id
+        };
+
+        for inst in subckt.instances.iter() {
+            let blackbox = self.blackbox_cells.contains(&inst.master);
+            let sinst = if let (false, Some(child)) =
+                (blackbox, self.subckts.get(&inst.master).copied())
+            {
+                let id = self.convert_subckt(child)?;
+                if child.ports.len() != inst.ports.len() {
+                    return Err(ConvError::IncorrectConnections {
+                        inst: inst.name.clone(),
+                        child: child.name.clone(),
+                        parent: parent_name.clone(),
+                    });
+                }
+                let mut sinst = scir::Instance::new(inst.name.clone(), id);
+                for (cport, iport) in child.ports.iter().zip(inst.ports.iter()) {
+                    sinst.connect(cport, node(iport, &mut cell));
+                }
+                for (k, v) in inst.params.iter() {
+                    if !child.params.iter().any(|(name, _)| name == k) {
+                        return Err(ConvError::UndeclaredParam {
+                            inst: inst.name.clone(),
+                            child: child.name.clone(),
+                            parent: parent_name.clone(),
+                            param: k.clone(),
+                        });
+                    }
+                    sinst.set_param(k.clone(), str_as_param_value(v, &scope));
+                }
+                sinst
+            } else if let (false, Some(id)) = (blackbox, self.lib.try_cell_id_named(&inst.master)) {
+                // A subcircuit defined in a SPICE-syntax block.
+                let child = self.lib.cell(id);
+                let ports: Vec<_> = child
+                    .ports()
+                    .map(|port| child.signal(port.signal()).name.clone())
+                    .collect();
+                if ports.len() != inst.ports.len() {
+                    return Err(ConvError::IncorrectConnections {
+                        inst: inst.name.clone(),
+                        child: inst.master.clone(),
+                        parent: parent_name.clone(),
+                    });
+                }
+                let mut sinst = scir::Instance::new(inst.name.clone(), id);
+                for (k, v) in inst.params.iter() {
+                    // SPICE parameter names are case-insensitive.
+                    let param = child
+                        .params()
+                        .map(|(name, _)| name)
+                        .find(|name| name.eq_ignore_ascii_case(k))
+                        .ok_or_else(|| ConvError::UndeclaredParam {
+                            inst: inst.name.clone(),
+                            child: inst.master.clone(),
+                            parent: parent_name.clone(),
+                            param: k.clone(),
+                        })?;
+                    sinst.set_param(param.clone(), str_as_param_value(v, &scope));
+                }
+                for (cport, iport) in ports.iter().zip(inst.ports.iter()) {
+                    sinst.connect(cport, node(iport, &mut cell));
+                }
+                sinst
+            } else {
+                let ports: Vec<_> = (0..inst.ports.len())
+                    .map(|i| arcstr::format!("{}", i + 1))
+                    .collect();
+                // TODO: Deduplicate primitives, though does not affect functionality
+                let id = self.lib.add_primitive(Primitive::RawInstance {
+                    cell: inst.master.clone(),
+                    ports: ports.clone(),
+                    params: params_as_param_values(&inst.params, &scope),
+                });
+                let mut sinst = scir::Instance::new(inst.name.clone(), id);
+                for (cport, iport) in ports.iter().zip(inst.ports.iter()) {
+                    sinst.connect(cport, node(iport, &mut cell));
+                }
+                sinst
+            };
+            cell.add_instance(sinst);
+        }
+
+        for port in subckt.ports.iter() {
+            let port = node(port, &mut cell);
+            // Spectre netlists do not declare port directions,
+            // so we expose all ports using the default direction.
+            cell.expose_port(port, Default::default());
+        }
+
+        let id = self.lib.add_cell(cell);
+        self.ids.insert(subckt.name.clone(), id);
+        Ok(id)
+    }
+}
+
+lazy_static! {
+    static ref NUMERIC_LITERAL_REGEX: Regex = Regex::new(
+        r"^([+-]?(?:[0-9]+\.?[0-9]*|\.[0-9]+))(?:[eE]([+-]?[0-9]+))?([TGMKkm_%cunpfa])?$"
+    )
+    .expect("failed to compile numeric literal regex");
+}
+
+/// Converts a Spectre numeric literal (e.g. `1.5k` or `2e-6`) to a [`Decimal`].
+///
+/// Unlike SPICE, Spectre scale factors are case-sensitive:
+/// `M` is 1e6, while `m` is 1e-3.
+pub(crate) fn convert_str_to_numeric_lit(s: &str) -> Option<Decimal> {
+    let caps = NUMERIC_LITERAL_REGEX.captures(s)?;
+    let num: Decimal = caps.get(1)?.as_str().parse().ok()?;
+    let mut exp: i64 = match caps.get(2) {
+        Some(exp) => exp.as_str().parse().ok()?,
+        None => 0,
+    };
+    exp += match caps.get(3).map(|s| s.as_str()) {
+        Some("T") => 12,
+        Some("G") => 9,
+        Some("M") => 6,
+        Some("K" | "k") => 3,
+        Some("%" | "c") => -2,
+        Some("m") => -3,
+        Some("u") => -6,
+        Some("n") => -9,
+        Some("p") => -12,
+        Some("f") => -15,
+        Some("a") => -18,
+        _ => 0,
+    };
+    num.checked_mul(Decimal::TEN.checked_powi(exp)?)
+        .map(|value| value.normalize())
+}
+
+/// Converts a Spectre parameter value to a SCIR [`ParamValue`].
+///
+/// Numeric literals become numeric values, and expressions that only reference
+/// parameters in `scope` become [`Expr`]s. String literals have their quotes removed.
+/// All other values (such as model names, vectors, or expressions referencing
+/// top-level parameters) are kept as strings.
+fn str_as_param_value(s: &ArcStr, scope: &HashSet<ArcStr>) -> ParamValue {
+    if let Some(v) = convert_str_to_numeric_lit(s) {
+        return ParamValue::Numeric(v);
+    }
+    if s.starts_with('"') {
+        return ParamValue::String(unquote(s).into());
+    }
+    match Expr::parse(s) {
+        Ok(expr) if expr.referenced_params().iter().all(|p| scope.contains(*p)) => {
+            ParamValue::Expr(expr)
+        }
+        _ => ParamValue::String(s.clone()),
+    }
+}
+
+fn params_as_param_values(params: &Params, scope: &HashSet<ArcStr>) -> HashMap<ArcStr, ParamValue> {
+    params
+        .iter()
+        .map(|(k, v)| (k.clone(), str_as_param_value(v, scope)))
+        .collect()
+}
+
+pub(crate) fn map_subckts(ast: &Ast) -> HashMap<ArcStr, &Subckt> {
+    let mut subckts = HashMap::new();
+    for elem in ast.elems.iter() {
+        if let Elem::Subckt(s) = elem {
+            if subckts.insert(s.name.clone(), s).is_some() {
+                tracing::warn!(name=%s.name, "Duplicate subcircuits: found two subcircuits with the same name. The last one found will be used.");
+            }
+        }
+    }
+    subckts
+}
@@ -0,0 +1,823 @@
+//! Spectre netlist parser.
+//!
+//! Supports the native Spectre netlist syntax used by legacy IP and foundry
+//! reference circuits: `subckt`/`ends` definitions, instances of the form
+//! `name (nodes) master param=value`, `parameters`, `model` and `global` statements,
+//! and `include` statements with an optional `section=`.
+//!
+//! Top-level blocks written in SPICE syntax (between `simulator lang=spice` and
+//! `simulator lang=spectre`) are parsed using the [`spice`] parser.
+
+pub mod conv;
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use arcstr::ArcStr;
+use indexmap::IndexMap;
+use spice::parser::Dialect;
+use thiserror::Error;
+
+use self::conv::ScirConverter;
+use crate::Spectre;
+
+/// The type representing nodes in a parsed Spectre circuit.
+pub type Node = ArcStr;
+
+/// Parses Spectre netlists.
+#[derive(Clone, Default, Debug)]
+pub struct Parser {
+    ast: Ast,
+    include_stack: Vec<PathBuf>,
+    /// Subcircuit definitions that have not yet been closed by `ends`.
+    ///
+    /// Nested subcircuit definitions are hoisted to the top level.
+    subckts: Vec<Subckt>,
+}
+
+/// Data associated with parsing a Spectre file.
+pub struct ParsedSpectre {
+    /// The parsed contents of the Spectre file.
+    pub ast: Ast,
+
+    /// The file path at the root of the `include` tree.
+    pub root: Option<PathBuf>,
+}
+
+/// The abstract syntax tree (AST) of a parsed Spectre netlist.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Ast {
+    /// The list of elements in the Spectre netlist.
+    pub elems: Vec<Elem>,
+}
+
+/// An element of a Spectre netlist AST.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Elem {
+    /// A subcircuit definition.
+    Subckt(Subckt),
+    /// A top-level instance.
+    ///
+    /// Analyses and control statements such as `tran tran stop=1u`
+    /// are also parsed as top-level instances.
+    Instance(Instance),
+    /// Top-level parameters and their values.
+    Parameters(Params),
+    /// A model definition.
+    Model(Model),
+    /// Global nodes.
+    Global(Vec<Node>),
+    /// A block of SPICE-syntax statements.
+    Spice(spice::parser::Ast),
+}
+
+/// The contents of a subcircuit.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Subckt {
+    /// The subcircuit name.
+    pub name: ArcStr,
+    /// The list of ports.
+    ///
+    /// Each port is a node exposed by this subcircuit.
+    pub ports: Vec<Node>,
+    /// Subcircuit parameters and their default values.
+    pub params: Params,
+    /// Instances in the subcircuit.
+    pub instances: Vec<Instance>,
+    /// Whether the subcircuit was declared using `inline subckt`.
+    pub inline: bool,
+}
+
+/// An instance of a subcircuit, model or built-in primitive.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instance {
+    /// The name of the instance.
+    pub name: ArcStr,
+    /// The list of port connections.
+    pub ports: Vec<Node>,
+    /// The name of the subcircuit, model or primitive being instantiated.
+    pub master: ArcStr,
+    /// Instance parameters.
+    pub params: Params,
+}
+
+/// A model definition.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Model {
+    /// The name of the model.
+    pub name: ArcStr,
+    /// The built-in primitive the model is based on (e.g. `bsim4`).
+    pub master: ArcStr,
+    /// Model parameters.
+    pub params: Params,
+}
+
+/// Parameter values.
+///
+/// Values are stored as written. String values keep their enclosing quotes.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Params {
+    /// A map of key-value pairs, in the order they were declared.
+    values: IndexMap<ArcStr, ArcStr>,
+}
+
+/// A Spectre token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Token {
+    /// An identifier, number, parameter value or string literal.
+    ///
+    /// String literals keep their enclosing quotes.
+    /// Escape sequences in identifiers are kept as written.
+    Ident(ArcStr),
+    /// An opening parenthesis.
+    LParen,
+    /// A closing parenthesis.
+    RParen,
+    /// An equal sign.
+    Equals,
+}
+
+/// An error arising from parsing a Spectre netlist.
+#[derive(Debug, Error)]
+pub enum ParserError {
+    /// A syntax error.
+    #[error("syntax error on line {line}: {message}")]
+    Syntax {
+        /// The line on which the error occurred.
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+    /// An error parsing a block of SPICE-syntax statements.
+    #[error("error parsing SPICE block starting on line {line}: {err}")]
+    Spice {
+        /// The line on which the SPICE block starts.
+        line: usize,
+        /// The underlying error.
+        #[source]
+        err: spice::parser::ParserError,
+    },
+    /// A relative path was used in an unsupported position.
+    ///
+    /// For example, relative paths are forbidden when parsing inline Spectre.
+    #[error("unexpected relative path: {0:?}")]
+    UnexpectedRelativePath(ArcStr),
+    /// An included file does not contain the requested section.
+    #[error("file at path `{path:?}` has no section named `{section}`")]
+    MissingSection {
+        /// The path of the included file.
+        path: PathBuf,
+        /// The name of the requested section.
+        section: ArcStr,
+    },
+    /// Error trying to read the given file.
+    #[error("failed to read file at path `{path:?}`: {err:?}")]
+    FailedToRead {
+        /// The path we attempted to read.
+        path: PathBuf,
+        /// The underlying error.
+        #[source]
+        err: std::io::Error,
+    },
+}
+
+/// The language of the statements being parsed.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum Lang {
+    #[default]
+    Spectre,
+    Spice,
+}
+
+impl Parser {
+    /// Parse the given string.
+    pub fn parse(data: impl Into<ArcStr>) -> Result<ParsedSpectre, ParserError> {
+        let mut parser = Self::default();
+        parser.parse_inner(data.into(), None)?;
+        parser.finish(None)
+    }
+
+    /// Parse the given file.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<ParsedSpectre, ParserError> {
+        let path = path.as_ref();
+        let mut parser = Self::default();
+        parser.parse_file_inner(path, None)?;
+        parser.finish(Some(path.to_path_buf()))
+    }
+
+    /// Parse only the given section of the given file.
+    ///
+    /// Equivalent to `include "path" section=section`.
+    pub fn parse_file_section(
+        path: impl AsRef<Path>,
+        section: impl Into<ArcStr>,
+    ) -> Result<ParsedSpectre, ParserError> {
+        let path = path.as_ref();
+        let mut parser = Self::default();
+        parser.parse_file_inner(path, Some(&section.into()))?;
+        parser.finish(Some(path.to_path_buf()))
+    }
+
+    fn finish(self, root: Option<PathBuf>) -> Result<ParsedSpectre, ParserError> {
+        if let Some(subckt) = self.subckts.last() {
+            return Err(ParserError::Syntax {
+                line: 0,
+                message: format!("subcircuit `{}` is missing `ends`", subckt.name),
+            });
+        }
+        Ok(ParsedSpectre {
+            ast: self.ast,
+            root,
+        })
+    }
+
+    fn parse_file_inner(
+        &mut self,
+        path: &Path,
+        section: Option<&ArcStr>,
+    ) -> Result<(), ParserError> {
+        tracing::debug!("reading Spectre file: {:?}", path);
+        let s: ArcStr = std::fs::read_to_string(path)
+            .map_err(|err| ParserError::FailedToRead {
+                path: path.into(),
+                err,
+            })?
+            .into();
+        self.include_stack.push(path.into());
+        let res = self.parse_inner(s, section);
+        self.include_stack.pop().unwrap();
+        res
+    }
+
+    /// Parses `data`, skipping statements outside of `section` if a section is given.
+    fn parse_inner(&mut self, data: ArcStr, section: Option<&ArcStr>) -> Result<(), ParserError> {
+        let mut lex = Lexer::new(data);
+        let mut sections: Vec<ArcStr> = Vec::new();
+        let mut found = section.is_none();
+        // The SPICE statements being collected, and the line they start on.
+        let mut spice: Option<(String, usize)> = None;
+        // The nesting depth of skipped `{ ... }` blocks.
+        let mut braces = 0usize;
+
+        loop {
+            let active = section.map_or(true, |s| sections.contains(s));
+            if let Some((buf, _)) = &mut spice {
+                let Some(line) = lex.raw_line() else {
+                    break;
+                };
+                if lang_switch(&line) == Some(Lang::Spectre) {
+                    let (buf, start) = spice.take().unwrap();
+                    self.push_spice(&buf, start)?;
+                } else if active {
+                    buf.push_str(&line);
+                    buf.push('\n');
+                }
+                continue;
+            }
+
+            let Some((line, tokens)) = lex.line()? else {
+                break;
+            };
+            let err = |message: String| ParserError::Syntax { line, message };
+            let keyword = match &tokens[0] {
+                Token::Ident(keyword) => keyword.as_str(),
+                tok => return Err(err(format!("unexpected token at start of line: {tok:?}"))),
+            };
+
+            // Section and library markers are processed even outside the selected section.
+            match keyword {
+                "section" => {
+                    let name =
+                        ident(&tokens, 1).ok_or_else(|| err("expected section name".into()))?;
+                    found |= section == Some(name);
+                    sections.push(name.clone());
+                    continue;
+                }
+                "endsection" => {
+                    sections
+                        .pop()
+                        .ok_or_else(|| err("unexpected `endsection`".into()))?;
+                    continue;
+                }
+                "library" | "endlibrary" => continue,
+                _ => {}
+            }
+            if !active {
+                continue;
+            }
+
+            // Skip blocks such as `statistics { ... }` and `real` functions.
+            let opens = tokens.iter().filter(|t| is_ident(t, "{")).count();
+            let closes = tokens.iter().filter(|t| is_ident(t, "}")).count();
+            if braces > 0 || opens > 0 {
+                braces = (braces + opens)
+                    .checked_sub(closes)
+                    .ok_or_else(|| err("unmatched `}`".into()))?;
+                continue;
+            }
+
+            match keyword {
+                "simulator" => {
+                    let lang = tokens
+                        .iter()
+                        .position(|t| is_ident(t, "lang"))
+                        .and_then(|i| ident(&tokens, i + 2));
+                    match lang.map(|lang| lang.as_str()) {
+                        Some("spice") => spice = Some((String::new(), line + 1)),
+                        Some("spectre") | None => {}
+                        Some(lang) => return Err(err(format!("unsupported language `{lang}`"))),
+                    }
+                }
+                "subckt" | "inline" => {
+                    let inline = keyword == "inline";
+                    let start = inline as usize + 1;
+                    if inline && !is_ident(&tokens[1], "subckt") {
+                        return Err(err("expected `subckt` after `inline`".into()));
+                    }
+                    let name = ident(&tokens, start)
+                        .ok_or_else(|| err("expected subcircuit name".into()))?
+                        .clone();
+                    let ports = match tokens.get(start + 1) {
+                        Some(Token::LParen) => {
+                            if tokens.last() != Some(&Token::RParen) {
+                                return Err(err("expected `)` after subcircuit ports".into()));
+                            }
+                            nodes(&tokens[start + 2..tokens.len() - 1]).map_err(err)?
+                        }
+                        _ => nodes(&tokens[start + 1..]).map_err(err)?,
+                    };
+                    self.subckts.push(Subckt {
+                        name,
+                        ports,
+                        inline,
+                        ..Default::default()
+                    });
+                }
+                "ends" => {
+                    let subckt = self
+                        .subckts
+                        .pop()
+                        .ok_or_else(|| err("unexpected `ends`".into()))?;
+                    if let Some(name) = ident(&tokens, 1) {
+                        if *name != subckt.name {
+                            return Err(err(format!(
+                                "`ends {name}` does not match subcircuit `{}`",
+                                subckt.name
+                            )));
+                        }
+                    }
+                    self.ast.elems.push(Elem::Subckt(subckt));
+                }
+                "parameters" => {
+                    let params = parse_params(&tokens[1..]).map_err(err)?;
+                    match self.subckts.last_mut() {
+                        Some(subckt) => subckt.params.values.extend(params.values),
+                        None => self.ast.elems.push(Elem::Parameters(params)),
+                    }
+                }
+                "include" => {
+                    let path =
+                        ident(&tokens, 1).ok_or_else(|| err("expected include path".into()))?;
+                    let path = ArcStr::from(unquote(path));
+                    let include_section = match tokens.get(2) {
+                        Some(t) if is_ident(t, "section") => Some(
+                            ident(&tokens, 4)
+                                .ok_or_else(|| err("expected section name".into()))?
+                                .clone(),
+                        ),
+                        _ => None,
+                    };
+                    let resolved = Path::new(path.as_str());
+                    let resolved = if resolved.is_relative() {
+                        let root = self
+                            .include_stack
+                            .last()
+                            .ok_or_else(|| ParserError::UnexpectedRelativePath(path.clone()))?;
+                        root.parent().unwrap().join(resolved)
+                    } else {
+                        resolved.into()
+                    };
+                    self.parse_file_inner(&resolved, include_section.as_ref())?;
+                }
+                "model" => {
+                    let name =
+                        ident(&tokens, 1).ok_or_else(|| err("expected model name".into()))?;
+                    let master =
+                        ident(&tokens, 2).ok_or_else(|| err("expected model type".into()))?;
+                    self.ast.elems.push(Elem::Model(Model {
+                        name: name.clone(),
+                        master: master.clone(),
+                        params: parse_params(&tokens[3..]).map_err(err)?,
+                    }));
+                }
+                "global" => {
+                    let nodes = nodes(&tokens[1..]).map_err(err)?;
+                    self.ast.elems.push(Elem::Global(nodes));
+                }
+                "ahdl_include" | "dspf_include" | "save" | "ic" | "nodeset" => {
+                    tracing::debug!(line, "ignoring `{keyword}` statement");
+                }
+                "if" | "else" => {
+                    return Err(err("conditional statements are not supported".into()));
+                }
+                _ => {
+                    let inst = parse_instance(&tokens).map_err(err)?;
+                    match self.subckts.last_mut() {
+                        Some(subckt) => subckt.instances.push(inst),
+                        None => self.ast.elems.push(Elem::Instance(inst)),
+                    }
+                }
+            }
+        }
+
+        if let Some((buf, start)) = spice {
+            self.push_spice(&buf, start)?;
+        }
+        if !found {
+            return Err(ParserError::MissingSection {
+                path: self.include_stack.last().cloned().unwrap_or_default(),
+                section: section.unwrap().clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Parses a block of SPICE statements starting on line `line`.
+    fn push_spice(&mut self, data: &str, line: usize) -> Result<(), ParserError> {
+        let parsed = spice::parser::Parser::parse(Dialect::Spice, data)
+            .map_err(|err| ParserError::Spice { line, err })?;
+        if parsed.ast.elems.is_empty() {
+            return Ok(());
+        }
+        if let Some(subckt) = self.subckts.last() {
+            return Err(ParserError::Syntax {
+                line,
+                message: format!(
+                    "SPICE statements inside Spectre subcircuit `{}` are not supported",
+                    subckt.name
+                ),
+            });
+        }
+        self.ast.elems.push(Elem::Spice(parsed.ast));
+        Ok(())
+    }
+}
+
+/// Parses an instance line of the form `name [(] nodes [)] master param=value ...`.
+fn parse_instance(tokens: &[Token]) -> Result<Instance, String> {
+    let name = ident(tokens, 0).ok_or("expected instance name")?;
+    let (ports, master_idx) = if tokens.get(1) == Some(&Token::LParen) {
+        let end = tokens
+            .iter()
+            .position(|t| *t == Token::RParen)
+            .ok_or("expected `)` after instance nodes")?;
+        (nodes(&tokens[2..end])?, end + 1)
+    } else {
+        // Without parentheses, the master is the last token before the first parameter.
+        let params_idx = tokens
+            .iter()
+            .position(|t| *t == Token::Equals)
+            .map_or(tokens.len(), |pos| pos - 1);
+        if params_idx < 2 {
+            return Err(format!("expected master name for instance `{name}`"));
+        }
+        (nodes(&tokens[1..params_idx - 1])?, params_idx - 1)
+    };
+    let master = ident(tokens, master_idx)
+        .ok_or_else(|| format!("expected master name for instance `{name}`"))?;
+    Ok(Instance {
+        name: unescape(name),
+        ports,
+        master: master.clone(),
+        params: parse_params(&tokens[master_idx + 1..])?,
+    })
+}
+
+/// Parses `tokens` as `key=value` pairs.
+fn parse_params(tokens: &[Token]) -> Result<Params, String> {
+    let mut params = Params::default();
+    for chunk in tokens.chunks(3) {
+        match chunk {
+            [Token::Ident(k), Token::Equals, Token::Ident(v)] => {
+                params.insert(k.clone(), v.clone());
+            }
+            [Token::Ident(k), ..] => return Err(format!("expected a value for parameter `{k}`")),
+            _ => return Err(format!("expected a parameter, found {:?}", chunk[0])),
+        }
+    }
+    Ok(params)
+}
+
+/// Parses `tokens` as a list of node names.
+fn nodes(tokens: &[Token]) -> Result<Vec<Node>, String> {
+    tokens
+        .iter()
+        .map(|t| match t {
+            Token::Ident(node) => Ok(unescape(node)),
+            t => Err(format!("expected a node name, found {t:?}")),
+        })
+        .collect()
+}
+
+fn ident(tokens: &[Token], idx: usize) -> Option<&ArcStr> {
+    match tokens.get(idx) {
+        Some(Token::Ident(id)) => Some(id),
+        _ => None,
+    }
+}
+
+fn is_ident(token: &Token, value: &str) -> bool {
+    matches!(token, Token::Ident(id) if id == value)
+}
+
+/// Removes backslash escapes from an identifier (e.g. `a\[0\]` becomes `a[0]`).
+fn unescape(s: &ArcStr) -> ArcStr {
+    if !s.contains('\\') {
+        return s.clone();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out.into()
+}
+
+/// Removes the enclosing double quotes from a string literal, if any.
+pub(crate) fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+/// Returns the language selected by a `simulator lang=...` line, if `line` is one.
+fn lang_switch(line: &str) -> Option<Lang> {
+    let line = line.split("//").next().unwrap();
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    let lang = compact.strip_prefix("simulatorlang=")?;
+    if lang.starts_with("spectre") {
+        Some(Lang::Spectre)
+    } else if lang.starts_with("spice") {
+        Some(Lang::Spice)
+    } else {
+        None
+    }
+}
+
+/// Splits Spectre source into logical lines of tokens.
+struct Lexer {
+    src: ArcStr,
+    pos: usize,
+    /// The current line number, starting from 1.
+    line: usize,
+}
+
+impl Lexer {
+    fn new(src: ArcStr) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek2(&self) -> Option<char> {
+        self.src[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_to_newline(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    /// Returns the next physical line, without its line terminator.
+    fn raw_line(&mut self) -> Option<ArcStr> {
+        if self.pos >= self.src.len() {
+            return None;
+        }
+        let start = self.pos;
+        self.skip_to_newline();
+        let line = self.src[start..self.pos].trim_end_matches('\r').into();
+        self.bump();
+        Some(line)
+    }
+
+    /// Returns the tokens of the next non-empty logical line,
+    /// along with the line number on which it starts.
+    fn line(&mut self) -> Result<Option<(usize, Vec<Token>)>, ParserError> {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut line_start = true;
+        let mut start = self.line;
+        let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+            if !word.is_empty() {
+                tokens.push(Token::Ident(std::mem::take(word).into()));
+            }
+        };
+
+        while let Some(c) = self.peek() {
+            if tokens.is_empty() && word.is_empty() {
+                start = self.line;
+            }
+            match c {
+                '*' if line_start => self.skip_to_newline(),
+                '/' if self.peek2() == Some('/') => self.skip_to_newline(),
+                '\\' if matches!(self.peek2(), Some('\n' | '\r')) => {
+                    // Line continuation.
+                    self.bump();
+                    self.skip_to_newline();
+                    self.bump();
+                    flush(&mut word, &mut tokens);
+                }
+                '\\' => {
+                    word.push(self.bump().unwrap());
+                    word.extend(self.bump());
+                    line_start = false;
+                }
+                '\n' => {
+                    self.bump();
+                    line_start = true;
+                    flush(&mut word, &mut tokens);
+                    if !tokens.is_empty() {
+                        return Ok(Some((start, tokens)));
+                    }
+                }
+                c if c.is_whitespace() => {
+                    self.bump();
+                    flush(&mut word, &mut tokens);
+                }
+                '(' | ')' | '{' | '}' => {
+                    self.bump();
+                    flush(&mut word, &mut tokens);
+                    tokens.push(match c {
+                        '(' => Token::LParen,
+                        ')' => Token::RParen,
+                        c => Token::Ident(c.to_string().into()),
+                    });
+                    line_start = false;
+                }
+                '=' => {
+                    self.bump();
+                    flush(&mut word, &mut tokens);
+                    tokens.push(Token::Equals);
+                    let value = self.value()?;
+                    if !value.is_empty() {
+                        tokens.push(Token::Ident(value.into()));
+                    }
+                    line_start = false;
+                }
+                '"' => {
+                    flush(&mut word, &mut tokens);
+                    tokens.push(Token::Ident(self.string()?.into()));
+                    line_start = false;
+                }
+                c => {
+                    self.bump();
+                    word.push(c);
+                    line_start = false;
+                }
+            }
+        }
+        flush(&mut word, &mut tokens);
+        Ok((!tokens.is_empty()).then_some((start, tokens)))
+    }
+
+    /// Reads a string literal, including the quotes.
+    fn string(&mut self) -> Result<String, ParserError> {
+        let line = self.line;
+        let mut s = String::new();
+        s.push(self.bump().unwrap());
+        loop {
+            match self.bump() {
+                Some('"') => {
+                    s.push('"');
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    s.push('\\');
+                    s.extend(self.bump());
+                }
+                Some('\n') | None => {
+                    return Err(ParserError::Syntax {
+                        line,
+                        message: "unterminated string".into(),
+                    })
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// Reads a parameter value following an equal sign.
+    ///
+    /// Values end at whitespace, unless the whitespace is enclosed in
+    /// parentheses, brackets or braces.
+    fn value(&mut self) -> Result<String, ParserError> {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+        if self.peek() == Some('"') {
+            return self.string();
+        }
+        let line = self.line;
+        let mut value = String::new();
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '/' if self.peek2() == Some('/') => break,
+                '\\' if matches!(self.peek2(), Some('\n' | '\r')) => {
+                    self.bump();
+                    self.skip_to_newline();
+                    self.bump();
+                    if depth == 0 {
+                        break;
+                    }
+                    value.push(' ');
+                }
+                '\n' => break,
+                c if c.is_whitespace() && depth == 0 => break,
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    value.push(c);
+                    self.bump();
+                }
+                ')' | ']' | '}' if depth == 0 => break,
+                ')' | ']' | '}' => {
+                    depth -= 1;
+                    value.push(c);
+                    self.bump();
+                }
+                c => {
+                    value.push(c);
+                    self.bump();
+                }
+            }
+        }
+        if depth > 0 {
+            return Err(ParserError::Syntax {
+                line,
+                message: format!("unbalanced parameter value `{value}`"),
+            });
+        }
+        Ok(value)
+    }
+}
+
+impl Params {
+    /// Create a new, empty parameter set.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a key-value pair into the parameter set.
+    pub fn insert(&mut self, k: impl Into<ArcStr>, v: impl Into<ArcStr>) {
+        self.values.insert(k.into(), v.into());
+    }
+
+    /// Get the value corresponding to the given key.
+    pub fn get(&self, k: &str) -> Option<&ArcStr> {
+        self.values.get(k)
+    }
+
+    /// An iterator over all key-value pairs, in the order they were inserted.
+    pub fn iter(&self) -> impl Iterator<Item = (&ArcStr, &ArcStr)> {
+        self.values.iter()
+    }
+
+    /// The number of parameters in the set.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the set contains no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl ParsedSpectre {
+    /// Convert this Spectre netlist to a SCIR library.
+    pub fn to_scir(&self) -> conv::ConvResult<scir::Library<Spectre>> {
+        let conv = ScirConverter::new(&self.ast);
+        conv.convert()
+    }
+}
@@ -0,0 +1,263 @@
+use super::*;
+
+use crate::parser::conv::convert_str_to_numeric_lit;
+use crate::Primitive;
+use rust_decimal_macros::dec;
+use scir::param::Expr;
+use scir::{IndexOwned, ParamValue};
+
+pub const TEST_DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/data");
+
+#[inline]
+pub fn test_data(file_name: &str) -> PathBuf {
+    PathBuf::from(TEST_DATA_DIR).join(file_name)
+}
+
+pub const SPECTRE_NETLIST: &str = r#"
+// A legacy Spectre netlist.
+simulator lang=spectre
+global 0 vdd!
+
+parameters vdd_nom=1.8
+
+* Resistor divider with a parameterized total resistance.
+subckt divider (top bot out)
+parameters rtot=2k frac=0.25
+    r0 (top out) resistor r=rtot*(1 - frac) // upper leg
+    r1 (out bot) resistor \
+        r=rtot*frac
+    c0 out bot capacitor c=10f
+ends divider
+
+inline subckt buf (a\[0\] y vdd vss)
+    mp (y a\[0\] vdd vdd) pch w=2u l=150n
+    mn (y a\[0\] vss vss) nch w=1u l=150n
+ends buf
+
+model nch bsim4 type=n
+model pch bsim4 type=p
+
+subckt top (vin vout vss)
+    xdiv (vin vss mid) divider rtot=1M frac=(0.5 * 0.5)
+    xbuf (mid vout vdd! vss) buf
+ends top
+
+tran tran stop=1u
+"#;
+
+#[test]
+fn spectre_tokens() {
+    let mut lex = Lexer::new(
+        r#"r1 (a b) resistor r=(x + 1) \
+  m=2 // comment
+* comment line
+save "a.b""#
+            .into(),
+    );
+    let ident = |s: &str| Token::Ident(s.into());
+    assert_eq!(
+        lex.line().unwrap(),
+        Some((
+            1,
+            vec![
+                ident("r1"),
+                Token::LParen,
+                ident("a"),
+                ident("b"),
+                Token::RParen,
+                ident("resistor"),
+                ident("r"),
+                Token::Equals,
+                ident("(x + 1)"),
+                ident("m"),
+                Token::Equals,
+                ident("2"),
+            ]
+        ))
+    );
+    assert_eq!(
+        lex.line().unwrap(),
+        Some((4, vec![ident("save"), ident("\"a.b\"")]))
+    );
+    assert_eq!(lex.line().unwrap(), None);
+}
+
+#[test]
+fn parse_spectre_netlist() {
+    let parsed = Parser::parse(SPECTRE_NETLIST).unwrap();
+    let subckts: Vec<_> = parsed
+        .ast
+        .elems
+        .iter()
+        .filter_map(|elem| match elem {
+            Elem::Subckt(subckt) => Some(subckt),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(subckts.len(), 3);
+
+    let divider = subckts[0];
+    assert_eq!(divider.name, "divider");
+    assert_eq!(divider.ports, vec!["top", "bot", "out"]);
+    assert_eq!(divider.params.get("rtot").unwrap(), "2k");
+    assert_eq!(divider.instances.len(), 3);
+    assert_eq!(
+        divider.instances[0].params.get("r").unwrap(),
+        "rtot*(1 - frac)"
+    );
+    assert_eq!(divider.instances[1].params.get("r").unwrap(), "rtot*frac");
+    assert_eq!(divider.instances[2].ports, vec!["out", "bot"]);
+    assert_eq!(divider.instances[2].master, "capacitor");
+
+    let buf = subckts[1];
+    assert!(buf.inline);
+    assert_eq!(buf.ports, vec!["a[0]", "y", "vdd", "vss"]);
+    assert_eq!(buf.instances[0].ports, vec!["y", "a[0]", "vdd", "vdd"]);
+    assert_eq!(buf.instances[0].master, "pch");
+
+    assert!(parsed
+        .ast
+        .elems
+        .contains(&Elem::Global(vec!["0".into(), "vdd!".into()])));
+    assert!(parsed.ast.elems.iter().any(|elem| matches!(
+        elem,
+        Elem::Model(Model { name, master, .. }) if name == "nch" && master == "bsim4"
+    )));
+    assert!(parsed.ast.elems.iter().any(|elem| matches!(
+        elem,
+        Elem::Instance(Instance { name, master, .. }) if name == "tran" && master == "tran"
+    )));
+}
+
+#[test]
+fn convert_spectre_to_scir() {
+    let lib = Spectre::scir_lib_from_str(SPECTRE_NETLIST);
+    assert_eq!(lib.cells().count(), 3);
+
+    let divider = lib.cell_named("divider");
+    assert_eq!(
+        divider.param("rtot"),
+        Some(&ParamValue::Numeric(dec!(2000)))
+    );
+    let r0 = divider.instance_named("r0");
+    match lib.primitive(r0.child().unwrap_primitive()) {
+        Primitive::RawInstance {
+            cell,
+            ports,
+            params,
+        } => {
+            assert_eq!(cell, "resistor");
+            assert_eq!(ports, &vec!["1", "2"]);
+            assert_eq!(
+                params.get("r"),
+                Some(&ParamValue::Expr(Expr::parse("rtot*(1-frac)").unwrap()))
+            );
+        }
+        _ => panic!("expected a raw instance"),
+    }
+
+    let top = lib.cell_named("top");
+    let xdiv = top.instance_named("xdiv");
+    assert_eq!(xdiv.child(), lib.cell_id_named("divider").into());
+    assert_eq!(xdiv.param("rtot"), Some(&ParamValue::Numeric(dec!(1e6))));
+    assert_eq!(
+        xdiv.param("frac"),
+        Some(&ParamValue::Expr(Expr::parse("0.5*0.5").unwrap()))
+    );
+    let xbuf = top.instance_named("xbuf");
+    assert_eq!(
+        top.signal(xbuf.connection("vdd").index(0).signal()).name,
+        "vdd!"
+    );
+    let mp = lib.cell_named("buf").instance_named("mp");
+    match lib.primitive(mp.child().unwrap_primitive()) {
+        Primitive::RawInstance { cell, params, .. } => {
+            assert_eq!(cell, "pch");
+            assert_eq!(params.get("l"), Some(&ParamValue::Numeric(dec!(150e-9))));
+        }
+        _ => panic!("expected a raw instance"),
+    }
+}
+
+#[test]
+fn convert_spectre_with_spice_blocks() {
+    let lib = Spectre::scir_lib_from_str(
+        r#"
+simulator lang=spice
+.subckt spice_res p n r=100
+R1 p n 'r'
+.ends
+simulator lang=spectre
+
+subckt top (a b)
+    x0 (a mid) spice_res R=200
+    x1 (mid b) spice_res
+ends top
+"#,
+    );
+    let top = lib.cell_named("top");
+    let x0 = top.instance_named("x0");
+    assert_eq!(x0.child(), lib.cell_id_named("spice_res").into());
+    assert_eq!(x0.param("r"), Some(&ParamValue::Numeric(dec!(200))));
+
+    let spice_res = lib.cell_named("spice_res");
+    let r1 = spice_res.instance_named("1");
+    assert!(matches!(
+        lib.primitive(r1.child().unwrap_primitive()),
+        Primitive::Spice(spice::Primitive::Res2 { .. })
+    ));
+
+    let err = Parser::parse(
+        r#"
+subckt top (a b)
+simulator lang=spice
+R1 a b 100
+simulator lang=spectre
+ends top
+"#,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(err, ParserError::Syntax { line: 4, .. }));
+}
+
+#[test]
+fn parse_spectre_sections() {
+    let path = test_data("spectre/example_lib.scs");
+    let resistance = |parsed: &ParsedSpectre| {
+        let lib = parsed.to_scir().unwrap();
+        let cell = lib.cell_named("example_resistor");
+        match lib.primitive(cell.instance_named("res0").child().unwrap_primitive()) {
+            Primitive::RawInstance { params, .. } => params.get("r").cloned(),
+            _ => panic!("expected a raw instance"),
+        }
+    };
+
+    let parsed = Parser::parse_file_section(&path, "section_b").unwrap();
+    assert_eq!(resistance(&parsed), Some(ParamValue::Numeric(dec!(500))));
+
+    let parsed = Parser::parse(format!("include {:?} section=section_a", path)).unwrap();
+    assert_eq!(resistance(&parsed), Some(ParamValue::Numeric(dec!(1000))));
+
+    assert!(matches!(
+        Parser::parse_file_section(&path, "section_c"),
+        Err(ParserError::MissingSection { .. })
+    ));
+    assert!(matches!(
+        Parser::parse("include \"example_lib.scs\""),
+        Err(ParserError::UnexpectedRelativePath(_))
+    ));
+}
+
+#[test]
+fn spectre_numeric_literals() {
+    assert_eq!(convert_str_to_numeric_lit("2"), Some(dec!(2)));
+    assert_eq!(convert_str_to_numeric_lit("-1.5k"), Some(dec!(-1500)));
+    assert_eq!(convert_str_to_numeric_lit("1M"), Some(dec!(1e6)));
+    assert_eq!(convert_str_to_numeric_lit("1m"), Some(dec!(1e-3)));
+    assert_eq!(convert_str_to_numeric_lit("2.5e-3u"), Some(dec!(2.5e-9)));
+    assert_eq!(convert_str_to_numeric_lit(".5"), Some(dec!(0.5)));
+    assert_eq!(convert_str_to_numeric_lit("5%"), Some(dec!(0.05)));
+    assert_eq!(convert_str_to_numeric_lit("1meg"), None);
+    assert_eq!(convert_str_to_numeric_lit("rtot"), None);
+}
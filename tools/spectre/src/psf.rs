@@ -0,0 +1,584 @@
+//! Reading of binary PSF files not supported by [`psfparser`].
+//!
+//! [`psfparser`] only supports binary PSF files containing swept data
+//! of real or complex type, so DC operating point results and
+//! results containing structures (such as noise contributions) are read here.
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+
+const SECTION_HEADER: u32 = 0;
+const SECTION_TYPE: u32 = 1;
+const SECTION_SWEEP: u32 = 2;
+const SECTION_TRACE: u32 = 3;
+const SECTION_VALUE: u32 = 4;
+
+const CHUNK_ENTRY: u32 = 16;
+const CHUNK_GROUP: u32 = 17;
+const CHUNK_STRUCT_END: u32 = 18;
+const CHUNK_SUBSECTION: u32 = 22;
+const CHUNK_PROP_STRING: u32 = 33;
+const CHUNK_PROP_INT: u32 = 34;
+const CHUNK_PROP_REAL: u32 = 35;
+
+const DATA_INT8: u32 = 1;
+const DATA_STRING: u32 = 2;
+const DATA_INT32: u32 = 5;
+const DATA_REAL: u32 = 11;
+const DATA_COMPLEX: u32 = 12;
+const DATA_STRUCT: u32 = 16;
+
+/// The values of the swept variable and of each signal in a swept PSF file.
+pub(crate) struct SweptData {
+    pub(crate) sweep: Vec<f64>,
+    pub(crate) signals: HashMap<String, Vec<f64>>,
+}
+
+/// A PSF type definition.
+struct TypeDef {
+    name: String,
+    data_type: u32,
+    /// The members of a structure type.
+    members: Vec<TypeDef>,
+}
+
+/// A cursor over big-endian PSF data.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Returns `true` if all data has been read.
+    ///
+    /// Readers are bounded by the end offsets stored in section headers,
+    /// so no trailing bytes remain after the last entry of a section.
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(Error::Parse);
+        }
+        let (bytes, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn peek_u32(&self) -> Result<u32> {
+        Reader::new(self.data).u32()
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn expect(&mut self, value: u32) -> Result<()> {
+        if self.u32()? != value {
+            return Err(Error::Parse);
+        }
+        Ok(())
+    }
+
+    /// Reads a length-prefixed string padded to a multiple of 4 bytes.
+    fn string(&mut self) -> Result<&'a str> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        self.take((4 - len % 4) % 4)?;
+        std::str::from_utf8(bytes).map_err(|_| Error::Parse)
+    }
+
+    /// Skips any properties attached to the preceding entry.
+    fn skip_properties(&mut self) -> Result<()> {
+        self.properties(|_, _| {})
+    }
+
+    /// Reads the properties attached to the preceding entry,
+    /// passing the name and value of each integer property to `f`.
+    fn properties(&mut self, mut f: impl FnMut(&'a str, i32)) -> Result<()> {
+        while !self.is_empty() {
+            match self.peek_u32()? {
+                CHUNK_PROP_STRING => {
+                    self.u32()?;
+                    self.string()?;
+                    self.string()?;
+                }
+                CHUNK_PROP_INT => {
+                    self.u32()?;
+                    let name = self.string()?;
+                    f(name, self.i32()?);
+                }
+                CHUNK_PROP_REAL => {
+                    self.u32()?;
+                    self.string()?;
+                    self.f64()?;
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a type definition, including the members of structure types.
+    fn type_def(&mut self) -> Result<(u32, TypeDef)> {
+        self.expect(CHUNK_ENTRY)?;
+        let id = self.u32()?;
+        let name = self.string()?.to_string();
+        let _array_type = self.u32()?;
+        let data_type = self.u32()?;
+        let mut members = Vec::new();
+        if data_type == DATA_STRUCT {
+            while self.peek_u32()? != CHUNK_STRUCT_END {
+                members.push(self.type_def()?.1);
+            }
+            self.u32()?;
+        }
+        self.skip_properties()?;
+        Ok((
+            id,
+            TypeDef {
+                name,
+                data_type,
+                members,
+            },
+        ))
+    }
+
+    /// Reads a value of the given type, passing the name and value of
+    /// each real value it contains to `f`.
+    ///
+    /// Structure members are named `{name}:{member}`.
+    fn value(&mut self, name: &str, ty: &TypeDef, f: &mut impl FnMut(String, f64)) -> Result<()> {
+        match ty.data_type {
+            DATA_REAL => f(name.to_string(), self.f64()?),
+            DATA_INT8 | DATA_INT32 => {
+                self.u32()?;
+            }
+            DATA_COMPLEX => {
+                self.f64()?;
+                self.f64()?;
+            }
+            DATA_STRING => {
+                self.string()?;
+            }
+            DATA_STRUCT => {
+                for member in ty.members.iter() {
+                    self.value(&format!("{name}:{}", member.name), member, f)?;
+                }
+            }
+            _ => return Err(Error::Parse),
+        }
+        Ok(())
+    }
+
+    /// Reads a signal reference, returning its name and type ID.
+    fn signal_ref(&mut self) -> Result<(&'a str, u32)> {
+        let _id = self.u32()?;
+        let name = self.string()?;
+        let type_id = self.u32()?;
+        self.skip_properties()?;
+        Ok((name, type_id))
+    }
+}
+
+/// Returns the entries of the indexed section of the given kind.
+fn section<'a>(data: &'a [u8], toc: &HashMap<u32, usize>, kind: u32) -> Result<Reader<'a>> {
+    let start = *toc.get(&kind).ok_or(Error::Parse)?;
+    let mut header = Reader::new(data.get(start + 8..).ok_or(Error::Parse)?);
+    header.expect(CHUNK_SUBSECTION)?;
+    let end = header.u32()? as usize;
+    Ok(Reader::new(data.get(start + 16..end).ok_or(Error::Parse)?))
+}
+
+/// Returns the contents of the non-indexed section of the given kind.
+fn simple_section<'a>(data: &'a [u8], toc: &HashMap<u32, usize>, kind: u32) -> Result<Reader<'a>> {
+    let start = *toc.get(&kind).ok_or(Error::Parse)?;
+    let end = Reader::new(data.get(start + 4..).ok_or(Error::Parse)?).u32()? as usize;
+    Ok(Reader::new(data.get(start + 8..end).ok_or(Error::Parse)?))
+}
+
+/// Reads the table of contents, mapping each section kind to its offset.
+fn parse_toc(data: &[u8]) -> Result<HashMap<u32, usize>> {
+    // The file ends with the table of contents, 8 bytes of padding and the data size.
+    let len = data.len();
+    let ds =
+        Reader::new(data.get(len.checked_sub(4).ok_or(Error::Parse)?..).unwrap()).u32()? as usize;
+    let n = len.checked_sub(ds + 12).ok_or(Error::Parse)? / 8;
+    let mut toc_reader = Reader::new(&data[len - 12 - 8 * n..]);
+    let mut toc = HashMap::new();
+    for _ in 0..n {
+        let kind = toc_reader.u32()?;
+        let ofs = toc_reader.u32()? as usize;
+        toc.insert(kind, ofs);
+    }
+    Ok(toc)
+}
+
+/// Reads the type definitions of a PSF file, keyed by type ID.
+fn parse_types(data: &[u8], toc: &HashMap<u32, usize>) -> Result<HashMap<u32, TypeDef>> {
+    let mut types = HashMap::new();
+    let mut reader = section(data, toc, SECTION_TYPE)?;
+    while !reader.is_empty() {
+        let (id, ty) = reader.type_def()?;
+        types.insert(id, ty);
+    }
+    Ok(types)
+}
+
+/// Parses the real-valued signals of a non-swept binary PSF file.
+///
+/// Values of other types are ignored.
+pub(crate) fn parse_non_swept(data: &[u8]) -> Result<HashMap<String, f64>> {
+    let toc = parse_toc(data)?;
+    let types = parse_types(data, &toc)?;
+
+    let mut values = HashMap::new();
+    let mut reader = section(data, &toc, SECTION_VALUE)?;
+    while !reader.is_empty() {
+        reader.expect(CHUNK_ENTRY)?;
+        let _id = reader.u32()?;
+        let name = reader.string()?;
+        let type_id = reader.u32()?;
+        let ty = types.get(&type_id).ok_or(Error::Parse)?;
+        if ty.data_type == DATA_REAL {
+            values.insert(name.to_string(), reader.f64()?);
+        } else {
+            reader.value(name, ty, &mut |_, _| {})?;
+        }
+        reader.skip_properties()?;
+    }
+
+    Ok(values)
+}
+
+/// Parses the real-valued signals of a swept binary PSF file,
+/// including the real-valued members of structures.
+///
+/// Only files without windowed data, such as those produced by
+/// frequency-domain analyses, are supported. Values of other types are ignored.
+pub(crate) fn parse_swept(data: &[u8]) -> Result<SweptData> {
+    let toc = parse_toc(data)?;
+
+    let mut points = None;
+    let mut windowed = false;
+    let mut reader = simple_section(data, &toc, SECTION_HEADER)?;
+    reader.properties(|name, value| match name {
+        "PSF sweep points" => points = Some(value as usize),
+        "PSF window size" => windowed = true,
+        _ => {}
+    })?;
+    // Windowed values are stored in a different layout.
+    if windowed {
+        return Err(Error::Parse);
+    }
+    let points = points.ok_or(Error::Parse)?;
+
+    let types = parse_types(data, &toc)?;
+
+    let mut reader = simple_section(data, &toc, SECTION_SWEEP)?;
+    reader.expect(CHUNK_ENTRY)?;
+    let (_, sweep_type) = reader.signal_ref()?;
+    let sweep_type = types.get(&sweep_type).ok_or(Error::Parse)?;
+
+    let mut traces = Vec::new();
+    let mut reader = section(data, &toc, SECTION_TRACE)?;
+    while !reader.is_empty() {
+        match reader.u32()? {
+            CHUNK_ENTRY => traces.push(reader.signal_ref()?),
+            CHUNK_GROUP => {
+                let _id = reader.u32()?;
+                reader.string()?;
+                let count = reader.u32()?;
+                for _ in 0..count {
+                    reader.expect(CHUNK_ENTRY)?;
+                    traces.push(reader.signal_ref()?);
+                }
+            }
+            _ => return Err(Error::Parse),
+        }
+    }
+
+    let mut sweep = Vec::with_capacity(points);
+    let mut signals: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut reader = simple_section(data, &toc, SECTION_VALUE)?;
+    for _ in 0..points {
+        reader.expect(CHUNK_ENTRY)?;
+        reader.u32()?;
+        reader.value("", sweep_type, &mut |_, value| sweep.push(value))?;
+        for (name, type_id) in traces.iter() {
+            reader.expect(CHUNK_ENTRY)?;
+            reader.u32()?;
+            let ty = types.get(type_id).ok_or(Error::Parse)?;
+            reader.value(name, ty, &mut |name, value| {
+                signals.entry(name).or_default().push(value)
+            })?;
+        }
+    }
+
+    Ok(SweptData { sweep, signals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_u32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn push_string(out: &mut Vec<u8>, value: &str) {
+        push_u32(out, value.len() as u32);
+        out.extend_from_slice(value.as_bytes());
+        out.resize(out.len() + (4 - value.len() % 4) % 4, 0);
+    }
+
+    fn push_section(out: &mut Vec<u8>, entries: &[u8]) -> u32 {
+        let start = out.len() as u32;
+        push_u32(out, 21);
+        push_u32(out, start + 16 + entries.len() as u32);
+        push_u32(out, CHUNK_SUBSECTION);
+        push_u32(out, start + 16 + entries.len() as u32);
+        out.extend_from_slice(entries);
+        start
+    }
+
+    #[test]
+    fn parses_non_swept_values() {
+        let mut types = Vec::new();
+        for (id, name, data_type) in [(1, "V", DATA_REAL), (2, "region", DATA_INT32)] {
+            push_u32(&mut types, CHUNK_ENTRY);
+            push_u32(&mut types, id);
+            push_string(&mut types, name);
+            push_u32(&mut types, 0);
+            push_u32(&mut types, data_type);
+            push_u32(&mut types, CHUNK_PROP_STRING);
+            push_string(&mut types, "units");
+            push_string(&mut types, name);
+        }
+
+        let mut values = Vec::new();
+        push_u32(&mut values, CHUNK_ENTRY);
+        push_u32(&mut values, 3);
+        push_string(&mut values, "vout");
+        push_u32(&mut values, 1);
+        values.extend_from_slice(&0.9f64.to_be_bytes());
+        push_u32(&mut values, CHUNK_ENTRY);
+        push_u32(&mut values, 4);
+        push_string(&mut values, "m1:region");
+        push_u32(&mut values, 2);
+        push_u32(&mut values, 2);
+        push_u32(&mut values, CHUNK_ENTRY);
+        push_u32(&mut values, 5);
+        push_string(&mut values, "v1:p");
+        push_u32(&mut values, 1);
+        values.extend_from_slice(&(-1e-3f64).to_be_bytes());
+        push_u32(&mut values, CHUNK_PROP_REAL);
+        push_string(&mut values, "scale");
+        values.extend_from_slice(&1f64.to_be_bytes());
+
+        let mut file = Vec::new();
+        let type_ofs = push_section(&mut file, &types);
+        let value_ofs = push_section(&mut file, &values);
+        let ds = file.len() as u32;
+        for (kind, ofs) in [(SECTION_TYPE, type_ofs), (SECTION_VALUE, value_ofs)] {
+            push_u32(&mut file, kind);
+            push_u32(&mut file, ofs);
+        }
+        file.extend_from_slice(b"Clarissa");
+        push_u32(&mut file, ds);
+
+        let parsed = parse_non_swept(&file).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["vout"], 0.9);
+        assert_eq!(parsed["v1:p"], -1e-3);
+    }
+
+    fn push_simple_section(out: &mut Vec<u8>, entries: &[u8]) -> u32 {
+        let start = out.len() as u32;
+        push_u32(out, 21);
+        push_u32(out, start + 8 + entries.len() as u32);
+        out.extend_from_slice(entries);
+        start
+    }
+
+    fn push_type(out: &mut Vec<u8>, id: u32, name: &str, data_type: u32) {
+        push_u32(out, CHUNK_ENTRY);
+        push_u32(out, id);
+        push_string(out, name);
+        push_u32(out, 0);
+        push_u32(out, data_type);
+    }
+
+    fn push_signal_ref(out: &mut Vec<u8>, id: u32, name: &str, type_id: u32) {
+        push_u32(out, CHUNK_ENTRY);
+        push_u32(out, id);
+        push_string(out, name);
+        push_u32(out, type_id);
+    }
+
+    #[test]
+    fn parses_swept_values_with_structs() {
+        let mut header = Vec::new();
+        push_u32(&mut header, CHUNK_PROP_STRING);
+        push_string(&mut header, "PSF style");
+        push_string(&mut header, "7");
+        push_u32(&mut header, CHUNK_PROP_INT);
+        push_string(&mut header, "PSF sweep points");
+        push_u32(&mut header, 2);
+
+        let mut types = Vec::new();
+        push_type(&mut types, 1, "V/sqrt(Hz)", DATA_REAL);
+        push_type(&mut types, 2, "resistor", DATA_STRUCT);
+        push_type(&mut types, 3, "rn", DATA_REAL);
+        push_type(&mut types, 4, "total", DATA_REAL);
+        push_u32(&mut types, CHUNK_STRUCT_END);
+        push_u32(&mut types, CHUNK_PROP_STRING);
+        push_string(&mut types, "key");
+        push_string(&mut types, "value");
+
+        let mut sweeps = Vec::new();
+        push_signal_ref(&mut sweeps, 5, "freq", 1);
+
+        let mut traces = Vec::new();
+        push_signal_ref(&mut traces, 6, "out", 1);
+        push_u32(&mut traces, CHUNK_GROUP);
+        push_u32(&mut traces, 7);
+        push_string(&mut traces, "devices");
+        push_u32(&mut traces, 2);
+        push_signal_ref(&mut traces, 8, "r1", 2);
+        push_signal_ref(&mut traces, 9, "step", 1);
+
+        let mut values = Vec::new();
+        for (freq, out, rn, step) in [(1., 1e-9, 1e-18, 0.5), (10., 2e-9, 2e-18, 1.5)] {
+            push_u32(&mut values, CHUNK_ENTRY);
+            push_u32(&mut values, 5);
+            values.extend_from_slice(&f64::to_be_bytes(freq));
+            push_u32(&mut values, CHUNK_ENTRY);
+            push_u32(&mut values, 6);
+            values.extend_from_slice(&f64::to_be_bytes(out));
+            push_u32(&mut values, CHUNK_ENTRY);
+            push_u32(&mut values, 8);
+            values.extend_from_slice(&f64::to_be_bytes(rn));
+            values.extend_from_slice(&f64::to_be_bytes(rn));
+            push_u32(&mut values, CHUNK_ENTRY);
+            push_u32(&mut values, 9);
+            values.extend_from_slice(&f64::to_be_bytes(step));
+        }
+
+        let mut file = Vec::new();
+        let header_ofs = push_simple_section(&mut file, &header);
+        let type_ofs = push_section(&mut file, &types);
+        let sweep_ofs = push_simple_section(&mut file, &sweeps);
+        let trace_ofs = push_section(&mut file, &traces);
+        let value_ofs = push_simple_section(&mut file, &values);
+        let ds = file.len() as u32;
+        for (kind, ofs) in [
+            (SECTION_HEADER, header_ofs),
+            (SECTION_TYPE, type_ofs),
+            (SECTION_SWEEP, sweep_ofs),
+            (SECTION_TRACE, trace_ofs),
+            (SECTION_VALUE, value_ofs),
+        ] {
+            push_u32(&mut file, kind);
+            push_u32(&mut file, ofs);
+        }
+        file.extend_from_slice(b"Clarissa");
+        push_u32(&mut file, ds);
+
+        let SweptData { sweep, signals } = parse_swept(&file).unwrap();
+        assert_eq!(sweep, vec![1., 10.]);
+        assert_eq!(signals.len(), 4);
+        assert_eq!(signals["out"], vec![1e-9, 2e-9]);
+        assert_eq!(signals["r1:rn"], vec![1e-18, 2e-18]);
+        assert_eq!(signals["r1:total"], vec![1e-18, 2e-18]);
+        assert_eq!(signals["step"], vec![0.5, 1.5]);
+    }
+
+    /// Checks that swept files without structures are read the same way by [`psfparser`],
+    /// which is tested against binary PSF files written by Spectre.
+    #[test]
+    fn parses_swept_values_like_psfparser() {
+        let mut header = Vec::new();
+        push_u32(&mut header, CHUNK_PROP_STRING);
+        push_string(&mut header, "PSF style");
+        push_string(&mut header, "7");
+        push_u32(&mut header, CHUNK_PROP_INT);
+        push_string(&mut header, "PSF sweep points");
+        push_u32(&mut header, 3);
+
+        let mut types = Vec::new();
+        push_type(&mut types, 10, "sweep", DATA_REAL);
+        push_type(&mut types, 11, "V", DATA_REAL);
+        push_u32(&mut types, CHUNK_PROP_STRING);
+        push_string(&mut types, "units");
+        push_string(&mut types, "V");
+
+        let mut sweeps = Vec::new();
+        push_signal_ref(&mut sweeps, 12, "freq", 10);
+
+        let mut traces = Vec::new();
+        push_u32(&mut traces, CHUNK_GROUP);
+        push_u32(&mut traces, 13);
+        push_string(&mut traces, "group");
+        push_u32(&mut traces, 2);
+        push_signal_ref(&mut traces, 14, "out", 11);
+        push_signal_ref(&mut traces, 15, "vdd", 11);
+
+        let mut values = Vec::new();
+        for (freq, out, vdd) in [(1., 0.1, 1.8), (10., 0.2, 1.8), (100., 0.4, 1.7)] {
+            push_u32(&mut values, CHUNK_ENTRY);
+            push_u32(&mut values, 12);
+            values.extend_from_slice(&f64::to_be_bytes(freq));
+            for (id, value) in [(14, out), (15, vdd)] {
+                push_u32(&mut values, CHUNK_ENTRY);
+                push_u32(&mut values, id);
+                values.extend_from_slice(&f64::to_be_bytes(value));
+            }
+        }
+
+        let mut file = Vec::new();
+        let header_ofs = push_simple_section(&mut file, &header);
+        let type_ofs = push_section(&mut file, &types);
+        let sweep_ofs = push_simple_section(&mut file, &sweeps);
+        let trace_ofs = push_section(&mut file, &traces);
+        let value_ofs = push_simple_section(&mut file, &values);
+        let ds = file.len() as u32;
+        for (kind, ofs) in [
+            (SECTION_HEADER, header_ofs),
+            (SECTION_TYPE, type_ofs),
+            (SECTION_SWEEP, sweep_ofs),
+            (SECTION_TRACE, trace_ofs),
+            (SECTION_VALUE, value_ofs),
+        ] {
+            push_u32(&mut file, kind);
+            push_u32(&mut file, ofs);
+        }
+        file.extend_from_slice(b"Clarissa");
+        push_u32(&mut file, ds);
+
+        let SweptData { sweep, signals } = parse_swept(&file).unwrap();
+        let ast = psfparser::binary::parse(&file).unwrap();
+        let mut expected = psfparser::analysis::transient::TransientData::from_binary(ast).signals;
+        assert_eq!(sweep, expected.remove("freq").unwrap());
+        assert_eq!(signals, expected);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        assert!(parse_non_swept(&[0, 0]).is_err());
+        assert!(parse_non_swept(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+}
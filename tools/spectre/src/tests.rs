@@ -0,0 +1,50 @@
+use crate::analysis::ac::Sweep;
+use crate::analysis::dc::SweepVar;
+use crate::analysis::montecarlo::{MonteCarlo, Variations};
+use crate::analysis::sp::Sp;
+use crate::analysis::sweep;
+use crate::error::Error;
+use crate::{Input, Spectre};
+use rust_decimal_macros::dec;
+use scir::{LibraryBuilder, NetlistLibConversion};
+
+fn sp() -> Input {
+    Input::Sp(Sp {
+        start: dec!(1e6),
+        stop: dec!(1e9),
+        sweep: Sweep::Decade(10),
+        ports: vec!["p1".into(), "p2".into()],
+    })
+}
+
+#[test]
+fn netlist_rejects_nested_sp_analyses() {
+    let lib = LibraryBuilder::<Spectre>::new().build().unwrap();
+    let conv = NetlistLibConversion::new();
+
+    let sweep = Input::Sweep(sweep::Sweep {
+        var: SweepVar::Temp,
+        values: vec![dec!(25), dec!(75)],
+        analysis: vec![sp()],
+    });
+    let mc = Input::MonteCarlo(MonteCarlo {
+        variations: Variations::Mismatch,
+        numruns: 4,
+        seed: None,
+        firstrun: None,
+        analysis: vec![sp()],
+    });
+    for input in [sweep, mc] {
+        let mut out = Vec::new();
+        assert!(matches!(
+            input.netlist(&mut out, "analysis_0", &lib, &conv),
+            Err(Error::UnsupportedNestedAnalysis(_))
+        ));
+    }
+
+    let mut out = Vec::new();
+    sp().netlist(&mut out, "analysis_0", &lib, &conv).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .contains("file=\"psf/analysis_0.s2p\""));
+}
@@ -0,0 +1,277 @@
+//! Touchstone (`.sNp`) S-parameter file reading and writing.
+//!
+//! Supports Touchstone 1.0 files containing S-parameters
+//! in any frequency unit and data format.
+//! Noise parameters following the S-parameter data of two-port files are ignored.
+
+use crate::error::{Error, Result};
+use num::complex::Complex64;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use substrate::simulation::data::sp::SParams;
+
+/// The number of complex values written per line.
+const VALUES_PER_LINE: usize = 4;
+
+/// The number of values in a two-port record: the frequency and 4 complex values.
+const TWO_PORT_RECORD_LEN: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Magnitude and angle (degrees).
+    MagnitudeAngle,
+    /// Magnitude (dB) and angle (degrees).
+    DbAngle,
+    /// Real and imaginary parts.
+    RealImag,
+}
+
+impl Format {
+    fn to_complex(self, a: f64, b: f64) -> Complex64 {
+        match self {
+            Format::MagnitudeAngle => Complex64::from_polar(a, b.to_radians()),
+            Format::DbAngle => Complex64::from_polar(10f64.powf(a / 20.), b.to_radians()),
+            Format::RealImag => Complex64::new(a, b),
+        }
+    }
+}
+
+/// Infers the number of ports from a file name with an `.sNp` extension.
+pub fn num_ports(path: impl AsRef<Path>) -> Option<usize> {
+    let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+    ext.strip_prefix('s')?.strip_suffix('p')?.parse().ok()
+}
+
+/// Reads the S-parameters stored in the given Touchstone file.
+///
+/// The number of ports is inferred from the file extension.
+pub fn read(path: impl AsRef<Path>) -> Result<SParams> {
+    let path = path.as_ref();
+    let num_ports = num_ports(path).ok_or_else(|| {
+        Error::Touchstone(format!(
+            "cannot infer number of ports from file name {path:?}"
+        ))
+    })?;
+    parse(&std::fs::read_to_string(path)?, num_ports)
+}
+
+/// Parses the contents of a Touchstone file with the given number of ports.
+pub fn parse(data: &str, num_ports: usize) -> Result<SParams> {
+    let err = |msg: &str| Error::Touchstone(msg.to_string());
+
+    let mut freq_scale = 1e9;
+    let mut format = Format::MagnitudeAngle;
+    let mut z0 = 50.;
+    let mut seen_options = false;
+    let mut values = Vec::new();
+
+    for line in data.lines() {
+        let line = line.split('!').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(options) = line.strip_prefix('#') {
+            if seen_options {
+                continue;
+            }
+            seen_options = true;
+            let mut tokens = options.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token.to_ascii_uppercase().as_str() {
+                    "HZ" => freq_scale = 1.,
+                    "KHZ" => freq_scale = 1e3,
+                    "MHZ" => freq_scale = 1e6,
+                    "GHZ" => freq_scale = 1e9,
+                    "S" => {}
+                    "Y" | "Z" | "H" | "G" => {
+                        return Err(err("only S-parameters are supported"));
+                    }
+                    "MA" => format = Format::MagnitudeAngle,
+                    "DB" => format = Format::DbAngle,
+                    "RI" => format = Format::RealImag,
+                    "R" => {
+                        z0 = tokens
+                            .next()
+                            .and_then(|r| r.parse().ok())
+                            .ok_or_else(|| err("invalid reference impedance"))?;
+                    }
+                    _ => return Err(err(&format!("unknown option `{token}`"))),
+                }
+            }
+            continue;
+        }
+        let start = values.len();
+        for token in line.split_whitespace() {
+            values.push(
+                token
+                    .parse::<f64>()
+                    .map_err(|_| err(&format!("invalid number `{token}`")))?,
+            );
+        }
+        // In two-port files, noise parameters start at the first frequency
+        // that does not exceed the last frequency of the S-parameter data.
+        if num_ports == 2
+            && start >= TWO_PORT_RECORD_LEN
+            && start % TWO_PORT_RECORD_LEN == 0
+            && values[start] <= values[start - TWO_PORT_RECORD_LEN]
+        {
+            values.truncate(start);
+            break;
+        }
+    }
+
+    let num_entries = num_ports * num_ports;
+    let record_len = 1 + 2 * num_entries;
+    if num_ports == 0 || values.len() % record_len != 0 {
+        return Err(err("unexpected number of values"));
+    }
+
+    let mut freq = Vec::with_capacity(values.len() / record_len);
+    let mut matrices = Vec::with_capacity(values.len() / record_len);
+    for record in values.chunks(record_len) {
+        freq.push(record[0] * freq_scale);
+        let mut matrix = vec![Complex64::default(); num_entries];
+        for (k, pair) in record[1..].chunks(2).enumerate() {
+            matrix[file_order_to_row_major(num_ports, k)] = format.to_complex(pair[0], pair[1]);
+        }
+        matrices.push(matrix);
+    }
+
+    Ok(SParams {
+        freq: Arc::new(freq),
+        z0,
+        num_ports,
+        matrices: Arc::new(matrices),
+    })
+}
+
+/// Writes the given S-parameters to a Touchstone file.
+///
+/// The file name should have an `.sNp` extension, where `N` is the number of ports.
+pub fn write(path: impl AsRef<Path>, params: &SParams) -> Result<()> {
+    let mut f = std::fs::File::create(path)?;
+    write_to(&mut f, params)
+}
+
+/// Writes the given S-parameters in Touchstone format to the given writer.
+///
+/// Values are written as real and imaginary parts, with frequencies in Hz.
+pub fn write_to<W: Write>(out: &mut W, params: &SParams) -> Result<()> {
+    let n = params.num_ports;
+    writeln!(out, "! Substrate {n}-port S-parameters")?;
+    writeln!(out, "# HZ S RI R {}", params.z0)?;
+    for (freq, matrix) in params.freq.iter().zip(params.matrices.iter()) {
+        write!(out, "{freq:e}")?;
+        for k in 0..n * n {
+            // Networks with 3 or more ports start each row of the matrix on a new line,
+            // wrapping long rows every `VALUES_PER_LINE` values.
+            if n > 2 && k > 0 && k % n % VALUES_PER_LINE == 0 {
+                write!(out, "\n ")?;
+            }
+            let value = matrix[file_order_to_row_major(n, k)];
+            write!(out, " {:e} {:e}", value.re, value.im)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Maps the `k`-th value of a Touchstone record to its row-major index in the S-matrix.
+///
+/// Two-port files are stored in column-major order (`S11 S21 S12 S22`);
+/// all others are stored in row-major order.
+fn file_order_to_row_major(num_ports: usize, k: usize) -> usize {
+    if num_ports == 2 {
+        (k % 2) * 2 + k / 2
+    } else {
+        k
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn parses_two_port_files() {
+        let data = r#"
+! A two-port file.
+# MHz S MA R 75
+100 0.5 90 0.25 0 0.125 180 1 -90 ! trailing comment
+200 0.5 0  0.25 0 0.125 0   1 0
+"#;
+        let params = parse(data, 2).unwrap();
+        assert_eq!(params.num_ports, 2);
+        assert_eq!(params.z0, 75.);
+        assert_eq!(*params.freq, vec![100e6, 200e6]);
+
+        // Two-port values are stored in column-major order.
+        assert_relative_eq!(params.get(0, 0, 0).im, 0.5, epsilon = 1e-12);
+        assert_relative_eq!(params.get(0, 1, 0).re, 0.25, epsilon = 1e-12);
+        assert_relative_eq!(params.get(0, 0, 1).re, -0.125, epsilon = 1e-12);
+        assert_relative_eq!(params.get(0, 1, 1).im, -1., epsilon = 1e-12);
+        assert_relative_eq!(params.s(1, 1)[1].re, 1., epsilon = 1e-12);
+    }
+
+    #[test]
+    fn ignores_two_port_noise_parameters() {
+        let data = r#"
+# GHZ S RI R 50
+1 0.5 0 0.1 0 0.1 0 0.5 0
+2 0.4 0 0.2 0 0.2 0 0.4 0
+! Noise parameters: frequency, NFmin (dB), Gamma_opt (MA), Rn/Z0.
+1 1.5 0.3 45 0.2
+2 1.8 0.35 60 0.25
+"#;
+        let params = parse(data, 2).unwrap();
+        assert_eq!(*params.freq, vec![1e9, 2e9]);
+        assert_relative_eq!(params.get(1, 0, 0).re, 0.4, epsilon = 1e-12);
+        assert_relative_eq!(params.get(1, 1, 0).re, 0.2, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn parses_db_files_with_continuation_lines() {
+        let data = "# GHZ S DB\n1 0 0 -20 0 -20 0\n  -20 0 0 0 -20 0\n  -20 0 -20 0 0 0\n";
+        let params = parse(data, 3).unwrap();
+        assert_eq!(params.z0, 50.);
+        assert_eq!(*params.freq, vec![1e9]);
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1. } else { 0.1 };
+                assert_relative_eq!(params.get(0, i, j).re, expected, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_through_touchstone() {
+        for n in 1..=5 {
+            let matrices = (0..3)
+                .map(|f| {
+                    (0..n * n)
+                        .map(|k| Complex64::new(f as f64 + k as f64 / 10., -(k as f64)))
+                        .collect()
+                })
+                .collect();
+            let params = SParams {
+                freq: Arc::new(vec![1e6, 2e6, 3e6]),
+                z0: 50.,
+                num_ports: n,
+                matrices: Arc::new(matrices),
+            };
+            let mut buf = Vec::new();
+            write_to(&mut buf, &params).unwrap();
+            let parsed = parse(std::str::from_utf8(&buf).unwrap(), n).unwrap();
+            assert_eq!(parsed, params);
+        }
+    }
+
+    #[test]
+    fn infers_num_ports_from_extension() {
+        assert_eq!(num_ports("amp.s2p"), Some(2));
+        assert_eq!(num_ports("/tmp/coupler.S12P"), Some(12));
+        assert_eq!(num_ports("netlist.scs"), None);
+    }
+}